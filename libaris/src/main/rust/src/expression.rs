@@ -0,0 +1,11581 @@
+//! The core expression representation used by the rest of the proof checker.
+//!
+//! This mirrors the variant layout of `edu.rpi.aris.ast.Expression` on the Java
+//! side: every `Expr` here corresponds 1:1 with a subclass of `Expression`, and
+//! changes to one side should usually be reflected on the other.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+pub mod debruijn;
+pub mod egraph;
+#[cfg(test)]
+pub(crate) mod testutil;
+
+/// The unary connectives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum USymbol {
+    Not,
+}
+
+/// The non-associative binary connectives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BSymbol {
+    Implies,
+    Plus,
+    Mult,
+    /// The Sheffer stroke, `A ↑ B`, read "not both": `¬(A ∧ B)`. Commutative
+    /// (per [`is_commutative_bsymbol`]) but, unlike `Plus`/`Mult`, not
+    /// associative, so it lives here rather than in [`ASymbol`] — see
+    /// [`normalize_sheffer`] for its elimination and [`to_nand_only`] for
+    /// going the other way.
+    Nand,
+    /// The Peirce arrow, `A ↓ B`, read "neither": `¬(A ∨ B)`. Commutative
+    /// (per [`is_commutative_bsymbol`]) but not associative, same as
+    /// [`BSymbol::Nand`] — see [`normalize_sheffer`].
+    Nor,
+}
+
+/// The associative, flattened n-ary connectives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ASymbol {
+    And,
+    Or,
+    Bicon,
+    Equiv,
+    /// Exclusive or. Associative and commutative, same as `And`/`Or`/
+    /// `Bicon`/`Equiv`; its n-ary semantics is parity (an odd number of
+    /// operands are true), not "exactly one" — see [`eval`]'s treatment of
+    /// it and [`normalize_xor`]'s doc comment.
+    Xor,
+}
+
+/// The quantifiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QSymbol {
+    Forall,
+    Exists,
+}
+
+/// An expression in the logic that ARIS checks proofs over.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Contradiction,
+    Tautology,
+    Var {
+        name: String,
+    },
+    Apply {
+        func: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    Unop {
+        symbol: USymbol,
+        operand: Box<Expr>,
+    },
+    Binop {
+        symbol: BSymbol,
+        l: Box<Expr>,
+        r: Box<Expr>,
+    },
+    AssocBinop {
+        symbol: ASymbol,
+        exprs: Vec<Expr>,
+    },
+    Quantifier {
+        symbol: QSymbol,
+        name: String,
+        body: Box<Expr>,
+    },
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Expr {
+        Expr::Var { name: name.to_owned() }
+    }
+
+    pub fn implies(l: Expr, r: Expr) -> Expr {
+        Expr::Binop { symbol: BSymbol::Implies, l: Box::new(l), r: Box::new(r) }
+    }
+
+    pub fn assoc(symbol: ASymbol, exprs: Vec<Expr>) -> Expr {
+        Expr::AssocBinop { symbol, exprs }
+    }
+
+    pub fn quantifier(symbol: QSymbol, name: &str, body: Expr) -> Expr {
+        Expr::Quantifier { symbol, name: name.to_owned(), body: Box::new(body) }
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Unop { symbol: USymbol::Not, operand: Box::new(self) }
+    }
+}
+
+/// Negate `e`, simplifying away double negations and `Contradiction`/
+/// `Tautology` instead of stacking a `Not` on top of them. Unlike `!e`
+/// (which always wraps), `negate(negate(e))` is structurally equal to `e`
+/// whenever `e` is not already negated.
+pub fn negate(e: Expr) -> Expr {
+    match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => *operand,
+        Expr::Contradiction => Expr::Tautology,
+        Expr::Tautology => Expr::Contradiction,
+        other => !other,
+    }
+}
+
+/// Like [`negate`], but for the shapes DeMorgan's laws and quantifier
+/// duality apply to, pushes the negation one level down instead of wrapping
+/// the whole expression: `¬(A ∧ B)` becomes `¬A ∨ ¬B`, `¬(A ∨ B)` becomes
+/// `¬A ∧ ¬B`, `¬∀x.A` becomes `∃x.¬A`, `¬∃x.A` becomes `∀x.¬A`, and
+/// `¬(A → B)` becomes `A ∧ ¬B`. `Bicon`/`Equiv` have no single-step
+/// DeMorgan form yet, so they (along with everything else) fall back to
+/// [`negate`]'s wrap-or-cancel behavior.
+pub fn negate_deep(e: Expr) -> Expr {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs: exprs.into_iter().map(negate).collect() }
+        }
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            Expr::AssocBinop { symbol: ASymbol::And, exprs: exprs.into_iter().map(negate).collect() }
+        }
+        Expr::Quantifier { symbol: QSymbol::Forall, name, body } => {
+            Expr::Quantifier { symbol: QSymbol::Exists, name, body: Box::new(negate(*body)) }
+        }
+        Expr::Quantifier { symbol: QSymbol::Exists, name, body } => {
+            Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(negate(*body)) }
+        }
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => Expr::assoc(ASymbol::And, vec![*l, negate(*r)]),
+        other => negate(other),
+    }
+}
+
+/// `{}` prints the fewest parens [`ladder_precedence`] says are needed to
+/// re-parse back to this exact tree; `{:#}` prints every `Binop` and
+/// `AssocBinop` fully parenthesized regardless, and every `Quantifier` that
+/// isn't in tail position (see [`fmt_fp`]), which is
+/// how this crate always printed before minimal-parens printing existed —
+/// kept around for debugging a tree whose grouping is hard to read off the
+/// minimal form.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            fmt_fully_parenthesized(self, f)
+        } else {
+            fmt_minimal(self, f, true)
+        }
+    }
+}
+
+fn fmt_fully_parenthesized(e: &Expr, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt_fp(e, f, true)
+}
+
+/// The body of [`fmt_fully_parenthesized`], tracking tail position the same
+/// way [`fmt_minimal`]/[`fmt_child`] do. Every `Binop` and `AssocBinop`
+/// already self-delimits with its own `(...)` here, but a bare `Quantifier`
+/// (or a `~`-chain wrapping one) doesn't — its body still runs to the end
+/// of whatever this call prints — so it needs the same
+/// [`is_quantifier_tailed`] guard `fmt_child` uses, even in this otherwise
+/// always-parenthesized mode.
+fn fmt_fp(e: &Expr, f: &mut fmt::Formatter, tail: bool) -> fmt::Result {
+    if is_quantifier_tailed(e) && !tail {
+        write!(f, "(")?;
+        fmt_fp(e, f, true)?;
+        return write!(f, ")");
+    }
+    match e {
+        Expr::Contradiction => write!(f, "_|_"),
+        Expr::Tautology => write!(f, "T"),
+        Expr::Var { name } => write!(f, "{}", name),
+        Expr::Apply { func, args } if matches!(func.as_ref(), Expr::Var { name } if name == "=") && args.len() == 2 => {
+            write!(f, "(")?;
+            fmt_fp(&args[0], f, true)?;
+            write!(f, " = ")?;
+            fmt_fp(&args[1], f, true)?;
+            write!(f, ")")
+        }
+        Expr::Apply { func, args } => {
+            fmt_fp(func, f, true)?;
+            write!(f, "(")?;
+            for (i, arg) in args.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_fp(arg, f, true)?;
+            }
+            write!(f, ")")
+        }
+        Expr::Unop { symbol: USymbol::Not, operand } => {
+            write!(f, "~")?;
+            fmt_fp(operand, f, tail)
+        }
+        Expr::Binop { symbol, l, r } => {
+            let op = bsymbol_str(*symbol);
+            write!(f, "(")?;
+            fmt_fp(l, f, false)?;
+            write!(f, " {} ", op)?;
+            fmt_fp(r, f, true)?;
+            write!(f, ")")
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let op = asymbol_str(*symbol);
+            let last = exprs.len() - 1;
+            write!(f, "(")?;
+            for (i, e) in exprs.iter().enumerate() {
+                if i != 0 {
+                    write!(f, " {} ", op)?;
+                }
+                fmt_fp(e, f, i == last)?;
+            }
+            write!(f, ")")
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let (label, mut inner) = quantifier_binder_label(*symbol, name, body);
+            let mut labels = vec![label];
+            let mut bound_names = vec![name.as_str()];
+            while let Expr::Quantifier { symbol: inner_symbol, name: inner_name, body: inner_body } = inner {
+                if inner_symbol != symbol || bound_names.contains(&inner_name.as_str()) {
+                    break;
+                }
+                let (label, next) = quantifier_binder_label(*inner_symbol, inner_name, inner_body);
+                labels.push(label);
+                bound_names.push(inner_name.as_str());
+                inner = next;
+            }
+            write!(f, "{} {}, ", qsymbol_str(*symbol), labels.join(" "))?;
+            fmt_fp(inner, f, tail)
+        }
+    }
+}
+
+fn bsymbol_str(symbol: BSymbol) -> &'static str {
+    match symbol {
+        BSymbol::Implies => "->",
+        BSymbol::Plus => "+",
+        BSymbol::Mult => "*",
+        BSymbol::Nand => "!&",
+        BSymbol::Nor => "!|",
+    }
+}
+
+fn asymbol_str(symbol: ASymbol) -> &'static str {
+    match symbol {
+        ASymbol::And => "&",
+        ASymbol::Or => "|",
+        ASymbol::Bicon => "<=>",
+        ASymbol::Equiv => "===",
+        ASymbol::Xor => "^",
+    }
+}
+
+fn qsymbol_str(symbol: QSymbol) -> &'static str {
+    match symbol {
+        QSymbol::Forall => "forall",
+        QSymbol::Exists => "exists",
+    }
+}
+
+/// The binding tightness of the five connectives that [`crate::parser`]
+/// now accepts bare (unparenthesized) chains of, from loosest (`1`,
+/// `<=>`/`===`) to tightest (`5`, `&`) — see the "precedence ladder"
+/// section of `crate::parser`'s module docs for the grammar this mirrors.
+/// `None` covers everything [`fmt_minimal`] always self-delimits: atoms,
+/// equality atoms, `~`, and the `Nand`/`Nor`/`Plus`/`Mult` binops, none of
+/// which this grammar defines a precedence for mixing bare with anything
+/// else.
+fn ladder_precedence(e: &Expr) -> Option<i8> {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, .. } => Some(1),
+        Expr::Binop { symbol: BSymbol::Implies, .. } => Some(2),
+        Expr::AssocBinop { symbol: ASymbol::Xor, .. } => Some(3),
+        Expr::AssocBinop { symbol: ASymbol::Or, .. } => Some(4),
+        Expr::AssocBinop { symbol: ASymbol::And, .. } => Some(5),
+        _ => None,
+    }
+}
+
+/// True for a quantifier, or a run of `~` wrapped around one. Both print
+/// with no closing delimiter — the quantifier's body just runs to the end
+/// of whatever [`fmt_minimal`] call printed it — so leaving one bare is
+/// only safe when it really is in `tail` position (see [`fmt_child`]):
+/// nothing else gets printed after it that its body would otherwise eat.
+fn is_quantifier_tailed(e: &Expr) -> bool {
+    match e {
+        Expr::Quantifier { .. } => true,
+        Expr::Unop { symbol: USymbol::Not, operand } => is_quantifier_tailed(operand),
+        _ => false,
+    }
+}
+
+/// Prints `e` as an operand of some enclosing connective: bare when that's
+/// unambiguous, wrapped in `(...)` otherwise. `parent_prec` is the
+/// tightness the caller requires of a bare child (see [`ladder_precedence`]
+/// — a child binding at or below that tightness gets parenthesized), and
+/// `tail` says whether `e` is the last thing [`fmt_minimal`] will print
+/// overall, which is the only place a bare quantifier (per
+/// [`is_quantifier_tailed`]) is safe to leave unwrapped.
+fn fmt_child(e: &Expr, f: &mut fmt::Formatter, parent_prec: i8, tail: bool) -> fmt::Result {
+    let needs_parens =
+        if is_quantifier_tailed(e) { !tail } else { matches!(ladder_precedence(e), Some(p) if p <= parent_prec) };
+    if needs_parens {
+        write!(f, "(")?;
+        fmt_minimal(e, f, true)?;
+        write!(f, ")")
+    } else {
+        fmt_minimal(e, f, tail)
+    }
+}
+
+/// The minimal-parens renderer behind [`fmt::Display`]'s ordinary `{}`.
+/// `tail` is threaded down from the root call (always `true` there) so
+/// [`fmt_child`] can tell a genuinely-last quantifier from one that would
+/// swallow a sibling — see [`is_quantifier_tailed`].
+fn fmt_minimal(e: &Expr, f: &mut fmt::Formatter, tail: bool) -> fmt::Result {
+    match e {
+        Expr::Contradiction => write!(f, "_|_"),
+        Expr::Tautology => write!(f, "T"),
+        Expr::Var { name } => write!(f, "{}", name),
+        Expr::Apply { func, args } if matches!(func.as_ref(), Expr::Var { name } if name == "=") && args.len() == 2 => {
+            write!(f, "(")?;
+            fmt_minimal(&args[0], f, true)?;
+            write!(f, " = ")?;
+            fmt_minimal(&args[1], f, true)?;
+            write!(f, ")")
+        }
+        Expr::Apply { func, args } => {
+            fmt_minimal(func, f, true)?;
+            write!(f, "(")?;
+            for (i, arg) in args.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_minimal(arg, f, true)?;
+            }
+            write!(f, ")")
+        }
+        // `~` binds tighter than every ladder connective (`5`, `&`'s own
+        // tightness, is high enough that all five wrap), so its operand
+        // only ever prints bare when it's an atom, an equality atom, a
+        // nested `~`, or a tail-position quantifier.
+        Expr::Unop { symbol: USymbol::Not, operand } => {
+            write!(f, "~")?;
+            fmt_child(operand, f, 5, tail)
+        }
+        // Right-associative: chaining another `->` off the right needs no
+        // parens (`a -> b -> c` already means `a -> (b -> c)`), but the
+        // left side always does, same as any other non-associative slot.
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => {
+            fmt_child(l, f, 2, false)?;
+            write!(f, " -> ")?;
+            fmt_child(r, f, 1, tail)
+        }
+        // `Nand`/`Nor`/`Plus`/`Mult` have no defined precedence relative to
+        // the ladder, so — same as before minimal-parens printing existed
+        // — they only ever appear as an explicit `(A op B)` pair, which
+        // [`Parser::parse_parenthesized`] only accepts with an equality
+        // atom (not a bare ladder chain) on either side; force parens
+        // around any operand that's a ladder connective in its own right
+        // (threshold `5`, the ladder's tightest level, wraps all of them)
+        // to keep this printer's output inside what that parser accepts.
+        Expr::Binop { symbol, l, r } => {
+            let op = bsymbol_str(*symbol);
+            write!(f, "(")?;
+            fmt_child(l, f, 5, true)?;
+            write!(f, " {} ", op)?;
+            fmt_child(r, f, 5, true)?;
+            write!(f, ")")
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let op = asymbol_str(*symbol);
+            let prec = ladder_precedence(e).expect("every ASymbol has a ladder precedence");
+            let last = exprs.len() - 1;
+            for (i, elem) in exprs.iter().enumerate() {
+                if i != 0 {
+                    write!(f, " {} ", op)?;
+                }
+                fmt_child(elem, f, prec, tail && i == last)?;
+            }
+            Ok(())
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let (label, mut inner) = quantifier_binder_label(*symbol, name, body);
+            let mut labels = vec![label];
+            let mut bound_names = vec![name.as_str()];
+            while let Expr::Quantifier { symbol: inner_symbol, name: inner_name, body: inner_body } = inner {
+                if inner_symbol != symbol || bound_names.contains(&inner_name.as_str()) {
+                    break;
+                }
+                let (label, next) = quantifier_binder_label(*inner_symbol, inner_name, inner_body);
+                labels.push(label);
+                bound_names.push(inner_name.as_str());
+                inner = next;
+            }
+            write!(f, "{} {}, ", qsymbol_str(*symbol), labels.join(" "))?;
+            fmt_minimal(inner, f, tail)
+        }
+    }
+}
+
+/// Recognizes the guard [`crate::parser`] desugars a `: SortName` binder
+/// annotation into — `Nat(x) -> phi` under a `forall x`, `Nat(x) & phi`
+/// under an `exists x` — and, when `body` has exactly that shape for `x`,
+/// returns the re-sugared `"x: Nat"` label and the guarded-away `phi`.
+/// Otherwise returns the plain name and `body` unchanged.
+fn quantifier_binder_label<'a>(symbol: QSymbol, name: &str, body: &'a Expr) -> (String, &'a Expr) {
+    fn sort_guard<'a>(name: &str, guard: &'a Expr) -> Option<&'a str> {
+        match guard {
+            Expr::Apply { func, args } => match (func.as_ref(), args.as_slice()) {
+                (Expr::Var { name: sort }, [Expr::Var { name: arg }]) if arg == name => Some(sort.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    let sugared = match (symbol, body) {
+        (QSymbol::Forall, Expr::Binop { symbol: BSymbol::Implies, l, r }) => sort_guard(name, l).map(|sort| (sort, r.as_ref())),
+        (QSymbol::Exists, Expr::AssocBinop { symbol: ASymbol::And, exprs }) => match exprs.as_slice() {
+            [guard, rest] => sort_guard(name, guard).map(|sort| (sort, rest)),
+            _ => None,
+        },
+        _ => None,
+    };
+    match sugared {
+        Some((sort, rest)) => (format!("{}: {}", name, sort), rest),
+        None => (name.to_owned(), body),
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = crate::parser::ParseError;
+
+    /// Parses `s` in the concrete syntax [`Display`](fmt::Display) produces,
+    /// via [`crate::parser::parse`]. Surrounding whitespace (including a
+    /// trailing newline) is ignored; anything left over after a complete
+    /// expression is a parse error.
+    fn from_str(s: &str) -> Result<Expr, Self::Err> {
+        crate::parser::parse(s.trim())
+    }
+}
+
+impl Expr {
+    /// Parses `s` as an [`Expr`]. Equivalent to `s.parse()`, spelled as an
+    /// associated function so callers don't need `FromStr` in scope.
+    pub fn parse(s: &str) -> Result<Expr, crate::parser::ParseError> {
+        s.parse()
+    }
+
+    /// Same string [`fmt::Display`]'s `{}` already prints — this crate's
+    /// minimal-parens output has only ever used the ASCII spellings
+    /// (`&`, `|`, `~`, `->`, `<=>`/`===`, `forall`, `exists`, `_|_`, `T`;
+    /// see `bsymbol_str`/`asymbol_str`/`qsymbol_str`) that [`crate::parser`]
+    /// accepts back, never the Unicode connectives (`∧`, `∀`, ...) that
+    /// show up only in doc comments and the Java GUI's rendering of the
+    /// same tree. This method exists so a caller who needs that ASCII
+    /// guarantee — e.g. writing a saved file out to a system that mangles
+    /// non-ASCII bytes — doesn't have to know that fact about `Display` to
+    /// rely on it.
+    pub fn to_ascii_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Pre-order traversal over every node of the tree, starting with `self`.
+    pub fn subexprs(&self) -> impl Iterator<Item = &Expr> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let e = stack.pop()?;
+            match e {
+                Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+                Expr::Apply { func, args } => {
+                    stack.push(func);
+                    for a in args.iter().rev() {
+                        stack.push(a);
+                    }
+                }
+                Expr::Unop { operand, .. } => stack.push(operand),
+                Expr::Binop { l, r, .. } => {
+                    stack.push(r);
+                    stack.push(l);
+                }
+                Expr::AssocBinop { exprs, .. } => {
+                    for e in exprs.iter().rev() {
+                        stack.push(e);
+                    }
+                }
+                Expr::Quantifier { body, .. } => stack.push(body),
+            }
+            Some(e)
+        })
+    }
+
+    /// Owned/consuming variant of [`Expr::subexprs`]. Children are cloned
+    /// onto the worklist so the parent node can still be yielded intact.
+    pub fn into_subexprs(self) -> impl Iterator<Item = Expr> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let e = stack.pop()?;
+            match &e {
+                Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+                Expr::Apply { func, args } => {
+                    stack.push((**func).clone());
+                    for a in args.iter().rev() {
+                        stack.push(a.clone());
+                    }
+                }
+                Expr::Unop { operand, .. } => stack.push((**operand).clone()),
+                Expr::Binop { l, r, .. } => {
+                    stack.push((**r).clone());
+                    stack.push((**l).clone());
+                }
+                Expr::AssocBinop { exprs, .. } => {
+                    for e in exprs.iter().rev() {
+                        stack.push(e.clone());
+                    }
+                }
+                Expr::Quantifier { body, .. } => stack.push((**body).clone()),
+            }
+            Some(e)
+        })
+    }
+}
+
+/// `true` iff `e` or some subexpression of it is `Contradiction`.
+pub fn contains_contradiction(e: &Expr) -> bool {
+    e.subexprs().any(|e| matches!(e, Expr::Contradiction))
+}
+
+/// The number of `Quantifier` nodes anywhere in `e`.
+pub fn count_quantifiers(e: &Expr) -> usize {
+    e.subexprs().filter(|e| matches!(e, Expr::Quantifier { .. })).count()
+}
+
+/// The total number of nodes in `e`, including `e` itself. Single pass, no
+/// intermediate allocations.
+pub fn expr_size(e: &Expr) -> usize {
+    1 + match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => 0,
+        Expr::Apply { func, args } => expr_size(func) + args.iter().map(expr_size).sum::<usize>(),
+        Expr::Unop { operand, .. } => expr_size(operand),
+        Expr::Binop { l, r, .. } => expr_size(l) + expr_size(r),
+        Expr::AssocBinop { exprs, .. } => exprs.iter().map(expr_size).sum(),
+        Expr::Quantifier { body, .. } => expr_size(body),
+    }
+}
+
+/// The length of the longest root-to-leaf path in `e`, counting `e` itself
+/// as depth 1. Single pass, no intermediate allocations.
+pub fn expr_depth(e: &Expr) -> usize {
+    1 + match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => 0,
+        Expr::Apply { func, args } => {
+            std::cmp::max(expr_depth(func), args.iter().map(expr_depth).max().unwrap_or(0))
+        }
+        Expr::Unop { operand, .. } => expr_depth(operand),
+        Expr::Binop { l, r, .. } => std::cmp::max(expr_depth(l), expr_depth(r)),
+        Expr::AssocBinop { exprs, .. } => exprs.iter().map(expr_depth).max().unwrap_or(0),
+        Expr::Quantifier { body, .. } => expr_depth(body),
+    }
+}
+
+/// Per-symbol occurrence counts produced by [`count_connectives`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectiveCounts {
+    pub not: usize,
+    pub implies: usize,
+    pub plus: usize,
+    pub mult: usize,
+    pub nand: usize,
+    pub nor: usize,
+    pub and: usize,
+    pub or: usize,
+    pub bicon: usize,
+    pub equiv: usize,
+    pub xor: usize,
+    pub forall: usize,
+    pub exists: usize,
+    pub vars: usize,
+    pub applies: usize,
+}
+
+/// Counts every connective and leaf kind occurring in `e`, in a single pass
+/// with no intermediate allocations. An n-ary `AssocBinop` (the flattened
+/// form of e.g. `And`) counts as `n - 1` connectives of its symbol, matching
+/// the count of an equivalent right-nested binary chain of the same symbol
+/// (e.g. `And[p, And[q, r]]` also counts as 2 `and`s), so the two
+/// representations of the same formula always agree.
+pub fn count_connectives(e: &Expr) -> ConnectiveCounts {
+    fn go(e: &Expr, counts: &mut ConnectiveCounts) {
+        match e {
+            Expr::Contradiction | Expr::Tautology => {}
+            Expr::Var { .. } => counts.vars += 1,
+            Expr::Apply { func, args } => {
+                counts.applies += 1;
+                go(func, counts);
+                for a in args {
+                    go(a, counts);
+                }
+            }
+            Expr::Unop { symbol, operand } => {
+                match symbol {
+                    USymbol::Not => counts.not += 1,
+                }
+                go(operand, counts);
+            }
+            Expr::Binop { symbol, l, r } => {
+                match symbol {
+                    BSymbol::Implies => counts.implies += 1,
+                    BSymbol::Plus => counts.plus += 1,
+                    BSymbol::Mult => counts.mult += 1,
+                    BSymbol::Nand => counts.nand += 1,
+                    BSymbol::Nor => counts.nor += 1,
+                }
+                go(l, counts);
+                go(r, counts);
+            }
+            Expr::AssocBinop { symbol, exprs } => {
+                let contribution = exprs.len().saturating_sub(1);
+                match symbol {
+                    ASymbol::And => counts.and += contribution,
+                    ASymbol::Or => counts.or += contribution,
+                    ASymbol::Bicon => counts.bicon += contribution,
+                    ASymbol::Equiv => counts.equiv += contribution,
+                    ASymbol::Xor => counts.xor += contribution,
+                }
+                for x in exprs {
+                    go(x, counts);
+                }
+            }
+            Expr::Quantifier { symbol, body, .. } => {
+                match symbol {
+                    QSymbol::Forall => counts.forall += 1,
+                    QSymbol::Exists => counts.exists += 1,
+                }
+                go(body, counts);
+            }
+        }
+    }
+    let mut counts = ConnectiveCounts::default();
+    go(e, &mut counts);
+    counts
+}
+
+/// Counter backing [`gensym`], used to manufacture variable names that cannot
+/// collide with anything a user has written.
+static GENSYM_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Generate a fresh name with the given prefix.
+pub fn gensym(prefix: &str) -> String {
+    let n = GENSYM_COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    format!("{}{}", prefix, n)
+}
+
+/// Collect the set of variable names that occur free (i.e. not bound by an
+/// enclosing `Quantifier`) in `e`.
+pub fn freevars(e: &Expr) -> std::collections::HashSet<String> {
+    fn go(e: &Expr, bound: &mut Vec<String>, out: &mut std::collections::HashSet<String>) {
+        match e {
+            Expr::Contradiction | Expr::Tautology => {}
+            Expr::Var { name } => {
+                if !bound.contains(name) {
+                    out.insert(name.clone());
+                }
+            }
+            Expr::Apply { func, args } => {
+                go(func, bound, out);
+                for a in args {
+                    go(a, bound, out);
+                }
+            }
+            Expr::Unop { operand, .. } => go(operand, bound, out),
+            Expr::Binop { l, r, .. } => {
+                go(l, bound, out);
+                go(r, bound, out);
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for e in exprs {
+                    go(e, bound, out);
+                }
+            }
+            Expr::Quantifier { name, body, .. } => {
+                bound.push(name.clone());
+                go(body, bound, out);
+                bound.pop();
+            }
+        }
+    }
+    let mut out = std::collections::HashSet::new();
+    go(e, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Collect the set of variable names bound by some `Quantifier` anywhere in
+/// `e`, including names that are shadowed by an inner binder of the same
+/// name. This can overlap with [`freevars`]: in `x & forall x, x`, `x` is
+/// both free (the left conjunct) and bound (the quantifier).
+pub fn boundvars(e: &Expr) -> std::collections::HashSet<String> {
+    fn go(e: &Expr, out: &mut std::collections::HashSet<String>) {
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+            Expr::Apply { func, args } => {
+                go(func, out);
+                for a in args {
+                    go(a, out);
+                }
+            }
+            Expr::Unop { operand, .. } => go(operand, out),
+            Expr::Binop { l, r, .. } => {
+                go(l, out);
+                go(r, out);
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for e in exprs {
+                    go(e, out);
+                }
+            }
+            Expr::Quantifier { name, body, .. } => {
+                out.insert(name.clone());
+                go(body, out);
+            }
+        }
+    }
+    let mut out = std::collections::HashSet::new();
+    go(e, &mut out);
+    out
+}
+
+/// `true` iff `name` occurs free anywhere in `e`. Cheaper than building a
+/// full [`freevars`] set when all that's needed is a yes/no answer, since it
+/// can stop as soon as one occurrence is found.
+pub fn contains_free(e: &Expr, name: &str) -> bool {
+    fn go(e: &Expr, name: &str, bound: &mut Vec<String>) -> bool {
+        match e {
+            Expr::Contradiction | Expr::Tautology => false,
+            Expr::Var { name: n } => n == name && !bound.iter().any(|b| b == name),
+            Expr::Apply { func, args } => go(func, name, bound) || args.iter().any(|a| go(a, name, bound)),
+            Expr::Unop { operand, .. } => go(operand, name, bound),
+            Expr::Binop { l, r, .. } => go(l, name, bound) || go(r, name, bound),
+            Expr::AssocBinop { exprs, .. } => exprs.iter().any(|e| go(e, name, bound)),
+            Expr::Quantifier { name: bound_name, body, .. } => {
+                bound.push(bound_name.clone());
+                let result = go(body, name, bound);
+                bound.pop();
+                result
+            }
+        }
+    }
+    go(e, name, &mut Vec::new())
+}
+
+/// The [`ExprPath`] of every free occurrence of `name` in `e`, in pre-order,
+/// correctly excluding occurrences shadowed by an enclosing `Quantifier`
+/// that rebinds `name`.
+pub fn free_occurrence_paths(e: &Expr, name: &str) -> Vec<ExprPath> {
+    fn go(e: &Expr, name: &str, bound: &mut Vec<String>, prefix: &mut Vec<usize>, out: &mut Vec<ExprPath>) {
+        match e {
+            Expr::Contradiction | Expr::Tautology => {}
+            Expr::Var { name: n } => {
+                if n == name && !bound.iter().any(|b| b == name) {
+                    out.push(ExprPath(prefix.clone()));
+                }
+            }
+            Expr::Apply { func, args } => {
+                prefix.push(0);
+                go(func, name, bound, prefix, out);
+                prefix.pop();
+                for (i, a) in args.iter().enumerate() {
+                    prefix.push(i + 1);
+                    go(a, name, bound, prefix, out);
+                    prefix.pop();
+                }
+            }
+            Expr::Unop { operand, .. } => {
+                prefix.push(0);
+                go(operand, name, bound, prefix, out);
+                prefix.pop();
+            }
+            Expr::Binop { l, r, .. } => {
+                prefix.push(0);
+                go(l, name, bound, prefix, out);
+                prefix.pop();
+                prefix.push(1);
+                go(r, name, bound, prefix, out);
+                prefix.pop();
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for (i, e) in exprs.iter().enumerate() {
+                    prefix.push(i);
+                    go(e, name, bound, prefix, out);
+                    prefix.pop();
+                }
+            }
+            Expr::Quantifier { name: bound_name, body, .. } => {
+                bound.push(bound_name.clone());
+                prefix.push(0);
+                go(body, name, bound, prefix, out);
+                prefix.pop();
+                bound.pop();
+            }
+        }
+    }
+    let mut out = Vec::new();
+    go(e, name, &mut Vec::new(), &mut Vec::new(), &mut out);
+    out
+}
+
+/// Why [`check_substitutable`] rejected a substitution: `path` is the first
+/// free occurrence of the substituted variable that lies under a capturing
+/// binder, and `binder` is that binder's name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureError {
+    pub path: ExprPath,
+    pub binder: String,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at path {:?}: substituting here would capture {} under its binder", self.path.0, self.binder)
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// Checks whether `term` is free for `var` in `e` — i.e. substituting `term`
+/// for every free occurrence of `var` in `e` would not accidentally capture
+/// a variable of `term` under one of `e`'s binders. On failure, reports the
+/// path to the first free occurrence of `var` that lies under such a
+/// capturing binder, and that binder's name. See [`is_substitutable`] for a
+/// plain `bool`, and [`would_capture`] for the full set of capturing binders
+/// rather than just the first.
+pub fn check_substitutable(e: &Expr, var: &str, term: &Expr) -> Result<(), CaptureError> {
+    let term_freevars = freevars(term);
+
+    fn go(e: &Expr, var: &str, term_freevars: &std::collections::HashSet<String>, binders: &mut Vec<String>, path: &mut Vec<usize>) -> Result<(), CaptureError> {
+        match e {
+            Expr::Contradiction | Expr::Tautology => Ok(()),
+            Expr::Var { name } => {
+                if name == var && !binders.iter().any(|b| b == var) {
+                    if let Some(capturing) = binders.iter().rev().find(|b| term_freevars.contains(*b)) {
+                        return Err(CaptureError { path: ExprPath(path.clone()), binder: capturing.clone() });
+                    }
+                }
+                Ok(())
+            }
+            Expr::Apply { func, args } => {
+                path.push(0);
+                let result = go(func, var, term_freevars, binders, path);
+                path.pop();
+                result?;
+                for (i, a) in args.iter().enumerate() {
+                    path.push(i + 1);
+                    let result = go(a, var, term_freevars, binders, path);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            Expr::Unop { operand, .. } => {
+                path.push(0);
+                let result = go(operand, var, term_freevars, binders, path);
+                path.pop();
+                result
+            }
+            Expr::Binop { l, r, .. } => {
+                path.push(0);
+                let result = go(l, var, term_freevars, binders, path);
+                path.pop();
+                result?;
+                path.push(1);
+                let result = go(r, var, term_freevars, binders, path);
+                path.pop();
+                result
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for (i, x) in exprs.iter().enumerate() {
+                    path.push(i);
+                    let result = go(x, var, term_freevars, binders, path);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            Expr::Quantifier { name: bound_name, body, .. } => {
+                binders.push(bound_name.clone());
+                path.push(0);
+                let result = go(body, var, term_freevars, binders, path);
+                path.pop();
+                binders.pop();
+                result
+            }
+        }
+    }
+
+    go(e, var, &term_freevars, &mut Vec::new(), &mut Vec::new())
+}
+
+/// `true` iff no free occurrence of `var` in `e` lies under a quantifier
+/// binding a variable that occurs free in `term` — i.e. substituting `term`
+/// for `var` in `e` is capture-free. This is the classic "t is free for x in
+/// φ" condition quantifier-elimination rules must check before accepting a
+/// student's instantiation, since [`subst`] itself silently renames binders
+/// instead of rejecting the substitution.
+pub fn is_substitutable(e: &Expr, var: &str, term: &Expr) -> bool {
+    check_substitutable(e, var, term).is_ok()
+}
+
+/// Whether a quantifier binding `bound_name` over `body` would capture a
+/// variable if a replacement whose free variables are `replacement_freevars`
+/// were substituted for `name` throughout `body`: `bound_name` isn't `name` itself, occurs free in the
+/// replacement, and `name` actually occurs free somewhere in `body`. Shared
+/// by [`subst`] (to decide whether a binder needs renaming) and
+/// [`would_capture`] (to report which binders would be captured), so the two
+/// can never disagree about what counts as capture.
+fn binder_would_capture(bound_name: &str, body: &Expr, name: &str, replacement_freevars: &std::collections::HashSet<String>) -> bool {
+    bound_name != name && replacement_freevars.contains(bound_name) && contains_free(body, name)
+}
+
+/// The set of binder names in `e` that lie above a free occurrence of `var`
+/// and also occur free in `term` — i.e. every binder that substituting
+/// `term` for `var` in `e` would capture something under. Empty exactly
+/// when [`is_substitutable`] is `true`. Meant for error messages like "your
+/// term mentions y, which would be captured by the ∀y on line 3", where
+/// naming every offending binder (not just the first) is useful.
+pub fn would_capture(e: &Expr, var: &str, term: &Expr) -> std::collections::HashSet<String> {
+    let term_freevars = freevars(term);
+
+    fn go(e: &Expr, var: &str, term_freevars: &std::collections::HashSet<String>, capturing: &mut std::collections::HashSet<String>) {
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+            Expr::Apply { func, args } => {
+                go(func, var, term_freevars, capturing);
+                for a in args {
+                    go(a, var, term_freevars, capturing);
+                }
+            }
+            Expr::Unop { operand, .. } => go(operand, var, term_freevars, capturing),
+            Expr::Binop { l, r, .. } => {
+                go(l, var, term_freevars, capturing);
+                go(r, var, term_freevars, capturing);
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for x in exprs {
+                    go(x, var, term_freevars, capturing);
+                }
+            }
+            Expr::Quantifier { name: bound_name, body, .. } => {
+                if binder_would_capture(bound_name, body, var, term_freevars) {
+                    capturing.insert(bound_name.clone());
+                }
+                go(body, var, term_freevars, capturing);
+            }
+        }
+    }
+
+    let mut capturing = std::collections::HashSet::new();
+    go(e, var, &term_freevars, &mut capturing);
+    capturing
+}
+
+/// Substitute `replacement` for every free occurrence of `name`, renaming
+/// bound variables with [`gensym`] whenever capture would otherwise occur.
+/// A numeral literal (per [`is_numeral`]) is never substituted into, even
+/// if `name` happens to be a digit string — a numeral isn't a variable
+/// that anything could bind.
+pub fn subst(name: &str, replacement: &Expr, e: Expr) -> Expr {
+    match e {
+        Expr::Contradiction | Expr::Tautology => e,
+        Expr::Var { name: ref n } => {
+            if n == name && !is_numeral(&e) {
+                replacement.clone()
+            } else {
+                e
+            }
+        }
+        Expr::Apply { func, args } => Expr::Apply {
+            func: Box::new(subst(name, replacement, *func)),
+            args: args.into_iter().map(|a| subst(name, replacement, a)).collect(),
+        },
+        Expr::Unop { symbol, operand } => Expr::Unop {
+            symbol,
+            operand: Box::new(subst(name, replacement, *operand)),
+        },
+        Expr::Binop { symbol, l, r } => Expr::Binop {
+            symbol,
+            l: Box::new(subst(name, replacement, *l)),
+            r: Box::new(subst(name, replacement, *r)),
+        },
+        Expr::AssocBinop { symbol, exprs } => Expr::AssocBinop {
+            symbol,
+            exprs: exprs.into_iter().map(|e| subst(name, replacement, e)).collect(),
+        },
+        Expr::Quantifier { symbol, name: bound_name, body } => {
+            if bound_name == name {
+                Expr::Quantifier { symbol, name: bound_name, body }
+            } else if binder_would_capture(&bound_name, &body, name, &freevars(replacement)) {
+                let fresh = gensym(&format!("{}_", bound_name));
+                let renamed_body = subst(&bound_name, &Expr::var(&fresh), *body);
+                Expr::Quantifier {
+                    symbol,
+                    name: fresh,
+                    body: Box::new(subst(name, replacement, renamed_body)),
+                }
+            } else {
+                Expr::Quantifier {
+                    symbol,
+                    name: bound_name,
+                    body: Box::new(subst(name, replacement, *body)),
+                }
+            }
+        }
+    }
+}
+
+/// Simultaneously substitute every binding in `map`, in a single
+/// capture-avoiding pass. Unlike folding repeated [`subst`] calls, this
+/// performs the replacements "all at once": `{x -> y, y -> x}` swaps the two
+/// variables rather than collapsing them into one.
+pub fn subst_map(e: &Expr, map: &HashMap<String, Expr>) -> Expr {
+    if map.is_empty() {
+        return e.clone();
+    }
+    match e {
+        Expr::Contradiction | Expr::Tautology => e.clone(),
+        Expr::Var { name } => map.get(name).cloned().unwrap_or_else(|| e.clone()),
+        Expr::Apply { func, args } => {
+            Expr::Apply { func: Box::new(subst_map(func, map)), args: args.iter().map(|a| subst_map(a, map)).collect() }
+        }
+        Expr::Unop { symbol, operand } => Expr::Unop { symbol: *symbol, operand: Box::new(subst_map(operand, map)) },
+        Expr::Binop { symbol, l, r } => {
+            Expr::Binop { symbol: *symbol, l: Box::new(subst_map(l, map)), r: Box::new(subst_map(r, map)) }
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            Expr::AssocBinop { symbol: *symbol, exprs: exprs.iter().map(|e| subst_map(e, map)).collect() }
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            // The binder shadows `name` in `map`, and only the replacements
+            // that are actually still in play (i.e. not shadowed) can cause
+            // capture.
+            let incoming_freevars: std::collections::HashSet<String> =
+                map.iter().filter(|(k, _)| *k != name).flat_map(|(_, v)| freevars(v)).collect();
+            if incoming_freevars.contains(name) {
+                let fresh = gensym(&format!("{}_", name));
+                let renamed_body = subst(name, &Expr::var(&fresh), (**body).clone());
+                let mut inner_map = map.clone();
+                inner_map.remove(name);
+                Expr::Quantifier { symbol: *symbol, name: fresh, body: Box::new(subst_map(&renamed_body, &inner_map)) }
+            } else {
+                let mut inner_map = map.clone();
+                inner_map.remove(name);
+                Expr::Quantifier { symbol: *symbol, name: name.clone(), body: Box::new(subst_map(body, &inner_map)) }
+            }
+        }
+    }
+}
+
+/// The result of [`match_context`]: whether `instance` really is `body` with
+/// `term` substituted for `bound` at some set of positions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchContextResult {
+    /// `instance` is exactly `body` with `term` standing in for `bound` at
+    /// `positions` (every other part of `body` untouched). `positions` is
+    /// empty exactly when `bound` does not occur free in `body` at all, in
+    /// which case `instance` must equal `body` verbatim.
+    Matched { positions: Vec<ExprPath> },
+    /// `instance` is not obtainable from `body` this way: substituting
+    /// `term` for every free occurrence of `bound` does not produce
+    /// `instance`.
+    NoMatch,
+}
+
+/// Checks the second-order matching problem behind rules like ∀-elimination
+/// ("from `forall bound, body` infer `instance`, where `instance` is `body`
+/// with `term` in place of `bound`"): whether `instance` equals `body` with
+/// `term` substituted for every free occurrence of `bound`.
+///
+/// `pattern_var` names the higher-order placeholder this check stands in
+/// for (e.g. `phi` in a rule schema `Apply{func: Var("phi"), args:
+/// [Var("x")]}`) and is carried through only for error-reporting by the
+/// caller; the actual context is always exactly "`body` with `bound`
+/// substituted", recovered by brute-force comparison rather than searched
+/// for, which sidesteps the classic pitfall of instead scanning `instance`
+/// for occurrences of `term` and guessing which ones came from `bound`: that
+/// approach is fooled the moment `term` already occurred in `body`
+/// independently of `bound`. Returns [`MatchContextResult::Matched`] with
+/// `bound`'s occurrence positions in `body` (computed via
+/// [`free_occurrence_paths`]) on success, even when there are zero such
+/// positions (a vacuous context, where `instance` must equal `body`
+/// unchanged regardless of `term`).
+pub fn match_context(_pattern_var: &str, bound: &str, body: &Expr, instance: &Expr, term: &Expr) -> MatchContextResult {
+    let positions = free_occurrence_paths(body, bound);
+    if subst(bound, term, body.clone()) == *instance {
+        MatchContextResult::Matched { positions }
+    } else {
+        MatchContextResult::NoMatch
+    }
+}
+
+/// Why [`signature_of`] could not assign a single arity to every symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArityConflict {
+    /// `name` was applied with `first` arguments somewhere and `second`
+    /// arguments somewhere else.
+    ArityMismatch { name: String, first: usize, second: usize },
+    /// `name` was used as a bare `Var` somewhere and applied with `arity`
+    /// arguments somewhere else.
+    AppliedAndBare { name: String, arity: usize },
+}
+
+impl fmt::Display for ArityConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArityConflict::ArityMismatch { name, first, second } => {
+                write!(f, "`{}` is applied with {} argument(s) in one place and {} in another", name, first, second)
+            }
+            ArityConflict::AppliedAndBare { name, arity } => {
+                write!(f, "`{}` is used as a bare variable and applied with {} argument(s) elsewhere", name, arity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArityConflict {}
+
+fn collect_signature(
+    e: &Expr,
+    sigs: &mut HashMap<String, usize>,
+    bare: &mut std::collections::HashSet<String>,
+) -> Result<(), ArityConflict> {
+    match e {
+        Expr::Contradiction | Expr::Tautology => Ok(()),
+        Expr::Var { name } => {
+            bare.insert(name.clone());
+            Ok(())
+        }
+        Expr::Apply { func, args } => {
+            if let Expr::Var { name } = func.as_ref() {
+                let arity = args.len();
+                match sigs.get(name) {
+                    Some(&existing) if existing != arity => {
+                        return Err(ArityConflict::ArityMismatch { name: name.clone(), first: existing, second: arity });
+                    }
+                    Some(_) => {}
+                    None => {
+                        sigs.insert(name.clone(), arity);
+                    }
+                }
+            } else {
+                collect_signature(func, sigs, bare)?;
+            }
+            for a in args {
+                collect_signature(a, sigs, bare)?;
+            }
+            Ok(())
+        }
+        Expr::Unop { operand, .. } => collect_signature(operand, sigs, bare),
+        Expr::Binop { l, r, .. } => {
+            collect_signature(l, sigs, bare)?;
+            collect_signature(r, sigs, bare)
+        }
+        Expr::AssocBinop { exprs, .. } => {
+            for x in exprs {
+                collect_signature(x, sigs, bare)?;
+            }
+            Ok(())
+        }
+        Expr::Quantifier { body, .. } => collect_signature(body, sigs, bare),
+    }
+}
+
+/// Walks `e` collecting the arity of every `Apply` whose head is a `Var`,
+/// returning an [`ArityConflict`] naming the symbol and the two conflicting
+/// arities if the same symbol is applied with different argument counts in
+/// different places. A symbol used both as a bare `Var` and as an `Apply`
+/// head is also reported as a conflict; use [`signature_of_allowing_bare_use`]
+/// if that should be allowed instead (e.g. while checking only the predicate
+/// symbols of a partially-elaborated expression).
+pub fn signature_of(e: &Expr) -> Result<HashMap<String, usize>, ArityConflict> {
+    signature_of_with(e, true)
+}
+
+/// Like [`signature_of`], but a symbol that is both applied and used as a
+/// bare `Var` is not treated as a conflict.
+pub fn signature_of_allowing_bare_use(e: &Expr) -> Result<HashMap<String, usize>, ArityConflict> {
+    signature_of_with(e, false)
+}
+
+fn signature_of_with(e: &Expr, bare_use_is_conflict: bool) -> Result<HashMap<String, usize>, ArityConflict> {
+    let mut sigs = HashMap::new();
+    let mut bare = std::collections::HashSet::new();
+    collect_signature(e, &mut sigs, &mut bare)?;
+    if bare_use_is_conflict {
+        for name in &bare {
+            if let Some(&arity) = sigs.get(name) {
+                return Err(ArityConflict::AppliedAndBare { name: name.clone(), arity });
+            }
+        }
+    }
+    Ok(sigs)
+}
+
+/// A path into an `Expr` tree: `Apply`'s `func` is child `0` and its `args`
+/// are `1..`, `Unop`'s operand and `Quantifier`'s body are child `0`,
+/// `Binop`'s `l`/`r` are children `0`/`1`, and `AssocBinop`'s elements are
+/// children `0..`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct ExprPath(pub Vec<usize>);
+
+/// Why [`get_at`]/[`subst_at`] failed to resolve an [`ExprPath`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The index at position `at` in the path was too large for the node
+    /// found there, which had `len` children.
+    OutOfRange { path: ExprPath, at: usize, len: usize },
+    /// The node found at position `at` in the path has no children at all
+    /// (e.g. `Var`, `Contradiction`), so the path cannot continue.
+    NotIndexable { path: ExprPath, at: usize },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::OutOfRange { path, at, len } => {
+                write!(f, "path {:?} out of range at index {} (node has {} children)", path.0, at, len)
+            }
+            PathError::NotIndexable { path, at } => {
+                write!(f, "path {:?} descends into a leaf at index {}", path.0, at)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// The direct children of `e`, in the order addressed by [`ExprPath`].
+fn children(e: &Expr) -> Vec<&Expr> {
+    match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => vec![],
+        Expr::Apply { func, args } => {
+            let mut v = vec![func.as_ref()];
+            v.extend(args.iter());
+            v
+        }
+        Expr::Unop { operand, .. } => vec![operand],
+        Expr::Binop { l, r, .. } => vec![l, r],
+        Expr::AssocBinop { exprs, .. } => exprs.iter().collect(),
+        Expr::Quantifier { body, .. } => vec![body],
+    }
+}
+
+/// Look up the subexpression at `path`, or `None` if the path runs off the
+/// tree (out of range or into a leaf).
+pub fn get_at<'a>(e: &'a Expr, path: &ExprPath) -> Option<&'a Expr> {
+    let mut cur = e;
+    for &i in &path.0 {
+        cur = *children(cur).get(i)?;
+    }
+    Some(cur)
+}
+
+/// Replace the subexpression at `path` with `with`, rebuilding every
+/// ancestor along the way. Returns a [`PathError`] (never panics) if `path`
+/// does not resolve inside `e`.
+pub fn subst_at(e: &Expr, path: &ExprPath, with: Expr) -> Result<Expr, PathError> {
+    fn go(e: &Expr, remaining: &[usize], full_path: &ExprPath, depth: usize, with: &Expr) -> Result<Expr, PathError> {
+        let i = match remaining.first() {
+            None => return Ok(with.clone()),
+            Some(&i) => i,
+        };
+        let rest = &remaining[1..];
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {
+                Err(PathError::NotIndexable { path: full_path.clone(), at: depth })
+            }
+            Expr::Apply { func, args } => {
+                if i == 0 {
+                    Ok(Expr::Apply { func: Box::new(go(func, rest, full_path, depth + 1, with)?), args: args.clone() })
+                } else if let Some(a) = args.get(i - 1) {
+                    let mut new_args = args.clone();
+                    new_args[i - 1] = go(a, rest, full_path, depth + 1, with)?;
+                    Ok(Expr::Apply { func: func.clone(), args: new_args })
+                } else {
+                    Err(PathError::OutOfRange { path: full_path.clone(), at: depth, len: args.len() + 1 })
+                }
+            }
+            Expr::Unop { symbol, operand } => {
+                if i == 0 {
+                    Ok(Expr::Unop { symbol: *symbol, operand: Box::new(go(operand, rest, full_path, depth + 1, with)?) })
+                } else {
+                    Err(PathError::OutOfRange { path: full_path.clone(), at: depth, len: 1 })
+                }
+            }
+            Expr::Binop { symbol, l, r } => match i {
+                0 => Ok(Expr::Binop { symbol: *symbol, l: Box::new(go(l, rest, full_path, depth + 1, with)?), r: r.clone() }),
+                1 => Ok(Expr::Binop { symbol: *symbol, l: l.clone(), r: Box::new(go(r, rest, full_path, depth + 1, with)?) }),
+                _ => Err(PathError::OutOfRange { path: full_path.clone(), at: depth, len: 2 }),
+            },
+            Expr::AssocBinop { symbol, exprs } => {
+                if let Some(child) = exprs.get(i) {
+                    let mut new_exprs = exprs.clone();
+                    new_exprs[i] = go(child, rest, full_path, depth + 1, with)?;
+                    Ok(Expr::AssocBinop { symbol: *symbol, exprs: new_exprs })
+                } else {
+                    Err(PathError::OutOfRange { path: full_path.clone(), at: depth, len: exprs.len() })
+                }
+            }
+            Expr::Quantifier { symbol, name, body } => {
+                if i == 0 {
+                    Ok(Expr::Quantifier {
+                        symbol: *symbol,
+                        name: name.clone(),
+                        body: Box::new(go(body, rest, full_path, depth + 1, with)?),
+                    })
+                } else {
+                    Err(PathError::OutOfRange { path: full_path.clone(), at: depth, len: 1 })
+                }
+            }
+        }
+    }
+    go(e, &path.0, path, 0, &with)
+}
+
+/// Why [`check_well_formed`] rejected an expression: `path` names the
+/// offending node and `reason` explains why it cannot appear there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WellFormedError {
+    pub path: ExprPath,
+    pub reason: String,
+}
+
+impl fmt::Display for WellFormedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at path {:?}: {}", self.path.0, self.reason)
+    }
+}
+
+impl std::error::Error for WellFormedError {}
+
+/// Whether a node is expected to denote a formula (something with a truth
+/// value, like `And`/`Or`/quantifiers) or a term (something denoting an
+/// object, like a function application or an arithmetic expression).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+    Formula,
+    Term,
+}
+
+/// Checks that `e` is well-formed first-order logic: `Apply` heads must be
+/// `Var`s, arguments to an `Apply` must be terms, `Plus`/`Mult` operands
+/// must be terms, and the operands of logical connectives and quantifier
+/// bodies must be formulas. `e` itself is checked as a formula, since that
+/// is the position premises and conclusions are parsed into.
+pub fn check_well_formed(e: &Expr) -> Result<(), WellFormedError> {
+    fn err(path: &[usize], reason: impl Into<String>) -> WellFormedError {
+        WellFormedError { path: ExprPath(path.to_vec()), reason: reason.into() }
+    }
+
+    fn descend(e: &Expr, pos: Position, index: usize, path: &mut Vec<usize>) -> Result<(), WellFormedError> {
+        path.push(index);
+        let result = go(e, pos, path);
+        path.pop();
+        result
+    }
+
+    fn check_apply(func: &Expr, args: &[Expr], path: &mut Vec<usize>) -> Result<(), WellFormedError> {
+        if !matches!(func, Expr::Var { .. }) {
+            path.push(0);
+            let result = Err(err(path, "the head of an Apply must be a variable naming a function or predicate"));
+            path.pop();
+            return result;
+        }
+        for (i, a) in args.iter().enumerate() {
+            descend(a, Position::Term, i + 1, path)?;
+        }
+        Ok(())
+    }
+
+    fn go(e: &Expr, pos: Position, path: &mut Vec<usize>) -> Result<(), WellFormedError> {
+        match (pos, e) {
+            (Position::Formula, Expr::Contradiction) | (Position::Formula, Expr::Tautology) => Ok(()),
+            (Position::Formula, Expr::Var { .. }) => Ok(()),
+            (Position::Formula, Expr::Apply { func, args }) => check_apply(func, args, path),
+            (Position::Formula, Expr::Unop { operand, .. }) => descend(operand, Position::Formula, 0, path),
+            (Position::Formula, Expr::Binop { symbol: BSymbol::Implies | BSymbol::Nand | BSymbol::Nor, l, r }) => {
+                descend(l, Position::Formula, 0, path)?;
+                descend(r, Position::Formula, 1, path)
+            }
+            (Position::Formula, Expr::Binop { symbol, .. }) => {
+                Err(err(path, format!("`{:?}` is an arithmetic operator and cannot appear where a formula is expected", symbol)))
+            }
+            (Position::Formula, Expr::AssocBinop { exprs, .. }) => {
+                for (i, x) in exprs.iter().enumerate() {
+                    descend(x, Position::Formula, i, path)?;
+                }
+                Ok(())
+            }
+            (Position::Formula, Expr::Quantifier { body, .. }) => descend(body, Position::Formula, 0, path),
+
+            (Position::Term, Expr::Contradiction) | (Position::Term, Expr::Tautology) => {
+                Err(err(path, "a contradiction/tautology cannot appear where a term is expected"))
+            }
+            (Position::Term, Expr::Var { .. }) => Ok(()),
+            (Position::Term, Expr::Apply { func, args }) => check_apply(func, args, path),
+            (Position::Term, Expr::Unop { .. }) => {
+                Err(err(path, "a logical connective cannot appear where a term is expected"))
+            }
+            (Position::Term, Expr::Binop { symbol: BSymbol::Implies | BSymbol::Nand | BSymbol::Nor, .. }) => {
+                Err(err(path, "a logical connective cannot appear where a term is expected"))
+            }
+            (Position::Term, Expr::Binop { l, r, .. }) => {
+                descend(l, Position::Term, 0, path)?;
+                descend(r, Position::Term, 1, path)
+            }
+            (Position::Term, Expr::AssocBinop { .. }) => {
+                Err(err(path, "a logical connective cannot appear where a term is expected"))
+            }
+            (Position::Term, Expr::Quantifier { .. }) => {
+                Err(err(path, "a quantifier binds a formula and cannot appear where a term is expected"))
+            }
+        }
+    }
+
+    go(e, Position::Formula, &mut Vec::new())
+}
+
+/// Why an [`ExprZipper`] navigation failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZipperError {
+    /// `up()` was called with no enclosing node to return to.
+    AtRoot,
+    /// `down()`/`left()`/`right()` asked for a child or sibling that does
+    /// not exist at the current focus.
+    NoSuchChild,
+}
+
+impl fmt::Display for ZipperError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ZipperError::AtRoot => write!(f, "already at the root of the zipper"),
+            ZipperError::NoSuchChild => write!(f, "no such child/sibling at the current focus"),
+        }
+    }
+}
+
+impl std::error::Error for ZipperError {}
+
+/// One step of context remembered by an [`ExprZipper`]: everything needed
+/// to rebuild the parent node once its missing child (the current focus) is
+/// supplied.
+#[derive(Clone, Debug)]
+enum Crumb {
+    ApplyFunc { args: Vec<Expr> },
+    ApplyArg { func: Expr, before: Vec<Expr>, after: Vec<Expr> },
+    Unop { symbol: USymbol },
+    BinopL { symbol: BSymbol, r: Expr },
+    BinopR { symbol: BSymbol, l: Expr },
+    AssocBinop { symbol: ASymbol, before: Vec<Expr>, after: Vec<Expr> },
+    Quantifier { symbol: QSymbol, name: String },
+}
+
+/// A cursor into an `Expr` tree that supports moving down into children,
+/// back up to parents, and sideways among `Apply`/`AssocBinop` siblings,
+/// editing the focused node in place without rebuilding the whole tree
+/// until [`ExprZipper::rebuild`] is called.
+#[derive(Clone, Debug)]
+pub struct ExprZipper {
+    focus: Expr,
+    crumbs: Vec<Crumb>,
+}
+
+impl ExprZipper {
+    pub fn new(e: Expr) -> ExprZipper {
+        ExprZipper { focus: e, crumbs: Vec::new() }
+    }
+
+    pub fn focus(&self) -> &Expr {
+        &self.focus
+    }
+
+    pub fn replace(&mut self, e: Expr) {
+        self.focus = e;
+    }
+
+    pub fn down(mut self, child_index: usize) -> Result<ExprZipper, ZipperError> {
+        let (new_focus, crumb) = match self.focus {
+            Expr::Apply { func, mut args } => {
+                if child_index == 0 {
+                    (*func, Crumb::ApplyFunc { args })
+                } else {
+                    let idx = child_index - 1;
+                    if idx >= args.len() {
+                        return Err(ZipperError::NoSuchChild);
+                    }
+                    let after = args.split_off(idx + 1);
+                    let child = args.pop().unwrap();
+                    (child, Crumb::ApplyArg { func: *func, before: args, after })
+                }
+            }
+            Expr::Unop { symbol, operand } => {
+                if child_index != 0 {
+                    return Err(ZipperError::NoSuchChild);
+                }
+                (*operand, Crumb::Unop { symbol })
+            }
+            Expr::Binop { symbol, l, r } => match child_index {
+                0 => (*l, Crumb::BinopL { symbol, r: *r }),
+                1 => (*r, Crumb::BinopR { symbol, l: *l }),
+                _ => return Err(ZipperError::NoSuchChild),
+            },
+            Expr::AssocBinop { symbol, mut exprs } => {
+                if child_index >= exprs.len() {
+                    return Err(ZipperError::NoSuchChild);
+                }
+                let after = exprs.split_off(child_index + 1);
+                let child = exprs.pop().unwrap();
+                (child, Crumb::AssocBinop { symbol, before: exprs, after })
+            }
+            Expr::Quantifier { symbol, name, body } => {
+                if child_index != 0 {
+                    return Err(ZipperError::NoSuchChild);
+                }
+                (*body, Crumb::Quantifier { symbol, name })
+            }
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => return Err(ZipperError::NoSuchChild),
+        };
+        self.focus = new_focus;
+        self.crumbs.push(crumb);
+        Ok(self)
+    }
+
+    pub fn up(mut self) -> Result<ExprZipper, ZipperError> {
+        let crumb = self.crumbs.pop().ok_or(ZipperError::AtRoot)?;
+        self.focus = match crumb {
+            Crumb::ApplyFunc { args } => Expr::Apply { func: Box::new(self.focus), args },
+            Crumb::ApplyArg { func, mut before, after } => {
+                before.push(self.focus);
+                before.extend(after);
+                Expr::Apply { func: Box::new(func), args: before }
+            }
+            Crumb::Unop { symbol } => Expr::Unop { symbol, operand: Box::new(self.focus) },
+            Crumb::BinopL { symbol, r } => Expr::Binop { symbol, l: Box::new(self.focus), r: Box::new(r) },
+            Crumb::BinopR { symbol, l } => Expr::Binop { symbol, l: Box::new(l), r: Box::new(self.focus) },
+            Crumb::AssocBinop { symbol, mut before, after } => {
+                before.push(self.focus);
+                before.extend(after);
+                Expr::AssocBinop { symbol, exprs: before }
+            }
+            Crumb::Quantifier { symbol, name } => Expr::Quantifier { symbol, name, body: Box::new(self.focus) },
+        };
+        Ok(self)
+    }
+
+    /// Move to the previous sibling within an `Apply`'s arguments or an
+    /// `AssocBinop`'s elements.
+    pub fn left(mut self) -> Result<ExprZipper, ZipperError> {
+        match self.crumbs.pop() {
+            Some(Crumb::AssocBinop { symbol, mut before, after }) => match before.pop() {
+                Some(prev) => {
+                    let mut new_after = vec![self.focus];
+                    new_after.extend(after);
+                    self.crumbs.push(Crumb::AssocBinop { symbol, before, after: new_after });
+                    self.focus = prev;
+                    Ok(self)
+                }
+                None => {
+                    self.crumbs.push(Crumb::AssocBinop { symbol, before, after });
+                    Err(ZipperError::NoSuchChild)
+                }
+            },
+            Some(Crumb::ApplyArg { func, mut before, after }) => match before.pop() {
+                Some(prev) => {
+                    let mut new_after = vec![self.focus];
+                    new_after.extend(after);
+                    self.crumbs.push(Crumb::ApplyArg { func, before, after: new_after });
+                    self.focus = prev;
+                    Ok(self)
+                }
+                None => {
+                    self.crumbs.push(Crumb::ApplyArg { func, before, after });
+                    Err(ZipperError::NoSuchChild)
+                }
+            },
+            Some(other) => {
+                self.crumbs.push(other);
+                Err(ZipperError::NoSuchChild)
+            }
+            None => Err(ZipperError::AtRoot),
+        }
+    }
+
+    /// Move to the next sibling within an `Apply`'s arguments or an
+    /// `AssocBinop`'s elements.
+    pub fn right(mut self) -> Result<ExprZipper, ZipperError> {
+        match self.crumbs.pop() {
+            Some(Crumb::AssocBinop { symbol, mut before, mut after }) => {
+                if after.is_empty() {
+                    self.crumbs.push(Crumb::AssocBinop { symbol, before, after });
+                    return Err(ZipperError::NoSuchChild);
+                }
+                let next = after.remove(0);
+                before.push(self.focus);
+                self.crumbs.push(Crumb::AssocBinop { symbol, before, after });
+                self.focus = next;
+                Ok(self)
+            }
+            Some(Crumb::ApplyArg { func, mut before, mut after }) => {
+                if after.is_empty() {
+                    self.crumbs.push(Crumb::ApplyArg { func, before, after });
+                    return Err(ZipperError::NoSuchChild);
+                }
+                let next = after.remove(0);
+                before.push(self.focus);
+                self.crumbs.push(Crumb::ApplyArg { func, before, after });
+                self.focus = next;
+                Ok(self)
+            }
+            Some(other) => {
+                self.crumbs.push(other);
+                Err(ZipperError::NoSuchChild)
+            }
+            None => Err(ZipperError::AtRoot),
+        }
+    }
+
+    /// Walk back up to the root and return the (possibly edited) whole
+    /// expression.
+    pub fn rebuild(mut self) -> Expr {
+        while !self.crumbs.is_empty() {
+            self = self.up().expect("crumbs non-empty implies up() succeeds");
+        }
+        self.focus
+    }
+}
+
+/// A mapping from unification-variable names to the expressions they were
+/// bound to.
+pub type Substitution = HashMap<String, Expr>;
+
+/// Composes two substitutions so that applying the result with [`subst_map`]
+/// in one pass has the same effect as applying `first` and then `second` in
+/// two separate passes: `subst_map(e, &compose_substitutions(first,
+/// second))` equals `subst_map(&subst_map(e, &first), &second)`. Standard
+/// substitution composition: `second` is applied to the range of `first`
+/// (so a variable `first` introduces is itself further substituted), and
+/// then `second`'s own bindings are appended for any name `first` doesn't
+/// already rebind. (`subst_map` already serves as the "apply" half of a
+/// `Substitution` here, since — unlike folding repeated [`subst`] calls —
+/// it substitutes every binding simultaneously rather than order-dependently;
+/// `Substitution` is a plain `HashMap`, so lookups are just [`HashMap::get`].)
+pub fn compose_substitutions(first: Substitution, second: Substitution) -> Substitution {
+    let mut composed: Substitution = first.into_iter().map(|(name, e)| (name, subst_map(&e, &second))).collect();
+    for (name, e) in second {
+        composed.entry(name).or_insert(e);
+    }
+    composed
+}
+
+/// Projects `sub` onto just the bindings for names in `vars`, dropping any
+/// others. `Substitution` is a plain `HashMap` (see [`compose_substitutions`]),
+/// so this is a free function rather than a method.
+pub fn restrict_substitution(sub: &Substitution, vars: &std::collections::HashSet<String>) -> Substitution {
+    sub.iter().filter(|(name, _)| vars.contains(*name)).map(|(name, e)| (name.clone(), e.clone())).collect()
+}
+
+/// `sub`'s bindings as a `Vec` sorted by variable name. `Substitution` is a
+/// `HashMap`, so two calls to [`unify`] that compute the very same set of
+/// bindings can still iterate them in a different order (hash iteration
+/// order isn't guaranteed stable across runs or Rust versions) — which
+/// [`unify`] and its variants in this module never depend on internally
+/// (their worklists are plain `Vec`s, processed in a fixed order), but which
+/// matters to a caller that wants to print, compare, or snapshot a
+/// substitution's bindings in a way that doesn't vary between runs.
+pub fn sorted_bindings(sub: &Substitution) -> Vec<(String, Expr)> {
+    let mut pairs: Vec<(String, Expr)> = sub.iter().map(|(name, e)| (name.clone(), e.clone())).collect();
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    pairs
+}
+
+/// Whether `a` is at least as general as `b` on `vars`: whether there is
+/// some substitution `sigma` with `sigma` composed onto `a` equal to `b`
+/// on every name in `vars` — i.e. `b` could have been produced from `a` by
+/// substituting further into whatever `a` already bound, rather than
+/// representing a genuinely different (or incompatible) choice. A name
+/// unbound by `a` is treated as bound to itself (an identity binding is
+/// free for `sigma` to specialize into anything), which is why the empty
+/// substitution is more general than every other substitution.
+///
+/// Implemented by [`restrict_substitution`]-ing both sides to `vars`, then
+/// [`match_expr`]-ing each of `a`'s (possibly-identity) bindings against
+/// the corresponding one in `b`, treating every metavariable (per the `__`
+/// convention) appearing in `a`'s range — including a name `a` leaves
+/// unbound, which ranges over itself — as one `sigma` is free to choose,
+/// and requiring every one of those matches to agree on a single,
+/// consistent `sigma`.
+pub fn more_general(a: &Substitution, b: &Substitution, vars: &std::collections::HashSet<String>) -> bool {
+    let a = restrict_substitution(a, vars);
+    let b = restrict_substitution(b, vars);
+    let sigma_vars: std::collections::HashSet<String> = vars
+        .iter()
+        .flat_map(|v| match a.get(v) {
+            Some(e) => pattern_vars_of(e).into_iter().collect::<Vec<_>>(),
+            None => vec![v.clone()],
+        })
+        .collect();
+    let mut sigma = Substitution::new();
+    vars.iter().all(|v| {
+        let pattern = a.get(v).cloned().unwrap_or_else(|| Expr::var(v));
+        let subject = b.get(v).cloned().unwrap_or_else(|| Expr::var(v));
+        match_inner(&pattern, &subject, &sigma_vars, false, &mut sigma)
+    })
+}
+
+/// Attempt to unify two expressions, treating every `__`-prefixed `Var` (see
+/// [`is_metavar`]) as a unification variable and every other `Var` as a
+/// rigid constant. Returns the most general substitution that makes
+/// `a` and `b` syntactically equal (up to alpha-equivalence of quantifiers),
+/// or `None` if no such substitution exists. See [`unify_explained`] for a
+/// variant that reports why unification failed instead of just `None`.
+///
+/// Deterministic: which bindings end up in the result (and, for an
+/// expression with more than one valid MGU, which one is found) depends only
+/// on `a` and `b`, never on incidental factors like `HashMap`/`HashSet`
+/// iteration order, since constraints are processed off a plain `Vec`
+/// worklist in a fixed order (see [`unify_worklist_opts`]). The returned
+/// `Substitution` is still a `HashMap`, so iterating it directly is not
+/// order-stable — use [`sorted_bindings`] for a canonical order.
+pub fn unify(a: &Expr, b: &Expr) -> Option<Substitution> {
+    let mut subs = Substitution::new();
+    if unify_inner(a, b, &mut subs) {
+        Some(subs)
+    } else {
+        None
+    }
+}
+
+/// The names of the maximal leading run of same-symbol quantifiers at the
+/// front of `e` (in binder order, outermost first), along with the body
+/// once they're all stripped off. `None` if `e` isn't itself a `Quantifier`.
+fn leading_quantifier_block(e: &Expr) -> Option<(QSymbol, Vec<&str>, &Expr)> {
+    let (symbol, first_name, mut body) = match e {
+        Expr::Quantifier { symbol, name, body } => (*symbol, name.as_str(), body.as_ref()),
+        _ => return None,
+    };
+    let mut names = vec![first_name];
+    while let Expr::Quantifier { symbol: s, name, body: inner } = body {
+        if *s != symbol {
+            break;
+        }
+        names.push(name.as_str());
+        body = inner.as_ref();
+    }
+    Some((symbol, names, body))
+}
+
+/// Every ordering of `0..n`, same divide-and-remove approach as
+/// [`permutations_of`] but over indices rather than `Expr`s, for pairing up
+/// two quantifier blocks' binders in [`unify_modulo_binder_permutation`].
+fn index_permutations(n: usize) -> Vec<Vec<usize>> {
+    fn go(remaining: Vec<usize>) -> Vec<Vec<usize>> {
+        if remaining.len() <= 1 {
+            return vec![remaining];
+        }
+        let mut result = Vec::new();
+        for i in 0..remaining.len() {
+            let mut rest = remaining.clone();
+            let chosen = rest.remove(i);
+            for mut perm in go(rest) {
+                perm.insert(0, chosen);
+                result.push(perm);
+            }
+        }
+        result
+    }
+    go((0..n).collect())
+}
+
+/// Like [`unify`], but when `a` and `b` both start with a block of
+/// same-symbol quantifiers (`∀x∀y...` or `∃x∃y...`), also tries every
+/// correspondence between `a`'s binders and `b`'s before giving up, instead
+/// of only the one that lines them up in the order written — so
+/// `∀x∀y, P(x,y)` unifies against `∀y∀x, P(x,y)` even though the two
+/// blocks declare their binders in opposite order. Ordinary [`unify`]
+/// can't do this since it peels and alpha-renames one quantifier at a time,
+/// which commits to a binder correspondence before it's had any chance to
+/// see whether it's the right one.
+///
+/// The candidate block is only the *leading run of a single quantifier
+/// symbol*: a mixed `∀x∃y` prefix never has its `∀` and `∃` binders
+/// permuted against each other, since that would change the formula's
+/// meaning, not just its presentation. If the two leading blocks differ in
+/// symbol or length, or either side doesn't start with a quantifier at
+/// all, this falls back to plain, order-sensitive [`unify`].
+///
+/// Bounded to the blocks' own length (factorial in the block size, same as
+/// [`index_permutations`]), so this is only meant for the short quantifier
+/// prefixes that occur in practice, not large blocks.
+pub fn unify_modulo_binder_permutation(a: &Expr, b: &Expr) -> Option<Substitution> {
+    let (symbol_a, names_a, matrix_a) = match leading_quantifier_block(a) {
+        Some(block) => block,
+        None => return unify(a, b),
+    };
+    let (symbol_b, names_b, matrix_b) = match leading_quantifier_block(b) {
+        Some(block) => block,
+        None => return unify(a, b),
+    };
+    if symbol_a != symbol_b || names_a.len() != names_b.len() {
+        return unify(a, b);
+    }
+    for perm in index_permutations(names_a.len()) {
+        // A rigid (non-`__`-prefixed) marker per binder position: shared
+        // between the two sides at corresponding positions so a correct
+        // correspondence compares literally equal, and an incorrect one
+        // produces a genuine clash between two differently-named markers
+        // rather than something `unify` could paper over by just binding
+        // one to the other, the way it could if these were metavariables.
+        let markers: Vec<String> = (0..names_a.len()).map(|_| gensym("binder_perm_marker")).collect();
+        let mut renamed_a = matrix_a.clone();
+        for (name, marker) in names_a.iter().zip(&markers) {
+            renamed_a = subst(name, &Expr::var(marker), renamed_a);
+        }
+        let mut renamed_b = matrix_b.clone();
+        for (&bi, marker) in perm.iter().zip(&markers) {
+            renamed_b = subst(names_b[bi], &Expr::var(marker), renamed_b);
+        }
+        if let Some(subs) = unify(&renamed_a, &renamed_b) {
+            return Some(subs);
+        }
+    }
+    None
+}
+
+/// Like [`unify_with_metavars`], but also allows an `AssocBinop` on one side
+/// with fewer operands to unify against one with more, by binding a
+/// bindable trailing operand to an `AssocBinop` grouping the remainder — so
+/// e.g. the pattern `phi & psi` unifies against `A & B & C` with `phi = A`,
+/// `psi = B & C`: the *last* operand of the shorter side is the one that
+/// absorbs everything past where the shorter side runs out, which is
+/// deterministic and keeps every earlier operand aligned positionally. This
+/// only fires when the shorter side's last operand is actually bindable
+/// (per `metavars`); mismatched non-variable operands still fail to unify,
+/// same as [`unify_with_metavars`].
+pub fn unify_with_grouping(a: &Expr, b: &Expr, metavars: &std::collections::HashSet<String>) -> Option<Substitution> {
+    let mut subs = Substitution::new();
+    if unify_worklist_opts(vec![UnifyItem::Ref(a, b)], &mut subs, &|name| metavars.contains(name), true) {
+        Some(subs)
+    } else {
+        None
+    }
+}
+
+/// The grouping half of [`unify_with_grouping`]'s `AssocBinop` handling:
+/// given both sides' operand lists (already known to differ in length),
+/// checks whether the shorter one's last operand is bindable and, if so,
+/// returns the pairs to unify — every operand before the last lined up
+/// positionally, and the shorter side's last operand paired against an
+/// `AssocBinop` of the longer side's remaining operands. Returns `None`
+/// (refusing to fire) when the shorter side is empty or its last operand
+/// isn't a bindable variable, since then there is nothing to absorb the
+/// remainder.
+fn try_assoc_grouping(symbol: ASymbol, e1: &[Expr], e2: &[Expr], is_bindable: &dyn Fn(&str) -> bool) -> Option<Vec<(Expr, Expr)>> {
+    let (shorter, longer, shorter_is_e1) = if e1.len() < e2.len() { (e1, e2, true) } else { (e2, e1, false) };
+    if shorter.is_empty() {
+        return None;
+    }
+    let split = shorter.len() - 1;
+    let var_name = match &shorter[split] {
+        Expr::Var { name } if is_bindable(name) => name.clone(),
+        _ => return None,
+    };
+    let remainder = Expr::AssocBinop { symbol, exprs: longer[split..].to_vec() };
+    let mut pairs: Vec<(Expr, Expr)> = shorter[..split].iter().cloned().zip(longer[..split].iter().cloned()).collect();
+    pairs.push((Expr::var(&var_name), remainder));
+    if !shorter_is_e1 {
+        pairs = pairs.into_iter().map(|(x, y)| (y, x)).collect();
+    }
+    Some(pairs)
+}
+
+/// Unification variables are conventionally written with a `__` prefix
+/// (e.g. `__a`) so that an ordinary object-level variable like `x` is never
+/// accidentally treated as something unify is free to bind.
+fn is_metavar(name: &str) -> bool {
+    name.starts_with("__")
+}
+
+/// A unification constraint on the worklist: either a pair of references
+/// into one of the two original input trees (the common case — pushed with
+/// no cloning at all), or a pair of freshly-built expressions (only
+/// produced by renaming a quantifier's bound variable, which has to
+/// materialize a new body).
+#[derive(Clone)]
+enum UnifyItem<'a> {
+    Ref(&'a Expr, &'a Expr),
+    Owned(Expr, Expr),
+}
+
+/// `Plus`, `Mult`, `Nand`, and `Nor` are commutative, so unifying a `Binop`
+/// of one of these symbols may need to try both operand orientations;
+/// `Implies` is not.
+fn is_commutative_bsymbol(symbol: BSymbol) -> bool {
+    matches!(symbol, BSymbol::Plus | BSymbol::Mult | BSymbol::Nand | BSymbol::Nor)
+}
+
+// Iterative rather than recursive so that unifying expressions thousands of
+// connectives deep doesn't blow the stack: constraints are pushed onto an
+// explicit worklist instead of being unified via a recursive call, but the
+// case-by-case logic (and its order of precedence between arms) is otherwise
+// unchanged from a direct recursive formulation. The `Ref` worklist items
+// borrow straight from the input trees rather than cloning them, since a
+// `.clone()` of a many-thousand-deep tree would itself recurse enough to
+// blow the stack before unification even begins.
+fn unify_inner(a: &Expr, b: &Expr, subs: &mut Substitution) -> bool {
+    unify_worklist_opts(vec![UnifyItem::Ref(a, b)], subs, &is_metavar, false)
+}
+
+// Drains `worklist` to completion against `subs`, same case logic as before.
+// `is_bindable` decides which `Var` names may be unification variables —
+// [`unify`] passes [`is_metavar`] (the `__`-prefix convention), while
+// [`unify_with_metavars`] passes a closure that only treats an explicit set
+// as bindable, leaving every other `Var` (even an `__`-prefixed one) as a
+// rigid constant. The only place this recurses is a commutative `Binop`
+// (`Plus`/`Mult`/`Nand`/`Nor`, see [`is_commutative_bsymbol`]): there it
+// clones the remaining worklist and substitution to try the same-order
+// pairing first, and only falls back to the swapped pairing (pushed onto
+// the caller's own `worklist`, against its own `subs`) if that whole
+// attempt — not just the immediate pair — fails downstream.
+// Every other case pushes onto the worklist and loops, exactly as a flat
+// iterative unify would, so ordinary (non-commutative) unification still
+// can't blow the stack on a long chain of connectives.
+// `allow_grouping` is only ever `true` via [`unify_with_grouping`], and is
+// threaded through every recursive call (including the commutative-`Binop`
+// backtracking below) so its relaxed `AssocBinop` handling (see
+// [`try_assoc_grouping`]) applies at every depth, not just the top level.
+fn unify_worklist_opts(mut worklist: Vec<UnifyItem>, subs: &mut Substitution, is_bindable: &dyn Fn(&str) -> bool, allow_grouping: bool) -> bool {
+    while let Some(item) = worklist.pop() {
+        match item {
+            UnifyItem::Ref(a, b) => match (a, b) {
+                (Expr::Var { name }, b) if is_bindable(name) => {
+                    if !unify_var_ref(name, b, subs, &mut worklist) {
+                        return false;
+                    }
+                }
+                (a, Expr::Var { name }) if is_bindable(name) => {
+                    if !unify_var_ref(name, a, subs, &mut worklist) {
+                        return false;
+                    }
+                }
+                (Expr::Var { name: n1 }, Expr::Var { name: n2 }) => {
+                    if n1 != n2 {
+                        return false;
+                    }
+                }
+                (Expr::Contradiction, Expr::Contradiction) => {}
+                (Expr::Tautology, Expr::Tautology) => {}
+                (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+                    if a1.len() != a2.len() {
+                        return false;
+                    }
+                    worklist.push(UnifyItem::Ref(f1, f2));
+                    worklist.extend(a1.iter().zip(a2.iter()).map(|(x, y)| UnifyItem::Ref(x, y)));
+                }
+                (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    worklist.push(UnifyItem::Ref(o1, o2));
+                }
+                (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    if is_commutative_bsymbol(*s1) {
+                        let mut same_order = worklist.clone();
+                        same_order.push(UnifyItem::Ref(l1, l2));
+                        same_order.push(UnifyItem::Ref(r1, r2));
+                        let mut trial_subs = subs.clone();
+                        if unify_worklist_opts(same_order, &mut trial_subs, is_bindable, allow_grouping) {
+                            *subs = trial_subs;
+                            return true;
+                        }
+                        worklist.push(UnifyItem::Ref(l1, r2));
+                        worklist.push(UnifyItem::Ref(r1, l2));
+                    } else {
+                        worklist.push(UnifyItem::Ref(l1, l2));
+                        worklist.push(UnifyItem::Ref(r1, r2));
+                    }
+                }
+                (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    if e1.len() == e2.len() {
+                        worklist.extend(e1.iter().zip(e2.iter()).map(|(x, y)| UnifyItem::Ref(x, y)));
+                    } else if allow_grouping {
+                        match try_assoc_grouping(*s1, e1, e2, is_bindable) {
+                            Some(pairs) => worklist.extend(pairs.into_iter().map(|(x, y)| UnifyItem::Owned(x, y))),
+                            None => return false,
+                        }
+                    } else {
+                        return false;
+                    }
+                }
+                (
+                    Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+                    Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+                ) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    // Check alpha-equivalence by substituting a shared gensym for
+                    // both binders before comparing/unifying the bodies.
+                    let fresh = gensym("__unification_var");
+                    let nb1 = subst(n1, &Expr::var(&fresh), (**b1).clone());
+                    let nb2 = subst(n2, &Expr::var(&fresh), (**b2).clone());
+                    worklist.push(UnifyItem::Owned(nb1, nb2));
+                }
+                _ => return false,
+            },
+            UnifyItem::Owned(a, b) => match (a, b) {
+                (Expr::Var { name }, b) if is_bindable(&name) => {
+                    if !unify_var_owned(&name, b, subs, &mut worklist) {
+                        return false;
+                    }
+                }
+                (a, Expr::Var { name }) if is_bindable(&name) => {
+                    if !unify_var_owned(&name, a, subs, &mut worklist) {
+                        return false;
+                    }
+                }
+                (Expr::Var { name: n1 }, Expr::Var { name: n2 }) => {
+                    if n1 != n2 {
+                        return false;
+                    }
+                }
+                (Expr::Contradiction, Expr::Contradiction) => {}
+                (Expr::Tautology, Expr::Tautology) => {}
+                (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+                    if a1.len() != a2.len() {
+                        return false;
+                    }
+                    worklist.push(UnifyItem::Owned(*f1, *f2));
+                    worklist.extend(a1.into_iter().zip(a2).map(|(x, y)| UnifyItem::Owned(x, y)));
+                }
+                (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    worklist.push(UnifyItem::Owned(*o1, *o2));
+                }
+                (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    if is_commutative_bsymbol(s1) {
+                        let mut same_order = worklist.clone();
+                        same_order.push(UnifyItem::Owned((*l1).clone(), (*l2).clone()));
+                        same_order.push(UnifyItem::Owned((*r1).clone(), (*r2).clone()));
+                        let mut trial_subs = subs.clone();
+                        if unify_worklist_opts(same_order, &mut trial_subs, is_bindable, allow_grouping) {
+                            *subs = trial_subs;
+                            return true;
+                        }
+                        worklist.push(UnifyItem::Owned(*l1, *r2));
+                        worklist.push(UnifyItem::Owned(*r1, *l2));
+                    } else {
+                        worklist.push(UnifyItem::Owned(*l1, *l2));
+                        worklist.push(UnifyItem::Owned(*r1, *r2));
+                    }
+                }
+                (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    if e1.len() == e2.len() {
+                        worklist.extend(e1.into_iter().zip(e2).map(|(x, y)| UnifyItem::Owned(x, y)));
+                    } else if allow_grouping {
+                        match try_assoc_grouping(s1, &e1, &e2, is_bindable) {
+                            Some(pairs) => worklist.extend(pairs.into_iter().map(|(x, y)| UnifyItem::Owned(x, y))),
+                            None => return false,
+                        }
+                    } else {
+                        return false;
+                    }
+                }
+                (
+                    Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+                    Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+                ) => {
+                    if s1 != s2 {
+                        return false;
+                    }
+                    let fresh = gensym("__unification_var");
+                    let nb1 = subst(&n1, &Expr::var(&fresh), *b1);
+                    let nb2 = subst(&n2, &Expr::var(&fresh), *b2);
+                    worklist.push(UnifyItem::Owned(nb1, nb2));
+                }
+                _ => return false,
+            },
+        }
+    }
+    true
+}
+
+/// Like [`unify`], but only the names in `metavars` are treated as
+/// unification variables — every other `Var` (even one spelled with the
+/// `__` prefix [`unify`] uses by convention) is a rigid constant that must
+/// match a `Var` of the same name on the other side. Useful when a rule
+/// schema's placeholder names (e.g. `phi`, `psi`) don't follow the `__`
+/// convention, or when the subject formula might coincidentally contain a
+/// variable that looks like a metavariable but shouldn't be unified away.
+pub fn unify_with_metavars(a: &Expr, b: &Expr, metavars: &std::collections::HashSet<String>) -> Option<Substitution> {
+    let mut subs = Substitution::new();
+    if unify_worklist_opts(vec![UnifyItem::Ref(a, b)], &mut subs, &|name| metavars.contains(name), false) {
+        Some(subs)
+    } else {
+        None
+    }
+}
+
+/// Caps for [`unify_bounded`]: processing more than `max_constraints`
+/// worklist items, or binding a variable to a term larger than
+/// `max_term_size` nodes, aborts with [`ResourceLimit`] instead of
+/// continuing to churn or grow unboundedly. The [`Default`] impl is
+/// generous enough that every plain [`unify`] call elsewhere in this
+/// crate's own test suite still succeeds through the bounded path — these
+/// limits are meant to catch a pathological or adversarial input, not to
+/// constrain ordinary use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnifyLimits {
+    pub max_constraints: usize,
+    pub max_term_size: usize,
+}
+
+impl Default for UnifyLimits {
+    fn default() -> UnifyLimits {
+        UnifyLimits { max_constraints: 1_000_000, max_term_size: 1_000_000 }
+    }
+}
+
+/// Why [`unify_bounded`] gave up without an answer, distinct from ordinary
+/// unification failure (`Ok(None)`): the input was too large or
+/// pathological to finish within its [`UnifyLimits`], not that it
+/// genuinely doesn't unify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceLimit {
+    /// More than `max_constraints` worklist items were processed.
+    TooManyConstraints,
+    /// A term bound to some variable grew past `max_term_size` nodes.
+    TermTooLarge,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceLimit::TooManyConstraints => write!(f, "exceeded the maximum number of unification constraints"),
+            ResourceLimit::TermTooLarge => write!(f, "a substituted term exceeded the maximum allowed size"),
+        }
+    }
+}
+
+impl std::error::Error for ResourceLimit {}
+
+/// Like [`unify`], but bounded: gives up with `Err(`[`ResourceLimit`]`)`
+/// instead of running unboundedly on a pathological input (deeply nested
+/// quantifiers, or a pattern whose bindings blow up the substituted term
+/// size), so a caller on a latency-sensitive path (e.g. the GUI's check
+/// thread) can report "expression too large to check" instead of freezing.
+/// `Ok(None)` still means ordinary, well-behaved unification failure — only
+/// hitting a limit before an answer is found returns `Err`.
+pub fn unify_bounded(a: &Expr, b: &Expr, limits: UnifyLimits) -> Result<Option<Substitution>, ResourceLimit> {
+    let mut subs = Substitution::new();
+    let mut processed = 0usize;
+    match unify_worklist_bounded(vec![UnifyItem::Ref(a, b)], &mut subs, &limits, &mut processed)? {
+        true => Ok(Some(subs)),
+        false => Ok(None),
+    }
+}
+
+// A bounded parallel of `unify_worklist_opts`, the same way `unify_explained_at`
+// is a parallel of it that reports a structured error instead of a bare
+// `bool` — here the orthogonal axis is resource limits rather than failure
+// explanations, so it gets its own worklist loop and its own binder
+// (`bind_bounded`) rather than threading a `Result` through the existing
+// ones. Supports the same metavariable convention and commutative-`Binop`
+// backtracking as `unify`, but not `unify_with_grouping`'s relaxed
+// `AssocBinop` handling, since `unify_bounded` is a bounded version of plain
+// `unify`, not of every variant.
+fn unify_worklist_bounded(mut worklist: Vec<UnifyItem>, subs: &mut Substitution, limits: &UnifyLimits, processed: &mut usize) -> Result<bool, ResourceLimit> {
+    while let Some(item) = worklist.pop() {
+        *processed += 1;
+        if *processed > limits.max_constraints {
+            return Err(ResourceLimit::TooManyConstraints);
+        }
+        match item {
+            UnifyItem::Ref(a, b) => match (a, b) {
+                (Expr::Var { name }, b) if is_metavar(name) => {
+                    if !bind_bounded(name, b.clone(), subs, &mut worklist, limits)? {
+                        return Ok(false);
+                    }
+                }
+                (a, Expr::Var { name }) if is_metavar(name) => {
+                    if !bind_bounded(name, a.clone(), subs, &mut worklist, limits)? {
+                        return Ok(false);
+                    }
+                }
+                (Expr::Var { name: n1 }, Expr::Var { name: n2 }) => {
+                    if n1 != n2 {
+                        return Ok(false);
+                    }
+                }
+                (Expr::Contradiction, Expr::Contradiction) => {}
+                (Expr::Tautology, Expr::Tautology) => {}
+                (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+                    if a1.len() != a2.len() {
+                        return Ok(false);
+                    }
+                    worklist.push(UnifyItem::Ref(f1, f2));
+                    worklist.extend(a1.iter().zip(a2.iter()).map(|(x, y)| UnifyItem::Ref(x, y)));
+                }
+                (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    worklist.push(UnifyItem::Ref(o1, o2));
+                }
+                (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    if is_commutative_bsymbol(*s1) {
+                        let mut same_order = worklist.clone();
+                        same_order.push(UnifyItem::Ref(l1, l2));
+                        same_order.push(UnifyItem::Ref(r1, r2));
+                        let mut trial_subs = subs.clone();
+                        let mut trial_processed = *processed;
+                        if unify_worklist_bounded(same_order, &mut trial_subs, limits, &mut trial_processed)? {
+                            *subs = trial_subs;
+                            *processed = trial_processed;
+                            return Ok(true);
+                        }
+                        worklist.push(UnifyItem::Ref(l1, r2));
+                        worklist.push(UnifyItem::Ref(r1, l2));
+                    } else {
+                        worklist.push(UnifyItem::Ref(l1, l2));
+                        worklist.push(UnifyItem::Ref(r1, r2));
+                    }
+                }
+                (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) => {
+                    if s1 != s2 || e1.len() != e2.len() {
+                        return Ok(false);
+                    }
+                    worklist.extend(e1.iter().zip(e2.iter()).map(|(x, y)| UnifyItem::Ref(x, y)));
+                }
+                (Expr::Quantifier { symbol: s1, name: n1, body: b1 }, Expr::Quantifier { symbol: s2, name: n2, body: b2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    let fresh = gensym("__unification_var");
+                    let nb1 = subst(n1, &Expr::var(&fresh), (**b1).clone());
+                    let nb2 = subst(n2, &Expr::var(&fresh), (**b2).clone());
+                    worklist.push(UnifyItem::Owned(nb1, nb2));
+                }
+                _ => return Ok(false),
+            },
+            UnifyItem::Owned(a, b) => match (a, b) {
+                (Expr::Var { name }, b) if is_metavar(&name) => {
+                    if !bind_bounded(&name, b, subs, &mut worklist, limits)? {
+                        return Ok(false);
+                    }
+                }
+                (a, Expr::Var { name }) if is_metavar(&name) => {
+                    if !bind_bounded(&name, a, subs, &mut worklist, limits)? {
+                        return Ok(false);
+                    }
+                }
+                (Expr::Var { name: n1 }, Expr::Var { name: n2 }) => {
+                    if n1 != n2 {
+                        return Ok(false);
+                    }
+                }
+                (Expr::Contradiction, Expr::Contradiction) => {}
+                (Expr::Tautology, Expr::Tautology) => {}
+                (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+                    if a1.len() != a2.len() {
+                        return Ok(false);
+                    }
+                    worklist.push(UnifyItem::Owned(*f1, *f2));
+                    worklist.extend(a1.into_iter().zip(a2).map(|(x, y)| UnifyItem::Owned(x, y)));
+                }
+                (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    worklist.push(UnifyItem::Owned(*o1, *o2));
+                }
+                (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    if is_commutative_bsymbol(s1) {
+                        let mut same_order = worklist.clone();
+                        same_order.push(UnifyItem::Owned((*l1).clone(), (*l2).clone()));
+                        same_order.push(UnifyItem::Owned((*r1).clone(), (*r2).clone()));
+                        let mut trial_subs = subs.clone();
+                        let mut trial_processed = *processed;
+                        if unify_worklist_bounded(same_order, &mut trial_subs, limits, &mut trial_processed)? {
+                            *subs = trial_subs;
+                            *processed = trial_processed;
+                            return Ok(true);
+                        }
+                        worklist.push(UnifyItem::Owned(*l1, *r2));
+                        worklist.push(UnifyItem::Owned(*r1, *l2));
+                    } else {
+                        worklist.push(UnifyItem::Owned(*l1, *l2));
+                        worklist.push(UnifyItem::Owned(*r1, *r2));
+                    }
+                }
+                (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) => {
+                    if s1 != s2 || e1.len() != e2.len() {
+                        return Ok(false);
+                    }
+                    worklist.extend(e1.into_iter().zip(e2).map(|(x, y)| UnifyItem::Owned(x, y)));
+                }
+                (Expr::Quantifier { symbol: s1, name: n1, body: b1 }, Expr::Quantifier { symbol: s2, name: n2, body: b2 }) => {
+                    if s1 != s2 {
+                        return Ok(false);
+                    }
+                    let fresh = gensym("__unification_var");
+                    let nb1 = subst(&n1, &Expr::var(&fresh), *b1);
+                    let nb2 = subst(&n2, &Expr::var(&fresh), *b2);
+                    worklist.push(UnifyItem::Owned(nb1, nb2));
+                }
+                _ => return Ok(false),
+            },
+        }
+    }
+    Ok(true)
+}
+
+fn bind_bounded<'a>(name: &str, e: Expr, subs: &mut Substitution, worklist: &mut Vec<UnifyItem<'a>>, limits: &UnifyLimits) -> Result<bool, ResourceLimit> {
+    if let Some(bound) = subs.get(name).cloned() {
+        worklist.push(UnifyItem::Owned(bound, e));
+        return Ok(true);
+    }
+    if e == Expr::var(name) {
+        return Ok(true);
+    }
+    if occurs_check(name, &e, subs) {
+        return Ok(false);
+    }
+    if expr_size(&e) > limits.max_term_size {
+        return Err(ResourceLimit::TermTooLarge);
+    }
+    subs.insert(name.to_owned(), e);
+    Ok(true)
+}
+
+/// Computes the least general generalization (anti-unification) of `a` and
+/// `b`: the most specific expression `g` such that some substitution of `g`
+/// gives back `a` and another gives back `b`. Identical structure is kept;
+/// wherever `a` and `b` disagree, a fresh metavariable takes that subterm's
+/// place, and the two returned substitutions record what each side's
+/// disagreement actually was. The same pair of mismatching subterms always
+/// reuses the same fresh variable, so e.g. anti-unifying `p -> p` against
+/// `q -> q` yields `__antiunifyN -> __antiunifyN` (one [`gensym`]-numbered
+/// variable used twice), not two unrelated variables.
+///
+/// `Quantifier`s are only matched up when both sides share the same symbol
+/// *and* bound-variable name; otherwise the whole quantified subtree is
+/// treated as a mismatch (anti-unification doesn't attempt alpha-renaming to
+/// find a common binder name).
+pub fn anti_unify(a: &Expr, b: &Expr) -> (Expr, Substitution, Substitution) {
+    let mut cache: HashMap<(Expr, Expr), String> = HashMap::new();
+    let mut subst_a = Substitution::new();
+    let mut subst_b = Substitution::new();
+    let avoid: std::collections::HashSet<String> = freevars(a).union(&freevars(b)).cloned().collect();
+    let generalization = anti_unify_at(a, b, &mut cache, &mut subst_a, &mut subst_b, &avoid);
+    (generalization, subst_a, subst_b)
+}
+
+fn anti_unify_mismatch(
+    a: &Expr,
+    b: &Expr,
+    cache: &mut HashMap<(Expr, Expr), String>,
+    subst_a: &mut Substitution,
+    subst_b: &mut Substitution,
+    avoid: &std::collections::HashSet<String>,
+) -> Expr {
+    let name = cache
+        .entry((a.clone(), b.clone()))
+        .or_insert_with(|| {
+            let mut fresh = gensym("__antiunify");
+            while avoid.contains(&fresh) {
+                fresh = gensym("__antiunify");
+            }
+            fresh
+        })
+        .clone();
+    subst_a.entry(name.clone()).or_insert_with(|| a.clone());
+    subst_b.entry(name.clone()).or_insert_with(|| b.clone());
+    Expr::var(&name)
+}
+
+fn anti_unify_at(
+    a: &Expr,
+    b: &Expr,
+    cache: &mut HashMap<(Expr, Expr), String>,
+    subst_a: &mut Substitution,
+    subst_b: &mut Substitution,
+    avoid: &std::collections::HashSet<String>,
+) -> Expr {
+    match (a, b) {
+        (Expr::Contradiction, Expr::Contradiction) => Expr::Contradiction,
+        (Expr::Tautology, Expr::Tautology) => Expr::Tautology,
+        (Expr::Var { name: n1 }, Expr::Var { name: n2 }) if n1 == n2 => Expr::var(n1),
+        (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) if a1.len() == a2.len() => Expr::Apply {
+            func: Box::new(anti_unify_at(f1, f2, cache, subst_a, subst_b, avoid)),
+            args: a1.iter().zip(a2).map(|(x, y)| anti_unify_at(x, y, cache, subst_a, subst_b, avoid)).collect(),
+        },
+        (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) if s1 == s2 => {
+            Expr::Unop { symbol: *s1, operand: Box::new(anti_unify_at(o1, o2, cache, subst_a, subst_b, avoid)) }
+        }
+        (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) if s1 == s2 => Expr::Binop {
+            symbol: *s1,
+            l: Box::new(anti_unify_at(l1, l2, cache, subst_a, subst_b, avoid)),
+            r: Box::new(anti_unify_at(r1, r2, cache, subst_a, subst_b, avoid)),
+        },
+        (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) if s1 == s2 && e1.len() == e2.len() => {
+            Expr::AssocBinop { symbol: *s1, exprs: e1.iter().zip(e2).map(|(x, y)| anti_unify_at(x, y, cache, subst_a, subst_b, avoid)).collect() }
+        }
+        (
+            Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+            Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+        ) if s1 == s2 && n1 == n2 => Expr::Quantifier {
+            symbol: *s1,
+            name: n1.clone(),
+            body: Box::new(anti_unify_at(b1, b2, cache, subst_a, subst_b, avoid)),
+        },
+        _ => anti_unify_mismatch(a, b, cache, subst_a, subst_b, avoid),
+    }
+}
+
+/// Whether binding `name` to `e` would create a cyclic substitution, given
+/// the bindings already recorded in `subs`. Checks `e` for a literal
+/// occurrence of `name`, but also follows any variable `e` bottoms out at
+/// through `subs` — catching a cycle that only appears once an earlier
+/// binding in the same constraint set is taken into account (e.g. `__x`
+/// bound to `__y`, then `__y` unified with something mentioning `__x`).
+/// Shared by [`unify_var_ref`]/[`unify_var_owned`] and [`bind_explained`] so
+/// plain `unify` and [`unify_explained`] agree on what counts as a cycle.
+fn occurs_check(name: &str, e: &Expr, subs: &Substitution) -> bool {
+    fn go(e: &Expr, name: &str, subs: &Substitution, seen: &mut std::collections::HashSet<String>) -> bool {
+        match e {
+            Expr::Var { name: n } => {
+                n == name || (seen.insert(n.clone()) && subs.get(n).is_some_and(|bound| go(bound, name, subs, seen)))
+            }
+            Expr::Contradiction | Expr::Tautology => false,
+            Expr::Apply { func, args } => go(func, name, subs, seen) || args.iter().any(|a| go(a, name, subs, seen)),
+            Expr::Unop { operand, .. } => go(operand, name, subs, seen),
+            Expr::Binop { l, r, .. } => go(l, name, subs, seen) || go(r, name, subs, seen),
+            Expr::AssocBinop { exprs, .. } => exprs.iter().any(|x| go(x, name, subs, seen)),
+            Expr::Quantifier { body, .. } => go(body, name, subs, seen),
+        }
+    }
+    go(e, name, subs, &mut std::collections::HashSet::new())
+}
+
+fn unify_var_ref<'a>(name: &str, e: &'a Expr, subs: &mut Substitution, worklist: &mut Vec<UnifyItem<'a>>) -> bool {
+    if let Some(bound) = subs.get(name).cloned() {
+        worklist.push(UnifyItem::Owned(bound, e.clone()));
+        return true;
+    }
+    if e == &Expr::var(name) {
+        return true;
+    }
+    if occurs_check(name, e, subs) {
+        return false;
+    }
+    subs.insert(name.to_owned(), e.clone());
+    true
+}
+
+fn unify_var_owned(name: &str, e: Expr, subs: &mut Substitution, worklist: &mut Vec<UnifyItem>) -> bool {
+    if let Some(bound) = subs.get(name).cloned() {
+        worklist.push(UnifyItem::Owned(bound, e));
+        return true;
+    }
+    if e == Expr::var(name) {
+        return true;
+    }
+    if occurs_check(name, &e, subs) {
+        return false;
+    }
+    subs.insert(name.to_owned(), e);
+    true
+}
+
+/// Why [`unify_explained`] failed to find a substitution, with enough
+/// structure for a GUI to explain the mismatch directly to a student rather
+/// than just reporting "doesn't match".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnificationError {
+    /// The two sides have incompatible shapes at `path` — different
+    /// variants, operator symbols, or quantifier kinds — and neither is a
+    /// metavariable that could absorb the difference.
+    SymbolClash { path: ExprPath, a: Expr, b: Expr },
+    /// An `Apply` or `AssocBinop` pair at `path` has a different number of
+    /// arguments/operands on each side.
+    ArityMismatch { path: ExprPath, left_arity: usize, right_arity: usize },
+    /// Binding `var` to `term` at `path` would create a cyclic substitution,
+    /// since `term` itself mentions `var`.
+    OccursCheck { path: ExprPath, var: String, term: Expr },
+    /// While comparing two quantifiers at `path` up to alpha-equivalence,
+    /// `var` got bound to a term mentioning the quantifier's freshly-renamed
+    /// bound variable — a binding that's meaningless once that quantifier's
+    /// scope is left.
+    QuantifierEscape { path: ExprPath, var: String },
+}
+
+impl fmt::Display for UnificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnificationError::SymbolClash { path, a, b } => write!(f, "at path {:?}: cannot unify {} with {}", path.0, a, b),
+            UnificationError::ArityMismatch { path, left_arity, right_arity } => {
+                write!(f, "at path {:?}: arity mismatch ({} vs {})", path.0, left_arity, right_arity)
+            }
+            UnificationError::OccursCheck { path, var, term } => write!(f, "at path {:?}: {} occurs in {}", path.0, var, term),
+            UnificationError::QuantifierEscape { path, var } => {
+                write!(f, "at path {:?}: {} would escape its quantifier's scope", path.0, var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UnificationError {}
+
+fn term_contains_var(e: &Expr, name: &str) -> bool {
+    match e {
+        Expr::Var { name: n } => n == name,
+        Expr::Contradiction | Expr::Tautology => false,
+        Expr::Apply { func, args } => term_contains_var(func, name) || args.iter().any(|a| term_contains_var(a, name)),
+        Expr::Unop { operand, .. } => term_contains_var(operand, name),
+        Expr::Binop { l, r, .. } => term_contains_var(l, name) || term_contains_var(r, name),
+        Expr::AssocBinop { exprs, .. } => exprs.iter().any(|x| term_contains_var(x, name)),
+        Expr::Quantifier { body, .. } => term_contains_var(body, name),
+    }
+}
+
+/// Like [`unify`], but on failure reports which of several distinct things
+/// went wrong instead of a bare `None`: a symbol clash, an arity mismatch,
+/// a cyclic binding caught by the occurs check, or a metavariable escaping
+/// a quantifier's scope during the alpha-equivalence check.
+pub fn unify_explained(a: &Expr, b: &Expr) -> Result<Substitution, UnificationError> {
+    let mut subs = Substitution::new();
+    unify_explained_at(a, b, &mut subs, &mut Vec::new())?;
+    Ok(subs)
+}
+
+fn bind_explained(name: &str, e: &Expr, subs: &mut Substitution, path: &[usize]) -> Result<(), UnificationError> {
+    if let Some(bound) = subs.get(name).cloned() {
+        return unify_explained_at(&bound, e, subs, &mut path.to_vec());
+    }
+    if e == &Expr::var(name) {
+        return Ok(());
+    }
+    if occurs_check(name, e, subs) {
+        return Err(UnificationError::OccursCheck { path: ExprPath(path.to_vec()), var: name.to_owned(), term: e.clone() });
+    }
+    subs.insert(name.to_owned(), e.clone());
+    Ok(())
+}
+
+fn unify_explained_at(a: &Expr, b: &Expr, subs: &mut Substitution, path: &mut Vec<usize>) -> Result<(), UnificationError> {
+    match (a, b) {
+        (Expr::Var { name }, _) if is_metavar(name) => bind_explained(name, b, subs, path),
+        (_, Expr::Var { name }) if is_metavar(name) => bind_explained(name, a, subs, path),
+        (Expr::Var { name: n1 }, Expr::Var { name: n2 }) if n1 == n2 => Ok(()),
+        (Expr::Contradiction, Expr::Contradiction) => Ok(()),
+        (Expr::Tautology, Expr::Tautology) => Ok(()),
+        (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+            if a1.len() != a2.len() {
+                return Err(UnificationError::ArityMismatch { path: ExprPath(path.clone()), left_arity: a1.len(), right_arity: a2.len() });
+            }
+            path.push(0);
+            let result = unify_explained_at(f1, f2, subs, path);
+            path.pop();
+            result?;
+            for (i, (x, y)) in a1.iter().zip(a2.iter()).enumerate() {
+                path.push(i + 1);
+                let result = unify_explained_at(x, y, subs, path);
+                path.pop();
+                result?;
+            }
+            Ok(())
+        }
+        (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) if s1 == s2 => {
+            path.push(0);
+            let result = unify_explained_at(o1, o2, subs, path);
+            path.pop();
+            result
+        }
+        (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) if s1 == s2 => {
+            path.push(0);
+            let result = unify_explained_at(l1, l2, subs, path);
+            path.pop();
+            result?;
+            path.push(1);
+            let result = unify_explained_at(r1, r2, subs, path);
+            path.pop();
+            result
+        }
+        (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) if s1 == s2 => {
+            if e1.len() != e2.len() {
+                return Err(UnificationError::ArityMismatch { path: ExprPath(path.clone()), left_arity: e1.len(), right_arity: e2.len() });
+            }
+            for (i, (x, y)) in e1.iter().zip(e2.iter()).enumerate() {
+                path.push(i);
+                let result = unify_explained_at(x, y, subs, path);
+                path.pop();
+                result?;
+            }
+            Ok(())
+        }
+        (
+            Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+            Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+        ) if s1 == s2 => {
+            // As in `unify_inner`: compare bodies up to alpha-equivalence by
+            // substituting a shared gensym for both binders. Since that
+            // gensym is itself `__`-prefixed, occurrences of it inside the
+            // bodies are handled by the ordinary metavariable-binding case
+            // above; afterwards we check that none of the *other* bindings
+            // produced while comparing the bodies mention it, since such a
+            // binding would be meaningless once this quantifier's scope ends.
+            let fresh = gensym("__unification_var");
+            let renamed_b1 = subst(n1, &Expr::var(&fresh), (**b1).clone());
+            let renamed_b2 = subst(n2, &Expr::var(&fresh), (**b2).clone());
+            let mut inner_subs = Substitution::new();
+            path.push(0);
+            let result = unify_explained_at(&renamed_b1, &renamed_b2, &mut inner_subs, path);
+            path.pop();
+            result?;
+            for (var, term) in &inner_subs {
+                if var != &fresh && term_contains_var(term, &fresh) {
+                    return Err(UnificationError::QuantifierEscape { path: ExprPath(path.clone()), var: var.clone() });
+                }
+            }
+            subs.extend(inner_subs);
+            Ok(())
+        }
+        _ => Err(UnificationError::SymbolClash { path: ExprPath(path.clone()), a: a.clone(), b: b.clone() }),
+    }
+}
+
+/// Repeatedly apply `f` to every node of `e` (innermost-first) until a fixed
+/// point is reached. `f` returns `(new_expr, changed)`; the worklist keeps
+/// going as long as some node reports a change, so it will loop infinitely
+/// if your transformation creates patterns that it matches.
+pub fn transform_expr(e: Expr, f: &dyn Fn(Expr) -> (Expr, bool)) -> Expr {
+    match transform_expr_result::<std::convert::Infallible>(e, &|e| Ok(f(e))) {
+        Ok(e) => e,
+        Err(never) => match never {},
+    }
+}
+
+/// Returned by [`transform_expr_bounded`] when the fixpoint iteration does
+/// not converge within the allotted number of iterations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransformLimitExceeded {
+    /// The expression as it stood after the last iteration before giving up.
+    pub last: Expr,
+}
+
+impl fmt::Display for TransformLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "transform_expr exceeded its iteration limit; last intermediate expression was {}", self.last)
+    }
+}
+
+impl std::error::Error for TransformLimitExceeded {}
+
+/// Like [`transform_expr`], but gives up after `max_iterations` fixpoint
+/// iterations instead of looping forever, returning the last intermediate
+/// expression via [`TransformLimitExceeded`].
+pub fn transform_expr_bounded(
+    e: Expr,
+    f: &dyn Fn(Expr) -> (Expr, bool),
+    max_iterations: usize,
+) -> Result<Expr, TransformLimitExceeded> {
+    let mut current = e;
+    for _ in 0..max_iterations {
+        let (next, changed): (Expr, bool) = match transform_expr_once_result::<std::convert::Infallible>(current, &|e| Ok(f(e))) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        };
+        if !changed {
+            return Ok(next);
+        }
+        current = next;
+    }
+    Err(TransformLimitExceeded { last: current })
+}
+
+/// Fallible variant of [`transform_expr`]: `f` may fail, in which case the
+/// whole traversal short-circuits and `transform_expr_result` returns `Err`
+/// without exposing any partially-rewritten tree to the caller.
+pub fn transform_expr_result<E>(e: Expr, f: &dyn Fn(Expr) -> Result<(Expr, bool), E>) -> Result<Expr, E> {
+    let (e, changed) = transform_expr_once_result(e, f)?;
+    if changed {
+        transform_expr_result(e, f)
+    } else {
+        Ok(e)
+    }
+}
+
+fn transform_expr_once_result<E>(e: Expr, f: &dyn Fn(Expr) -> Result<(Expr, bool), E>) -> Result<(Expr, bool), E> {
+    let (e, changed_here) = match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => (e, false),
+        Expr::Apply { func, args } => {
+            let (func, c1) = transform_expr_once_result(*func, f)?;
+            let mut changed = c1;
+            let mut new_args = Vec::with_capacity(args.len());
+            for a in args {
+                let (a, c) = transform_expr_once_result(a, f)?;
+                changed |= c;
+                new_args.push(a);
+            }
+            (Expr::Apply { func: Box::new(func), args: new_args }, changed)
+        }
+        Expr::Unop { symbol, operand } => {
+            let (operand, changed) = transform_expr_once_result(*operand, f)?;
+            (Expr::Unop { symbol, operand: Box::new(operand) }, changed)
+        }
+        Expr::Binop { symbol, l, r } => {
+            let (l, c1) = transform_expr_once_result(*l, f)?;
+            let (r, c2) = transform_expr_once_result(*r, f)?;
+            (Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) }, c1 || c2)
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let mut changed = false;
+            let mut new_exprs = Vec::with_capacity(exprs.len());
+            for e in exprs {
+                let (e, c) = transform_expr_once_result(e, f)?;
+                changed |= c;
+                new_exprs.push(e);
+            }
+            (Expr::AssocBinop { symbol, exprs: new_exprs }, changed)
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let (body, changed) = transform_expr_once_result(*body, f)?;
+            (Expr::Quantifier { symbol, name, body: Box::new(body) }, changed)
+        }
+    };
+    let (e, changed_at_root) = f(e)?;
+    Ok((e, changed_here || changed_at_root))
+}
+
+/// Which nodes [`transform_expr_with_strategy`] (and
+/// [`reduce_pattern_with_strategy`]) is allowed to rewrite, and whether it
+/// keeps going once something has changed. Needed on top of plain
+/// [`transform_expr`] because grading a student's proof step sometimes
+/// requires checking that exactly one rewrite was applied, not that the two
+/// sides are eventually reachable from each other by however many steps it
+/// takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// [`transform_expr`]'s existing behavior: an innermost-first (children
+    /// before parent) traversal, repeated until a full pass makes no change.
+    Fixpoint,
+    /// One innermost-first traversal of the whole tree, trying `f` at every
+    /// node exactly once. Because a node's children are rebuilt before `f`
+    /// is tried on the node itself, a rewrite at a child can expose a fresh
+    /// match at its parent within this same single pass — e.g. two separate
+    /// nested occurrences of a pattern can both fire in one `InnermostOnce`
+    /// call, the second only because the first already ran.
+    InnermostOnce,
+    /// One outermost-first (parent before children) traversal: `f` is tried
+    /// at a node before its children. If it fires, the rewritten result is
+    /// kept as-is and that subtree is not descended into any further (there
+    /// is nothing left there to visit in the old shape); if it does not
+    /// fire, traversal continues into the children, each independently able
+    /// to rewrite at their own outermost position.
+    OutermostOnce,
+    /// Visits nodes in the same innermost-first order as [`InnermostOnce`],
+    /// but stops at the very first node where `f` fires, leaving everything
+    /// else in the tree — including any other site that would also have
+    /// matched — untouched. This is what answers "does applying exactly one
+    /// rewrite step suffice?"
+    FirstMatchOnly,
+}
+
+fn transform_expr_outermost_once(e: Expr, f: &dyn Fn(Expr) -> (Expr, bool)) -> (Expr, bool) {
+    let (e, changed_here) = f(e);
+    if changed_here {
+        return (e, true);
+    }
+    match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => (e, false),
+        Expr::Apply { func, args } => {
+            let (func, c1) = transform_expr_outermost_once(*func, f);
+            let mut changed = c1;
+            let args = args
+                .into_iter()
+                .map(|a| {
+                    let (a, c) = transform_expr_outermost_once(a, f);
+                    changed |= c;
+                    a
+                })
+                .collect();
+            (Expr::Apply { func: Box::new(func), args }, changed)
+        }
+        Expr::Unop { symbol, operand } => {
+            let (operand, changed) = transform_expr_outermost_once(*operand, f);
+            (Expr::Unop { symbol, operand: Box::new(operand) }, changed)
+        }
+        Expr::Binop { symbol, l, r } => {
+            let (l, c1) = transform_expr_outermost_once(*l, f);
+            let (r, c2) = transform_expr_outermost_once(*r, f);
+            (Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) }, c1 || c2)
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let mut changed = false;
+            let exprs = exprs
+                .into_iter()
+                .map(|x| {
+                    let (x, c) = transform_expr_outermost_once(x, f);
+                    changed |= c;
+                    x
+                })
+                .collect();
+            (Expr::AssocBinop { symbol, exprs }, changed)
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let (body, changed) = transform_expr_outermost_once(*body, f);
+            (Expr::Quantifier { symbol, name, body: Box::new(body) }, changed)
+        }
+    }
+}
+
+/// Innermost-first traversal that stops rewriting as soon as `fired`
+/// becomes `true`: every call checks `fired` first (so once a match has
+/// fired anywhere, every later sibling and ancestor is passed through
+/// unchanged) and, after recursing into children, checks again before
+/// trying `f` on the current node (so the node whose child just fired is
+/// not also tested).
+fn transform_expr_first_match_only(e: Expr, f: &dyn Fn(Expr) -> (Expr, bool), fired: &mut bool) -> Expr {
+    if *fired {
+        return e;
+    }
+    let e = match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => e,
+        Expr::Apply { func, args } => {
+            let func = transform_expr_first_match_only(*func, f, fired);
+            let args = args.into_iter().map(|a| transform_expr_first_match_only(a, f, fired)).collect();
+            Expr::Apply { func: Box::new(func), args }
+        }
+        Expr::Unop { symbol, operand } => {
+            let operand = transform_expr_first_match_only(*operand, f, fired);
+            Expr::Unop { symbol, operand: Box::new(operand) }
+        }
+        Expr::Binop { symbol, l, r } => {
+            let l = transform_expr_first_match_only(*l, f, fired);
+            let r = transform_expr_first_match_only(*r, f, fired);
+            Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) }
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let exprs = exprs.into_iter().map(|x| transform_expr_first_match_only(x, f, fired)).collect();
+            Expr::AssocBinop { symbol, exprs }
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let body = transform_expr_first_match_only(*body, f, fired);
+            Expr::Quantifier { symbol, name, body: Box::new(body) }
+        }
+    };
+    if *fired {
+        return e;
+    }
+    let (e, changed) = f(e);
+    if changed {
+        *fired = true;
+    }
+    e
+}
+
+/// Like [`transform_expr`], but lets the caller pick which nodes get to
+/// rewrite via [`Strategy`] instead of always running an innermost-first
+/// traversal to a fixpoint.
+pub fn transform_expr_with_strategy(e: Expr, f: &dyn Fn(Expr) -> (Expr, bool), strategy: Strategy) -> Expr {
+    match strategy {
+        Strategy::Fixpoint => transform_expr(e, f),
+        Strategy::InnermostOnce => match transform_expr_once_result::<std::convert::Infallible>(e, &|e| Ok(f(e))) {
+            Ok((e, _)) => e,
+            Err(never) => match never {},
+        },
+        Strategy::OutermostOnce => transform_expr_outermost_once(e, f).0,
+        Strategy::FirstMatchOnly => {
+            let mut fired = false;
+            transform_expr_first_match_only(e, f, &mut fired)
+        }
+    }
+}
+
+/// The set of `Quantifier` binder names enclosing the node currently being
+/// visited by [`transform_expr_with_scope`].
+pub type Scope = std::collections::HashSet<String>;
+/// The closure type accepted by [`transform_expr_with_scope`].
+pub type ScopedTransformFn<'a> = dyn Fn(Expr, &Scope) -> (Expr, bool) + 'a;
+
+/// Like [`transform_expr`], but `f` additionally receives the set of binder
+/// names of every `Quantifier` enclosing the current node, so it can write
+/// scope-sensitive rewrites (e.g. "only touch free occurrences of `x`").
+/// Shadowing (the same name bound twice) is tracked correctly: the name
+/// stays in scope until the outermost binder that introduced it is left.
+pub fn transform_expr_with_scope(e: Expr, f: &ScopedTransformFn) -> Expr {
+    let mut scope = Scope::new();
+    let (e, changed) = transform_expr_with_scope_once(e, f, &mut scope);
+    if changed {
+        transform_expr_with_scope(e, f)
+    } else {
+        e
+    }
+}
+
+fn transform_expr_with_scope_once(e: Expr, f: &ScopedTransformFn, scope: &mut Scope) -> (Expr, bool) {
+    let (e, changed_here) = match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => (e, false),
+        Expr::Apply { func, args } => {
+            let (func, c1) = transform_expr_with_scope_once(*func, f, scope);
+            let mut changed = c1;
+            let args = args
+                .into_iter()
+                .map(|a| {
+                    let (a, c) = transform_expr_with_scope_once(a, f, scope);
+                    changed |= c;
+                    a
+                })
+                .collect();
+            (Expr::Apply { func: Box::new(func), args }, changed)
+        }
+        Expr::Unop { symbol, operand } => {
+            let (operand, changed) = transform_expr_with_scope_once(*operand, f, scope);
+            (Expr::Unop { symbol, operand: Box::new(operand) }, changed)
+        }
+        Expr::Binop { symbol, l, r } => {
+            let (l, c1) = transform_expr_with_scope_once(*l, f, scope);
+            let (r, c2) = transform_expr_with_scope_once(*r, f, scope);
+            (Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) }, c1 || c2)
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let mut changed = false;
+            let exprs = exprs
+                .into_iter()
+                .map(|e| {
+                    let (e, c) = transform_expr_with_scope_once(e, f, scope);
+                    changed |= c;
+                    e
+                })
+                .collect();
+            (Expr::AssocBinop { symbol, exprs }, changed)
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            let freshly_inserted = scope.insert(name.clone());
+            let (body, changed) = transform_expr_with_scope_once(*body, f, scope);
+            if freshly_inserted {
+                scope.remove(&name);
+            }
+            (Expr::Quantifier { symbol, name, body: Box::new(body) }, changed)
+        }
+    };
+    let (e, changed_at_root) = f(e, scope);
+    (e, changed_here || changed_at_root)
+}
+
+/// The closure type accepted by [`transform_expr_with_path`].
+pub type PathTransformFn<'a> = dyn Fn(Expr, &ExprPath) -> (Expr, bool) + 'a;
+
+/// Like [`transform_expr`], but `f` additionally receives the [`ExprPath`] of
+/// the node currently being visited, so a caller can record exactly where a
+/// rewrite fired even when the same subterm occurs at more than one
+/// position. The path is always relative to the tree as it stands going into
+/// the current fixpoint iteration, so it stays exact across occurrences that
+/// a rewrite elsewhere in the same pass has not yet disturbed.
+pub fn transform_expr_with_path(e: Expr, f: &PathTransformFn) -> Expr {
+    let mut path = Vec::new();
+    let (e, changed) = transform_expr_with_path_once(e, f, &mut path);
+    if changed {
+        transform_expr_with_path(e, f)
+    } else {
+        e
+    }
+}
+
+fn transform_expr_with_path_once(e: Expr, f: &PathTransformFn, path: &mut Vec<usize>) -> (Expr, bool) {
+    let (e, changed_here) = match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => (e, false),
+        Expr::Apply { func, args } => {
+            path.push(0);
+            let (func, c1) = transform_expr_with_path_once(*func, f, path);
+            path.pop();
+            let mut changed = c1;
+            let mut new_args = Vec::with_capacity(args.len());
+            for (i, a) in args.into_iter().enumerate() {
+                path.push(i + 1);
+                let (a, c) = transform_expr_with_path_once(a, f, path);
+                path.pop();
+                changed |= c;
+                new_args.push(a);
+            }
+            (Expr::Apply { func: Box::new(func), args: new_args }, changed)
+        }
+        Expr::Unop { symbol, operand } => {
+            path.push(0);
+            let (operand, changed) = transform_expr_with_path_once(*operand, f, path);
+            path.pop();
+            (Expr::Unop { symbol, operand: Box::new(operand) }, changed)
+        }
+        Expr::Binop { symbol, l, r } => {
+            path.push(0);
+            let (l, c1) = transform_expr_with_path_once(*l, f, path);
+            path.pop();
+            path.push(1);
+            let (r, c2) = transform_expr_with_path_once(*r, f, path);
+            path.pop();
+            (Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) }, c1 || c2)
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            let mut changed = false;
+            let mut new_exprs = Vec::with_capacity(exprs.len());
+            for (i, e) in exprs.into_iter().enumerate() {
+                path.push(i);
+                let (e, c) = transform_expr_with_path_once(e, f, path);
+                path.pop();
+                changed |= c;
+                new_exprs.push(e);
+            }
+            (Expr::AssocBinop { symbol, exprs: new_exprs }, changed)
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            path.push(0);
+            let (body, changed) = transform_expr_with_path_once(*body, f, path);
+            path.pop();
+            (Expr::Quantifier { symbol, name, body: Box::new(body) }, changed)
+        }
+    };
+    let current_path = ExprPath(path.clone());
+    let (e, changed_at_root) = f(e, &current_path);
+    (e, changed_here || changed_at_root)
+}
+
+/// Compare two expressions for equality up to consistent renaming of bound
+/// variables, without going through the unification machinery. Free
+/// variables must match exactly; bound variables may be renamed as long as
+/// the renaming is consistent within its binder's scope (shadowing included).
+pub fn alpha_equal(a: &Expr, b: &Expr) -> bool {
+    // `env` is a stack of (name in `a`, name in `b`) pairs for binders
+    // currently in scope, searched from the innermost outward so shadowed
+    // binders resolve correctly.
+    fn go(a: &Expr, b: &Expr, env: &mut Vec<(String, String)>) -> bool {
+        match (a, b) {
+            (Expr::Contradiction, Expr::Contradiction) => true,
+            (Expr::Tautology, Expr::Tautology) => true,
+            (Expr::Var { name: n1 }, Expr::Var { name: n2 }) => {
+                for (x, y) in env.iter().rev() {
+                    if x == n1 || y == n2 {
+                        return x == n1 && y == n2;
+                    }
+                }
+                n1 == n2
+            }
+            (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) => {
+                a1.len() == a2.len() && go(f1, f2, env) && a1.iter().zip(a2.iter()).all(|(x, y)| go(x, y, env))
+            }
+            (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) => {
+                s1 == s2 && go(o1, o2, env)
+            }
+            (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) => {
+                s1 == s2 && go(l1, l2, env) && go(r1, r2, env)
+            }
+            (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) => {
+                s1 == s2 && e1.len() == e2.len() && e1.iter().zip(e2.iter()).all(|(x, y)| go(x, y, env))
+            }
+            (
+                Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+                Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+            ) => {
+                if s1 != s2 {
+                    return false;
+                }
+                env.push((n1.clone(), n2.clone()));
+                let result = go(b1, b2, env);
+                env.pop();
+                result
+            }
+            _ => false,
+        }
+    }
+    go(a, b, &mut Vec::new())
+}
+
+/// Hashes `e` in a way that depends only on its de Bruijn form, so that
+/// `alpha_equal(a, b)` implies `alpha_hash(a) == alpha_hash(b)`: renaming a
+/// bound variable never changes the hash, but free variable names still do
+/// (they have no binder to erase them against). Intended for memoizing
+/// expensive checks (e.g. [`is_tautology`]) keyed by expression without
+/// missing cache hits after alpha-renaming; see [`AlphaHashed`] for a
+/// ready-made `HashMap` key wrapper.
+pub fn alpha_hash(e: &Expr) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    debruijn::to_debruijn(e).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A `HashMap`/`HashSet` key wrapper around [`Expr`] whose `Hash` and
+/// `PartialEq` are alpha-equivalence-aware ([`alpha_hash`] and
+/// [`alpha_equal`]) rather than the derived, binder-name-sensitive ones on
+/// `Expr` itself. Two expressions that differ only by bound variable names
+/// collide in a `HashMap<AlphaHashed, _>`.
+#[derive(Clone, Debug)]
+pub struct AlphaHashed(pub Expr);
+
+impl PartialEq for AlphaHashed {
+    fn eq(&self, other: &Self) -> bool {
+        alpha_equal(&self.0, &other.0)
+    }
+}
+
+impl Eq for AlphaHashed {}
+
+impl std::hash::Hash for AlphaHashed {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(alpha_hash(&self.0));
+    }
+}
+
+/// One spot where [`expr_diff`] found `a` and `b` to disagree: `path` names
+/// the shallowest subterm at which they diverge, and `a`/`b` are the
+/// mismatching subterms themselves (a differing connective, a differing
+/// variable, a differing arity, or — for `AssocBinop` lists that don't line
+/// up after matching a common prefix/suffix — the unmatched remainder of
+/// each list).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffSite {
+    pub path: ExprPath,
+    pub a: Expr,
+    pub b: Expr,
+}
+
+/// Finds where `a` and `b` structurally disagree, descending in lockstep and
+/// stopping at the shallowest mismatching nodes rather than continuing past
+/// them. Two expressions with no disagreement (including being completely
+/// equal) diff to an empty list.
+///
+/// `AssocBinop` operand lists of different lengths are not aligned
+/// element-by-element, since in general there's no canonical way to pair up
+/// a shorter list against a longer one; instead a single [`DiffSite`] is
+/// reported for the lists themselves, after first greedily trimming away any
+/// common leading and trailing operands (which cannot be the cause of the
+/// length mismatch) so the reported site is as small as possible.
+pub fn expr_diff(a: &Expr, b: &Expr) -> Vec<DiffSite> {
+    let mut sites = Vec::new();
+    diff_go(a, b, &mut Vec::new(), &mut sites);
+    sites
+}
+
+fn diff_go(a: &Expr, b: &Expr, path: &mut Vec<usize>, sites: &mut Vec<DiffSite>) {
+    match (a, b) {
+        (Expr::Contradiction, Expr::Contradiction) | (Expr::Tautology, Expr::Tautology) => {}
+        (Expr::Var { name: n1 }, Expr::Var { name: n2 }) if n1 == n2 => {}
+        (Expr::Apply { func: f1, args: a1 }, Expr::Apply { func: f2, args: a2 }) if a1.len() == a2.len() => {
+            path.push(0);
+            diff_go(f1, f2, path, sites);
+            path.pop();
+            for (i, (x, y)) in a1.iter().zip(a2.iter()).enumerate() {
+                path.push(i + 1);
+                diff_go(x, y, path, sites);
+                path.pop();
+            }
+        }
+        (Expr::Unop { symbol: s1, operand: o1 }, Expr::Unop { symbol: s2, operand: o2 }) if s1 == s2 => {
+            path.push(0);
+            diff_go(o1, o2, path, sites);
+            path.pop();
+        }
+        (Expr::Binop { symbol: s1, l: l1, r: r1 }, Expr::Binop { symbol: s2, l: l2, r: r2 }) if s1 == s2 => {
+            path.push(0);
+            diff_go(l1, l2, path, sites);
+            path.pop();
+            path.push(1);
+            diff_go(r1, r2, path, sites);
+            path.pop();
+        }
+        (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) if s1 == s2 && e1.len() == e2.len() => {
+            for (i, (x, y)) in e1.iter().zip(e2.iter()).enumerate() {
+                path.push(i);
+                diff_go(x, y, path, sites);
+                path.pop();
+            }
+        }
+        (Expr::AssocBinop { symbol: s1, exprs: e1 }, Expr::AssocBinop { symbol: s2, exprs: e2 }) if s1 == s2 => {
+            let prefix = e1.iter().zip(e2.iter()).take_while(|(x, y)| x == y).count();
+            let max_suffix = e1.len().min(e2.len()) - prefix;
+            let suffix = e1[prefix..].iter().rev().zip(e2[prefix..].iter().rev()).take(max_suffix).take_while(|(x, y)| x == y).count();
+            let mid_a = e1[prefix..e1.len() - suffix].to_vec();
+            let mid_b = e2[prefix..e2.len() - suffix].to_vec();
+            sites.push(DiffSite {
+                path: ExprPath(path.clone()),
+                a: Expr::assoc(*s1, mid_a),
+                b: Expr::assoc(*s2, mid_b),
+            });
+        }
+        (
+            Expr::Quantifier { symbol: s1, name: n1, body: b1 },
+            Expr::Quantifier { symbol: s2, name: n2, body: b2 },
+        ) if s1 == s2 && n1 == n2 => {
+            path.push(0);
+            diff_go(b1, b2, path, sites);
+            path.pop();
+        }
+        _ => sites.push(DiffSite { path: ExprPath(path.clone()), a: a.clone(), b: b.clone() }),
+    }
+}
+
+/// Replaces every subexpression of `e` structurally equal to `target` (or,
+/// if `alpha_equivalent` is set, every subexpression alpha-equivalent to
+/// `target`) with `replacement`, returning the rewritten expression and how
+/// many replacements were made. Unlike [`transform_expr`], this is a single
+/// top-down pass that never descends into a freshly inserted `replacement`,
+/// so it terminates even when `replacement` itself contains `target`.
+pub fn replace_subexpr(e: Expr, target: &Expr, replacement: &Expr, alpha_equivalent: bool) -> (Expr, usize) {
+    fn matches(e: &Expr, target: &Expr, alpha_equivalent: bool) -> bool {
+        if alpha_equivalent {
+            alpha_equal(e, target)
+        } else {
+            e == target
+        }
+    }
+
+    fn go(e: Expr, target: &Expr, replacement: &Expr, alpha_equivalent: bool, count: &mut usize) -> Expr {
+        if matches(&e, target, alpha_equivalent) {
+            *count += 1;
+            return replacement.clone();
+        }
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => e,
+            Expr::Apply { func, args } => Expr::Apply {
+                func: Box::new(go(*func, target, replacement, alpha_equivalent, count)),
+                args: args.into_iter().map(|a| go(a, target, replacement, alpha_equivalent, count)).collect(),
+            },
+            Expr::Unop { symbol, operand } => {
+                Expr::Unop { symbol, operand: Box::new(go(*operand, target, replacement, alpha_equivalent, count)) }
+            }
+            Expr::Binop { symbol, l, r } => Expr::Binop {
+                symbol,
+                l: Box::new(go(*l, target, replacement, alpha_equivalent, count)),
+                r: Box::new(go(*r, target, replacement, alpha_equivalent, count)),
+            },
+            Expr::AssocBinop { symbol, exprs } => Expr::AssocBinop {
+                symbol,
+                exprs: exprs.into_iter().map(|x| go(x, target, replacement, alpha_equivalent, count)).collect(),
+            },
+            Expr::Quantifier { symbol, name, body } => {
+                Expr::Quantifier { symbol, name, body: Box::new(go(*body, target, replacement, alpha_equivalent, count)) }
+            }
+        }
+    }
+
+    let mut count = 0;
+    let result = go(e, target, replacement, alpha_equivalent, &mut count);
+    (result, count)
+}
+
+/// Options for [`to_dot_with`]: which paths in the tree to render in a
+/// different color, and what that color should be (any color name or
+/// `#rrggbb` triple Graphviz accepts).
+#[derive(Clone, Debug)]
+pub struct DotOptions {
+    pub highlighted: std::collections::HashSet<ExprPath>,
+    pub highlight_color: String,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions { highlighted: std::collections::HashSet::new(), highlight_color: "red".to_owned() }
+    }
+}
+
+fn escape_dot_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Renders `e` as a Graphviz DOT digraph for debugging rewrite pipelines and
+/// for lecture slides. Shorthand for `to_dot_with(e, &DotOptions::default())`.
+pub fn to_dot(e: &Expr) -> String {
+    to_dot_with(e, &DotOptions::default())
+}
+
+/// Like [`to_dot`], but additionally draws every node whose path is in
+/// `options.highlighted` in `options.highlight_color` (e.g. to visualize
+/// where a rewrite just fired). Each node gets a fresh, unique id even when
+/// two subtrees are structurally equal, so the tree renders as a tree rather
+/// than collapsing shared-looking nodes together.
+pub fn to_dot_with(e: &Expr, options: &DotOptions) -> String {
+    fn node_label(e: &Expr) -> String {
+        match e {
+            Expr::Contradiction => "_|_".to_owned(),
+            Expr::Tautology => "T".to_owned(),
+            Expr::Var { name } => name.clone(),
+            Expr::Apply { .. } => "Apply".to_owned(),
+            Expr::Unop { symbol: USymbol::Not, .. } => "~".to_owned(),
+            Expr::Binop { symbol, .. } => match symbol {
+                BSymbol::Implies => "->",
+                BSymbol::Plus => "+",
+                BSymbol::Mult => "*",
+                BSymbol::Nand => "!&",
+                BSymbol::Nor => "!|",
+            }
+            .to_owned(),
+            Expr::AssocBinop { symbol, .. } => match symbol {
+                ASymbol::And => "&",
+                ASymbol::Or => "|",
+                ASymbol::Bicon => "<=>",
+                ASymbol::Equiv => "===",
+                ASymbol::Xor => "^",
+            }
+            .to_owned(),
+            Expr::Quantifier { symbol, name, .. } => {
+                let q = match symbol {
+                    QSymbol::Forall => "forall",
+                    QSymbol::Exists => "exists",
+                };
+                format!("{} {}", q, name)
+            }
+        }
+    }
+
+    fn go(e: &Expr, path: &mut Vec<usize>, next_id: &mut usize, options: &DotOptions, out: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        let attrs = if options.highlighted.contains(&ExprPath(path.clone())) {
+            format!(", color=\"{0}\", fontcolor=\"{0}\"", options.highlight_color)
+        } else {
+            String::new()
+        };
+        out.push_str(&format!("  n{} [label=\"{}\"{}];\n", id, escape_dot_label(&node_label(e)), attrs));
+
+        let link_child = |child: &Expr, index: usize, path: &mut Vec<usize>, next_id: &mut usize, out: &mut String| {
+            path.push(index);
+            let child_id = go(child, path, next_id, options, out);
+            path.pop();
+            out.push_str(&format!("  n{} -> n{};\n", id, child_id));
+        };
+
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+            Expr::Apply { func, args } => {
+                link_child(func, 0, path, next_id, out);
+                for (i, a) in args.iter().enumerate() {
+                    link_child(a, i + 1, path, next_id, out);
+                }
+            }
+            Expr::Unop { operand, .. } => link_child(operand, 0, path, next_id, out),
+            Expr::Binop { l, r, .. } => {
+                link_child(l, 0, path, next_id, out);
+                link_child(r, 1, path, next_id, out);
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for (i, x) in exprs.iter().enumerate() {
+                    link_child(x, i, path, next_id, out);
+                }
+            }
+            Expr::Quantifier { body, .. } => link_child(body, 0, path, next_id, out),
+        }
+        id
+    }
+
+    let mut out = String::from("digraph Expr {\n");
+    let mut next_id = 0usize;
+    go(e, &mut Vec::new(), &mut next_id, options, &mut out);
+    out.push_str("}\n");
+    out
+}
+
+/// Renames every `Quantifier` binder to a name produced by [`gensym`], so
+/// that no binder in the result shadows another binder or clashes with
+/// `avoid` or `freevars(e)`. Substitution into each body is capture-avoiding,
+/// so the result is alpha-equivalent to `e` (verifiable with [`alpha_equal`]).
+pub fn freshen_binders(e: &Expr, avoid: &std::collections::HashSet<String>) -> Expr {
+    fn go(e: &Expr, taken: &mut std::collections::HashSet<String>) -> Expr {
+        match e {
+            Expr::Contradiction => Expr::Contradiction,
+            Expr::Tautology => Expr::Tautology,
+            Expr::Var { name } => Expr::var(name),
+            Expr::Apply { func, args } => Expr::Apply {
+                func: Box::new(go(func, taken)),
+                args: args.iter().map(|a| go(a, taken)).collect(),
+            },
+            Expr::Unop { symbol, operand } => Expr::Unop { symbol: *symbol, operand: Box::new(go(operand, taken)) },
+            Expr::Binop { symbol, l, r } => {
+                Expr::Binop { symbol: *symbol, l: Box::new(go(l, taken)), r: Box::new(go(r, taken)) }
+            }
+            Expr::AssocBinop { symbol, exprs } => {
+                Expr::AssocBinop { symbol: *symbol, exprs: exprs.iter().map(|x| go(x, taken)).collect() }
+            }
+            Expr::Quantifier { symbol, name, body } => {
+                let mut fresh_name = gensym(name);
+                while taken.contains(&fresh_name) {
+                    fresh_name = gensym(name);
+                }
+                taken.insert(fresh_name.clone());
+                let renamed_body = subst(name, &Expr::var(&fresh_name), go(body, taken));
+                Expr::Quantifier { symbol: *symbol, name: fresh_name, body: Box::new(renamed_body) }
+            }
+        }
+    }
+    let mut taken = avoid.clone();
+    taken.extend(freevars(e));
+    go(e, &mut taken)
+}
+
+/// The metavariables a pattern may bind: every [`Expr::Var`] in `pattern`
+/// whose name [`is_metavar`].
+pub(crate) fn pattern_vars_of(pattern: &Expr) -> std::collections::HashSet<String> {
+    pattern
+        .subexprs()
+        .filter_map(|e| match e {
+            Expr::Var { name } if is_metavar(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tries to match every element of `pe` against some unused element of `se`
+/// (all of `AssocBinop`'s connectives are commutative, so any permutation is
+/// a legal pairing), backtracking on failure. `pe.len() == se.len()` is
+/// required by the caller. Worst case is `se.len()!` trial matches, so this
+/// is only reasonable for the small operand counts `AssocBinop` actually
+/// has in practice; bindings already made by an earlier pairing prune later
+/// branches since a repeated metavariable must then match exactly.
+fn match_assoc_commutative(
+    pe: &[Expr],
+    se: &[Expr],
+    pattern_vars: &std::collections::HashSet<String>,
+    bindings: &mut Substitution,
+) -> bool {
+    fn go(
+        pe: &[Expr],
+        idx: usize,
+        se: &[Expr],
+        used: &mut Vec<bool>,
+        pattern_vars: &std::collections::HashSet<String>,
+        bindings: &mut Substitution,
+    ) -> bool {
+        if idx == pe.len() {
+            return true;
+        }
+        for j in 0..se.len() {
+            if used[j] {
+                continue;
+            }
+            let mut trial = bindings.clone();
+            used[j] = true;
+            if match_inner(&pe[idx], &se[j], pattern_vars, true, &mut trial) && go(pe, idx + 1, se, used, pattern_vars, &mut trial) {
+                *bindings = trial;
+                return true;
+            }
+            used[j] = false;
+        }
+        false
+    }
+    let mut used = vec![false; se.len()];
+    go(pe, 0, se, &mut used, pattern_vars, bindings)
+}
+
+/// The rest-variable convention for variadic `AssocBinop` patterns: a
+/// pattern metavariable named `__foo...` (a metavariable, per [`is_metavar`],
+/// whose name ends in a literal `...`) may appear as one of an `AssocBinop`
+/// pattern's operands to mean "every operand not otherwise matched by a
+/// sibling operand in this pattern, bundled back up into one `AssocBinop`".
+fn is_rest_var(name: &str) -> bool {
+    is_metavar(name) && name.ends_with("...")
+}
+
+/// Finds the rest-variable operand of an `AssocBinop` pattern's operand
+/// list, if it has one. At most one is supported; `pe` is a pattern so this
+/// is checked once per pattern, not once per subexpression matched against.
+fn find_rest_var<'a>(pe: &'a [Expr], pattern_vars: &std::collections::HashSet<String>) -> Option<&'a str> {
+    pe.iter().find_map(|e| match e {
+        Expr::Var { name } if pattern_vars.contains(name) && is_rest_var(name) => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+/// Rebuilds an `AssocBinop` of `symbol` from `exprs`, collapsing to the
+/// connective's identity element when `exprs` is empty exactly like
+/// [`from_conjuncts`]/[`from_disjuncts`] do, so a rest-variable binding that
+/// ends up empty (every operand was consumed by a fixed pattern operand)
+/// vanishes cleanly from the instantiated replacement instead of leaving a
+/// malformed zero-operand `AssocBinop`.
+fn rebuild_assoc(symbol: ASymbol, exprs: Vec<Expr>) -> Expr {
+    match symbol {
+        ASymbol::And => from_conjuncts(exprs),
+        ASymbol::Or => from_disjuncts(exprs),
+        _ => Expr::AssocBinop { symbol, exprs },
+    }
+}
+
+/// Matches each of a pattern's non-rest operands (`fixed`) against some
+/// unused element of the subject's operands (`se`), exactly like
+/// [`match_assoc_commutative`] but allowing `se` to have more elements than
+/// `fixed` — whatever's left over after a successful match is bound to
+/// `rest_name` via [`rebuild_assoc`]. Fails outright if `se` has fewer
+/// elements than `fixed`.
+fn match_assoc_with_rest(
+    fixed: &[&Expr],
+    se: &[Expr],
+    rest_name: &str,
+    symbol: ASymbol,
+    pattern_vars: &std::collections::HashSet<String>,
+    bindings: &mut Substitution,
+) -> bool {
+    fn go(
+        fixed: &[&Expr],
+        idx: usize,
+        se: &[Expr],
+        used: &mut Vec<bool>,
+        pattern_vars: &std::collections::HashSet<String>,
+        bindings: &mut Substitution,
+    ) -> bool {
+        if idx == fixed.len() {
+            return true;
+        }
+        for j in 0..se.len() {
+            if used[j] {
+                continue;
+            }
+            let mut trial = bindings.clone();
+            used[j] = true;
+            if match_inner(fixed[idx], &se[j], pattern_vars, true, &mut trial) && go(fixed, idx + 1, se, used, pattern_vars, &mut trial) {
+                *bindings = trial;
+                return true;
+            }
+            used[j] = false;
+        }
+        false
+    }
+    if fixed.len() > se.len() {
+        return false;
+    }
+    let mut used = vec![false; se.len()];
+    if !go(fixed, 0, se, &mut used, pattern_vars, bindings) {
+        return false;
+    }
+    let leftover: Vec<Expr> = se.iter().zip(&used).filter(|(_, used)| !**used).map(|(e, _)| e.clone()).collect();
+    bindings.insert(rest_name.to_owned(), rebuild_assoc(symbol, leftover));
+    true
+}
+
+fn match_inner(pattern: &Expr, subject: &Expr, pattern_vars: &std::collections::HashSet<String>, commutative: bool, bindings: &mut Substitution) -> bool {
+    if let Expr::Var { name } = pattern {
+        if pattern_vars.contains(name) {
+            return match bindings.get(name) {
+                Some(bound) => bound == subject,
+                None => {
+                    bindings.insert(name.clone(), subject.clone());
+                    true
+                }
+            };
+        }
+    }
+    match (pattern, subject) {
+        (Expr::Contradiction, Expr::Contradiction) => true,
+        (Expr::Tautology, Expr::Tautology) => true,
+        (Expr::Var { name: p }, Expr::Var { name: s }) => p == s,
+        (Expr::Apply { func: pf, args: pa }, Expr::Apply { func: sf, args: sa }) => {
+            pa.len() == sa.len()
+                && match_inner(pf, sf, pattern_vars, commutative, bindings)
+                && pa.iter().zip(sa).all(|(p, s)| match_inner(p, s, pattern_vars, commutative, bindings))
+        }
+        (Expr::Unop { symbol: ps, operand: po }, Expr::Unop { symbol: ss, operand: so }) => {
+            ps == ss && match_inner(po, so, pattern_vars, commutative, bindings)
+        }
+        (Expr::Binop { symbol: ps, l: pl, r: pr }, Expr::Binop { symbol: ss, l: sl, r: sr }) => {
+            ps == ss && match_inner(pl, sl, pattern_vars, commutative, bindings) && match_inner(pr, sr, pattern_vars, commutative, bindings)
+        }
+        (Expr::AssocBinop { symbol: ps, exprs: pe }, Expr::AssocBinop { symbol: ss, exprs: se }) => {
+            if ps != ss {
+                false
+            } else if let Some(rest_name) = find_rest_var(pe, pattern_vars) {
+                let fixed: Vec<&Expr> = pe.iter().filter(|e| !matches!(e, Expr::Var { name } if name == rest_name)).collect();
+                match_assoc_with_rest(&fixed, se, rest_name, *ps, pattern_vars, bindings)
+            } else if pe.len() != se.len() {
+                false
+            } else if commutative {
+                match_assoc_commutative(pe, se, pattern_vars, bindings)
+            } else {
+                pe.iter().zip(se).all(|(p, s)| match_inner(p, s, pattern_vars, commutative, bindings))
+            }
+        }
+        (Expr::Quantifier { symbol: ps, name: pn, body: pb }, Expr::Quantifier { symbol: ss, name: sn, body: sb }) => {
+            ps == ss && pn == sn && match_inner(pb, sb, pattern_vars, commutative, bindings)
+        }
+        _ => false,
+    }
+}
+
+/// Matches `pattern` against `subject` one-sidedly: only the names in
+/// `pattern_vars` are treated as bindable metavariables, every other `Var`
+/// in `pattern` must match a `Var` of the same name in `subject`, and
+/// `subject` is never substituted into (bindings only ever flow out of it).
+/// A metavariable that occurs more than once in `pattern` must bind to
+/// structurally equal subtrees everywhere it occurs. Unlike [`unify`], this
+/// is one-sided: a `Var` in `subject` that happens to share a name with a
+/// pattern variable is matched literally, not bound.
+///
+/// If `commutative` is set, `AssocBinop` operands (all of which are
+/// commutative connectives) are matched up to permutation rather than
+/// requiring the pattern and subject to list them in the same order; see
+/// [`reduce_pattern_ac`] for the rewriting entry point that sets this.
+pub fn match_expr(pattern: &Expr, subject: &Expr, pattern_vars: &std::collections::HashSet<String>, commutative: bool) -> Option<Substitution> {
+    let mut bindings = Substitution::new();
+    if match_inner(pattern, subject, pattern_vars, commutative, &mut bindings) {
+        Some(bindings)
+    } else {
+        None
+    }
+}
+
+fn validate_patterns(patterns: &[(Expr, Expr)]) {
+    for (pattern, replacement) in patterns {
+        let pattern_metavars = pattern_vars_of(pattern);
+        debug_assert!(
+            replacement
+                .subexprs()
+                .filter_map(|e| match e {
+                    Expr::Var { name } if is_metavar(name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .all(|name| pattern_metavars.contains(name)),
+            "reduce_pattern: replacement {} uses a metavariable not bound by pattern {}",
+            replacement,
+            pattern
+        );
+    }
+}
+
+fn apply_patterns_once(e: Expr, patterns: &[(Expr, Expr)], pattern_vars: &[std::collections::HashSet<String>], commutative: bool) -> (Expr, bool) {
+    for ((pattern, replacement), vars) in patterns.iter().zip(pattern_vars) {
+        if let Some(subs) = match_expr(pattern, &e, vars, commutative) {
+            return (subst_map(replacement, &subs), true);
+        }
+    }
+    (e, false)
+}
+
+/// Why [`reduce_pattern`] rejected a `(pattern, replacement)` pair instead of
+/// reducing with it. `pattern_index` names the offending pair's position in
+/// the `patterns` slice that was passed in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternError {
+    pub pattern_index: usize,
+    pub kind: PatternErrorKind,
+}
+
+/// What was wrong with a malformed pattern, as reported by [`PatternError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    /// `replacement` mentions a metavariable that `pattern` never binds, so
+    /// there would be nothing to substitute in for it at the call site. This
+    /// is a defect in how the pattern pair itself was written, not something
+    /// that depends on the subject being reduced — unlike a subject merely
+    /// failing to match, which is reported as "no match", not an error.
+    UnboundReplacementVariable { name: String },
+    /// Like `UnboundReplacementVariable`, but for a [`Pattern`] wildcard
+    /// hole: `replacement` reuses a hole name that `pattern` never binds,
+    /// so [`instantiate_pattern`] would have nothing to resolve it to.
+    UnboundReplacementHole { name: String },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            PatternErrorKind::UnboundReplacementVariable { name } => {
+                write!(f, "pattern {}: replacement uses metavariable {} which the pattern never binds", self.pattern_index, name)
+            }
+            PatternErrorKind::UnboundReplacementHole { name } => {
+                write!(f, "pattern {}: replacement uses wildcard hole {} which the pattern never binds", self.pattern_index, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+fn check_patterns(patterns: &[(Expr, Expr)]) -> Result<(), PatternError> {
+    for (pattern_index, (pattern, replacement)) in patterns.iter().enumerate() {
+        let pattern_metavars = pattern_vars_of(pattern);
+        for e in replacement.subexprs() {
+            if let Expr::Var { name } = e {
+                if is_metavar(name) && !pattern_metavars.contains(name) {
+                    return Err(PatternError { pattern_index, kind: PatternErrorKind::UnboundReplacementVariable { name: name.clone() } });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply a set of `(pattern, replacement)` pairs to `e` until none of them
+/// fire any more, by matching `pattern` against each subexpression with
+/// [`match_expr`] and, on a match, substituting into `replacement`. Returns
+/// a [`PatternError`] instead of panicking if one of the `patterns` is
+/// malformed (e.g. a replacement introduces a variable its pattern never
+/// bound) — patterns will eventually come from instructor-supplied rule
+/// files, so a badly authored one should be a reportable error rather than
+/// a crash. A subject simply failing to match every pattern is not an
+/// error: it is reported by leaving that subexpression unchanged, same as
+/// always.
+pub fn reduce_pattern(e: Expr, patterns: &[(Expr, Expr)]) -> Result<Expr, PatternError> {
+    reduce_pattern_with_strategy(e, patterns, Strategy::Fixpoint)
+}
+
+/// Like [`reduce_pattern`], but lets the caller pick a [`Strategy`] other
+/// than the default fixpoint — e.g. `Strategy::FirstMatchOnly` to check that
+/// a single rewrite step, applied anywhere in the tree, is enough to turn
+/// one side of a claimed equivalence into the other.
+pub fn reduce_pattern_with_strategy(e: Expr, patterns: &[(Expr, Expr)], strategy: Strategy) -> Result<Expr, PatternError> {
+    check_patterns(patterns)?;
+    let pattern_vars: Vec<_> = patterns.iter().map(|(p, _)| pattern_vars_of(p)).collect();
+    Ok(transform_expr_with_strategy(e, &|e| apply_patterns_once(e, patterns, &pattern_vars, false), strategy))
+}
+
+/// Like [`reduce_pattern`], but matches `AssocBinop` operands up to
+/// permutation (`commutative: true` on [`match_expr`]) instead of requiring
+/// the pattern to list them in the pattern's exact order. This lets a
+/// pattern like `phi & (phi | psi)` match `(a | b) & a` without the caller
+/// having to write out every operand ordering by hand.
+pub fn reduce_pattern_ac(e: Expr, patterns: &[(Expr, Expr)]) -> Expr {
+    validate_patterns(patterns);
+    let pattern_vars: Vec<_> = patterns.iter().map(|(p, _)| pattern_vars_of(p)).collect();
+    transform_expr(e, &|e| apply_patterns_once(e, patterns, &pattern_vars, true))
+}
+
+/// A symbol bound by one of [`Pattern`]'s wildcard holes. Unlike
+/// [`Substitution`]'s bindings, a hole stands for the connective itself, not
+/// a subexpression, so [`PatternMatch`] keeps it in its own map rather than
+/// trying to shoehorn it into a `Substitution`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolBinding {
+    Binop(BSymbol),
+    Assoc(ASymbol),
+    Quantifier(QSymbol),
+}
+
+/// What [`match_pattern`] found: ordinary `__`-prefixed pattern variables in
+/// `bindings`, exactly as from [`match_expr`], plus whichever symbol each of
+/// the pattern's wildcard holes (see [`Pattern`]) actually matched, in
+/// `symbols`.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PatternMatch {
+    pub bindings: Substitution,
+    pub symbols: std::collections::HashMap<String, SymbolBinding>,
+}
+
+/// Like an `Expr`, but a `Binop`, `AssocBinop`, or `Quantifier` position can
+/// instead be a wildcard hole matching *any* symbol of that shape (any
+/// `BSymbol`, any `ASymbol`, any `QSymbol`), binding it under `hole`'s name
+/// so a replacement [`Pattern`] can reuse whichever symbol actually
+/// matched. This is what lets one pattern express, e.g., "drop the
+/// outermost connective's duplicate operand" for `&` and `|` alike — an
+/// ordinary `Expr` pattern can't, since `Expr::Binop`/`AssocBinop`/
+/// `Quantifier` always carry one concrete symbol, never a placeholder for
+/// "whichever one was there."
+///
+/// Everywhere else — ordinary formula structure, and `__`-prefixed pattern
+/// variables — is expressed exactly as in `Expr` via `Pattern::Literal`,
+/// rather than this type re-deriving a parallel case for every `Expr`
+/// variant that has no wildcard to offer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// An ordinary `Expr`, matched with [`match_expr`]'s semantics
+    /// (including its own `__`-prefixed pattern variables) and instantiated
+    /// with [`subst_map`], same as [`reduce_pattern`] already does.
+    Literal(Expr),
+    /// Matches a `Binop` of any symbol, recording it under `hole`.
+    AnyBinop { hole: String, l: Box<Pattern>, r: Box<Pattern> },
+    /// Matches an `AssocBinop` of any symbol with exactly `exprs.len()`
+    /// operands, recording the symbol under `hole`.
+    AnyAssocBinop { hole: String, exprs: Vec<Pattern> },
+    /// Matches a `Quantifier` of any kind whose bound variable is literally
+    /// named `name` (no alpha-renaming, same limitation [`match_inner`] has
+    /// for an ordinary `Expr::Quantifier` pattern), recording the symbol
+    /// under `hole`.
+    AnyQuantifier { hole: String, name: String, body: Box<Pattern> },
+}
+
+/// Matches `pattern` against `subject`: literal parts behave exactly like
+/// [`match_expr`] (non-commutative; one-sided — `subject` is never
+/// substituted into), and a wildcard hole matches any symbol of its shape,
+/// recording which one it was.
+pub fn match_pattern(pattern: &Pattern, subject: &Expr) -> Option<PatternMatch> {
+    let mut result = PatternMatch::default();
+    if match_pattern_inner(pattern, subject, &mut result) {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+fn match_pattern_inner(pattern: &Pattern, subject: &Expr, result: &mut PatternMatch) -> bool {
+    match pattern {
+        Pattern::Literal(p) => {
+            let pattern_vars = pattern_vars_of(p);
+            match_inner(p, subject, &pattern_vars, false, &mut result.bindings)
+        }
+        Pattern::AnyBinop { hole, l, r } => match subject {
+            Expr::Binop { symbol, l: sl, r: sr } => {
+                result.symbols.insert(hole.clone(), SymbolBinding::Binop(*symbol));
+                match_pattern_inner(l, sl, result) && match_pattern_inner(r, sr, result)
+            }
+            _ => false,
+        },
+        Pattern::AnyAssocBinop { hole, exprs } => match subject {
+            Expr::AssocBinop { symbol, exprs: sexprs } if exprs.len() == sexprs.len() => {
+                result.symbols.insert(hole.clone(), SymbolBinding::Assoc(*symbol));
+                exprs.iter().zip(sexprs).all(|(p, s)| match_pattern_inner(p, s, result))
+            }
+            _ => false,
+        },
+        Pattern::AnyQuantifier { hole, name, body } => match subject {
+            Expr::Quantifier { symbol, name: sname, body: sbody } if name == sname => {
+                result.symbols.insert(hole.clone(), SymbolBinding::Quantifier(*symbol));
+                match_pattern_inner(body, sbody, result)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// The names of every `__`-prefixed pattern variable appearing in any
+/// `Pattern::Literal` embedded anywhere in `pattern`, analogous to
+/// [`pattern_vars_of`] for a plain `Expr`.
+fn pattern_vars_of_pattern(pattern: &Pattern) -> std::collections::HashSet<String> {
+    match pattern {
+        Pattern::Literal(e) => pattern_vars_of(e),
+        Pattern::AnyBinop { l, r, .. } => pattern_vars_of_pattern(l).union(&pattern_vars_of_pattern(r)).cloned().collect(),
+        Pattern::AnyAssocBinop { exprs, .. } => exprs.iter().flat_map(pattern_vars_of_pattern).collect(),
+        Pattern::AnyQuantifier { body, .. } => pattern_vars_of_pattern(body),
+    }
+}
+
+/// The names of every wildcard hole appearing anywhere in `pattern`.
+fn pattern_holes_of(pattern: &Pattern) -> std::collections::HashSet<String> {
+    match pattern {
+        Pattern::Literal(_) => std::collections::HashSet::new(),
+        Pattern::AnyBinop { hole, l, r } => {
+            let mut holes = pattern_holes_of(l);
+            holes.extend(pattern_holes_of(r));
+            holes.insert(hole.clone());
+            holes
+        }
+        Pattern::AnyAssocBinop { hole, exprs } => {
+            let mut holes: std::collections::HashSet<String> = exprs.iter().flat_map(pattern_holes_of).collect();
+            holes.insert(hole.clone());
+            holes
+        }
+        Pattern::AnyQuantifier { hole, body, .. } => {
+            let mut holes = pattern_holes_of(body);
+            holes.insert(hole.clone());
+            holes
+        }
+    }
+}
+
+fn check_pattern_holes(patterns: &[(Pattern, Pattern)]) -> Result<(), PatternError> {
+    for (pattern_index, (pattern, replacement)) in patterns.iter().enumerate() {
+        let metavars = pattern_vars_of_pattern(pattern);
+        for e in replacement_literal_exprs(replacement) {
+            if let Expr::Var { name } = e {
+                if is_metavar(name) && !metavars.contains(name) {
+                    return Err(PatternError { pattern_index, kind: PatternErrorKind::UnboundReplacementVariable { name: name.clone() } });
+                }
+            }
+        }
+        let holes = pattern_holes_of(pattern);
+        for hole in replacement_holes(replacement) {
+            if !holes.contains(&hole) {
+                return Err(PatternError { pattern_index, kind: PatternErrorKind::UnboundReplacementHole { name: hole } });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn replacement_literal_exprs(pattern: &Pattern) -> Vec<&Expr> {
+    match pattern {
+        Pattern::Literal(e) => e.subexprs().collect(),
+        Pattern::AnyBinop { l, r, .. } => replacement_literal_exprs(l).into_iter().chain(replacement_literal_exprs(r)).collect(),
+        Pattern::AnyAssocBinop { exprs, .. } => exprs.iter().flat_map(replacement_literal_exprs).collect(),
+        Pattern::AnyQuantifier { body, .. } => replacement_literal_exprs(body),
+    }
+}
+
+fn replacement_holes(pattern: &Pattern) -> Vec<String> {
+    match pattern {
+        Pattern::Literal(_) => Vec::new(),
+        Pattern::AnyBinop { hole, l, r } => {
+            let mut holes = vec![hole.clone()];
+            holes.extend(replacement_holes(l));
+            holes.extend(replacement_holes(r));
+            holes
+        }
+        Pattern::AnyAssocBinop { hole, exprs } => {
+            let mut holes = vec![hole.clone()];
+            holes.extend(exprs.iter().flat_map(replacement_holes));
+            holes
+        }
+        Pattern::AnyQuantifier { hole, body, .. } => {
+            let mut holes = vec![hole.clone()];
+            holes.extend(replacement_holes(body));
+            holes
+        }
+    }
+}
+
+/// Builds a concrete `Expr` from `template`: a `Pattern::Literal` is
+/// instantiated with [`subst_map`] against `m.bindings`, and a wildcard hole
+/// is resolved to whichever symbol `m.symbols` recorded for it. `template`
+/// must come from a `(pattern, template)` pair already checked by
+/// [`reduce_pattern_with_holes`] (every hole and metavariable it uses bound
+/// by `pattern`), so this never fails.
+fn instantiate_pattern(template: &Pattern, m: &PatternMatch) -> Expr {
+    match template {
+        Pattern::Literal(e) => subst_map(e, &m.bindings),
+        Pattern::AnyBinop { hole, l, r } => {
+            let symbol = match m.symbols.get(hole) {
+                Some(SymbolBinding::Binop(s)) => *s,
+                _ => unreachable!("instantiate_pattern: hole {} was not bound to a Binop symbol", hole),
+            };
+            Expr::Binop { symbol, l: Box::new(instantiate_pattern(l, m)), r: Box::new(instantiate_pattern(r, m)) }
+        }
+        Pattern::AnyAssocBinop { hole, exprs } => {
+            let symbol = match m.symbols.get(hole) {
+                Some(SymbolBinding::Assoc(s)) => *s,
+                _ => unreachable!("instantiate_pattern: hole {} was not bound to an ASymbol", hole),
+            };
+            Expr::AssocBinop { symbol, exprs: exprs.iter().map(|p| instantiate_pattern(p, m)).collect() }
+        }
+        Pattern::AnyQuantifier { hole, name, body } => {
+            let symbol = match m.symbols.get(hole) {
+                Some(SymbolBinding::Quantifier(s)) => *s,
+                _ => unreachable!("instantiate_pattern: hole {} was not bound to a QSymbol", hole),
+            };
+            Expr::Quantifier { symbol, name: name.clone(), body: Box::new(instantiate_pattern(body, m)) }
+        }
+    }
+}
+
+/// Like [`reduce_pattern`], but over [`Pattern`]s rather than plain `Expr`s,
+/// so a `(pattern, replacement)` pair may use a wildcard hole to match any
+/// connective of a given shape and reuse whichever one actually matched in
+/// the replacement — e.g. one pattern expressing idempotence
+/// (`phi <op> phi -> phi`) for every `ASymbol` at once, instead of one
+/// pattern per symbol.
+pub fn reduce_pattern_with_holes(e: Expr, patterns: &[(Pattern, Pattern)]) -> Result<Expr, PatternError> {
+    check_pattern_holes(patterns)?;
+    Ok(transform_expr(e, &|e| match patterns.iter().find_map(|(p, r)| match_pattern(p, &e).map(|m| (r, m))) {
+        Some((r, m)) => (instantiate_pattern(r, &m), true),
+        None => (e, false),
+    }))
+}
+
+/// Expands every pattern whose top-level node is a commutative connective
+/// (`Plus`/`Mult` per [`is_commutative_bsymbol`], or any `AssocBinop` — `And`,
+/// `Or`, `Bicon`, and `Equiv` are all commutative) into every distinct
+/// operand-order permutation of that pattern, keeping the same replacement
+/// for each. Patterns whose top-level node isn't commutative (`Implies`,
+/// variables, etc.) pass through unchanged, as a single "variant".
+///
+/// This lets a rule table be authored as one canonical pattern per law
+/// instead of one per operand ordering: `[`with_commutative_variants`]` does
+/// the permuting that would otherwise have to be hand-written out.
+/// Permutation count is factorial in the top-level operand count, so this is
+/// meant for the small (2-4 operand) patterns a rule table typically has, not
+/// for expanding arbitrary subject expressions.
+pub fn with_commutative_variants(patterns: Vec<(Expr, Expr)>) -> Vec<(Expr, Expr)> {
+    patterns
+        .into_iter()
+        .flat_map(|(pattern, replacement)| commutative_variants(&pattern).into_iter().map(move |p| (p, replacement.clone())).collect::<Vec<_>>())
+        .collect()
+}
+
+fn commutative_variants(pattern: &Expr) -> Vec<Expr> {
+    match pattern {
+        Expr::Binop { symbol, l, r } if is_commutative_bsymbol(*symbol) => {
+            let swapped = Expr::Binop { symbol: *symbol, l: r.clone(), r: l.clone() };
+            dedup_exprs(vec![pattern.clone(), swapped])
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            dedup_exprs(permutations_of(exprs).into_iter().map(|exprs| Expr::AssocBinop { symbol: *symbol, exprs }).collect())
+        }
+        _ => vec![pattern.clone()],
+    }
+}
+
+fn dedup_exprs(exprs: Vec<Expr>) -> Vec<Expr> {
+    let mut seen = std::collections::HashSet::new();
+    exprs.into_iter().filter(|e| seen.insert(e.clone())).collect()
+}
+
+/// All orderings of `exprs`, including the original order. `O(n!)`; only
+/// meant for the small slices [`commutative_variants`] calls this with.
+fn permutations_of(exprs: &[Expr]) -> Vec<Vec<Expr>> {
+    if exprs.len() <= 1 {
+        return vec![exprs.to_vec()];
+    }
+    let mut result = Vec::new();
+    for i in 0..exprs.len() {
+        let mut rest = exprs.to_vec();
+        let chosen = rest.remove(i);
+        for mut perm in permutations_of(&rest) {
+            perm.insert(0, chosen.clone());
+            result.push(perm);
+        }
+    }
+    result
+}
+
+/// Every position in `subject` where `pattern` matches, paired with the
+/// bindings found there — unlike [`reduce_pattern`]/[`reduce_pattern_ac`],
+/// nothing is rewritten, so a caller (the equivalence-rule checker, a
+/// search, or a human picking a site) can see every candidate before
+/// committing to one via [`apply_match`]. Matching is commutative
+/// (`AssocBinop` operands may line up in any order, as in
+/// [`reduce_pattern_ac`]); when a position's top-level connective is
+/// commutative there can be more than one way to pair up operands, and
+/// every distinct binding found there is included, not just the first.
+pub fn find_matches(pattern: &Expr, pattern_vars: &std::collections::HashSet<String>, subject: &Expr) -> Vec<(ExprPath, Substitution)> {
+    let mut out = Vec::new();
+    collect_matches(pattern, pattern_vars, subject, &mut Vec::new(), &mut out);
+    out
+}
+
+fn collect_matches(
+    pattern: &Expr,
+    pattern_vars: &std::collections::HashSet<String>,
+    subject: &Expr,
+    path: &mut Vec<usize>,
+    out: &mut Vec<(ExprPath, Substitution)>,
+) {
+    for bindings in match_all_commutative(pattern, subject, pattern_vars) {
+        out.push((ExprPath(path.clone()), bindings));
+    }
+    for (i, child) in children(subject).into_iter().enumerate() {
+        path.push(i);
+        collect_matches(pattern, pattern_vars, child, path, out);
+        path.pop();
+    }
+}
+
+/// Every distinct binding that matches `pattern` against `subject` at this
+/// one position, trying all operand orderings when both are an `AssocBinop`
+/// of the same connective (mirroring [`match_assoc_commutative`]'s
+/// backtracking, but collecting every success instead of stopping at the
+/// first). Anything else delegates to the ordinary single-result
+/// [`match_expr`], since only a commutative `AssocBinop` can have more than
+/// one way to satisfy the same pattern against the same subject.
+fn match_all_commutative(pattern: &Expr, subject: &Expr, pattern_vars: &std::collections::HashSet<String>) -> Vec<Substitution> {
+    match (pattern, subject) {
+        (Expr::AssocBinop { symbol: ps, exprs: pe }, Expr::AssocBinop { symbol: ss, exprs: se })
+            if ps == ss && pe.len() == se.len() && find_rest_var(pe, pattern_vars).is_none() =>
+        {
+            let mut out = Vec::new();
+            for se_perm in permutations_of(se) {
+                let mut bindings = Substitution::new();
+                if pe.iter().zip(&se_perm).all(|(p, s)| match_inner(p, s, pattern_vars, true, &mut bindings)) && !out.contains(&bindings) {
+                    out.push(bindings);
+                }
+            }
+            out
+        }
+        _ => match_expr(pattern, subject, pattern_vars, true).into_iter().collect(),
+    }
+}
+
+/// Rewrites exactly the site `path` (as found by [`find_matches`]) by
+/// substituting `bindings` into `replacement_template` and splicing the
+/// result into `subject` at `path`, leaving every other matching site
+/// untouched. Returns a [`PathError`] if `path` doesn't resolve inside
+/// `subject`.
+pub fn apply_match(subject: &Expr, path: &ExprPath, replacement_template: &Expr, bindings: &Substitution) -> Result<Expr, PathError> {
+    subst_at(subject, path, subst_map(replacement_template, bindings))
+}
+
+/// One rewrite performed by [`reduce_pattern_traced`]: which law fired
+/// (`label`), the subexpression it matched, and what it was replaced with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewriteStep {
+    pub label: String,
+    pub matched: Expr,
+    pub replacement: Expr,
+}
+
+/// Like [`reduce_pattern`], but also returns the ordered list of rewrites
+/// that were applied to reach the result. `patterns` is `(label, pattern,
+/// replacement)` so callers (e.g. `normalize_*_traced` wrappers) can name
+/// the law each pattern implements. Steps are recorded in the order
+/// `transform_expr`'s fixpoint loop applies them.
+pub fn reduce_pattern_traced(e: Expr, patterns: &[(String, Expr, Expr)]) -> (Expr, Vec<RewriteStep>) {
+    validate_patterns(&patterns.iter().map(|(_, p, r)| (p.clone(), r.clone())).collect::<Vec<_>>());
+    let pattern_vars: Vec<_> = patterns.iter().map(|(_, p, _)| pattern_vars_of(p)).collect();
+    let steps = std::cell::RefCell::new(Vec::new());
+    let result = transform_expr(e, &|e| {
+        for ((label, pattern, replacement), vars) in patterns.iter().zip(&pattern_vars) {
+            if let Some(subs) = match_expr(pattern, &e, vars, false) {
+                let instantiated = subst_map(replacement, &subs);
+                steps.borrow_mut().push(RewriteStep {
+                    label: label.clone(),
+                    matched: e.clone(),
+                    replacement: instantiated.clone(),
+                });
+                return (instantiated, true);
+            }
+        }
+        (e, false)
+    });
+    (result, steps.into_inner())
+}
+
+/// One pattern firing recorded by [`reduce_pattern_with_report`]: which
+/// pattern matched (`pattern_index` into the slice passed in), the
+/// subexpression it matched against (pre-rewrite), the bindings that match
+/// produced, and the [`ExprPath`] of the site it fired at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternApplication {
+    pub pattern_index: usize,
+    pub matched: Expr,
+    pub bindings: Substitution,
+    pub path: ExprPath,
+}
+
+/// Like [`reduce_pattern`], but also returns a [`PatternApplication`] for
+/// every rewrite performed, recording exactly which pattern fired and where.
+/// Positions are computed during the traversal itself (via
+/// [`transform_expr_with_path`]) rather than by re-finding the matched
+/// subterm afterwards, so they stay exact even when the same subterm occurs
+/// at more than one site.
+pub fn reduce_pattern_with_report(e: Expr, patterns: Vec<(Expr, Expr)>) -> (Expr, Vec<PatternApplication>) {
+    validate_patterns(&patterns);
+    let pattern_vars: Vec<_> = patterns.iter().map(|(p, _)| pattern_vars_of(p)).collect();
+    let applications = std::cell::RefCell::new(Vec::new());
+    let result = transform_expr_with_path(e, &|e, path| {
+        for (index, ((pattern, replacement), vars)) in patterns.iter().zip(&pattern_vars).enumerate() {
+            if let Some(subs) = match_expr(pattern, &e, vars, false) {
+                applications.borrow_mut().push(PatternApplication {
+                    pattern_index: index,
+                    matched: e.clone(),
+                    bindings: subs.clone(),
+                    path: path.clone(),
+                });
+                return (subst_map(replacement, &subs), true);
+            }
+        }
+        (e, false)
+    });
+    (result, applications.into_inner())
+}
+
+/// Like [`reduce_pattern`], but bails out with [`TransformLimitExceeded`]
+/// instead of looping forever if the patterns never reach a fixed point.
+pub fn reduce_pattern_bounded(
+    e: Expr,
+    patterns: &[(Expr, Expr)],
+    max_iterations: usize,
+) -> Result<Expr, TransformLimitExceeded> {
+    validate_patterns(patterns);
+    let pattern_vars: Vec<_> = patterns.iter().map(|(p, _)| pattern_vars_of(p)).collect();
+    transform_expr_bounded(e, &|e| apply_patterns_once(e, patterns, &pattern_vars, false), max_iterations)
+}
+
+/// A `(pattern, replacement)` rule set prepared once so that reducing many
+/// expressions against it doesn't redo the same per-pattern setup
+/// ([`check_patterns`]'s validation, [`pattern_vars_of`]'s variable-set
+/// computation) on every call. Build with [`RewriteSystem::new`], then call
+/// [`RewriteSystem::reduce`] as many times as needed.
+#[derive(Debug)]
+pub struct RewriteSystem {
+    patterns: Vec<(Expr, Expr)>,
+    pattern_vars: Vec<std::collections::HashSet<String>>,
+}
+
+impl RewriteSystem {
+    /// Validates `patterns` and precomputes each pattern's variable set.
+    /// Returns the same [`PatternError`] [`reduce_pattern`] would, since a
+    /// malformed pattern is exactly as much of a problem once as it would be
+    /// on every call to [`RewriteSystem::reduce`].
+    pub fn new(patterns: Vec<(Expr, Expr)>) -> Result<RewriteSystem, PatternError> {
+        check_patterns(&patterns)?;
+        let pattern_vars = patterns.iter().map(|(p, _)| pattern_vars_of(p)).collect();
+        Ok(RewriteSystem { patterns, pattern_vars })
+    }
+
+    /// Like [`reduce_pattern`], but against this system's already-prepared
+    /// patterns instead of re-validating and re-deriving variable sets from
+    /// scratch.
+    pub fn reduce(&self, e: Expr) -> Expr {
+        transform_expr(e, &|e| apply_patterns_once(e, &self.patterns, &self.pattern_vars, false))
+    }
+
+    /// Checks that every rule's left side is strictly greater than its right
+    /// side under [`kbo_compare`], so that repeatedly applying [`reduce`] (or
+    /// any `transform_expr`-based reduction over these patterns) is
+    /// guaranteed to terminate: each rewrite strictly decreases the ordering,
+    /// and the ordering is well-founded. Fails with [`CannotOrient`] naming
+    /// the first rule that does not decrease (including rules the ordering
+    /// simply can't compare, since those are just as unable to guarantee
+    /// termination as a rule that visibly increases).
+    ///
+    /// [`reduce`]: RewriteSystem::reduce
+    pub fn orient(&self) -> Result<OrientedSystem, CannotOrient> {
+        for (pattern_index, (lhs, rhs)) in self.patterns.iter().enumerate() {
+            if kbo_compare(lhs, rhs) != Some(Ordering::Greater) {
+                return Err(CannotOrient { pattern_index, lhs: lhs.clone(), rhs: rhs.clone() });
+            }
+        }
+        Ok(OrientedSystem { patterns: self.patterns.clone(), pattern_vars: self.pattern_vars.clone() })
+    }
+}
+
+/// A [`RewriteSystem`] whose every rule has been checked by
+/// [`RewriteSystem::orient`] to strictly decrease under [`kbo_compare`].
+/// Reducing with [`OrientedSystem::reduce`] is therefore guaranteed to
+/// terminate, unlike the general [`RewriteSystem::reduce`] or
+/// [`reduce_pattern`], which can loop forever on a pattern set that rewrites
+/// in a cycle.
+#[derive(Debug)]
+pub struct OrientedSystem {
+    patterns: Vec<(Expr, Expr)>,
+    pattern_vars: Vec<std::collections::HashSet<String>>,
+}
+
+impl OrientedSystem {
+    /// Reduces `e` to a fixed point. Always terminates, since every rewrite
+    /// strictly decreases [`kbo_compare`]'s well-founded ordering.
+    pub fn reduce(&self, e: Expr) -> Expr {
+        transform_expr(e, &|e| apply_patterns_once(e, &self.patterns, &self.pattern_vars, false))
+    }
+}
+
+/// Why [`RewriteSystem::orient`] refused to orient a rule set: `lhs`/`rhs`
+/// are the offending rule at `pattern_index`, which [`kbo_compare`] found to
+/// be non-decreasing (either because `rhs` is not strictly smaller, or
+/// because the two sides are incomparable).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CannotOrient {
+    pub pattern_index: usize,
+    pub lhs: Expr,
+    pub rhs: Expr,
+}
+
+impl fmt::Display for CannotOrient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rule {} (`{}` ==> `{}`) does not decrease under the term ordering", self.pattern_index, self.lhs, self.rhs)
+    }
+}
+
+impl std::error::Error for CannotOrient {}
+
+/// The Knuth–Bendix weight of a symbol: every connective and quantifier
+/// weighs 1, matching the convention that a variable also weighs 1, so that
+/// a rule is only guaranteed to terminate if its right side has strictly
+/// fewer total symbols (or, at equal weight, a lower-precedence head) than
+/// its left side.
+fn symbol_weight() -> usize {
+    1
+}
+
+/// The total weight of `e`: the sum of every node's [`symbol_weight`],
+/// including leaves (`Var`, `Contradiction`, `Tautology`, each weighing 1).
+fn kbo_weight(e: &Expr) -> usize {
+    symbol_weight()
+        + match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => 0,
+            Expr::Apply { func, args } => kbo_weight(func) + args.iter().map(kbo_weight).sum::<usize>(),
+            Expr::Unop { operand, .. } => kbo_weight(operand),
+            Expr::Binop { l, r, .. } => kbo_weight(l) + kbo_weight(r),
+            Expr::AssocBinop { exprs, .. } => exprs.iter().map(kbo_weight).sum(),
+            Expr::Quantifier { body, .. } => kbo_weight(body),
+        }
+}
+
+/// Counts how many times each `Var` name occurs in `e`, including binder
+/// names (`Quantifier`'s own `name` field is not itself an occurrence, only
+/// uses of it as a `Var` are counted). Used by [`kbo_compare`] to enforce
+/// the Knuth–Bendix ordering's variable condition: `a` can only be greater
+/// than `b` if `a` contains at least as many occurrences of every variable
+/// that occurs in `b`.
+fn variable_occurrences(e: &Expr) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for sub in e.subexprs() {
+        if let Expr::Var { name } = sub {
+            *counts.entry(name.as_str()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// An arbitrary but fixed precedence over node shapes, used by
+/// [`kbo_compare`] to break ties between two equal-weight expressions with
+/// different head symbols. Only the relative order matters, not the
+/// specific numbers.
+fn head_precedence(e: &Expr) -> u32 {
+    match e {
+        Expr::Contradiction => 0,
+        Expr::Tautology => 1,
+        Expr::Var { .. } => 2,
+        Expr::Apply { .. } => 3,
+        Expr::Unop { symbol, .. } => 10 + *symbol as u32,
+        Expr::Binop { symbol, .. } => 20 + *symbol as u32,
+        Expr::AssocBinop { symbol, .. } => 30 + *symbol as u32,
+        Expr::Quantifier { symbol, .. } => 40 + *symbol as u32,
+    }
+}
+
+/// Compares `a` and `b` by a simplified Knuth–Bendix ordering: a well-founded
+/// ordering over [`Expr`] suitable for proving a rewrite rule `lhs ==> rhs`
+/// always terminates, by checking `kbo_compare(lhs, rhs) == Some(Ordering::Greater)`.
+///
+/// Every symbol (including quantifiers and the implicit head of an `Apply`)
+/// weighs 1, same as a variable, per [`kbo_weight`]. `a` is greater than `b`
+/// only if `a` contains at least as many occurrences of every variable that
+/// occurs in `b` (the classical KBO "variable condition", checked by
+/// [`variable_occurrences`]) and either `a` is strictly heavier, or the two
+/// are equally heavy and `a`'s head outranks `b`'s under [`head_precedence`]
+/// (falling back to a pairwise comparison of same-headed children when the
+/// heads tie too). Returns `None` when the variable condition fails for both
+/// directions, since the two expressions are then incomparable.
+pub fn kbo_compare(a: &Expr, b: &Expr) -> Option<Ordering> {
+    if a == b {
+        return Some(Ordering::Equal);
+    }
+    let occ_a = variable_occurrences(a);
+    let occ_b = variable_occurrences(b);
+    let a_covers_b = occ_b.iter().all(|(name, count)| occ_a.get(name).copied().unwrap_or(0) >= *count);
+    let b_covers_a = occ_a.iter().all(|(name, count)| occ_b.get(name).copied().unwrap_or(0) >= *count);
+
+    let wa = kbo_weight(a);
+    let wb = kbo_weight(b);
+    match wa.cmp(&wb) {
+        Ordering::Greater if a_covers_b => Some(Ordering::Greater),
+        Ordering::Less if b_covers_a => Some(Ordering::Less),
+        Ordering::Equal if a_covers_b && b_covers_a => kbo_compare_same_weight(a, b),
+        _ => None,
+    }
+}
+
+/// The tie-breaking half of [`kbo_compare`], reached once the variable
+/// condition holds both ways and the two sides have equal [`kbo_weight`].
+fn kbo_compare_same_weight(a: &Expr, b: &Expr) -> Option<Ordering> {
+    match head_precedence(a).cmp(&head_precedence(b)) {
+        Ordering::Equal => kbo_compare_same_head(a, b),
+        other => Some(other),
+    }
+}
+
+/// Compares same-headed, equal-weight `a` and `b` by their first pairwise
+/// differing child, lexicographically. Reached only when [`head_precedence`]
+/// ties, which (since the precedence is injective per symbol) means `a` and
+/// `b` share the same variant and symbol.
+fn kbo_compare_same_head(a: &Expr, b: &Expr) -> Option<Ordering> {
+    let (xs, ys): (Vec<&Expr>, Vec<&Expr>) = match (a, b) {
+        (Expr::Apply { func: fa, args: aa }, Expr::Apply { func: fb, args: ab }) if aa.len() == ab.len() => {
+            (std::iter::once(fa.as_ref()).chain(aa.iter()).collect(), std::iter::once(fb.as_ref()).chain(ab.iter()).collect())
+        }
+        (Expr::Unop { operand: oa, .. }, Expr::Unop { operand: ob, .. }) => (vec![oa.as_ref()], vec![ob.as_ref()]),
+        (Expr::Binop { l: la, r: ra, .. }, Expr::Binop { l: lb, r: rb, .. }) => (vec![la.as_ref(), ra.as_ref()], vec![lb.as_ref(), rb.as_ref()]),
+        (Expr::AssocBinop { exprs: ea, .. }, Expr::AssocBinop { exprs: eb, .. }) if ea.len() == eb.len() => {
+            (ea.iter().collect(), eb.iter().collect())
+        }
+        (Expr::Quantifier { body: ba, .. }, Expr::Quantifier { body: bb, .. }) => (vec![ba.as_ref()], vec![bb.as_ref()]),
+        _ => return None,
+    };
+    for (x, y) in xs.iter().zip(&ys) {
+        match kbo_compare(x, y) {
+            Some(Ordering::Equal) => continue,
+            other => return other,
+        }
+    }
+    Some(Ordering::Equal)
+}
+
+/// One place where two rules in a [`critical_pairs`] rule set can rewrite
+/// the same term two different ways: `rule_a`'s left side overlapped
+/// `rule_b`'s left side (at some subterm of it), and applying each rule
+/// independently to the shared `overlap` produces `left` and `right`
+/// respectively. If `left != right`, the rule set is not confluent at this
+/// overlap unless further rewriting can still join them — see
+/// [`is_locally_confluent`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CriticalPair {
+    pub rule_a: usize,
+    pub rule_b: usize,
+    pub overlap: Expr,
+    pub left: Expr,
+    pub right: Expr,
+}
+
+/// Every name used by some `Var` node in `e`, bound or free, metavariable or
+/// not. Used by [`critical_pairs`] to pick fresh names for one rule's
+/// variables that can't collide with the other rule's.
+fn all_var_names(e: &Expr) -> std::collections::HashSet<String> {
+    e.subexprs()
+        .filter_map(|sub| match sub {
+            Expr::Var { name } => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Renames every pattern variable in `lhs`/`rhs` (as determined by
+/// [`pattern_vars_of`] on `lhs`) to a fresh name outside `avoid`, applying
+/// the same renaming to both so the rule's left and right sides stay
+/// consistent with each other.
+fn freshen_rule_vars(lhs: &Expr, rhs: &Expr, avoid: &std::collections::HashSet<String>) -> (Expr, Expr) {
+    let renaming: HashMap<String, Expr> = pattern_vars_of(lhs)
+        .into_iter()
+        .map(|name| {
+            let mut fresh = gensym("__cp");
+            while avoid.contains(&fresh) {
+                fresh = gensym("__cp");
+            }
+            (name, Expr::var(&fresh))
+        })
+        .collect();
+    (subst_map(lhs, &renaming), subst_map(rhs, &renaming))
+}
+
+/// Every subterm of `e` (pre-order, including `e` itself) that is not itself
+/// one of `metavars`, paired with its [`ExprPath`]. A pattern variable has
+/// no internal structure to overlap with another rule's left side, so it is
+/// excluded as an overlap site.
+fn non_metavar_subterms<'a>(e: &'a Expr, metavars: &std::collections::HashSet<String>) -> Vec<(ExprPath, &'a Expr)> {
+    fn go<'a>(e: &'a Expr, path: &mut Vec<usize>, metavars: &std::collections::HashSet<String>, out: &mut Vec<(ExprPath, &'a Expr)>) {
+        let is_metavar_leaf = matches!(e, Expr::Var { name } if metavars.contains(name));
+        if !is_metavar_leaf {
+            out.push((ExprPath(path.clone()), e));
+        }
+        for (i, child) in children(e).into_iter().enumerate() {
+            path.push(i);
+            go(child, path, metavars, out);
+            path.pop();
+        }
+    }
+    let mut out = Vec::new();
+    go(e, &mut Vec::new(), metavars, &mut out);
+    out
+}
+
+/// Finds every [`CriticalPair`] among `rules`: for each rule `j` and each
+/// non-variable subterm of its left side, tries unifying a freshly-renamed
+/// copy of every rule `i`'s left side (including `j` itself, at a subterm
+/// other than the root) against that subterm. A successful unification means
+/// both rules could fire on the same concrete term — `rule_a` at the
+/// subterm, `rule_b` at the whole of `rule_j`'s left side — and the two
+/// results are reported whenever they differ syntactically.
+///
+/// This only reports *overlaps*; it does not attempt to join them (see
+/// [`is_locally_confluent`] for that), and it does not itself require
+/// `rules` to come from a validated [`RewriteSystem`].
+pub fn critical_pairs(rules: &[(Expr, Expr)]) -> Vec<CriticalPair> {
+    let pattern_vars: Vec<_> = rules.iter().map(|(lhs, _)| pattern_vars_of(lhs)).collect();
+    let mut pairs = Vec::new();
+    for (j, (lhs_j, rhs_j)) in rules.iter().enumerate() {
+        let mut avoid = all_var_names(lhs_j);
+        avoid.extend(all_var_names(rhs_j));
+        for (path, subterm) in non_metavar_subterms(lhs_j, &pattern_vars[j]) {
+            for (i, (lhs_i, rhs_i)) in rules.iter().enumerate() {
+                if i == j && path.0.is_empty() {
+                    continue;
+                }
+                let (lhs_i, rhs_i) = freshen_rule_vars(lhs_i, rhs_i, &avoid);
+                let mut metavars = pattern_vars[j].clone();
+                metavars.extend(pattern_vars_of(&lhs_i));
+                let subs = match unify_with_metavars(&lhs_i, subterm, &metavars) {
+                    Some(subs) => subs,
+                    None => continue,
+                };
+                let overlap = subst_map(lhs_j, &subs);
+                let rewritten_at_subterm = subst_map(&rhs_i, &subs);
+                let left = subst_at(&overlap, &path, rewritten_at_subterm).expect("path was read from lhs_j itself");
+                let right = subst_map(rhs_j, &subs);
+                if left != right {
+                    pairs.push(CriticalPair { rule_a: i, rule_b: j, overlap, left, right });
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Checks that every [`critical_pairs`] overlap among `rules` *joins*: that
+/// reducing `left` and `right` against `rules` (each up to `budget`
+/// `reduce_pattern_bounded` iterations) lands on the same normal form. Rule
+/// sets failing this are not confluent — which pattern fires first can
+/// change the final answer — and the unjoined pairs are returned so the
+/// caller can see exactly which overlap is the problem.
+pub fn is_locally_confluent(rules: &[(Expr, Expr)], budget: usize) -> Result<(), Vec<CriticalPair>> {
+    let unjoined: Vec<CriticalPair> = critical_pairs(rules)
+        .into_iter()
+        .filter(|pair| {
+            let left = reduce_pattern_bounded(pair.left.clone(), rules, budget);
+            let right = reduce_pattern_bounded(pair.right.clone(), rules, budget);
+            match (left, right) {
+                (Ok(left), Ok(right)) => left != right,
+                _ => true,
+            }
+        })
+        .collect();
+    if unjoined.is_empty() {
+        Ok(())
+    } else {
+        Err(unjoined)
+    }
+}
+
+/// Drops every `Quantifier` whose bound variable does not occur free in its
+/// body, e.g. `forall x, P` becomes `P` when `x` is not free in `P`. Built
+/// on [`transform_expr`]'s post-order fixpoint, so nested vacuous binders
+/// (`forall x, forall y, A`) are all removed in one call, and a binder is
+/// correctly kept when its variable occurs free only because an inner
+/// shadowing binder of the same name has already been resolved away.
+pub fn normalize_vacuous_quantifiers(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Quantifier { name, body, .. } if !freevars(&body).contains(&name) => (*body, true),
+        other => (other, false),
+    })
+}
+
+fn miniscope_wrap_if_free(symbol: QSymbol, name: &str, e: Expr) -> Expr {
+    if freevars(&e).contains(name) {
+        Expr::quantifier(symbol, name, e)
+    } else {
+        e
+    }
+}
+
+/// Handles the combination where distributing the quantifier over every
+/// element is not generally sound (`forall`/`Or`, `exists`/`And`): narrows
+/// the quantifier onto the single element that still has `name` free,
+/// drops it entirely if no element does, and otherwise leaves the node
+/// untouched since splitting it would change the meaning.
+fn miniscope_narrow(symbol: QSymbol, assoc: ASymbol, name: String, mut exprs: Vec<Expr>) -> (Expr, bool) {
+    let free_indices: Vec<usize> =
+        exprs.iter().enumerate().filter(|(_, x)| freevars(x).contains(&name)).map(|(i, _)| i).collect();
+    match free_indices.as_slice() {
+        [] => (Expr::AssocBinop { symbol: assoc, exprs }, true),
+        [only] => {
+            let target = std::mem::replace(&mut exprs[*only], Expr::Contradiction);
+            exprs[*only] = Expr::quantifier(symbol, &name, target);
+            (Expr::AssocBinop { symbol: assoc, exprs }, true)
+        }
+        _ => (Expr::Quantifier { symbol, name, body: Box::new(Expr::AssocBinop { symbol: assoc, exprs }) }, false),
+    }
+}
+
+/// Pushes quantifiers as far inward as their scope allows, the dual of
+/// prenexing. Distributes `forall x, (A ∧ B)` into `(forall x, A) ∧ (forall
+/// x, B)` and `exists x, (A ∨ B)` into the analogous disjunction — always
+/// sound — dropping the quantifier from any conjunct/disjunct where `x` is
+/// not free. For the opposite pairing (`forall`/`Or`, `exists`/`And`), which
+/// is only sound to split when `x` occurs free in exactly one element, it
+/// narrows the quantifier onto that element instead of distributing, and
+/// leaves the node alone when `x` is free in more than one (distributing
+/// there would change the meaning). Built on [`transform_expr`], so nested
+/// quantifiers of either kind are fully miniscoped in one call.
+pub fn miniscope(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Quantifier { symbol: QSymbol::Forall, name, body } => match *body {
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => (
+                Expr::AssocBinop {
+                    symbol: ASymbol::And,
+                    exprs: exprs.into_iter().map(|x| miniscope_wrap_if_free(QSymbol::Forall, &name, x)).collect(),
+                },
+                true,
+            ),
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => miniscope_narrow(QSymbol::Forall, ASymbol::Or, name, exprs),
+            other => (Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(other) }, false),
+        },
+        Expr::Quantifier { symbol: QSymbol::Exists, name, body } => match *body {
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => (
+                Expr::AssocBinop {
+                    symbol: ASymbol::Or,
+                    exprs: exprs.into_iter().map(|x| miniscope_wrap_if_free(QSymbol::Exists, &name, x)).collect(),
+                },
+                true,
+            ),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => miniscope_narrow(QSymbol::Exists, ASymbol::And, name, exprs),
+            other => (Expr::Quantifier { symbol: QSymbol::Exists, name, body: Box::new(other) }, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// Why [`to_prenex`] could not produce a prenex normal form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrenexError {
+    /// `to_prenex` has no hoisting rule for this connective; expand it
+    /// (e.g. `Bicon`/`Equiv` into `And` of two `Implies`) first.
+    UnsupportedConnective(ASymbol),
+}
+
+impl fmt::Display for PrenexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrenexError::UnsupportedConnective(symbol) => {
+                write!(f, "to_prenex does not support `{:?}`; expand it into And/Or/Implies before calling to_prenex", symbol)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrenexError {}
+
+/// Pulls every quantifier in `e` to the front, producing a prenex normal
+/// form formula: a (possibly empty) run of quantifiers followed by a
+/// quantifier-free matrix. `Forall`/`Exists` flip when hoisted through a
+/// `Not`, through the left side of an `Implies`, or through either side of a
+/// `Nand`/`Nor`. Binders are renamed with
+/// [`gensym`] whenever their name would otherwise collide with a free
+/// variable of `e` or with another hoisted binder, so no hoisted quantifier
+/// ever captures a variable it did not originally bind — e.g. `(forall x,
+/// P(x)) -> Q(x)` hoists to `exists x', P(x') -> Q(x)` rather than
+/// capturing the free `x` in `Q(x)`. `Bicon`/`Equiv` have no
+/// quantifier-hoisting rule defined, so this reports [`PrenexError`]
+/// instead of silently producing something unsound; expand them first
+/// (e.g. into `And` of two `Implies`) if they occur.
+pub fn to_prenex(e: Expr) -> Result<Expr, PrenexError> {
+    fn flip(symbol: QSymbol) -> QSymbol {
+        match symbol {
+            QSymbol::Forall => QSymbol::Exists,
+            QSymbol::Exists => QSymbol::Forall,
+        }
+    }
+
+    fn go(
+        e: Expr,
+        avoid: &mut std::collections::HashSet<String>,
+    ) -> Result<(Vec<(QSymbol, String)>, Expr), PrenexError> {
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } | Expr::Apply { .. } => Ok((Vec::new(), e)),
+            Expr::Unop { symbol: USymbol::Not, operand } => {
+                let (prefix, matrix) = go(*operand, avoid)?;
+                let flipped = prefix.into_iter().map(|(s, n)| (flip(s), n)).collect();
+                Ok((flipped, !matrix))
+            }
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => {
+                let (prefix_l, matrix_l) = go(*l, avoid)?;
+                let mut combined: Vec<_> = prefix_l.into_iter().map(|(s, n)| (flip(s), n)).collect();
+                let (prefix_r, matrix_r) = go(*r, avoid)?;
+                combined.extend(prefix_r);
+                Ok((combined, Expr::Binop { symbol: BSymbol::Implies, l: Box::new(matrix_l), r: Box::new(matrix_r) }))
+            }
+            Expr::Binop { symbol: symbol @ (BSymbol::Nand | BSymbol::Nor), l, r } => {
+                // `A !& B` is `~(A & B)` and `A !| B` is `~(A | B)`, so quantifiers from both sides flip.
+                let (prefix_l, matrix_l) = go(*l, avoid)?;
+                let (prefix_r, matrix_r) = go(*r, avoid)?;
+                let combined = prefix_l.into_iter().chain(prefix_r).map(|(s, n)| (flip(s), n)).collect();
+                Ok((combined, Expr::Binop { symbol, l: Box::new(matrix_l), r: Box::new(matrix_r) }))
+            }
+            Expr::Binop { symbol, l, r } => {
+                // Plus/Mult are arithmetic, not logical connectives: nothing to hoist through them.
+                Ok((Vec::new(), Expr::Binop { symbol, l, r }))
+            }
+            Expr::AssocBinop { symbol: symbol @ (ASymbol::And | ASymbol::Or), exprs } => {
+                let mut combined = Vec::new();
+                let mut new_exprs = Vec::with_capacity(exprs.len());
+                for x in exprs {
+                    let (prefix, matrix) = go(x, avoid)?;
+                    combined.extend(prefix);
+                    new_exprs.push(matrix);
+                }
+                Ok((combined, Expr::AssocBinop { symbol, exprs: new_exprs }))
+            }
+            Expr::AssocBinop { symbol, .. } => Err(PrenexError::UnsupportedConnective(symbol)),
+            Expr::Quantifier { symbol, name, body } => {
+                let final_name = if avoid.contains(&name) { gensym(&format!("{}_", name)) } else { name.clone() };
+                avoid.insert(final_name.clone());
+                let body = if final_name == name { *body } else { subst(&name, &Expr::var(&final_name), *body) };
+                let (prefix_body, matrix) = go(body, avoid)?;
+                let mut prefix = vec![(symbol, final_name)];
+                prefix.extend(prefix_body);
+                Ok((prefix, matrix))
+            }
+        }
+    }
+
+    let mut avoid = freevars(&e);
+    let (prefix, matrix) = go(e, &mut avoid)?;
+    Ok(prefix.into_iter().rev().fold(matrix, |body, (symbol, name)| Expr::quantifier(symbol, &name, body)))
+}
+
+/// Like [`is_prenex`], but on failure reports the path to the first
+/// quantifier found nested inside the matrix instead of just `false`.
+pub fn check_prenex(e: &Expr) -> Result<(), WhyNot> {
+    fn matrix_has_no_quantifier(e: &Expr, path: &mut Vec<usize>) -> Result<(), WhyNot> {
+        match e {
+            Expr::Quantifier { .. } => Err(WhyNot {
+                path: ExprPath(path.clone()),
+                reason: "quantifier nested inside the matrix; prenex form requires all quantifiers at the front".to_owned(),
+            }),
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => Ok(()),
+            Expr::Apply { func, args } => {
+                path.push(0);
+                let result = matrix_has_no_quantifier(func, path);
+                path.pop();
+                result?;
+                for (i, a) in args.iter().enumerate() {
+                    path.push(i + 1);
+                    let result = matrix_has_no_quantifier(a, path);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            Expr::Unop { operand, .. } => {
+                path.push(0);
+                let result = matrix_has_no_quantifier(operand, path);
+                path.pop();
+                result
+            }
+            Expr::Binop { l, r, .. } => {
+                path.push(0);
+                let result = matrix_has_no_quantifier(l, path);
+                path.pop();
+                result?;
+                path.push(1);
+                let result = matrix_has_no_quantifier(r, path);
+                path.pop();
+                result
+            }
+            Expr::AssocBinop { exprs, .. } => {
+                for (i, x) in exprs.iter().enumerate() {
+                    path.push(i);
+                    let result = matrix_has_no_quantifier(x, path);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn go(e: &Expr, path: &mut Vec<usize>) -> Result<(), WhyNot> {
+        match e {
+            Expr::Quantifier { body, .. } => {
+                path.push(0);
+                let result = go(body, path);
+                path.pop();
+                result
+            }
+            other => matrix_has_no_quantifier(other, path),
+        }
+    }
+
+    go(e, &mut Vec::new())
+}
+
+/// `true` iff `e` is in prenex normal form: zero or more leading
+/// quantifiers followed by a quantifier-free matrix, i.e. the shape
+/// [`to_prenex`] produces.
+pub fn is_prenex(e: &Expr) -> bool {
+    check_prenex(e).is_ok()
+}
+
+fn skolemize_fresh_symbol(prefix: &str, used: &mut std::collections::HashSet<String>) -> String {
+    loop {
+        let candidate = gensym(prefix);
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+    }
+}
+
+fn skolemize_go(
+    e: Expr,
+    universals: &mut Vec<String>,
+    used: &mut std::collections::HashSet<String>,
+    strip_universals: bool,
+) -> Expr {
+    match e {
+        Expr::Quantifier { symbol: QSymbol::Forall, name, body } => {
+            universals.push(name.clone());
+            let body = skolemize_go(*body, universals, used, strip_universals);
+            universals.pop();
+            if strip_universals {
+                body
+            } else {
+                Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(body) }
+            }
+        }
+        Expr::Quantifier { symbol: QSymbol::Exists, name, body } => {
+            let skolem_name = skolemize_fresh_symbol("sk", used);
+            let replacement = if universals.is_empty() {
+                Expr::var(&skolem_name)
+            } else {
+                Expr::Apply {
+                    func: Box::new(Expr::var(&skolem_name)),
+                    args: universals.iter().map(|v| Expr::var(v)).collect(),
+                }
+            };
+            let body = subst(&name, &replacement, *body);
+            skolemize_go(body, universals, used, strip_universals)
+        }
+        other => other,
+    }
+}
+
+/// Skolemizes `e`, which is assumed to already be in prenex form (see
+/// [`to_prenex`]): replaces each existentially quantified variable with a
+/// fresh function symbol applied to the universally quantified variables
+/// currently in scope (a fresh 0-ary constant when there are none),
+/// choosing names with [`gensym`] that avoid every symbol already free or
+/// bound in `e`. The universal quantifiers are kept; use
+/// [`skolemize_stripping_universals`] to drop them as well (appropriate
+/// once the result is about to be fed to a prover that treats all
+/// remaining variables as implicitly universal). Only a leading run of
+/// quantifiers is processed — anything after the first non-quantifier node
+/// is left untouched.
+pub fn skolemize(e: Expr) -> Expr {
+    skolemize_with(e, false)
+}
+
+/// Like [`skolemize`], but also drops the (now-vacuous-for-Skolem-purposes)
+/// universal quantifiers from the prefix instead of retaining them.
+pub fn skolemize_stripping_universals(e: Expr) -> Expr {
+    skolemize_with(e, true)
+}
+
+fn skolemize_with(e: Expr, strip_universals: bool) -> Expr {
+    let mut used = freevars(&e);
+    used.extend(boundvars(&e));
+    let mut universals = Vec::new();
+    skolemize_go(e, &mut universals, &mut used, strip_universals)
+}
+
+/// Rewrites every `A -> B` in `e` into `~A \/ B` (the Implication
+/// equivalence rule), to a fixpoint, so nested and chained implications —
+/// `A -> (B -> C)`, implications under quantifiers, and so on — are all
+/// eliminated. Built on [`reduce_pattern`] with a single metavariable
+/// pattern; the pattern is fixed and known-valid, so the only way
+/// `reduce_pattern` could return an error is a bug in this function itself.
+pub fn normalize_implication(e: Expr) -> Expr {
+    let patterns = vec![(
+        Expr::implies(Expr::var("__phi"), Expr::var("__psi")),
+        Expr::assoc(ASymbol::Or, vec![Expr::Unop { symbol: USymbol::Not, operand: Box::new(Expr::var("__phi")) }, Expr::var("__psi")]),
+    )];
+    reduce_pattern(e, &patterns).expect("normalize_implication's pattern is fixed and well-formed")
+}
+
+/// The reverse of [`normalize_implication`]: rewrites every `~A \/ B` in `e`
+/// back into `A -> B`, to a fixpoint. Useful for presenting a result in the
+/// form students expect after a derivation has been worked in terms of
+/// `Implies`-free connectives.
+///
+/// Only the exact binary shape `~A \/ B` is recognized, matching
+/// [`normalize_implication`]'s own output; an n-ary `Or` with more than two
+/// operands, or one whose first operand isn't a negation, is left alone
+/// rather than guessed at.
+pub fn introduce_implication(e: Expr) -> Expr {
+    let patterns = vec![(
+        Expr::assoc(ASymbol::Or, vec![Expr::Unop { symbol: USymbol::Not, operand: Box::new(Expr::var("__phi")) }, Expr::var("__psi")]),
+        Expr::implies(Expr::var("__phi"), Expr::var("__psi")),
+    )];
+    reduce_pattern(e, &patterns).expect("introduce_implication's pattern is fixed and well-formed")
+}
+
+/// Which two-operand expansion [`normalize_biconditional`] produces for
+/// `A <-> B`. [`to_nnf`] always uses `Implication` internally (its output
+/// needs to already be `Implies`-shaped before it eliminates those in turn);
+/// `Disjunction` is the XNOR-style form, handy when the caller wants an
+/// `And`/`Or`/`Not` result without going through `Implies` at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BiconStyle {
+    /// `A <-> B` becomes `(A -> B) /\ (B -> A)`.
+    Implication,
+    /// `A <-> B` becomes `(A /\ B) \/ (~A /\ ~B)`.
+    Disjunction,
+}
+
+/// Expands every `Bicon`/`Equiv` in `e` into `style`'s two-operand form. An
+/// n-ary biconditional is chained pairwise across consecutive operands —
+/// `A <-> B <-> C` becomes `(A <-> B) /\ (B <-> C)` before expansion, the
+/// same n-ary elimination [`to_nnf`] uses, since both connectives already
+/// share the "all operands agree" semantics everywhere else in this module
+/// (see [`eval`] and [`tseitin`]'s treatment of them). `Equiv` is
+/// expanded identically to `Bicon`: this checker gives the two connectives
+/// the same truth-table semantics, so there is no separate "Equiv"
+/// expansion to define.
+pub fn normalize_biconditional(e: Expr, style: BiconStyle) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, exprs } => {
+            let clauses = exprs.windows(2).map(|w| biconditional_pair(w[0].clone(), w[1].clone(), style)).collect();
+            (Expr::assoc(ASymbol::And, clauses), true)
+        }
+        other => (other, false),
+    })
+}
+
+fn biconditional_pair(a: Expr, b: Expr, style: BiconStyle) -> Expr {
+    match style {
+        BiconStyle::Implication => Expr::assoc(ASymbol::And, vec![Expr::implies(a.clone(), b.clone()), Expr::implies(b, a)]),
+        BiconStyle::Disjunction => Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::assoc(ASymbol::And, vec![a.clone(), b.clone()]), Expr::assoc(ASymbol::And, vec![negate(a), negate(b)])],
+        ),
+    }
+}
+
+/// Expands an n-ary `Bicon`/`Equiv` chain into the AND of the ordinary
+/// two-operand biconditional definition applied to each adjacent pair:
+/// `A <-> B <-> C` becomes `(A <-> B) /\ (B <-> C)`, each pair then
+/// expanded per [`BiconStyle::Implication`] to `(A -> B) /\ (B -> A)`. The
+/// two-operand case is exactly that same expansion with one pair. This is
+/// the textbook "chained `<->`" reading — and it is *also* exactly the
+/// "every operand shares the same truth value" reading [`eval`],
+/// [`truth_table`], [`to_nnf`], and [`tseitin`] already give n-ary
+/// `Bicon`/`Equiv` (see [`normalize_biconditional`]'s doc comment):
+/// biconditional composes transitively, so an AND of adjacent-pair
+/// biconditionals and "all operands equal" are the same formula, and the
+/// two readings never actually disagree. What *does* disagree with both,
+/// starting at three operands, is a third reading this crate deliberately
+/// does not implement: folding `<->` as a single left-associated chain of
+/// binary applications (`(A <-> B) <-> C`), which computes a parity rather
+/// than an equality — see
+/// `test_eval_n_ary_bicon_uses_all_equal_not_parity_reading` for a worked
+/// counterexample. [`to_nnf`] is built directly on this function so the two
+/// never drift apart.
+pub fn expand_bicon_chain(e: Expr) -> Expr {
+    normalize_biconditional(e, BiconStyle::Implication)
+}
+
+fn binary_xor_expand(a: Expr, b: Expr) -> Expr {
+    Expr::assoc(ASymbol::And, vec![Expr::assoc(ASymbol::Or, vec![a.clone(), b.clone()]), negate(Expr::assoc(ASymbol::And, vec![a, b]))])
+}
+
+/// Expands an n-ary `Xor` chain into `And`/`Or`/`Not`, preserving the parity
+/// semantics [`eval`] gives `Xor` (true iff an odd number of operands are
+/// true). The two-operand case is the textbook definition
+/// `(A \/ B) /\ ~(A /\ B)`; an n-ary chain is eliminated by folding that same
+/// two-operand expansion pairwise, left-to-right, exactly as a literal
+/// left-associated chain of binary `Xor`s would compute it. Unlike
+/// [`expand_bicon_chain`], there is no "all operands agree" vs.
+/// "pairwise chain" ambiguity to resolve here: `Xor`'s parity is the only
+/// reading consistent with associativity, so the pairwise fold is simply
+/// the n-ary semantics, not a choice between competing ones.
+pub fn normalize_xor(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::Xor, exprs } => {
+            let mut it = exprs.into_iter();
+            let first = it.next().expect("AssocBinop always has at least two operands");
+            (it.fold(first, binary_xor_expand), true)
+        }
+        other => (other, false),
+    })
+}
+
+/// Expands every `Nand`/`Nor` in `e` into `Not`/`And`/`Or`: the Sheffer
+/// stroke `A ↑ B` becomes `~(A /\ B)` and the Peirce arrow `A ↓ B` becomes
+/// `~(A \/ B)`, their defining identities. The reverse direction —
+/// expressing an arbitrary formula using only `Nand` — is [`to_nand_only`].
+pub fn normalize_sheffer(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Binop { symbol: BSymbol::Nand, l, r } => (negate(Expr::assoc(ASymbol::And, vec![*l, *r])), true),
+        Expr::Binop { symbol: BSymbol::Nor, l, r } => (negate(Expr::assoc(ASymbol::Or, vec![*l, *r])), true),
+        other => (other, false),
+    })
+}
+
+/// Rewrites the propositional fragment of `e` using only `Nand`, via the
+/// standard functionally-complete identities `~A = A nand A` and
+/// `A /\ B = (A nand B) nand (A nand B)` (`\/` falls out by De Morgan:
+/// `A \/ B = (~A) nand (~B)`). `e` is first reduced to `Not`/`And`/`Or` over
+/// atoms with [`to_nnf`] (which, via [`normalize_sheffer`], also eliminates
+/// any `Nand`/`Nor` already present), then every remaining connective in
+/// that NNF tree is replaced by its `Nand` encoding. `Var`, `Tautology`,
+/// `Contradiction`, and `Apply` leaves are passed through unchanged, since
+/// there is nothing to rewrite about an atom; a quantifier's body is
+/// rewritten in place.
+pub fn to_nand_only(e: Expr) -> Expr {
+    fn nand(a: Expr, b: Expr) -> Expr {
+        Expr::Binop { symbol: BSymbol::Nand, l: Box::new(a), r: Box::new(b) }
+    }
+    fn not_via_nand(a: Expr) -> Expr {
+        nand(a.clone(), a)
+    }
+    fn and_via_nand(a: Expr, b: Expr) -> Expr {
+        let n = nand(a, b);
+        nand(n.clone(), n)
+    }
+    fn or_via_nand(a: Expr, b: Expr) -> Expr {
+        nand(not_via_nand(a), not_via_nand(b))
+    }
+    fn go(e: Expr) -> Expr {
+        match e {
+            Expr::Unop { symbol: USymbol::Not, operand } => not_via_nand(go(*operand)),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+                let mut it = exprs.into_iter().map(go);
+                let first = it.next().expect("AssocBinop always has at least two operands");
+                it.fold(first, and_via_nand)
+            }
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+                let mut it = exprs.into_iter().map(go);
+                let first = it.next().expect("AssocBinop always has at least two operands");
+                it.fold(first, or_via_nand)
+            }
+            Expr::Quantifier { symbol, name, body } => Expr::Quantifier { symbol, name, body: Box::new(go(*body)) },
+            other => other,
+        }
+    }
+    go(to_nnf(e))
+}
+
+/// Folds a `Tautology`/`Contradiction` operand out of a `Bicon`/`Equiv`
+/// chain. Both connectives mean "every operand shares the same truth
+/// value" (see [`eval`]'s treatment of them), so a `Tautology` operand
+/// forces every other operand to be true — `A === T === B` becomes
+/// `A /\ B`, not `A === B` — and a `Contradiction` operand forces every
+/// other operand to be false — `A === F === B` becomes `~A /\ ~B`. In the
+/// two-operand case this is exactly the Identity (`A === T <=> A`) and
+/// Complement (`A === F <=> ~A`) laws. If both a `Tautology` and a
+/// `Contradiction` appear together, no truth value can satisfy both at
+/// once, so the whole chain is `Contradiction`.
+pub fn normalize_biconditional_constants(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: symbol @ (ASymbol::Bicon | ASymbol::Equiv), exprs } => {
+            let has_tautology = exprs.contains(&Expr::Tautology);
+            let has_contradiction = exprs.contains(&Expr::Contradiction);
+            if has_tautology && has_contradiction {
+                return (Expr::Contradiction, true);
+            }
+            if has_tautology {
+                let rest = exprs.into_iter().filter(|x| *x != Expr::Tautology).collect();
+                return (assoc_or_single(ASymbol::And, rest, Expr::Tautology), true);
+            }
+            if has_contradiction {
+                let rest = exprs.into_iter().filter(|x| *x != Expr::Contradiction).map(negate).collect();
+                return (assoc_or_single(ASymbol::And, rest, Expr::Tautology), true);
+            }
+            (Expr::AssocBinop { symbol, exprs }, false)
+        }
+        other => (other, false),
+    })
+}
+
+/// The inverse of [`negate`]: if `e` is itself in one of the forms `negate`
+/// produces from some `inner`, returns that `inner`; otherwise `None`.
+fn as_negation(e: &Expr) -> Option<Expr> {
+    match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => Some((**operand).clone()),
+        Expr::Contradiction => Some(Expr::Tautology),
+        Expr::Tautology => Some(Expr::Contradiction),
+        _ => None,
+    }
+}
+
+/// Applies the Transposition equivalence law, `(A -> B) <=> (~B -> ~A)`, to
+/// every implication in `e` whose antecedent *and* consequent are already
+/// negated: `~A -> ~B` becomes `B -> A`. Built on [`transform_expr`] and the
+/// smart [`negate`]/[`as_negation`] helpers, which cancel a double negation
+/// rather than introducing one, so `~A -> ~B` becomes exactly `B -> A`, not
+/// `~~B -> ~~A`.
+///
+/// Only the "both sides already negated" direction fires, which is what
+/// makes this terminate: the rewritten form's antecedent and consequent are
+/// `B` and `A` as they appeared in the original, so it only matches again if
+/// `B` and `A` themselves happen to both be negated, strictly unwrapping one
+/// layer of negation each time rather than looping. Firing unconditionally
+/// in both directions — also rewriting `A -> B` to `~B -> ~A` — would have
+/// each application immediately match the pattern for the opposite
+/// direction and loop forever, which is exactly why only one direction is
+/// canonical here.
+pub fn normalize_contraposition(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => match (as_negation(&l), as_negation(&r)) {
+            (Some(a), Some(b)) => (Expr::implies(b, a), true),
+            _ => (Expr::Binop { symbol: BSymbol::Implies, l, r }, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// Which way [`normalize_exportation`] applies the Exportation law,
+/// `(A /\ B) -> C <=> A -> (B -> C)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportationDirection {
+    /// `(A /\ B) -> C` becomes `A -> (B -> C)`.
+    Curry,
+    /// `A -> (B -> C)` becomes `(A /\ B) -> C`.
+    Uncurry,
+}
+
+fn curry_exportation_step(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => match *l {
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } if exprs.len() >= 2 => {
+                let mut conjuncts = exprs.into_iter();
+                let first = conjuncts.next().expect("length checked above");
+                let rest: Vec<Expr> = conjuncts.collect();
+                let rest_antecedent = if rest.len() == 1 { rest.into_iter().next().expect("length checked above") } else { Expr::assoc(ASymbol::And, rest) };
+                (Expr::implies(first, Expr::implies(rest_antecedent, *r)), true)
+            }
+            other => (Expr::implies(other, *r), false),
+        },
+        other => (other, false),
+    }
+}
+
+fn uncurry_exportation_step(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => match *r {
+            Expr::Binop { symbol: BSymbol::Implies, l: inner_l, r: inner_r } => {
+                (Expr::implies(Expr::assoc(ASymbol::And, vec![*l, *inner_l]), *inner_r), true)
+            }
+            other => (Expr::implies(*l, other), false),
+        },
+        other => (other, false),
+    }
+}
+
+/// Applies the Exportation equivalence law to every implication in `e`, in
+/// `direction`, to a fixpoint. `Curry` peels one conjunct at a time off an
+/// `And`-shaped antecedent — `(A /\ B /\ C) -> D` becomes `A -> (B -> (C ->
+/// D))` after three fixpoint steps, rather than building the whole
+/// right-nested chain in one rewrite — which naturally handles any arity of
+/// antecedent through repeated application. `Uncurry` is the mirror image,
+/// folding a chain of implications back into one `And`-antecedent
+/// implication one layer at a time; [`combine_associative_ops`] is run
+/// afterward to flatten the `And`s that repeated folding nests (the same
+/// cleanup [`normalize_distribution`] needs for the same reason).
+pub fn normalize_exportation(e: Expr, direction: ExportationDirection) -> Expr {
+    let exported = match direction {
+        ExportationDirection::Curry => transform_expr(e, &curry_exportation_step),
+        ExportationDirection::Uncurry => transform_expr(e, &uncurry_exportation_step),
+    };
+    combine_associative_ops(exported)
+}
+
+/// Applies the Idempotence equivalence law to every `AssocBinop` in `e`:
+/// repeated operands are removed wherever they occur in the list, not just
+/// when adjacent, keeping the first occurrence of each and preserving the
+/// relative order of the survivors — `A /\ A /\ B` and `A /\ B /\ A` both
+/// become `A /\ B`. If dedup ever leaves a single operand, the `AssocBinop`
+/// collapses to that operand directly.
+///
+/// Only `AssocBinop` operand lists are touched: a `Binop`'s two operands or
+/// an `Apply`'s arguments are positional, not a commutative operand list, so
+/// a repeated value there is left alone even if it looks identical —
+/// collapsing `A -> A` would be applying a different law (Implication, not
+/// Idempotence) by coincidence of shared operand values.
+///
+/// Duplicates are compared structurally by default; pass
+/// `alpha_equivalent: true` to also collapse operands that differ only by a
+/// consistent renaming of bound variables (see [`alpha_equal`]). This never
+/// merges operands that are merely logically equivalent but not
+/// structurally (or alpha-)equal — `A` and `~~A` survive as distinct
+/// operands, since only [`equivalent`]'s SAT-based check, not this
+/// syntactic rule, can tell they agree.
+pub fn normalize_idempotence(e: Expr, alpha_equivalent: bool) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol, exprs } => {
+            let original_len = exprs.len();
+            let mut deduped: Vec<Expr> = Vec::with_capacity(exprs.len());
+            for x in exprs {
+                let is_dup = deduped.iter().any(|d| if alpha_equivalent { alpha_equal(d, &x) } else { d == &x });
+                if !is_dup {
+                    deduped.push(x);
+                }
+            }
+            let changed = deduped.len() != original_len || deduped.len() < 2;
+            (repair_assoc_arity(symbol, deduped), changed)
+        }
+        other => (other, false),
+    })
+}
+
+/// Peels every layer of a literal `Not` off of `e`, tracking the parity of
+/// how many layers were peeled. `~~~~A` peels down to `(A, false)` (an even
+/// number of negations cancel); `~~~A` peels down to `(A, true)`. Shared by
+/// [`complements`] so that complementary-pair detection sees past however
+/// many balanced `~~` pairs happen to wrap either side.
+fn strip_double_negation(mut e: &Expr) -> (&Expr, bool) {
+    let mut negated = false;
+    while let Expr::Unop { symbol: USymbol::Not, operand } = e {
+        e = operand;
+        negated = !negated;
+    }
+    (e, negated)
+}
+
+/// `true` iff `a` and `b` are complementary, i.e. one is semantically `~`
+/// the other once any `~~` pairs wrapping either side are stripped — so
+/// `~~A` and `~A` are recognized as complementary just as readily as `A`
+/// and `~A` are, and `~A` and `~~~A` (which are just the same literal
+/// twice, not a complementary pair) are correctly told apart from it.
+/// Unlike [`as_negation`], this doesn't treat `Contradiction` and
+/// `Tautology` as negations of each other, since neither is reachable from
+/// the other by peeling `Not`s.
+fn complements(a: &Expr, b: &Expr) -> bool {
+    let (a_core, a_negated) = strip_double_negation(a);
+    let (b_core, b_negated) = strip_double_negation(b);
+    a_core == b_core && a_negated != b_negated
+}
+
+/// `true` iff some two operands of `exprs` form a complementary pair (see
+/// [`complements`]).
+fn has_complementary_pair(exprs: &[Expr]) -> bool {
+    exprs.iter().enumerate().any(|(i, a)| exprs[i + 1..].iter().any(|b| complements(a, b)))
+}
+
+/// Applies the Complement equivalence law to every `AssocBinop` in `e`:
+/// an `And` with any two operands `phi`/`~phi` anywhere in its list (not
+/// just a two-operand list, and not just adjacent) collapses to
+/// `Contradiction`; dually, an `Or` with such a pair collapses to
+/// `Tautology`. `~psi` and `~~psi` count as a complementary pair just like
+/// `psi` and `~psi` do, since `~psi` is itself the operand being
+/// complemented.
+///
+/// This is a dedicated [`transform_expr`] closure rather than a
+/// [`reduce_pattern`] call, since the pair can be anywhere in an arbitrarily
+/// long operand list — not a fixed-arity shape a pattern's metavariables
+/// could pin down.
+pub fn normalize_complement(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } if has_complementary_pair(&exprs) => (Expr::Contradiction, true),
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } if has_complementary_pair(&exprs) => (Expr::Tautology, true),
+        other => (other, false),
+    })
+}
+
+/// Builds an `AssocBinop{symbol}` out of `exprs`, except that zero operands
+/// collapse to `if_empty` (the symbol's identity element) and exactly one
+/// operand unwraps to that operand directly, rather than leaving a
+/// degenerate `AssocBinop` around.
+fn assoc_or_single(symbol: ASymbol, exprs: Vec<Expr>, if_empty: Expr) -> Expr {
+    match exprs.len() {
+        0 => if_empty,
+        1 => exprs.into_iter().next().expect("length checked above"),
+        _ => Expr::AssocBinop { symbol, exprs },
+    }
+}
+
+/// The identity element an empty `AssocBinop{symbol}` operand list
+/// collapses to, for every [`ASymbol`] (not just `And`/`Or`, which the
+/// individual normalizers that already know their own identity — e.g. via
+/// [`assoc_or_single`] — handle directly): `Tautology` for `And`, and for
+/// `Bicon`/`Equiv` too, since vacuously "every one of zero operands agrees"
+/// is true; `Contradiction` for `Or`, and for `Xor`, whose n-ary semantics
+/// is parity — the parity of zero operands is even, i.e. false.
+fn assoc_arity_identity(symbol: ASymbol) -> Expr {
+    match symbol {
+        ASymbol::And | ASymbol::Bicon | ASymbol::Equiv => Expr::Tautology,
+        ASymbol::Or | ASymbol::Xor => Expr::Contradiction,
+    }
+}
+
+/// Repairs one `AssocBinop{symbol}` operand list that may violate the arity
+/// invariant (every `AssocBinop` should have at least two operands, but
+/// nothing enforces that on construction): a singleton unwraps to its one
+/// operand, and an empty list collapses to [`assoc_arity_identity`].
+/// Shared by [`normalize_assoc_arity`] and by the other normalizers whose
+/// own filtering or deduping can leave exactly this shape behind
+/// ([`canonicalize_assoc`], [`normalize_idempotence`]), so they repair it
+/// the same way rather than each re-deriving the right identity element.
+fn repair_assoc_arity(symbol: ASymbol, mut exprs: Vec<Expr>) -> Expr {
+    match exprs.len() {
+        0 => assoc_arity_identity(symbol),
+        1 => exprs.remove(0),
+        _ => Expr::AssocBinop { symbol, exprs },
+    }
+}
+
+/// Applies [`repair_assoc_arity`] to every `AssocBinop` in `e`. Nothing
+/// about [`Expr::assoc`] or the `AssocBinop` variant itself prevents
+/// constructing a zero- or one-operand operand list, and a handwritten or
+/// externally-produced expression can carry one in by accident; this
+/// normalizes it away so downstream code — [`fmt::Display`] (which would
+/// otherwise print a parenthesized singleton like `(A)`), [`unify`] (which
+/// would otherwise fail to match a singleton `AssocBinop` against its bare
+/// operand even though they denote the same formula) — never has to handle
+/// the degenerate shape. [`validate`] can be used to check for a violation
+/// before this repairs it.
+pub fn normalize_assoc_arity(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol, exprs } if exprs.len() < 2 => (repair_assoc_arity(symbol, exprs), true),
+        other => (other, false),
+    })
+}
+
+/// In debug builds, asserts that every `AssocBinop` in `e` has at least two
+/// operands — the arity invariant [`normalize_assoc_arity`] repairs. A
+/// no-op in release builds, like this crate's other `debug_assert!`-based
+/// internal consistency checks; call it after hand-constructing an
+/// `AssocBinop` (e.g. in a test, or a caller building one from user input)
+/// to catch a violation immediately rather than downstream in `Display` or
+/// `unify`.
+pub fn validate(e: &Expr) {
+    for sub in e.subexprs() {
+        if let Expr::AssocBinop { symbol, exprs } = sub {
+            debug_assert!(
+                exprs.len() >= 2,
+                "AssocBinop{{{:?}}} has {} operand(s), violating the arity invariant (expected at least 2)",
+                symbol,
+                exprs.len()
+            );
+        }
+    }
+}
+
+/// Applies the Identity law to every `AssocBinop` in `e`: `Tautology`
+/// operands are removed from an `And` (`A /\ T <=> A`), and `Contradiction`
+/// operands are removed from an `Or` (`A \/ F <=> A`), from any position in
+/// the operand list, not just a fixed two-operand shape. If removing the
+/// identity elements leaves nothing behind, the result is the identity
+/// element itself (`And[]` would mean "vacuously true"); if it leaves
+/// exactly one operand, the `AssocBinop` unwraps to that operand.
+pub fn normalize_identity(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            let original_len = exprs.len();
+            let kept: Vec<Expr> = exprs.into_iter().filter(|x| !matches!(x, Expr::Tautology)).collect();
+            let changed = kept.len() != original_len;
+            (assoc_or_single(ASymbol::And, kept, Expr::Tautology), changed)
+        }
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            let original_len = exprs.len();
+            let kept: Vec<Expr> = exprs.into_iter().filter(|x| !matches!(x, Expr::Contradiction)).collect();
+            let changed = kept.len() != original_len;
+            (assoc_or_single(ASymbol::Or, kept, Expr::Contradiction), changed)
+        }
+        other => (other, false),
+    })
+}
+
+/// Applies the Annihilation law to every `AssocBinop` in `e`: an `And` with
+/// a `Contradiction` anywhere in its operand list collapses to
+/// `Contradiction` (`A /\ F <=> F`), and an `Or` with a `Tautology` anywhere
+/// collapses to `Tautology` (`A \/ T <=> T`), regardless of how many other
+/// operands are present or where the annihilating constant sits in the list.
+pub fn normalize_annihilation(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } if exprs.iter().any(|x| matches!(x, Expr::Contradiction)) => {
+            (Expr::Contradiction, true)
+        }
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } if exprs.iter().any(|x| matches!(x, Expr::Tautology)) => (Expr::Tautology, true),
+        other => (other, false),
+    })
+}
+
+/// Looks for an operand of `exprs` that is itself an `AssocBinop{other_symbol}`
+/// one of whose inner operands structurally equals some *other* operand of
+/// `exprs` (the shape the Absorption law removes), and returns its index if
+/// found. Checks every operand against every sibling, not just the first,
+/// so e.g. the second operand of a three-operand list can absorb against
+/// the third.
+fn find_absorbable_operand(exprs: &[Expr], other_symbol: ASymbol) -> Option<usize> {
+    exprs.iter().enumerate().find_map(|(i, x)| match x {
+        Expr::AssocBinop { symbol, exprs: inner } if *symbol == other_symbol => {
+            let absorbed = inner.iter().any(|d| exprs.iter().enumerate().any(|(j, o)| j != i && o == d));
+            if absorbed {
+                Some(i)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Applies the Absorption equivalence law to every `AssocBinop` in `e`, to a
+/// fixpoint: within an `And`, any operand that is itself an `Or` containing
+/// a disjunct equal to some other operand of the `And` is redundant and is
+/// removed (`A /\ B /\ (A \/ C)` becomes `A /\ B`, since whenever `A` holds
+/// the `Or` operand is already satisfied); dually, within an `Or`, any
+/// operand that is an `And` containing a conjunct equal to some other
+/// operand is removed. Removing one absorbable operand can reveal another
+/// (e.g. once idempotence or a prior absorption collapses a sibling), which
+/// is why this runs to a fixpoint via [`transform_expr`] rather than a
+/// single pass.
+pub fn normalize_absorption(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::And, mut exprs } => match find_absorbable_operand(&exprs, ASymbol::Or) {
+            Some(i) => {
+                exprs.remove(i);
+                (assoc_or_single(ASymbol::And, exprs, Expr::Tautology), true)
+            }
+            None => (Expr::AssocBinop { symbol: ASymbol::And, exprs }, false),
+        },
+        Expr::AssocBinop { symbol: ASymbol::Or, mut exprs } => match find_absorbable_operand(&exprs, ASymbol::And) {
+            Some(i) => {
+                exprs.remove(i);
+                (assoc_or_single(ASymbol::Or, exprs, Expr::Contradiction), true)
+            }
+            None => (Expr::AssocBinop { symbol: ASymbol::Or, exprs }, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// The set of disjuncts (for `inner == Or`) or conjuncts (for `inner ==
+/// And`) an operand of the opposite `AssocBinop` contributes, after
+/// `canonicalize`ing it so e.g. `a \/ b` and `b \/ a` produce equal sets. An
+/// operand that isn't itself an `AssocBinop{inner}` is a single literal, and
+/// counts as a one-element set.
+fn subsumption_literal_set(inner: ASymbol, operand: &Expr) -> std::collections::HashSet<Expr> {
+    match canonicalize(operand.clone()) {
+        Expr::AssocBinop { symbol, exprs } if symbol == inner => exprs.into_iter().collect(),
+        other => std::iter::once(other).collect(),
+    }
+}
+
+/// Removes every operand of `exprs` whose literal set (see
+/// [`subsumption_literal_set`]) is a superset of some other operand's: that
+/// operand is implied by the smaller one, so it contributes nothing. Ties
+/// (two operands with equal sets, e.g. differently-ordered duplicates) keep
+/// the earlier operand and drop the later one.
+fn remove_subsumed_operands(inner: ASymbol, exprs: Vec<Expr>) -> (Vec<Expr>, bool) {
+    let sets: Vec<std::collections::HashSet<Expr>> = exprs.iter().map(|x| subsumption_literal_set(inner, x)).collect();
+    let keep: Vec<bool> = (0..exprs.len())
+        .map(|i| {
+            !(0..exprs.len()).any(|j| j != i && sets[j].is_subset(&sets[i]) && (sets[j].len() < sets[i].len() || j < i))
+        })
+        .collect();
+    let original_len = exprs.len();
+    let kept: Vec<Expr> = exprs.into_iter().zip(keep).filter(|(_, k)| *k).map(|(x, _)| x).collect();
+    let changed = kept.len() != original_len;
+    (kept, changed)
+}
+
+/// Removes subsumed clauses/implicants from every `AssocBinop` in `e`: within
+/// an `And` whose operands are disjunctions (or bare literals), any operand
+/// whose disjunct set is a superset of some other operand's is redundant and
+/// is dropped, since satisfying the smaller clause already satisfies the
+/// bigger one (`(A \/ B) /\ (A \/ B \/ C)` becomes `A \/ B`); dually, within
+/// an `Or` whose operands are conjunctions (or bare literals), any operand
+/// whose conjunct set is a superset of some other operand's is dropped.
+/// Operands are compared as sets after [`canonicalize`], so operand order —
+/// both of the outer list and within each inner clause — doesn't matter.
+/// This generalizes [`normalize_absorption`]: a one-literal operand `phi`
+/// subsuming `phi \/ psi` is exactly the absorption case, stated as a set
+/// relation instead of a single shared literal.
+pub fn remove_subsumed(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            let (kept, changed) = remove_subsumed_operands(ASymbol::Or, exprs);
+            (assoc_or_single(ASymbol::And, kept, Expr::Tautology), changed)
+        }
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            let (kept, changed) = remove_subsumed_operands(ASymbol::And, exprs);
+            (assoc_or_single(ASymbol::Or, kept, Expr::Contradiction), changed)
+        }
+        other => (other, false),
+    })
+}
+
+/// Applies the Double Negation (Involution) law to every `Not` in `e`:
+/// `~~phi` collapses to `phi`, to a fixpoint, so a chain of several stacked
+/// negations (e.g. `~~~~phi`) fully cancels rather than losing just the
+/// outermost pair. Also known as the Inverse law in some textbooks; this is
+/// the same single equivalence under either name.
+pub fn normalize_double_negation(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => match *operand {
+            Expr::Unop { symbol: USymbol::Not, operand } => (*operand, true),
+            operand => (!operand, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// Applies De Morgan's law for `And`/`Or` to every negated conjunction or
+/// disjunction in `e`, to a fixpoint: `~(phi_1 /\ ... /\ phi_n)` becomes
+/// `~phi_1 \/ ... \/ ~phi_n`, and dually `~(phi_1 \/ ... \/ phi_n)` becomes
+/// `~phi_1 /\ ... /\ ~phi_n`. Uses the smart [`negate`] helper on every
+/// pushed-in operand — and on the negation itself, via the same match arms
+/// that handle `~~phi` and `~T`/`~_|_` — so none of that litter is left for
+/// a separate double-negation pass to mop up afterward. Running a plain
+/// "push the negation in" rewrite and a separate double-negation cleanup as
+/// two passes can make a fixpoint loop ping-pong between them on deeply
+/// negated input (the first pass's output is exactly what triggers the
+/// second, whose output can expose more work for the first); folding both
+/// into one pass's match arms avoids that.
+///
+/// This only touches negated `And`/`Or`/`Not`/`Contradiction`/`Tautology`;
+/// [`normalize_quantifier_demorgans`] is the sibling that does the same for
+/// negated quantifiers, and [`to_nnf`] applies both alongside its other NNF
+/// steps (connective elimination) in a single pass.
+pub fn normalize_demorgans(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => match *operand {
+            Expr::Unop { symbol: USymbol::Not, operand } => (*operand, true),
+            Expr::Contradiction => (Expr::Tautology, true),
+            Expr::Tautology => (Expr::Contradiction, true),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+                (Expr::AssocBinop { symbol: ASymbol::Or, exprs: exprs.into_iter().map(negate).collect() }, true)
+            }
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+                (Expr::AssocBinop { symbol: ASymbol::And, exprs: exprs.into_iter().map(negate).collect() }, true)
+            }
+            operand => (!operand, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// Applies De Morgan's law for quantifiers to every negated quantifier in
+/// `e`: `~(forall x, phi)` becomes `exists x, ~phi`, and `~(exists x, phi)`
+/// becomes `forall x, ~phi`, to a fixpoint, so a negation buried under
+/// several alternating quantifiers (e.g. `~forall x exists y, phi`) fully
+/// resolves rather than stopping after one layer. The binder name is
+/// preserved exactly — nothing moves across a binder's scope here, so there
+/// is nothing to rename. Uses the smart [`negate`] helper on the pushed-in
+/// body, so a body that is itself already negated cancels rather than
+/// stacking an extra `Not`.
+///
+/// This only touches negated quantifiers; `And`/`Or` under a `Not` is
+/// [`normalize_demorgans`]'s job, and everything else is left exactly as it
+/// is, unlike [`to_nnf`], which applies this same quantifier rewrite
+/// alongside every other NNF step.
+pub fn normalize_quantifier_demorgans(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => match *operand {
+            Expr::Quantifier { symbol: QSymbol::Forall, name, body } => {
+                (Expr::Quantifier { symbol: QSymbol::Exists, name, body: Box::new(negate(*body)) }, true)
+            }
+            Expr::Quantifier { symbol: QSymbol::Exists, name, body } => {
+                (Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(negate(*body)) }, true)
+            }
+            operand => (Expr::Unop { symbol: USymbol::Not, operand: Box::new(operand) }, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// The owned counterpart of [`leading_quantifier_block`]: strips the
+/// maximal leading run of same-symbol quantifiers off the front of `e`,
+/// returning their names outermost-first along with the remaining body.
+/// Panics if `e` is not itself a `Quantifier`.
+fn take_quantifier_block(e: Expr) -> (QSymbol, Vec<String>, Expr) {
+    match e {
+        Expr::Quantifier { symbol, name, body } => {
+            if matches!(*body, Expr::Quantifier { symbol: inner, .. } if inner == symbol) {
+                let (_, mut names, matrix) = take_quantifier_block(*body);
+                names.insert(0, name);
+                (symbol, names, matrix)
+            } else {
+                (symbol, vec![name], *body)
+            }
+        }
+        _ => unreachable!("take_quantifier_block called on a non-Quantifier"),
+    }
+}
+
+/// Rebuilds a quantifier block from its binder names (outermost first) and
+/// matrix — the inverse of [`take_quantifier_block`].
+fn rebuild_quantifier_block(symbol: QSymbol, names: Vec<String>, matrix: Expr) -> Expr {
+    names.into_iter().rev().fold(matrix, |body, name| Expr::Quantifier { symbol, name, body: Box::new(body) })
+}
+
+/// The index, in a pre-order walk of `body`, of the first free occurrence of
+/// `name` — `None` if `name` never occurs free in `body` (a vacuous binder).
+/// Used by [`sort_quantifier_blocks`] as the canonical ordering key, since it
+/// depends only on where each binder is actually used, not on its spelling.
+fn first_free_use_position(body: &Expr, name: &str) -> Option<usize> {
+    let free: Vec<&Expr> = free_occurrence_paths(body, name).iter().filter_map(|p| get_at(body, p)).collect();
+    body.subexprs().position(|sub| free.iter().any(|f| std::ptr::eq(*f, sub)))
+}
+
+/// Reorders each maximal run of identical-kind adjacent quantifiers
+/// (`forall`/`forall`/... or `exists`/`exists`/...) so their binders appear
+/// in the order each is first referenced in the shared body, rather than the
+/// order they were originally written in — so `forall x forall y, P(x,y)`
+/// and `forall y forall x, P(x,y)` both canonicalize to the same tree, and
+/// re-deriving the key from an alpha-renamed copy gives the same answer,
+/// since the key never looks at a binder's spelling. `forall`/`exists`
+/// boundaries are never crossed: only a contiguous run of one `QSymbol` is
+/// reordered as a unit, and a mixed `forall x exists y` prefix is left
+/// exactly as written.
+///
+/// A run whose binder names collide (e.g. a shadowed `forall x forall x,
+/// ...`) is renamed apart first with [`freshen_binders`], since permuting
+/// two same-named binders would otherwise silently change which occurrences
+/// of the name each one captures.
+pub fn sort_quantifier_blocks(e: Expr) -> Expr {
+    transform_expr(e, &|e| match &e {
+        Expr::Quantifier { .. } => {
+            let original = e.clone();
+            let (symbol, names, matrix) = take_quantifier_block(e);
+            if names.len() < 2 {
+                return (original, false);
+            }
+            let mut seen = std::collections::HashSet::new();
+            let (names, matrix) = if names.iter().any(|name| !seen.insert(name.clone())) {
+                let block = rebuild_quantifier_block(symbol, names, matrix);
+                let freshened = freshen_binders(&block, &std::collections::HashSet::new());
+                let (_, names, matrix) = take_quantifier_block(freshened);
+                (names, matrix)
+            } else {
+                (names, matrix)
+            };
+            let mut order: Vec<usize> = (0..names.len()).collect();
+            // A binder that's vacuous in `matrix` has no first-use position to
+            // sort by; falling back to its own original index (rather than,
+            // say, always sinking it to the end) means a vacuous binder never
+            // gets dragged out of place by binders it has no relationship to.
+            order.sort_by_key(|&i| first_free_use_position(&matrix, &names[i]).unwrap_or(i));
+            let sorted_names = order.into_iter().map(|i| names[i].clone()).collect();
+            let result = rebuild_quantifier_block(symbol, sorted_names, matrix);
+            let changed = result != original;
+            (result, changed)
+        }
+        _ => (e, false),
+    })
+}
+
+/// Which way [`distribute_quantifiers`] moves a quantifier across an
+/// `AssocBinop`. Only the two sound equivalences are implemented — `Forall`
+/// over `And` and `Exists` over `Or` — since `Forall` does not distribute
+/// over `Or`, nor `Exists` over `And` (e.g. `forall x, (P(x) \/ Q(x))` does
+/// not imply `(forall x, P(x)) \/ (forall x, Q(x))`: `x` can satisfy the
+/// disjunction by a different disjunct on each iteration).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantifierDistributionDirection {
+    /// `forall x, (A /\ B)` becomes `(forall x, A) /\ (forall x, B)`, and
+    /// `exists x, (A \/ B)` becomes `(exists x, A) \/ (exists x, B)` —
+    /// pushing the quantifier in, for miniscoping.
+    Split,
+    /// The reverse: `(forall x, A) /\ (forall x, B)` becomes `forall x, (A
+    /// /\ B)`, and `(exists x, A) \/ (exists x, B)` becomes `exists x, (A
+    /// \/ B)` — pulling the quantifier out, for prenexing.
+    Merge,
+}
+
+/// `Expr::quantifier(symbol, name, body)`, except that if `body` doesn't
+/// mention `name` the quantifier is vacuous and is dropped, leaving `body`
+/// as-is — the degenerate case [`distribute_quantifiers`]'s `Split`
+/// direction needs when distributing over an operand the bound variable
+/// never occurs in.
+fn quantify_or_drop(symbol: QSymbol, name: &str, body: Expr) -> Expr {
+    if freevars(&body).contains(name) {
+        Expr::quantifier(symbol, name, body)
+    } else {
+        body
+    }
+}
+
+fn distribute_quantifiers_split_step(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::Quantifier { symbol: QSymbol::Forall, name, body } => match *body {
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+                let split = exprs.into_iter().map(|x| quantify_or_drop(QSymbol::Forall, &name, x)).collect();
+                (Expr::assoc(ASymbol::And, split), true)
+            }
+            other => (Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(other) }, false),
+        },
+        Expr::Quantifier { symbol: QSymbol::Exists, name, body } => match *body {
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+                let split = exprs.into_iter().map(|x| quantify_or_drop(QSymbol::Exists, &name, x)).collect();
+                (Expr::assoc(ASymbol::Or, split), true)
+            }
+            other => (Expr::Quantifier { symbol: QSymbol::Exists, name, body: Box::new(other) }, false),
+        },
+        other => (other, false),
+    }
+}
+
+/// If every one of `exprs` is a `Quantifier{symbol}` binding the same name,
+/// merges them into one `Quantifier{symbol}` over an `AssocBinop{inner}` of
+/// their bodies; otherwise hands `exprs` back unchanged.
+fn merge_same_quantifier(symbol: QSymbol, inner: ASymbol, exprs: Vec<Expr>) -> Result<Expr, Vec<Expr>> {
+    let name = match exprs.first() {
+        Some(Expr::Quantifier { symbol: s, name, .. }) if *s == symbol => name.clone(),
+        _ => return Err(exprs),
+    };
+    let all_match = exprs.iter().all(|x| matches!(x, Expr::Quantifier { symbol: s, name: n, .. } if *s == symbol && *n == name));
+    if !all_match {
+        return Err(exprs);
+    }
+    let bodies = exprs
+        .into_iter()
+        .map(|x| match x {
+            Expr::Quantifier { body, .. } => *body,
+            _ => unreachable!("all_match confirmed every operand is this quantifier shape"),
+        })
+        .collect();
+    Ok(Expr::quantifier(symbol, &name, Expr::assoc(inner, bodies)))
+}
+
+fn distribute_quantifiers_merge_step(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => match merge_same_quantifier(QSymbol::Forall, ASymbol::And, exprs) {
+            Ok(merged) => (merged, true),
+            Err(exprs) => (Expr::AssocBinop { symbol: ASymbol::And, exprs }, false),
+        },
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => match merge_same_quantifier(QSymbol::Exists, ASymbol::Or, exprs) {
+            Ok(merged) => (merged, true),
+            Err(exprs) => (Expr::AssocBinop { symbol: ASymbol::Or, exprs }, false),
+        },
+        other => (other, false),
+    }
+}
+
+/// Moves a quantifier across an `AssocBinop` it sits next to, in
+/// `direction`, to a fixpoint, handling an n-ary operand list by
+/// distributing over every operand in one step. Only [`QuantifierDistributionDirection`]'s
+/// two sound equivalences ever fire; the unsound combinations (`Forall`
+/// over `Or`, `Exists` over `And`) are left exactly as they are, in both
+/// directions.
+pub fn distribute_quantifiers(e: Expr, direction: QuantifierDistributionDirection) -> Expr {
+    match direction {
+        QuantifierDistributionDirection::Split => transform_expr(e, &distribute_quantifiers_split_step),
+        QuantifierDistributionDirection::Merge => transform_expr(e, &distribute_quantifiers_merge_step),
+    }
+}
+
+/// Converts `e` to negation normal form: `Implies`, `Bicon`/`Equiv`, `Xor`,
+/// and `Nand`/`Nor` are eliminated in favor of `And`/`Or`/`Not`, negations
+/// are pushed inward through `And`, `Or`, and quantifiers (flipping
+/// `Forall`/`Exists`), and double negations cancel, so that every `Not`
+/// ends up directly above an atom (`Var`, `Apply`, `Tautology`, or
+/// `Contradiction`). This is a single [`transform_expr`] pass rather than
+/// several separately-ordered rewrites, so callers never need to worry
+/// about which normalizer to run first. An n-ary `Bicon`/`Equiv` is
+/// eliminated via [`expand_bicon_chain`]'s pairwise-chain reading, which
+/// (since biconditional composes transitively) is equivalent to asserting
+/// all of its operands share the same truth value; an n-ary `Xor` is
+/// eliminated via [`normalize_xor`]'s parity-preserving expansion; `Nand`
+/// and `Nor` are eliminated via [`normalize_sheffer`]'s defining
+/// identities; the resulting `Implies`/`And`/`Or` structure is then
+/// resolved to NNF by this same pass's other rules.
+pub fn to_nnf(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => (Expr::assoc(ASymbol::Or, vec![negate(*l), *r]), true),
+        Expr::Binop { symbol: symbol @ (BSymbol::Nand | BSymbol::Nor), l, r } => (normalize_sheffer(Expr::Binop { symbol, l, r }), true),
+        Expr::AssocBinop { symbol: symbol @ (ASymbol::Bicon | ASymbol::Equiv), exprs } => (expand_bicon_chain(Expr::AssocBinop { symbol, exprs }), true),
+        Expr::AssocBinop { symbol: ASymbol::Xor, exprs } => (normalize_xor(Expr::AssocBinop { symbol: ASymbol::Xor, exprs }), true),
+        Expr::Unop { symbol: USymbol::Not, operand } => match *operand {
+            Expr::Unop { symbol: USymbol::Not, operand } => (*operand, true),
+            Expr::Contradiction => (Expr::Tautology, true),
+            Expr::Tautology => (Expr::Contradiction, true),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+                (Expr::AssocBinop { symbol: ASymbol::Or, exprs: exprs.into_iter().map(negate).collect() }, true)
+            }
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+                (Expr::AssocBinop { symbol: ASymbol::And, exprs: exprs.into_iter().map(negate).collect() }, true)
+            }
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => (Expr::assoc(ASymbol::And, vec![*l, negate(*r)]), true),
+            Expr::Quantifier { symbol: QSymbol::Forall, name, body } => {
+                (Expr::Quantifier { symbol: QSymbol::Exists, name, body: Box::new(negate(*body)) }, true)
+            }
+            Expr::Quantifier { symbol: QSymbol::Exists, name, body } => {
+                (Expr::Quantifier { symbol: QSymbol::Forall, name, body: Box::new(negate(*body)) }, true)
+            }
+            operand => (!operand, false),
+        },
+        other => (other, false),
+    })
+}
+
+/// Why a structural normal-form check (e.g. [`check_nnf`], [`check_cnf`])
+/// rejected an expression: `path` names the first offending node and
+/// `reason` explains why it violates the claimed form. Mirrors
+/// [`WellFormedError`]'s shape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WhyNot {
+    pub path: ExprPath,
+    pub reason: String,
+}
+
+impl fmt::Display for WhyNot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at path {:?}: {}", self.path.0, self.reason)
+    }
+}
+
+impl std::error::Error for WhyNot {}
+
+/// Like [`is_nnf`], but on failure reports the path to the first node that
+/// isn't in negation normal form instead of just `false`, so a GUI can
+/// highlight exactly what a student needs to fix.
+pub fn check_nnf(e: &Expr) -> Result<(), WhyNot> {
+    fn go(e: &Expr, path: &mut Vec<usize>) -> Result<(), WhyNot> {
+        match e {
+            Expr::Contradiction | Expr::Tautology | Expr::Var { .. } | Expr::Apply { .. } => Ok(()),
+            Expr::Unop { symbol: USymbol::Not, operand } => {
+                if matches!(operand.as_ref(), Expr::Var { .. } | Expr::Apply { .. } | Expr::Contradiction | Expr::Tautology) {
+                    Ok(())
+                } else {
+                    Err(WhyNot { path: ExprPath(path.clone()), reason: "negation must sit directly above an atom".to_owned() })
+                }
+            }
+            Expr::Binop { symbol: BSymbol::Implies, .. } => {
+                Err(WhyNot { path: ExprPath(path.clone()), reason: "`->` must be eliminated in negation normal form".to_owned() })
+            }
+            Expr::Binop { symbol: BSymbol::Nand | BSymbol::Nor, .. } => {
+                Err(WhyNot { path: ExprPath(path.clone()), reason: "nand/nor must be eliminated in negation normal form".to_owned() })
+            }
+            Expr::Binop { .. } => Ok(()),
+            Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, .. } => Err(WhyNot {
+                path: ExprPath(path.clone()),
+                reason: "n-ary biconditional must be eliminated in negation normal form".to_owned(),
+            }),
+            Expr::AssocBinop { symbol: ASymbol::Xor, .. } => Err(WhyNot {
+                path: ExprPath(path.clone()),
+                reason: "xor must be eliminated in negation normal form".to_owned(),
+            }),
+            Expr::AssocBinop { exprs, .. } => {
+                for (i, x) in exprs.iter().enumerate() {
+                    path.push(i);
+                    let result = go(x, path);
+                    path.pop();
+                    result?;
+                }
+                Ok(())
+            }
+            Expr::Quantifier { body, .. } => {
+                path.push(0);
+                let result = go(body, path);
+                path.pop();
+                result
+            }
+        }
+    }
+    go(e, &mut Vec::new())
+}
+
+/// `true` iff every `Not` in `e` sits directly above an atom (`Var`,
+/// `Apply`, `Tautology`, or `Contradiction`) and `e` contains no `Implies`,
+/// `Bicon`, `Equiv`, `Xor`, `Nand`, or `Nor` — i.e. `e` is exactly what
+/// [`to_nnf`] would produce.
+pub fn is_nnf(e: &Expr) -> bool {
+    check_nnf(e).is_ok()
+}
+
+/// Flattens nested `AssocBinop`s that share the same symbol into their
+/// parent, e.g. `And[And[a, b], c]` becomes `And[a, b, c]`. This undoes the
+/// re-nesting that rewrites like [`to_nnf`]'s `Implies`/`Bicon` elimination
+/// introduce when they build a fresh `AssocBinop` around an already-`AssocBinop`
+/// child, and is a prerequisite for [`to_cnf`] to see a clause set as a single
+/// flat `And` of `Or`s rather than a tree of them.
+pub fn combine_associative_ops(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::AssocBinop { symbol, exprs } => {
+            let mut changed = false;
+            let mut flat = Vec::with_capacity(exprs.len());
+            for x in exprs {
+                match x {
+                    Expr::AssocBinop { symbol: inner_symbol, exprs: inner_exprs } if inner_symbol == symbol => {
+                        changed = true;
+                        flat.extend(inner_exprs);
+                    }
+                    other => flat.push(other),
+                }
+            }
+            (Expr::AssocBinop { symbol, exprs: flat }, changed)
+        }
+        other => (other, false),
+    })
+}
+
+/// `true` iff `e` is a literal: a possibly-negated `Var`, `Apply`,
+/// `Tautology`, or `Contradiction`.
+fn is_literal(e: &Expr) -> bool {
+    match e {
+        Expr::Var { .. } | Expr::Apply { .. } | Expr::Tautology | Expr::Contradiction => true,
+        Expr::Unop { symbol: USymbol::Not, operand } => is_literal(operand),
+        _ => false,
+    }
+}
+
+fn clause_why_not(e: &Expr, path: &mut Vec<usize>) -> Result<(), WhyNot> {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            for (i, x) in exprs.iter().enumerate() {
+                if !is_literal(x) {
+                    path.push(i);
+                    let result = Err(WhyNot { path: ExprPath(path.clone()), reason: "clause operand is not a literal".to_owned() });
+                    path.pop();
+                    return result;
+                }
+            }
+            Ok(())
+        }
+        other if is_literal(other) => Ok(()),
+        _ => Err(WhyNot { path: ExprPath(path.clone()), reason: "expected a clause: a literal, or an Or of literals".to_owned() }),
+    }
+}
+
+/// Like [`is_cnf`], but on failure reports the path to the first clause (or
+/// clause operand) that breaks the form instead of just `false`.
+pub fn check_cnf(e: &Expr) -> Result<(), WhyNot> {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            let mut path = Vec::new();
+            for (i, clause) in exprs.iter().enumerate() {
+                path.push(i);
+                let result = clause_why_not(clause, &mut path);
+                path.pop();
+                result?;
+            }
+            Ok(())
+        }
+        other => clause_why_not(other, &mut Vec::new()),
+    }
+}
+
+/// `true` iff `e` is in conjunctive normal form: an `And` of clauses, or a
+/// single clause on its own. This is the structural shape [`to_cnf`]
+/// produces for the propositional fragment; quantified formulas are never
+/// in CNF by this definition, since [`to_cnf`] leaves them untouched.
+pub fn is_cnf(e: &Expr) -> bool {
+    check_cnf(e).is_ok()
+}
+
+/// Distributes `Or` over `And` one layer at a time: if any operand of an
+/// `Or` is itself an `And`, that `And` is pulled out and the rest of the
+/// `Or`'s operands are distributed over each of its conjuncts. Run inside
+/// [`transform_expr`] so that nested occurrences are handled bottom-up and
+/// the rewrite repeats until no `Or` has an `And` child left.
+fn distribute_or_over_and(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            match exprs.iter().position(|x| matches!(x, Expr::AssocBinop { symbol: ASymbol::And, .. })) {
+                Some(i) => {
+                    let mut exprs = exprs;
+                    let and_operand = exprs.remove(i);
+                    let conjuncts = match and_operand {
+                        Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs,
+                        _ => unreachable!(),
+                    };
+                    let rest = exprs;
+                    let distributed = conjuncts
+                        .into_iter()
+                        .map(|c| {
+                            let mut operands = rest.clone();
+                            operands.push(c);
+                            Expr::assoc(ASymbol::Or, operands)
+                        })
+                        .collect();
+                    (Expr::assoc(ASymbol::And, distributed), true)
+                }
+                None => (Expr::assoc(ASymbol::Or, exprs), false),
+            }
+        }
+        other => (other, false),
+    }
+}
+
+/// Converts the propositional fragment of `e` (no quantifiers) to
+/// conjunctive normal form: an `And` of `Or`s of literals. Quantified
+/// subformulas are left untouched rather than rejected, since a quantifier
+/// nested under a connective still has a well-defined (if not itself CNF)
+/// shape to return; callers that need to reject them outright can check
+/// [`is_cnf`] on the result, or `count_quantifiers` on the input.
+///
+/// The construction is `to_nnf`, then repeated distribution of `Or` over
+/// `And`, then [`combine_associative_ops`] to flatten the resulting nested
+/// `AssocBinop`s into one `And` of flat `Or` clauses. Because naive
+/// distribution can blow the clause set up, the result is cleaned up once
+/// more: literals are deduplicated within each clause, clauses containing a
+/// literal and its complement are dropped (they are tautologies), and
+/// duplicate clauses are merged.
+pub fn to_cnf(e: Expr) -> Expr {
+    let nnf = to_nnf(e);
+    let distributed = transform_expr(nnf, &distribute_or_over_and);
+    let flat = combine_associative_ops(distributed);
+    let clauses = match flat {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs,
+        other => vec![other],
+    };
+    let mut seen_clauses: Vec<Expr> = Vec::with_capacity(clauses.len());
+    for clause in clauses {
+        let lits = match clause {
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs,
+            other => vec![other],
+        };
+        let mut dedup_lits: Vec<Expr> = Vec::with_capacity(lits.len());
+        for l in lits {
+            if !dedup_lits.contains(&l) {
+                dedup_lits.push(l);
+            }
+        }
+        let is_tautological = has_complementary_pair(&dedup_lits);
+        if is_tautological {
+            continue;
+        }
+        let clause_expr = match dedup_lits.len() {
+            1 => dedup_lits.into_iter().next().unwrap(),
+            _ => Expr::assoc(ASymbol::Or, dedup_lits),
+        };
+        if !seen_clauses.contains(&clause_expr) {
+            seen_clauses.push(clause_expr);
+        }
+    }
+    if seen_clauses.is_empty() {
+        Expr::Tautology
+    } else {
+        Expr::assoc(ASymbol::And, seen_clauses)
+    }
+}
+
+fn conjunct_why_not(e: &Expr, path: &mut Vec<usize>) -> Result<(), WhyNot> {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            for (i, x) in exprs.iter().enumerate() {
+                if !is_literal(x) {
+                    path.push(i);
+                    let result = Err(WhyNot { path: ExprPath(path.clone()), reason: "conjunct operand is not a literal".to_owned() });
+                    path.pop();
+                    return result;
+                }
+            }
+            Ok(())
+        }
+        other if is_literal(other) => Ok(()),
+        _ => Err(WhyNot { path: ExprPath(path.clone()), reason: "expected a conjunct: a literal, or an And of literals".to_owned() }),
+    }
+}
+
+/// Like [`is_dnf`], but on failure reports the path to the first disjunct
+/// (or disjunct operand) that breaks the form instead of just `false`.
+/// Mirror of [`check_cnf`].
+pub fn check_dnf(e: &Expr) -> Result<(), WhyNot> {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+            let mut path = Vec::new();
+            for (i, disjunct) in exprs.iter().enumerate() {
+                path.push(i);
+                let result = conjunct_why_not(disjunct, &mut path);
+                path.pop();
+                result?;
+            }
+            Ok(())
+        }
+        other => conjunct_why_not(other, &mut Vec::new()),
+    }
+}
+
+/// `true` iff `e` is in disjunctive normal form: an `Or` of conjuncts, or a
+/// single conjunct on its own. Mirror of [`is_cnf`]; quantified formulas are
+/// never in DNF by this definition, since [`to_dnf`] leaves them untouched.
+pub fn is_dnf(e: &Expr) -> bool {
+    check_dnf(e).is_ok()
+}
+
+/// Distributes `And` over `Or` one layer at a time, mirroring
+/// [`distribute_or_over_and`] with the symbols swapped.
+fn distribute_and_over_or(e: Expr) -> (Expr, bool) {
+    match e {
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+            match exprs.iter().position(|x| matches!(x, Expr::AssocBinop { symbol: ASymbol::Or, .. })) {
+                Some(i) => {
+                    let mut exprs = exprs;
+                    let or_operand = exprs.remove(i);
+                    let disjuncts = match or_operand {
+                        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs,
+                        _ => unreachable!(),
+                    };
+                    let rest = exprs;
+                    let distributed = disjuncts
+                        .into_iter()
+                        .map(|d| {
+                            let mut operands = rest.clone();
+                            operands.push(d);
+                            Expr::assoc(ASymbol::And, operands)
+                        })
+                        .collect();
+                    (Expr::assoc(ASymbol::Or, distributed), true)
+                }
+                None => (Expr::assoc(ASymbol::And, exprs), false),
+            }
+        }
+        other => (other, false),
+    }
+}
+
+/// Converts the propositional fragment of `e` (no quantifiers) to
+/// disjunctive normal form: an `Or` of `And`s of literals. Mirror of
+/// [`to_cnf`]: `to_nnf`, then repeated distribution of `And` over `Or`,
+/// then [`combine_associative_ops`] to flatten. Conjuncts containing both a
+/// literal and its complement are contradictory and are dropped, as are
+/// duplicate conjuncts; if every conjunct is dropped this way, the whole
+/// formula is unsatisfiable and `to_dnf` returns [`Expr::Contradiction`].
+pub fn to_dnf(e: Expr) -> Expr {
+    let nnf = to_nnf(e);
+    let distributed = transform_expr(nnf, &distribute_and_over_or);
+    let flat = combine_associative_ops(distributed);
+    let disjuncts = match flat {
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs,
+        other => vec![other],
+    };
+    let mut seen_disjuncts: Vec<Expr> = Vec::with_capacity(disjuncts.len());
+    for disjunct in disjuncts {
+        let lits = match disjunct {
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs,
+            other => vec![other],
+        };
+        let mut dedup_lits: Vec<Expr> = Vec::with_capacity(lits.len());
+        for l in lits {
+            if !dedup_lits.contains(&l) {
+                dedup_lits.push(l);
+            }
+        }
+        let is_contradictory = has_complementary_pair(&dedup_lits);
+        if is_contradictory {
+            continue;
+        }
+        let disjunct_expr = match dedup_lits.len() {
+            1 => dedup_lits.into_iter().next().unwrap(),
+            _ => Expr::assoc(ASymbol::And, dedup_lits),
+        };
+        if !seen_disjuncts.contains(&disjunct_expr) {
+            seen_disjuncts.push(disjunct_expr);
+        }
+    }
+    if seen_disjuncts.is_empty() {
+        Expr::Contradiction
+    } else {
+        Expr::assoc(ASymbol::Or, seen_disjuncts)
+    }
+}
+
+/// Which way [`normalize_distribution`] pushes a connective through the
+/// other. Distributing in only one direction per call is what makes it
+/// terminate: each direction's helper only ever pulls operands of the
+/// *other* symbol out of an `AssocBinop`, which strictly shrinks how deeply
+/// that other symbol is nested, so repeated application to a fixpoint can't
+/// loop. Running both directions in the same pass would have each one's
+/// output immediately match the other's input pattern, looping forever.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DistributionDirection {
+    /// `Or` distributes over `And`, the step [`to_cnf`] uses on its way to
+    /// conjunctive normal form.
+    TowardCnf,
+    /// `And` distributes over `Or`, the step [`to_dnf`] uses on its way to
+    /// disjunctive normal form.
+    TowardDnf,
+}
+
+/// Applies the Distribution equivalence rule to `e` to a fixpoint, in just
+/// one `direction`, then flattens the result with [`combine_associative_ops`].
+/// Unlike [`to_cnf`]/[`to_dnf`], this performs only the distribution step —
+/// no [`to_nnf`] first, no literal deduplication or tautology/contradiction
+/// clause removal after — so it's useful anywhere a caller wants Distribution
+/// applied on its own, e.g. as one equivalence rule in a proof checker,
+/// rather than as part of a full CNF/DNF conversion pipeline.
+pub fn normalize_distribution(e: Expr, direction: DistributionDirection) -> Expr {
+    let distribute = match direction {
+        DistributionDirection::TowardCnf => distribute_or_over_and,
+        DistributionDirection::TowardDnf => distribute_and_over_or,
+    };
+    combine_associative_ops(transform_expr(e, &distribute))
+}
+
+/// The hard cap on the number of rounds [`simplify`] will run before giving
+/// up and returning its best-so-far result. Each round already drives every
+/// individual law to its own internal fixpoint, so reaching this cap means
+/// the *interaction* between laws (e.g. absorption exposing a fresh
+/// complementary pair) is still producing changes after an unreasonable
+/// number of rounds, not that any single law is looping.
+pub const SIMPLIFY_MAX_ITERATIONS: usize = 128;
+
+/// The result of [`simplify`]: the simplified expression, and whether
+/// [`SIMPLIFY_MAX_ITERATIONS`] was reached before a fixpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Simplified {
+    /// The best-so-far expression: fully simplified if `hit_iteration_limit`
+    /// is `false`, otherwise the result of the last round that still ran.
+    pub expr: Expr,
+    /// `true` if [`SIMPLIFY_MAX_ITERATIONS`] rounds ran without reaching a
+    /// fixpoint.
+    pub hit_iteration_limit: bool,
+}
+
+/// Repeatedly applies the whole battery of boolean simplification laws —
+/// in order, every round: [`combine_associative_ops`] (flatten nested
+/// associative operators back together, since a law below may have just
+/// rebuilt one), [`normalize_identity`], [`normalize_annihilation`],
+/// [`normalize_complement`] (already n-ary: a complementary pair is found
+/// anywhere in an operand list, not just a fixed two-operand shape),
+/// [`normalize_double_negation`] (the Involution/Inverse law), then
+/// [`normalize_idempotence`] (structural, not alpha-equivalence, dedup —
+/// matching every other law here, which is purely syntactic) and
+/// [`normalize_absorption`] — until a full round leaves `e` unchanged.
+///
+/// This fixed order is what makes the result deterministic: every round
+/// flattens before folding constants, folds constants before deduplicating,
+/// and deduplicates before absorbing, so the same input always reduces the
+/// same way regardless of which law happened to "notice" an opportunity
+/// first. The loop itself is still necessary even with that order, because
+/// applying the whole sequence once can expose a fresh opportunity for a law
+/// earlier in the sequence — e.g. annihilation collapsing an operand to
+/// `Contradiction` can turn a sibling into a fresh complementary pair, and
+/// absorption removing an operand can turn what's left into a double
+/// negation — so rounds repeat until none of the laws have anything left to
+/// do. Gives up after [`SIMPLIFY_MAX_ITERATIONS`] rounds, returning the
+/// best-so-far expression via [`Simplified::hit_iteration_limit`] rather
+/// than looping forever on a pathological input.
+pub fn simplify(e: Expr) -> Simplified {
+    let mut current = e;
+    for _ in 0..SIMPLIFY_MAX_ITERATIONS {
+        let mut next = combine_associative_ops(current.clone());
+        next = normalize_identity(next);
+        next = normalize_annihilation(next);
+        next = normalize_complement(next);
+        next = normalize_double_negation(next);
+        next = normalize_idempotence(next, false);
+        next = normalize_absorption(next);
+        if next == current {
+            return Simplified { expr: next, hit_iteration_limit: false };
+        }
+        current = next;
+    }
+    Simplified { expr: current, hit_iteration_limit: true }
+}
+
+/// Puts `e` through a fixed, documented pipeline of syntactic identities —
+/// flattening nested associative operators ([`combine_associative_ops`]),
+/// collapsing double negations, folding identity/annihilator/complement
+/// laws for `And`/`Or`, deduplicating repeated arguments, and sorting
+/// commutative arguments into a deterministic order — iterated to a
+/// fixpoint. Two expressions that differ only by those laws canonicalize to
+/// structurally equal trees, so `canonicalize(a) == canonicalize(b)` is a
+/// convenient (if incomplete, since it's purely syntactic) stand-in for
+/// semantic equivalence in places like answer-checking.
+///
+/// `canonicalize` never reorders the operands of a non-commutative
+/// connective, so e.g. `a -> b` and `b -> a` always canonicalize to
+/// distinct trees. It is deterministic and idempotent:
+/// `canonicalize(canonicalize(e)) == canonicalize(e)`.
+pub fn canonicalize(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Unop { symbol: USymbol::Not, operand } => match *operand {
+            Expr::Unop { symbol: USymbol::Not, operand } => (*operand, true),
+            Expr::Contradiction => (Expr::Tautology, true),
+            Expr::Tautology => (Expr::Contradiction, true),
+            operand => (!operand, false),
+        },
+        Expr::AssocBinop { symbol, exprs } => canonicalize_assoc(symbol, exprs),
+        other => (other, false),
+    })
+}
+
+/// Which tier of [`canonical_cmp`]'s ordering `e` falls into: a bare atom
+/// (`Var`/`Tautology`/`Contradiction`) sorts before a negated atom, which
+/// sorts before every compound expression.
+fn canonical_tier(e: &Expr) -> u8 {
+    match e {
+        Expr::Var { .. } | Expr::Tautology | Expr::Contradiction => 0,
+        Expr::Unop { symbol: USymbol::Not, operand } if canonical_tier(operand) == 0 => 1,
+        _ => 2,
+    }
+}
+
+/// A string tag for `e`'s outer constructor and, where relevant, its
+/// symbol — used by [`canonical_cmp`] to order two compound expressions of
+/// equal size before falling back to comparing their children, so e.g. an
+/// `Apply` and a same-size `AssocBinop{And}` don't tie just because neither
+/// has children left to distinguish them by.
+fn canonical_shape_key(e: &Expr) -> String {
+    match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => String::new(),
+        Expr::Apply { .. } => "apply".to_owned(),
+        Expr::Unop { symbol, .. } => format!("unop:{:?}", symbol),
+        Expr::Binop { symbol, .. } => format!("binop:{:?}", symbol),
+        Expr::AssocBinop { symbol, .. } => format!("assoc:{:?}", symbol),
+        Expr::Quantifier { symbol, .. } => format!("quant:{:?}", symbol),
+    }
+}
+
+/// A total, explicit canonical ordering over [`Expr`], used by
+/// [`sort_by_canonical_key`] to put the operands of a commutative connective
+/// into a deterministic order that's easy to explain: atoms first (sorted by
+/// name), then negated atoms (sorted by the name of what they negate), then
+/// every compound expression (sorted by size, i.e. total node count, and
+/// then recursively by shape and children). This is unrelated to the
+/// derived, discriminant-based `Expr` field order — that one is never
+/// exposed as `Ord` — and is consistent with [`canonicalize`]: resorting an
+/// already-sorted operand list with `canonical_cmp` is a no-op.
+pub fn canonical_cmp(a: &Expr, b: &Expr) -> Ordering {
+    let (tier_a, tier_b) = (canonical_tier(a), canonical_tier(b));
+    if tier_a != tier_b {
+        return tier_a.cmp(&tier_b);
+    }
+    match tier_a {
+        0 => a.to_string().cmp(&b.to_string()),
+        1 => match (a, b) {
+            (Expr::Unop { operand: oa, .. }, Expr::Unop { operand: ob, .. }) => canonical_cmp(oa, ob),
+            _ => unreachable!("tier 1 is only ever assigned to a Not of an atom"),
+        },
+        _ => {
+            let (size_a, size_b) = (a.subexprs().count(), b.subexprs().count());
+            size_a
+                .cmp(&size_b)
+                .then_with(|| canonical_shape_key(a).cmp(&canonical_shape_key(b)))
+                .then_with(|| {
+                    let (children_a, children_b) = (children(a), children(b));
+                    children_a.len().cmp(&children_b.len()).then_with(|| {
+                        children_a
+                            .into_iter()
+                            .zip(children_b)
+                            .map(|(x, y)| canonical_cmp(x, y))
+                            .find(|o| *o != Ordering::Equal)
+                            .unwrap_or(Ordering::Equal)
+                    })
+                })
+        }
+    }
+}
+
+/// Puts a list of commutative operands into [`canonical_cmp`] order. Shared
+/// by [`canonicalize_assoc`] (for `And`/`Or`/`Bicon`/`Equiv`) and
+/// [`flatten_arithmetic`] (for `Plus`/`Mult`), so the two normalizers agree
+/// on what "sorted" means.
+fn sort_by_canonical_key(exprs: &mut [Expr]) {
+    exprs.sort_by(canonical_cmp);
+}
+
+/// Rebuilds a left-nested `Binop{symbol}` chain from `exprs`, e.g.
+/// `[a, b, c]` becomes `(a symbol b) symbol c`. The inverse of flattening a
+/// `Binop` chain into its list of operands; `exprs` must be non-empty.
+pub fn unflatten_arithmetic(symbol: BSymbol, mut exprs: Vec<Expr>) -> Expr {
+    let first = exprs.remove(0);
+    exprs.into_iter().fold(first, |acc, x| Expr::Binop { symbol, l: Box::new(acc), r: Box::new(x) })
+}
+
+fn flatten_binop_chain(e: Expr, symbol: BSymbol, out: &mut Vec<Expr>) {
+    match e {
+        Expr::Binop { symbol: s, l, r } if s == symbol => {
+            flatten_binop_chain(*l, symbol, out);
+            flatten_binop_chain(*r, symbol, out);
+        }
+        other => out.push(other),
+    }
+}
+
+/// Canonicalizes nested `Binop{Plus}`/`Binop{Mult}` chains: collects every
+/// operand of a maximal same-symbol chain (however it happens to be
+/// nested), sorts them with the same [`sort_by_canonical_key`] that
+/// [`canonicalize`] uses for `And`/`Or`, and rebuilds a left-nested chain
+/// with [`unflatten_arithmetic`]. `Plus` and `Mult` live in `Binop` rather
+/// than `AssocBinop` (whose symbol type is [`ASymbol`], not [`BSymbol`]), so
+/// unlike [`combine_associative_ops`] this has to walk and rebuild the
+/// `Binop` tree directly instead of flattening a `Vec`. Only like symbols
+/// merge — a `Plus` chain never absorbs a nested `Mult`, and vice versa —
+/// and non-arithmetic `Binop`s (`Implies`) are left untouched. Iterated to a
+/// fixpoint like the other `normalize_*`/`canonicalize` passes, so e.g.
+/// `(a + b) + c` and `a + (c + b)` both canonicalize to the same tree.
+pub fn flatten_arithmetic(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Binop { symbol, l, r } if matches!(symbol, BSymbol::Plus | BSymbol::Mult) => {
+            let original = Expr::Binop { symbol, l: l.clone(), r: r.clone() };
+            let mut leaves = Vec::new();
+            flatten_binop_chain(Expr::Binop { symbol, l, r }, symbol, &mut leaves);
+            sort_by_canonical_key(&mut leaves);
+            let rebuilt = unflatten_arithmetic(symbol, leaves);
+            let changed = rebuilt != original;
+            (rebuilt, changed)
+        }
+        other => (other, false),
+    })
+}
+
+/// There is no dedicated numeral variant of [`Expr`] yet, so a numeric
+/// literal is, for now, a [`Expr::Var`] whose name parses as an `i64` —
+/// e.g. `Expr::var("3")`. `Some` iff `e` is one of these. `pub(crate)`
+/// because [`crate::parser`] needs it too, to keep the numerals it
+/// produces from `parse` in this same representation.
+pub(crate) fn as_numeral(e: &Expr) -> Option<i64> {
+    match e {
+        Expr::Var { name } => name.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether `e` is a numeral literal per [`as_numeral`]'s convention.
+pub(crate) fn is_numeral(e: &Expr) -> bool {
+    as_numeral(e).is_some()
+}
+
+/// The numeral literal for `n`, in the same `Var`-with-digit-name
+/// representation [`as_numeral`] reads back.
+pub(crate) fn numeral(n: i64) -> Expr {
+    Expr::var(&n.to_string())
+}
+
+/// Folds every numeral operand of a maximal `Binop{symbol}` chain together,
+/// leaving symbolic operands in their original relative order with the
+/// folded constant appended last (so `1 + x + 2` becomes `x + 3`, not
+/// `3 + x`). Shares [`flatten_binop_chain`]/[`unflatten_arithmetic`] with
+/// [`flatten_arithmetic`], so it sees the whole chain at once rather than
+/// just one `Binop` at a time. `original` is returned unchanged if folding
+/// the numerals together would overflow `i64` — constant folding never
+/// happens partially.
+fn fold_arithmetic_chain(symbol: BSymbol, original: Expr) -> (Expr, bool) {
+    let mut leaves = Vec::new();
+    flatten_binop_chain(original.clone(), symbol, &mut leaves);
+
+    let identity: i64 = if symbol == BSymbol::Plus { 0 } else { 1 };
+    let mut constant = identity;
+    let mut symbolic = Vec::new();
+    for leaf in leaves {
+        match as_numeral(&leaf) {
+            Some(n) => {
+                let combined = if symbol == BSymbol::Plus { constant.checked_add(n) } else { constant.checked_mul(n) };
+                match combined {
+                    Some(c) => constant = c,
+                    None => return (original, false),
+                }
+            }
+            None => symbolic.push(leaf),
+        }
+    }
+
+    // `x * 0 => 0`: a zero factor annihilates the whole product, symbolic operands and all.
+    if symbol == BSymbol::Mult && constant == 0 {
+        return (numeral(0), true);
+    }
+
+    let mut result_leaves = symbolic;
+    if constant != identity || result_leaves.is_empty() {
+        result_leaves.push(numeral(constant));
+    }
+    let rebuilt = unflatten_arithmetic(symbol, result_leaves);
+    let changed = rebuilt != original;
+    (rebuilt, changed)
+}
+
+/// Evaluates `Binop{Plus}`/`Binop{Mult}` chains whose operands are numeral
+/// literals (see [`as_numeral`]), applies the unit laws `x + 0 => x`,
+/// `x * 1 => x`, and the annihilator `x * 0 => 0`, and leaves any other
+/// symbolic operand untouched. Composes with [`flatten_arithmetic`]: both
+/// are built on the same flatten/unflatten primitives, so folding a chain
+/// that mixes numerals and variables (e.g. `1 + x + 2`) works in one pass
+/// regardless of how the chain happens to be nested.
+pub fn fold_arithmetic(e: Expr) -> Expr {
+    transform_expr(e, &|e| match e {
+        Expr::Binop { symbol, l, r } if matches!(symbol, BSymbol::Plus | BSymbol::Mult) => {
+            fold_arithmetic_chain(symbol, Expr::Binop { symbol, l, r })
+        }
+        other => (other, false),
+    })
+}
+
+fn canonicalize_assoc(symbol: ASymbol, exprs: Vec<Expr>) -> (Expr, bool) {
+    let original_len = exprs.len();
+    let mut changed = false;
+
+    // Flatten nested occurrences of the same associative operator.
+    let mut flat = Vec::with_capacity(exprs.len());
+    for x in exprs {
+        match x {
+            Expr::AssocBinop { symbol: inner, exprs: inner_exprs } if inner == symbol => {
+                changed = true;
+                flat.extend(inner_exprs);
+            }
+            other => flat.push(other),
+        }
+    }
+
+    // Fold identity/annihilator/complement laws for the boolean connectives.
+    if matches!(symbol, ASymbol::And | ASymbol::Or) {
+        let annihilator = if symbol == ASymbol::And { Expr::Contradiction } else { Expr::Tautology };
+        let identity = if symbol == ASymbol::And { Expr::Tautology } else { Expr::Contradiction };
+        if flat.contains(&annihilator) {
+            return (annihilator, true);
+        }
+        if has_complementary_pair(&flat) {
+            return (annihilator, true);
+        }
+        let before = flat.len();
+        flat.retain(|x| *x != identity);
+        changed = changed || flat.len() != before;
+    }
+
+    // Dedupe repeated arguments (idempotence: `A & A` is just `A`). This law
+    // does not hold for `Xor`, whose parity semantics makes a repeated
+    // operand cancel out entirely (`A ^ A` is `Contradiction`, not `A`)
+    // rather than collapse to one copy, so `Xor` is left for [`normalize_xor`]
+    // to handle instead.
+    let mut exprs = if matches!(symbol, ASymbol::Xor) {
+        flat
+    } else {
+        let before = flat.len();
+        let mut deduped = Vec::with_capacity(flat.len());
+        for x in flat {
+            if !deduped.contains(&x) {
+                deduped.push(x);
+            }
+        }
+        changed = changed || deduped.len() != before;
+        deduped
+    };
+
+    // Sort commutative operators into a deterministic order.
+    if matches!(symbol, ASymbol::And | ASymbol::Or | ASymbol::Bicon | ASymbol::Equiv | ASymbol::Xor) {
+        let before = exprs.clone();
+        sort_by_canonical_key(&mut exprs);
+        changed = changed || exprs != before;
+    }
+
+    if exprs.len() < 2 {
+        return (repair_assoc_arity(symbol, exprs), true);
+    }
+
+    changed = changed || exprs.len() != original_len;
+    (Expr::AssocBinop { symbol, exprs }, changed)
+}
+
+fn flatten_assoc_refs<'a>(e: &'a Expr, symbol: ASymbol, out: &mut Vec<&'a Expr>) {
+    match e {
+        Expr::AssocBinop { symbol: s, exprs } if *s == symbol => {
+            for x in exprs {
+                flatten_assoc_refs(x, symbol, out);
+            }
+        }
+        other => out.push(other),
+    }
+}
+
+fn flatten_assoc_owned(e: Expr, symbol: ASymbol, out: &mut Vec<Expr>) {
+    match e {
+        Expr::AssocBinop { symbol: s, exprs } if s == symbol => {
+            for x in exprs {
+                flatten_assoc_owned(x, symbol, out);
+            }
+        }
+        other => out.push(other),
+    }
+}
+
+/// The conjuncts of `e`: `e` itself in a one-element vector, unless `e` is
+/// an `AssocBinop{And}`, in which case its operands (recursively flattening
+/// any nested `And`s) are returned. Unlike [`combine_associative_ops`], this
+/// doesn't rebuild `e` — it's a read-only view, meant for rule checkers that
+/// just want "the list of conjuncts" regardless of how deeply nested the
+/// `And` structure happens to be.
+pub fn conjuncts(e: &Expr) -> Vec<&Expr> {
+    let mut out = Vec::new();
+    flatten_assoc_refs(e, ASymbol::And, &mut out);
+    out
+}
+
+/// The disjuncts of `e`, analogous to [`conjuncts`] but for `AssocBinop{Or}`.
+pub fn disjuncts(e: &Expr) -> Vec<&Expr> {
+    let mut out = Vec::new();
+    flatten_assoc_refs(e, ASymbol::Or, &mut out);
+    out
+}
+
+/// Owned variant of [`conjuncts`].
+pub fn conjuncts_owned(e: Expr) -> Vec<Expr> {
+    let mut out = Vec::new();
+    flatten_assoc_owned(e, ASymbol::And, &mut out);
+    out
+}
+
+/// Owned variant of [`disjuncts`].
+pub fn disjuncts_owned(e: Expr) -> Vec<Expr> {
+    let mut out = Vec::new();
+    flatten_assoc_owned(e, ASymbol::Or, &mut out);
+    out
+}
+
+/// Builds an `AssocBinop{And}` out of `conjuncts`, the inverse of
+/// [`conjuncts_owned`]: a single conjunct is returned bare (not wrapped in a
+/// one-operand `AssocBinop`), and the empty list produces `Tautology`, the
+/// identity element for `And`.
+pub fn from_conjuncts(mut conjuncts: Vec<Expr>) -> Expr {
+    match conjuncts.len() {
+        0 => Expr::Tautology,
+        1 => conjuncts.pop().unwrap(),
+        _ => Expr::AssocBinop { symbol: ASymbol::And, exprs: conjuncts },
+    }
+}
+
+/// Builds an `AssocBinop{Or}` out of `disjuncts`, analogous to
+/// [`from_conjuncts`] but with `Contradiction`, the identity element for
+/// `Or`, for the empty list.
+pub fn from_disjuncts(mut disjuncts: Vec<Expr>) -> Expr {
+    match disjuncts.len() {
+        0 => Expr::Contradiction,
+        1 => disjuncts.pop().unwrap(),
+        _ => Expr::AssocBinop { symbol: ASymbol::Or, exprs: disjuncts },
+    }
+}
+
+fn tseitin_fresh_symbol(used: &mut std::collections::HashSet<String>) -> Expr {
+    loop {
+        let candidate = gensym("t");
+        if used.insert(candidate.clone()) {
+            return Expr::var(&candidate);
+        }
+    }
+}
+
+/// Tseitin-encodes the propositional fragment of `e` into a list of clauses
+/// (`AssocBinop{Or}` of literals) together with the name of a fresh variable
+/// whose truth value tracks `e`'s: `clauses` is satisfiable with that
+/// variable set to true iff `e` is satisfiable. `And`, `Or`, `Implies`,
+/// `Bicon`, `Equiv`, and `Xor` nodes each introduce one fresh gate variable
+/// (`Bicon`/`Equiv` chain consecutive pairwise gates, same as [`to_nnf`]'s
+/// elimination; `Xor` cascades a parity chain, reusing each `a xor b` gate as
+/// `not (a <-> b)`) defined by a constant number of clauses relating it to
+/// its operands, so the clause count grows linearly with the size of `e`
+/// rather than exploding the way naive [`to_cnf`] distribution can. `Not` is
+/// encoded for free by negating its operand's representative literal rather
+/// than allocating a gate. Anything else (`Var`, `Apply`, `Tautology`,
+/// `Contradiction`, and any other subterm, since those are opaque to a
+/// propositional encoding) is its own representative.
+pub fn tseitin(e: &Expr) -> (Vec<Expr>, String) {
+    fn iff_clauses(clauses: &mut Vec<Expr>, g: &Expr, a: &Expr, b: &Expr) {
+        clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), negate(a.clone()), b.clone()]));
+        clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), a.clone(), negate(b.clone())]));
+        clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), a.clone(), b.clone()]));
+        clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), negate(a.clone()), negate(b.clone())]));
+    }
+
+    fn go(e: &Expr, used: &mut std::collections::HashSet<String>, clauses: &mut Vec<Expr>) -> Expr {
+        match e {
+            Expr::Unop { symbol: USymbol::Not, operand } => negate(go(operand, used, clauses)),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => {
+                let reps: Vec<Expr> = exprs.iter().map(|x| go(x, used, clauses)).collect();
+                let g = tseitin_fresh_symbol(used);
+                for r in &reps {
+                    clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), r.clone()]));
+                }
+                let mut big: Vec<Expr> = reps.iter().map(|r| negate(r.clone())).collect();
+                big.push(g.clone());
+                clauses.push(Expr::assoc(ASymbol::Or, big));
+                g
+            }
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => {
+                let reps: Vec<Expr> = exprs.iter().map(|x| go(x, used, clauses)).collect();
+                let g = tseitin_fresh_symbol(used);
+                for r in &reps {
+                    clauses.push(Expr::assoc(ASymbol::Or, vec![negate(r.clone()), g.clone()]));
+                }
+                let mut big = reps.clone();
+                big.push(negate(g.clone()));
+                clauses.push(Expr::assoc(ASymbol::Or, big));
+                g
+            }
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => {
+                let rl = go(l, used, clauses);
+                let rr = go(r, used, clauses);
+                let g = tseitin_fresh_symbol(used);
+                clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), negate(rl.clone()), rr.clone()]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![rl, g.clone()]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![negate(rr), g.clone()]));
+                g
+            }
+            Expr::Binop { symbol: BSymbol::Nand, l, r } => {
+                let rl = go(l, used, clauses);
+                let rr = go(r, used, clauses);
+                let g = tseitin_fresh_symbol(used);
+                clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), negate(rl.clone()), negate(rr.clone())]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), rl]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), rr]));
+                g
+            }
+            Expr::Binop { symbol: BSymbol::Nor, l, r } => {
+                let rl = go(l, used, clauses);
+                let rr = go(r, used, clauses);
+                let g = tseitin_fresh_symbol(used);
+                clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), negate(rl.clone())]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), negate(rr.clone())]));
+                clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), rl, rr]));
+                g
+            }
+            Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, exprs } => {
+                let reps: Vec<Expr> = exprs.iter().map(|x| go(x, used, clauses)).collect();
+                let mut pair_gates: Vec<Expr> = Vec::new();
+                for w in reps.windows(2) {
+                    let g = tseitin_fresh_symbol(used);
+                    iff_clauses(clauses, &g, &w[0], &w[1]);
+                    pair_gates.push(g);
+                }
+                match pair_gates.len() {
+                    1 => pair_gates.into_iter().next().unwrap(),
+                    _ => {
+                        let g = tseitin_fresh_symbol(used);
+                        for pg in &pair_gates {
+                            clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), pg.clone()]));
+                        }
+                        let mut big: Vec<Expr> = pair_gates.iter().map(|pg| negate(pg.clone())).collect();
+                        big.push(g.clone());
+                        clauses.push(Expr::assoc(ASymbol::Or, big));
+                        g
+                    }
+                }
+            }
+            Expr::AssocBinop { symbol: ASymbol::Xor, exprs } => {
+                // Parity cascades left-to-right: `g1 = r0^r1`, `g2 = g1^r2`, ...
+                // Each gate is `g <-> (a <-> b)` with `g` negated, since
+                // `a xor b` is exactly `not (a <-> b)`.
+                let reps: Vec<Expr> = exprs.iter().map(|x| go(x, used, clauses)).collect();
+                let mut acc = reps[0].clone();
+                for r in &reps[1..] {
+                    let g = tseitin_fresh_symbol(used);
+                    iff_clauses(clauses, &negate(g.clone()), &acc, r);
+                    acc = g;
+                }
+                acc
+            }
+            atom => atom.clone(),
+        }
+    }
+
+    let mut used = freevars(e);
+    let mut clauses = Vec::new();
+    let top = go(e, &mut used, &mut clauses);
+    let top_name = match top {
+        Expr::Var { name } => name,
+        literal => {
+            let g = tseitin_fresh_symbol(&mut used);
+            clauses.push(Expr::assoc(ASymbol::Or, vec![negate(g.clone()), literal.clone()]));
+            clauses.push(Expr::assoc(ASymbol::Or, vec![g.clone(), negate(literal)]));
+            match g {
+                Expr::Var { name } => name,
+                _ => unreachable!(),
+            }
+        }
+    };
+    (clauses, top_name)
+}
+
+/// Returned by [`truth_table`] when `e` falls outside the propositional
+/// fragment it can evaluate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NonPropositional {
+    /// `e` contains an `Apply`, `Quantifier`, `Plus`, or `Mult` subterm,
+    /// which have no fixed Boolean meaning independent of a model.
+    UnsupportedSubterm(Expr),
+    /// `e` has more free variables than [`truth_table`] is willing to
+    /// enumerate rows for.
+    TooManyVariables { count: usize, limit: usize },
+}
+
+impl fmt::Display for NonPropositional {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NonPropositional::UnsupportedSubterm(e) => {
+                write!(f, "{} is not a propositional subterm (quantifiers, Apply, Plus, and Mult are not supported)", e)
+            }
+            NonPropositional::TooManyVariables { count, limit } => {
+                write!(f, "expression has {} free variables, which exceeds the truth table limit of {}", count, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NonPropositional {}
+
+/// The number of free variables [`truth_table`] will enumerate rows for
+/// before giving up with [`NonPropositional::TooManyVariables`]; above this,
+/// `2^n` rows stops being something a student (or a GUI) can usefully look
+/// at anyway.
+pub const MAX_TRUTH_TABLE_VARIABLES: usize = 20;
+
+/// One row of a [`TruthTable`]: an assignment of truth values to
+/// [`TruthTable::variables`] (same order, same length) and the resulting
+/// value of the expression under that assignment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruthTableRow {
+    pub assignment: Vec<bool>,
+    pub result: bool,
+}
+
+/// The full truth table of a propositional expression, as produced by
+/// [`truth_table`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TruthTable {
+    /// The expression's free variables, sorted, giving the column order
+    /// that indexes every row's `assignment`.
+    pub variables: Vec<String>,
+    /// One row per assignment of `variables`, in order of `true` before
+    /// `false` with `variables[0]` changing slowest, i.e. the usual
+    /// textbook row order.
+    pub rows: Vec<TruthTableRow>,
+}
+
+fn check_propositional(e: &Expr) -> Result<(), NonPropositional> {
+    match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => Ok(()),
+        Expr::Apply { .. } | Expr::Quantifier { .. } => Err(NonPropositional::UnsupportedSubterm(e.clone())),
+        Expr::Unop { operand, .. } => check_propositional(operand),
+        Expr::Binop { symbol: BSymbol::Implies | BSymbol::Nand | BSymbol::Nor, l, r } => {
+            check_propositional(l)?;
+            check_propositional(r)
+        }
+        Expr::Binop { symbol: BSymbol::Plus | BSymbol::Mult, .. } => Err(NonPropositional::UnsupportedSubterm(e.clone())),
+        Expr::AssocBinop { exprs, .. } => exprs.iter().try_for_each(check_propositional),
+    }
+}
+
+fn eval_propositional(e: &Expr, assignment: &HashMap<String, bool>) -> bool {
+    match e {
+        Expr::Contradiction => false,
+        Expr::Tautology => true,
+        Expr::Var { name } => assignment[name],
+        Expr::Unop { symbol: USymbol::Not, operand } => !eval_propositional(operand, assignment),
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => !eval_propositional(l, assignment) || eval_propositional(r, assignment),
+        Expr::Binop { symbol: BSymbol::Nand, l, r } => !(eval_propositional(l, assignment) && eval_propositional(r, assignment)),
+        Expr::Binop { symbol: BSymbol::Nor, l, r } => !(eval_propositional(l, assignment) || eval_propositional(r, assignment)),
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs.iter().all(|x| eval_propositional(x, assignment)),
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs.iter().any(|x| eval_propositional(x, assignment)),
+        // An n-ary Bicon/Equiv is true iff every operand shares the same truth value.
+        Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, exprs } => {
+            let vals: Vec<bool> = exprs.iter().map(|x| eval_propositional(x, assignment)).collect();
+            vals.windows(2).all(|w| w[0] == w[1])
+        }
+        // An n-ary Xor is true iff an odd number of operands are true (parity), matching `eval`.
+        Expr::AssocBinop { symbol: ASymbol::Xor, exprs } => exprs.iter().fold(false, |acc, x| acc ^ eval_propositional(x, assignment)),
+        _ => unreachable!("truth_table already rejected non-propositional subterms"),
+    }
+}
+
+/// Computes the truth table of `e` by evaluating it under every assignment
+/// of its free variables. `Tautology`/`Contradiction` are constants, `Not`,
+/// `And`, `Or`, and `Implies` get their usual semantics, an n-ary
+/// `Bicon`/`Equiv` is true iff all of its operands share the same truth
+/// value, and an n-ary `Xor` is true iff an odd number of its operands are
+/// true (see [`eval_propositional`]). Returns
+/// [`NonPropositional::UnsupportedSubterm`] if `e` contains an `Apply`,
+/// quantifier, `Plus`, or `Mult`, since those have no fixed Boolean meaning
+/// on their own, or [`NonPropositional::TooManyVariables`] if `e` has more
+/// than [`MAX_TRUTH_TABLE_VARIABLES`] free variables.
+pub fn truth_table(e: &Expr) -> Result<TruthTable, NonPropositional> {
+    check_propositional(e)?;
+    let mut variables: Vec<String> = freevars(e).into_iter().collect();
+    variables.sort();
+    if variables.len() > MAX_TRUTH_TABLE_VARIABLES {
+        return Err(NonPropositional::TooManyVariables { count: variables.len(), limit: MAX_TRUTH_TABLE_VARIABLES });
+    }
+    let n = variables.len();
+    let mut rows = Vec::with_capacity(1usize << n);
+    for i in 0..(1usize << n) {
+        let bits = (1usize << n) - 1 - i;
+        let assignment: Vec<bool> = (0..n).map(|j| (bits >> (n - 1 - j)) & 1 == 1).collect();
+        let lookup: HashMap<String, bool> = variables.iter().cloned().zip(assignment.iter().copied()).collect();
+        let result = eval_propositional(e, &lookup);
+        rows.push(TruthTableRow { assignment, result });
+    }
+    Ok(TruthTable { variables, rows })
+}
+
+/// Returned by [`eval`] when it cannot compute a truth value for `e` under
+/// the given assignment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    /// `e` refers to a variable the assignment doesn't cover.
+    MissingVariable(String),
+    /// `e` contains an `Apply`, `Quantifier`, `Plus`, or `Mult` subterm.
+    UnsupportedSubterm(Expr),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::MissingVariable(name) => write!(f, "assignment has no value for variable `{}`", name),
+            EvalError::UnsupportedSubterm(e) => {
+                write!(f, "{} is not a propositional subterm (quantifiers, Apply, Plus, and Mult are not supported)", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Evaluates `e` to a truth value under `assignment`, erroring with
+/// [`EvalError::MissingVariable`] if `e` mentions a variable `assignment`
+/// doesn't cover, or [`EvalError::UnsupportedSubterm`] if `e` contains an
+/// `Apply`, quantifier, `Plus`, or `Mult`. `Not`, `And`, `Or`, and `Implies`
+/// get their usual semantics. An n-ary `Bicon`/`Equiv` is `true` iff *all*
+/// of its operands share the same truth value — equivalently, each
+/// consecutive pair is equal, which by transitivity means every pair is.
+/// This is the same choice [`to_nnf`] and [`truth_table`] make, and it is a
+/// real choice: for three or more operands it disagrees with the other
+/// natural reading, repeated left-to-right binary `<->` (which computes
+/// parity — true iff an even number of operands are false — rather than
+/// "all equal"). An n-ary `Xor` is `true` iff an *odd* number of its
+/// operands are true: unlike `Bicon`/`Equiv`, parity is the only reading
+/// consistent with `Xor` being associative, so there is no analogous choice
+/// to make (see [`normalize_xor`]).
+pub fn eval(e: &Expr, assignment: &HashMap<String, bool>) -> Result<bool, EvalError> {
+    match e {
+        Expr::Contradiction => Ok(false),
+        Expr::Tautology => Ok(true),
+        Expr::Var { name } => assignment.get(name).copied().ok_or_else(|| EvalError::MissingVariable(name.clone())),
+        Expr::Unop { symbol: USymbol::Not, operand } => eval(operand, assignment).map(|b| !b),
+        Expr::Binop { symbol: BSymbol::Implies, l, r } => Ok(!eval(l, assignment)? || eval(r, assignment)?),
+        Expr::Binop { symbol: BSymbol::Nand, l, r } => Ok(!(eval(l, assignment)? && eval(r, assignment)?)),
+        Expr::Binop { symbol: BSymbol::Nor, l, r } => Ok(!(eval(l, assignment)? || eval(r, assignment)?)),
+        Expr::Binop { symbol: BSymbol::Plus | BSymbol::Mult, .. } => Err(EvalError::UnsupportedSubterm(e.clone())),
+        Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs.iter().try_fold(true, |acc, x| eval(x, assignment).map(|v| acc && v)),
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs.iter().try_fold(false, |acc, x| eval(x, assignment).map(|v| acc || v)),
+        Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, exprs } => {
+            let vals = exprs.iter().map(|x| eval(x, assignment)).collect::<Result<Vec<bool>, EvalError>>()?;
+            Ok(vals.windows(2).all(|w| w[0] == w[1]))
+        }
+        Expr::AssocBinop { symbol: ASymbol::Xor, exprs } => exprs.iter().try_fold(false, |acc, x| eval(x, assignment).map(|v| acc ^ v)),
+        Expr::Apply { .. } | Expr::Quantifier { .. } => Err(EvalError::UnsupportedSubterm(e.clone())),
+    }
+}
+
+/// A DPLL literal: a propositional variable together with the polarity it
+/// must take to satisfy the clause it occurs in.
+type SatLiteral = (String, bool);
+
+/// Reduces a CNF literal `Expr` (as produced by [`tseitin`]) to a
+/// [`SatLiteral`], or `None` if it's the constant `Tautology`/`Contradiction`
+/// (callers special-case those rather than threading a fake variable through
+/// the solver).
+fn sat_literal_of(e: &Expr) -> Option<SatLiteral> {
+    match e {
+        Expr::Var { name } => Some((name.clone(), true)),
+        Expr::Unop { symbol: USymbol::Not, operand } => match operand.as_ref() {
+            Expr::Var { name } => Some((name.clone(), false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Converts a clause `Expr` (an `Or` of literals, or a bare literal) into
+/// its [`SatLiteral`] form for the solver, or `None` if the clause contains
+/// a `Tautology` (or a negated `Contradiction`) and so is trivially
+/// satisfied and can be dropped entirely. A bare `Contradiction` literal
+/// (or negated `Tautology`) contributes nothing to the clause and is simply
+/// omitted, which is what correctly turns an all-`Contradiction` clause into
+/// the empty clause (the solver's signal for an immediate conflict).
+fn sat_clause_of(clause: &Expr) -> Option<Vec<SatLiteral>> {
+    let literals = match clause {
+        Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs.iter().collect::<Vec<_>>(),
+        other => vec![other],
+    };
+    let mut out = Vec::with_capacity(literals.len());
+    for lit in literals {
+        match lit {
+            Expr::Tautology => return None,
+            Expr::Contradiction => {}
+            Expr::Unop { symbol: USymbol::Not, operand } if operand.as_ref() == &Expr::Tautology => {}
+            Expr::Unop { symbol: USymbol::Not, operand } if operand.as_ref() == &Expr::Contradiction => return None,
+            other => out.push(sat_literal_of(other).expect("tseitin/CNF clauses only contain Var, Not(Var), Tautology, or Contradiction literals")),
+        }
+    }
+    Some(out)
+}
+
+/// Removes every clause satisfied by `name = polarity` and strips the
+/// (falsified) opposite literal from the rest.
+fn sat_assign(clauses: Vec<Vec<SatLiteral>>, name: &str, polarity: bool) -> Vec<Vec<SatLiteral>> {
+    clauses
+        .into_iter()
+        .filter(|clause| !clause.iter().any(|(n, p)| n == name && *p == polarity))
+        .map(|clause| clause.into_iter().filter(|(n, p)| !(n == name && *p != polarity)).collect())
+        .collect()
+}
+
+/// A variable that occurs with only one polarity across every remaining
+/// clause can be set to satisfy all of them at once, with no risk of
+/// backtracking.
+fn sat_find_pure_literal(clauses: &[Vec<SatLiteral>]) -> Option<SatLiteral> {
+    let mut polarity: HashMap<String, Option<bool>> = HashMap::new();
+    for clause in clauses {
+        for (name, p) in clause {
+            polarity
+                .entry(name.clone())
+                .and_modify(|seen| {
+                    if *seen != Some(*p) {
+                        *seen = None;
+                    }
+                })
+                .or_insert(Some(*p));
+        }
+    }
+    polarity.into_iter().find_map(|(name, p)| p.map(|polarity| (name, polarity)))
+}
+
+/// A textbook DPLL: unit propagation and pure-literal elimination to shrink
+/// the problem for free, then branch on an arbitrary remaining variable and
+/// recurse. No clause learning or variable-order heuristics (CDCL is
+/// overkill for the classroom-sized formulas this backs), but unit
+/// propagation alone already makes the common cases (long conjunctions,
+/// chains of implications) resolve without any branching at all.
+fn sat_dpll(mut clauses: Vec<Vec<SatLiteral>>, assignment: &mut HashMap<String, bool>) -> bool {
+    loop {
+        if clauses.iter().any(|c| c.is_empty()) {
+            return false;
+        }
+        if clauses.is_empty() {
+            return true;
+        }
+        if let Some((name, polarity)) = clauses.iter().find(|c| c.len() == 1).map(|c| c[0].clone()) {
+            assignment.insert(name.clone(), polarity);
+            clauses = sat_assign(clauses, &name, polarity);
+            continue;
+        }
+        if let Some((name, polarity)) = sat_find_pure_literal(&clauses) {
+            assignment.insert(name.clone(), polarity);
+            clauses = sat_assign(clauses, &name, polarity);
+            continue;
+        }
+        break;
+    }
+    let (name, _) = clauses[0][0].clone();
+    for &polarity in &[true, false] {
+        let mut branch_assignment = assignment.clone();
+        branch_assignment.insert(name.clone(), polarity);
+        if sat_dpll(sat_assign(clauses.clone(), &name, polarity), &mut branch_assignment) {
+            *assignment = branch_assignment;
+            return true;
+        }
+    }
+    false
+}
+
+/// Checks satisfiability of the propositional fragment of `e` with a DPLL
+/// solver over its [`tseitin`] encoding (rather than brute-force enumeration
+/// of `2^n` assignments), returning a satisfying assignment of `e`'s free
+/// variables if one exists. Variables that turn out not to matter (no
+/// remaining clause constrains them) are reported as `false`, an arbitrary
+/// but valid choice. This is the shared backend for [`is_tautology`],
+/// [`equivalent`], and [`find_countermodel`].
+pub fn satisfying_assignment(e: &Expr) -> Result<Option<HashMap<String, bool>>, NonPropositional> {
+    check_propositional(e)?;
+    let (mut clauses, top) = tseitin(e);
+    clauses.push(Expr::var(&top));
+    let sat_clauses: Vec<Vec<SatLiteral>> = clauses.iter().filter_map(sat_clause_of).collect();
+
+    let mut assignment = HashMap::new();
+    if !sat_dpll(sat_clauses, &mut assignment) {
+        return Ok(None);
+    }
+    let free = freevars(e);
+    Ok(Some(
+        free.into_iter()
+            .map(|name| {
+                let value = assignment.get(&name).copied().unwrap_or(false);
+                (name, value)
+            })
+            .collect(),
+    ))
+}
+
+/// `true` iff `e` is a tautology, i.e. every assignment satisfies it.
+/// Implemented as unsatisfiability of `e`'s negation, reusing
+/// [`satisfying_assignment`]'s DPLL backend.
+pub fn is_tautology(e: &Expr) -> Result<bool, NonPropositional> {
+    Ok(satisfying_assignment(&negate(e.clone()))?.is_none())
+}
+
+/// The result of checking [`equivalent`]: either the two expressions agree
+/// under every assignment, or they don't, in which case a concrete
+/// assignment under which they disagree is attached so the GUI can show it
+/// as a counterexample row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    NotEquivalent { countermodel: HashMap<String, bool> },
+}
+
+/// Checks whether `a` and `b` are logically equivalent, i.e. agree under
+/// every assignment of their combined free variables. Implemented as
+/// unsatisfiability of `a`'s and `b`'s negated biconditional, reusing
+/// [`satisfying_assignment`]'s DPLL backend; when that search succeeds, the
+/// witness it finds is exactly an assignment on which `a` and `b` disagree,
+/// so it's returned as-is rather than thrown away the way a bare
+/// `is_tautology` call would.
+pub fn equivalent(a: &Expr, b: &Expr) -> Result<Equivalence, NonPropositional> {
+    let biconditional = Expr::assoc(ASymbol::Bicon, vec![a.clone(), b.clone()]);
+    match satisfying_assignment(&negate(biconditional))? {
+        None => Ok(Equivalence::Equivalent),
+        Some(countermodel) => Ok(Equivalence::NotEquivalent { countermodel }),
+    }
+}
+
+/// Checks whether `premises` entail `conclusion`, returning a countermodel
+/// — an assignment making every premise true and the conclusion false — if
+/// they don't, or `None` if the entailment holds. Built by handing
+/// `premise₁ ∧ … ∧ premiseₙ ∧ ¬conclusion` to [`satisfying_assignment`]: that
+/// conjunction is satisfiable exactly when some assignment satisfies every
+/// premise while falsifying the conclusion, which is exactly an invalid
+/// inference.
+pub fn find_countermodel(premises: &[Expr], conclusion: &Expr) -> Result<Option<HashMap<String, bool>>, NonPropositional> {
+    let mut conjuncts: Vec<Expr> = premises.to_vec();
+    conjuncts.push(negate(conclusion.clone()));
+    let model = satisfying_assignment(&Expr::assoc(ASymbol::And, conjuncts))?;
+    if let Some(ref model) = model {
+        debug_assert!(premises.iter().all(|p| eval(p, model) == Ok(true)));
+        debug_assert_eq!(eval(conclusion, model), Ok(false));
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freevars() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", !Expr::var("x"));
+        assert_eq!(freevars(&e), std::collections::HashSet::new());
+
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]);
+        let fv = freevars(&e);
+        assert!(fv.contains("x") && fv.contains("y"));
+    }
+
+    #[test]
+    fn test_subst() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]);
+        let result = subst("x", &Expr::var("z"), e);
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![Expr::var("z"), Expr::var("y")]));
+    }
+
+    #[test]
+    fn test_subst_avoids_capture() {
+        // forall y, x  [x := y]  should not become forall y, y
+        let e = Expr::quantifier(QSymbol::Forall, "y", Expr::var("x"));
+        let result = subst("x", &Expr::var("y"), e);
+        match result {
+            Expr::Quantifier { name, body, .. } => {
+                assert_ne!(name, "y");
+                assert_eq!(*body, Expr::var("y"));
+            }
+            _ => panic!("expected a quantifier"),
+        }
+    }
+
+    #[test]
+    fn test_sorted_quantifier_desugars_the_sort_into_a_guard() {
+        assert_eq!(Expr::parse("forall n: Nat, p(n)"), Expr::parse("forall n, (Nat(n) -> p(n))"));
+        assert_eq!(Expr::parse("exists n: Nat, p(n)"), Expr::parse("exists n, (Nat(n) & p(n))"));
+    }
+
+    #[test]
+    fn test_sorted_quantifier_treats_the_sort_name_as_a_free_predicate_not_a_bound_variable() {
+        let e = Expr::parse("forall n: Nat, p(n)").unwrap();
+        assert_eq!(freevars(&e), std::collections::HashSet::from(["Nat".to_owned(), "p".to_owned()]));
+    }
+
+    #[test]
+    fn test_subst_on_a_sorted_binder_never_touches_the_bound_variable() {
+        // substituting for the sort name (an ordinary free predicate
+        // symbol) rewrites the guard but leaves the bound variable `n`,
+        // and its occurrences in the guard and body, untouched
+        let e = Expr::parse("forall n: Nat, p(n)").unwrap();
+        let result = subst("Nat", &Expr::var("Int"), e);
+        assert_eq!(result, Expr::parse("forall n, (Int(n) -> p(n))").unwrap());
+    }
+
+    #[test]
+    fn test_is_substitutable_rejects_capture_by_matching_binder() {
+        // forall y, P(x)  [x := y] would capture y under the forall
+        let e = Expr::quantifier(QSymbol::Forall, "y", apply1("p", "x"));
+        assert!(!is_substitutable(&e, "x", &Expr::var("y")));
+        assert_eq!(
+            check_substitutable(&e, "x", &Expr::var("y")),
+            Err(CaptureError { path: ExprPath(vec![0, 1]), binder: "y".to_owned() })
+        );
+    }
+
+    #[test]
+    fn test_is_substitutable_accepts_non_capturing_term() {
+        // forall y, P(x)  [x := z] is fine: z is not bound by the forall
+        let e = Expr::quantifier(QSymbol::Forall, "y", apply1("p", "x"));
+        assert!(is_substitutable(&e, "x", &Expr::var("z")));
+    }
+
+    #[test]
+    fn test_is_substitutable_ignores_binders_that_dont_enclose_the_occurrence() {
+        // (exists y, P(y)) & Q(x)  [x := y]: the only free `x` is outside the `exists y`
+        let e = Expr::assoc(ASymbol::And, vec![Expr::quantifier(QSymbol::Exists, "y", apply1("p", "y")), apply1("q", "x")]);
+        assert!(is_substitutable(&e, "x", &Expr::var("y")));
+    }
+
+    #[test]
+    fn test_is_substitutable_is_vacuously_true_when_shadowed_everywhere() {
+        // forall y, forall x, P(x): the only `x` is bound by the inner forall, so it's not free
+        let e = Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")));
+        assert!(is_substitutable(&e, "x", &Expr::var("y")));
+    }
+
+    #[test]
+    fn test_would_capture_collects_every_distinct_capturing_binder() {
+        // forall y, forall z, P(x)  [x := y + z]: both the `forall y` and `forall z` capture
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "y",
+            Expr::quantifier(QSymbol::Forall, "z", apply1("p", "x")),
+        );
+        let term = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("y")), r: Box::new(Expr::var("z")) };
+        let captured = would_capture(&e, "x", &term);
+        assert_eq!(captured, ["y".to_owned(), "z".to_owned()].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_would_capture_distinguishes_capturing_and_non_capturing_binders_with_same_name() {
+        // (forall y, P(x)) & (forall y, Q(w))  [x := y]: only the first `forall y` captures
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::quantifier(QSymbol::Forall, "y", apply1("p", "x")),
+                Expr::quantifier(QSymbol::Forall, "y", apply1("q", "w")),
+            ],
+        );
+        let captured = would_capture(&e, "x", &Expr::var("y"));
+        assert_eq!(captured, ["y".to_owned()].iter().cloned().collect());
+    }
+
+    #[test]
+    fn test_would_capture_is_empty_exactly_when_is_substitutable() {
+        let e = Expr::quantifier(QSymbol::Forall, "y", apply1("p", "x"));
+        assert!(would_capture(&e, "x", &Expr::var("z")).is_empty());
+        assert!(is_substitutable(&e, "x", &Expr::var("z")));
+        assert!(!would_capture(&e, "x", &Expr::var("y")).is_empty());
+        assert!(!is_substitutable(&e, "x", &Expr::var("y")));
+    }
+
+    #[test]
+    fn test_reduce_pattern_traced_records_ordered_steps() {
+        let idempotence = (
+            "idempotence".to_owned(),
+            Expr::assoc(ASymbol::Or, vec![Expr::var("__a"), Expr::var("__a")]),
+            Expr::var("__a"),
+        );
+        // (B | B) | (B | B) should collapse in three recorded steps: both
+        // inner `B | B`s first, then the resulting `B | B` at the root.
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("B"), Expr::var("B")]),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("B"), Expr::var("B")]),
+            ],
+        );
+        let (result, steps) = reduce_pattern_traced(e, &[idempotence]);
+        assert_eq!(result, Expr::var("B"));
+        assert_eq!(steps.len(), 3);
+        assert!(steps.iter().all(|s| s.label == "idempotence"));
+        assert_eq!(steps[2].matched, Expr::assoc(ASymbol::Or, vec![Expr::var("B"), Expr::var("B")]));
+        assert_eq!(steps[2].replacement, Expr::var("B"));
+    }
+
+    #[test]
+    fn test_transform_expr_with_scope_only_rewrites_free_occurrences() {
+        // x & (forall x, P(x) & Q(y))
+        let p_of_x = Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("x")] };
+        let q_of_y = Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("y")] };
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::var("x"),
+                Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![p_of_x, q_of_y])),
+            ],
+        );
+
+        let scoped = transform_expr_with_scope(e.clone(), &|e, scope| match &e {
+            Expr::Var { name } if name == "x" && !scope.contains("x") => (Expr::var("x2"), true),
+            _ => (e, false),
+        });
+        // only the free `x` at the top is renamed; P(x)'s bound `x` is untouched
+        assert!(freevars(&scoped).contains("x2"));
+        assert!(!freevars(&scoped).contains("x"));
+        let inner_untouched = matches!(
+            &scoped,
+            Expr::AssocBinop { exprs, .. } if matches!(&exprs[1], Expr::Quantifier { name, .. } if name == "x")
+        );
+        assert!(inner_untouched);
+
+        // plain transform_expr has no notion of scope and rewrites every
+        // occurrence of the name, including the one bound by `forall x`.
+        let naive = transform_expr(e, &|e| match &e {
+            Expr::Var { name } if name == "x" => (Expr::var("x2"), true),
+            _ => (e, false),
+        });
+        assert!(!freevars(&naive).contains("x"));
+        assert_ne!(naive, scoped);
+    }
+
+    #[test]
+    fn test_transform_expr_bounded_reports_limit_instead_of_hanging() {
+        // A commutativity pattern like `A | B ==> B | A` oscillates forever
+        // under naive fixpoint iteration: it keeps "firing" by swapping the
+        // operands back and forth.
+        let patterns = vec![(
+            Expr::assoc(ASymbol::Or, vec![Expr::var("__a"), Expr::var("__b")]),
+            Expr::assoc(ASymbol::Or, vec![Expr::var("__b"), Expr::var("__a")]),
+        )];
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")]);
+        let result = reduce_pattern_bounded(e, &patterns, 50);
+        assert!(matches!(result, Err(TransformLimitExceeded { .. })));
+    }
+
+    #[test]
+    fn test_transform_expr_result_propagates_error_from_assoc_binop_child() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("bad")]);
+        let result: Result<Expr, String> = transform_expr_result(e, &|e| match &e {
+            Expr::Var { name } if name == "bad" => Err("hit bad".to_owned()),
+            _ => Ok((e, false)),
+        });
+        assert_eq!(result, Err("hit bad".to_owned()));
+    }
+
+    #[test]
+    fn test_transform_expr_result_propagates_error_from_quantifier_body() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::var("bad"));
+        let result: Result<Expr, String> = transform_expr_result(e, &|e| match &e {
+            Expr::Var { name } if name == "bad" => Err("hit bad".to_owned()),
+            _ => Ok((e, false)),
+        });
+        assert_eq!(result, Err("hit bad".to_owned()));
+    }
+
+    #[test]
+    fn test_negate_is_its_own_inverse_for_non_negated_input() {
+        let e = Expr::var("p");
+        assert_eq!(negate(negate(e.clone())), e);
+        assert_eq!(negate(Expr::Contradiction), Expr::Tautology);
+        assert_eq!(negate(Expr::Tautology), Expr::Contradiction);
+        assert_eq!(negate(!Expr::var("p")), Expr::var("p"));
+    }
+
+    #[test]
+    fn test_negate_deep_applies_demorgan_in_one_call() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(
+            negate_deep(e),
+            Expr::assoc(ASymbol::Or, vec![!Expr::var("a"), !Expr::var("b")])
+        );
+        let q = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        assert_eq!(negate_deep(q), Expr::quantifier(QSymbol::Exists, "x", !Expr::var("x")));
+    }
+
+    #[test]
+    fn test_subexprs_preorder() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), !Expr::var("y")]);
+        let seen: Vec<&Expr> = e.subexprs().collect();
+        assert_eq!(seen[0], &e);
+        assert!(seen.contains(&&Expr::var("x")));
+        assert!(seen.contains(&&Expr::var("y")));
+    }
+
+    #[test]
+    fn test_into_subexprs() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        let owned: Vec<Expr> = e.clone().into_subexprs().collect();
+        assert_eq!(owned.len(), 2);
+        assert_eq!(owned[0], e);
+    }
+
+    #[test]
+    fn test_contains_contradiction_and_count_quantifiers() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(ASymbol::Or, vec![Expr::Contradiction, Expr::quantifier(QSymbol::Exists, "y", Expr::var("y"))]),
+        );
+        assert!(contains_contradiction(&e));
+        assert_eq!(count_quantifiers(&e), 2);
+        assert!(!contains_contradiction(&Expr::var("x")));
+    }
+
+    #[test]
+    fn test_expr_size_and_depth() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]),
+        );
+        // forall(1) -> and-node(1) -> {x, y} = 4 nodes, 3 deep.
+        assert_eq!(expr_size(&e), 4);
+        assert_eq!(expr_depth(&e), 3);
+        assert_eq!(expr_size(&Expr::var("x")), 1);
+        assert_eq!(expr_depth(&Expr::var("x")), 1);
+    }
+
+    #[test]
+    fn test_count_connectives_flattened_assoc_binop_agrees_with_nested_chain() {
+        let p = Expr::var("p");
+        let q = Expr::var("q");
+        let r = Expr::var("r");
+        let flat = Expr::assoc(ASymbol::And, vec![p.clone(), q.clone(), r.clone()]);
+        let nested = Expr::assoc(ASymbol::And, vec![p, Expr::assoc(ASymbol::And, vec![q, r])]);
+        let flat_counts = count_connectives(&flat);
+        let nested_counts = count_connectives(&nested);
+        assert_eq!(flat_counts.and, 2);
+        assert_eq!(flat_counts.and, nested_counts.and);
+        assert_eq!(flat_counts.vars, 3);
+    }
+
+    #[test]
+    fn test_count_connectives_covers_every_symbol_kind() {
+        let e = Expr::quantifier(
+            QSymbol::Exists,
+            "x",
+            !Expr::implies(Expr::var("x"), Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("x")] }),
+        );
+        let counts = count_connectives(&e);
+        assert_eq!(counts.exists, 1);
+        assert_eq!(counts.not, 1);
+        assert_eq!(counts.implies, 1);
+        assert_eq!(counts.applies, 1);
+        assert_eq!(counts.vars, 3); // the implies's `x`, the apply's `f` head, and the apply's `x` argument
+    }
+
+    #[test]
+    fn test_boundvars() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "y", Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")])),
+        );
+        let bv = boundvars(&e);
+        assert!(bv.contains("x") && bv.contains("y"));
+        assert_eq!(bv.len(), 2);
+    }
+
+    #[test]
+    fn test_boundvars_shadowing() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "x", Expr::var("x")),
+        );
+        assert_eq!(boundvars(&e), vec!["x".to_owned()].into_iter().collect());
+    }
+
+    #[test]
+    fn test_freevars_and_boundvars_can_overlap() {
+        // x & forall x, x
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("x"), Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"))],
+        );
+        assert!(freevars(&e).contains("x"));
+        assert!(boundvars(&e).contains("x"));
+    }
+
+    #[test]
+    fn test_contains_free_respects_shadowing() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        assert!(!contains_free(&e, "x"));
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"))]);
+        assert!(contains_free(&e, "x"));
+    }
+
+    #[test]
+    fn test_free_occurrence_paths_excludes_shadowed_and_orders_results() {
+        // forall x, (x & (a(x) & b))   -- all `x`s here are bound, no results
+        let bound_example = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(
+                ASymbol::And,
+                vec![Expr::var("x"), Expr::Apply { func: Box::new(Expr::var("a")), args: vec![Expr::var("x")] }],
+            ),
+        );
+        assert_eq!(free_occurrence_paths(&bound_example, "x"), vec![]);
+
+        // x & (x & x) -- three free occurrences, reported left to right
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("x")])]);
+        let paths = free_occurrence_paths(&e, "x");
+        assert_eq!(paths, vec![ExprPath(vec![0]), ExprPath(vec![1, 0]), ExprPath(vec![1, 1])]);
+        for p in &paths {
+            assert_eq!(get_at(&e, p), Some(&Expr::var("x")));
+        }
+    }
+
+    #[test]
+    fn test_match_context_reports_the_positions_of_the_bound_variable() {
+        // body: P(x) & Q(x)   instance: P(t) & Q(t)
+        let body = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("x")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("x")] },
+            ],
+        );
+        let instance = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("t")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("t")] },
+            ],
+        );
+        let result = match_context("phi", "x", &body, &instance, &Expr::var("t"));
+        assert_eq!(result, MatchContextResult::Matched { positions: free_occurrence_paths(&body, "x") });
+        match result {
+            MatchContextResult::Matched { positions } => assert_eq!(positions.len(), 2),
+            MatchContextResult::NoMatch => panic!("expected a match"),
+        }
+    }
+
+    #[test]
+    fn test_match_context_is_not_fooled_by_the_term_already_occurring_in_the_body() {
+        // The classic pitfall: `t` already appears in `body` independently
+        // of `x`, so naively scanning `instance` for occurrences of `t` and
+        // calling those "the substituted positions" would wrongly include
+        // the pre-existing one. body: P(x) & Q(t)   instance: P(t) & Q(t)
+        let body = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("x")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("t")] },
+            ],
+        );
+        let instance = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("t")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("t")] },
+            ],
+        );
+        let result = match_context("phi", "x", &body, &instance, &Expr::var("t"));
+        // Only the one true `x` position is reported, not the `Q(t)` site.
+        assert_eq!(result, MatchContextResult::Matched { positions: vec![ExprPath(vec![0, 1])] });
+    }
+
+    #[test]
+    fn test_match_context_succeeds_with_zero_occurrences_when_instance_is_unchanged() {
+        // body: P(y) has no free `x` at all, so substituting `t` for `x` is
+        // a no-op: the only valid instance is `body` itself, and there are
+        // no positions to report.
+        let body = Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("y")] };
+        let result = match_context("phi", "x", &body, &body, &Expr::var("t"));
+        assert_eq!(result, MatchContextResult::Matched { positions: vec![] });
+    }
+
+    #[test]
+    fn test_match_context_rejects_zero_occurrences_when_instance_differs_anyway() {
+        let body = Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("y")] };
+        let instance = Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("t")] };
+        assert_eq!(match_context("phi", "x", &body, &instance, &Expr::var("t")), MatchContextResult::NoMatch);
+    }
+
+    #[test]
+    fn test_match_context_rejects_a_partial_substitution() {
+        // body: P(x) & Q(x)   instance: P(t) & Q(x) -- only one occurrence
+        // was substituted, so this is not "body with t for every free x".
+        let body = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("x")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("x")] },
+            ],
+        );
+        let instance = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::Apply { func: Box::new(Expr::var("P")), args: vec![Expr::var("t")] },
+                Expr::Apply { func: Box::new(Expr::var("Q")), args: vec![Expr::var("x")] },
+            ],
+        );
+        assert_eq!(match_context("phi", "x", &body, &instance, &Expr::var("t")), MatchContextResult::NoMatch);
+    }
+
+    #[test]
+    fn test_zipper_drill_and_replace() {
+        // (a & (b | c)) -> d
+        let e = Expr::implies(
+            Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("c")])]),
+            Expr::var("d"),
+        );
+        let mut z = ExprZipper::new(e).down(0).unwrap().down(1).unwrap().down(1).unwrap();
+        assert_eq!(z.focus(), &Expr::var("c"));
+        z.replace(Expr::var("z"));
+        let rebuilt = z.rebuild();
+        let expected = Expr::implies(
+            Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("z")])]),
+            Expr::var("d"),
+        );
+        assert_eq!(rebuilt, expected);
+    }
+
+    #[test]
+    fn test_zipper_siblings() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let z = ExprZipper::new(e).down(1).unwrap();
+        assert_eq!(z.focus(), &Expr::var("b"));
+        let z = z.right().unwrap();
+        assert_eq!(z.focus(), &Expr::var("c"));
+        assert!(z.clone().right().is_err());
+        let z = z.left().unwrap().left().unwrap();
+        assert_eq!(z.focus(), &Expr::var("a"));
+        assert!(z.left().is_err());
+    }
+
+    #[test]
+    fn test_zipper_error_cases() {
+        let e = Expr::var("a");
+        let z = ExprZipper::new(e);
+        assert_eq!(z.clone().up().unwrap_err(), ZipperError::AtRoot);
+        assert_eq!(z.down(0).unwrap_err(), ZipperError::NoSuchChild);
+    }
+
+    #[test]
+    fn test_subst_at_inside_assoc_binop() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let path = ExprPath(vec![1]);
+        let result = subst_at(&e, &path, Expr::var("z")).unwrap();
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("z"), Expr::var("c")]));
+        assert_eq!(get_at(&result, &path), Some(&Expr::var("z")));
+    }
+
+    #[test]
+    fn test_subst_at_inside_quantifier_body() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]));
+        let path = ExprPath(vec![0, 1]);
+        let result = subst_at(&e, &path, Expr::var("z")).unwrap();
+        assert_eq!(get_at(&result, &path), Some(&Expr::var("z")));
+        match result {
+            Expr::Quantifier { body, .. } => {
+                assert_eq!(*body, Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("z")]))
+            }
+            _ => panic!("expected a quantifier"),
+        }
+    }
+
+    #[test]
+    fn test_subst_at_errors_on_bad_paths() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(
+            subst_at(&e, &ExprPath(vec![5]), Expr::var("z")),
+            Err(PathError::OutOfRange { path: ExprPath(vec![5]), at: 0, len: 2 })
+        );
+        assert_eq!(
+            subst_at(&e, &ExprPath(vec![0, 0]), Expr::var("z")),
+            Err(PathError::NotIndexable { path: ExprPath(vec![0, 0]), at: 1 })
+        );
+        assert_eq!(get_at(&e, &ExprPath(vec![9])), None);
+    }
+
+    #[test]
+    fn test_check_well_formed_accepts_valid_fol_sentence() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::implies(
+                Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x")] },
+                Expr::assoc(
+                    ASymbol::And,
+                    vec![
+                        Expr::Apply { func: Box::new(Expr::var("q")), args: vec![Expr::var("x")] },
+                        !Expr::Apply { func: Box::new(Expr::var("r")), args: vec![Expr::var("x")] },
+                    ],
+                ),
+            ),
+        );
+        assert_eq!(check_well_formed(&e), Ok(()));
+    }
+
+    #[test]
+    fn test_check_well_formed_rejects_connective_applied_as_predicate() {
+        // (A & B)(x): a connective used as an Apply head.
+        let e = Expr::Apply {
+            func: Box::new(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")])),
+            args: vec![Expr::var("x")],
+        };
+        let err = check_well_formed(&e).unwrap_err();
+        assert_eq!(err.path, ExprPath(vec![0]));
+    }
+
+    #[test]
+    fn test_check_well_formed_rejects_formula_inside_arithmetic_term() {
+        // forall x, p(x + (y -> z)): a formula used as a Plus operand.
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::Apply {
+                func: Box::new(Expr::var("p")),
+                args: vec![Expr::Binop {
+                    symbol: BSymbol::Plus,
+                    l: Box::new(Expr::var("x")),
+                    r: Box::new(Expr::implies(Expr::var("y"), Expr::var("z"))),
+                }],
+            },
+        );
+        let err = check_well_formed(&e).unwrap_err();
+        assert_eq!(err.path, ExprPath(vec![0, 1, 1]));
+    }
+
+    #[test]
+    fn test_signature_of_reports_conflicting_arities() {
+        let f = |args: Vec<Expr>| Expr::Apply { func: Box::new(Expr::var("f")), args };
+        let e = Expr::assoc(ASymbol::And, vec![f(vec![Expr::var("x")]), f(vec![Expr::var("x"), Expr::var("y")])]);
+        assert_eq!(signature_of(&e), Err(ArityConflict::ArityMismatch { name: "f".to_owned(), first: 1, second: 2 }));
+    }
+
+    #[test]
+    fn test_signature_of_clean_input_produces_full_map() {
+        let f = |args: Vec<Expr>| Expr::Apply { func: Box::new(Expr::var("f")), args };
+        let g = |args: Vec<Expr>| Expr::Apply { func: Box::new(Expr::var("g")), args };
+        let e = Expr::assoc(ASymbol::And, vec![f(vec![Expr::var("x")]), g(vec![Expr::var("x"), Expr::var("y")])]);
+        let sigs = signature_of(&e).unwrap();
+        assert_eq!(sigs.get("f"), Some(&1));
+        assert_eq!(sigs.get("g"), Some(&2));
+        assert_eq!(sigs.len(), 2);
+    }
+
+    #[test]
+    fn test_signature_of_reports_bare_and_applied_use() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("f"), Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("x")] }],
+        );
+        assert_eq!(signature_of(&e), Err(ArityConflict::AppliedAndBare { name: "f".to_owned(), arity: 1 }));
+        assert!(signature_of_allowing_bare_use(&e).is_ok());
+    }
+
+    #[test]
+    fn test_subst_map_swap() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]);
+        let mut map = HashMap::new();
+        map.insert("x".to_owned(), Expr::var("y"));
+        map.insert("y".to_owned(), Expr::var("x"));
+        let result = subst_map(&e, &map);
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![Expr::var("y"), Expr::var("x")]));
+    }
+
+    #[test]
+    fn test_subst_map_avoids_capture() {
+        let e = Expr::quantifier(QSymbol::Forall, "y", Expr::var("x"));
+        let mut map = HashMap::new();
+        map.insert("x".to_owned(), Expr::var("y"));
+        let result = subst_map(&e, &map);
+        match result {
+            Expr::Quantifier { name, body, .. } => {
+                assert_ne!(name, "y");
+                assert_eq!(*body, Expr::var("y"));
+            }
+            _ => panic!("expected a quantifier"),
+        }
+    }
+
+    #[test]
+    fn test_unify() {
+        let l = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        let r = Expr::quantifier(QSymbol::Forall, "y", Expr::var("y"));
+        assert!(alpha_equal(&l, &r));
+        let subst_l = unify(&l, &r);
+        let subst_r = unify(&r, &l);
+        assert!(subst_l.is_some());
+        assert!(subst_r.is_some());
+    }
+
+    #[test]
+    fn test_unify_produces_bindings_in_the_same_order_across_repeated_runs() {
+        // `unify`'s worklist is a `Vec`, processed in a fixed order, so
+        // repeated runs on the same inputs always discover bindings in the
+        // same order — `sorted_bindings` then gives a single canonical
+        // ordering on top of that for anything (like this assertion) that
+        // needs to compare a `Substitution`'s contents exactly.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::assoc(ASymbol::Or, vec![Expr::var("__psi"), Expr::var("__chi")])]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::assoc(ASymbol::Or, vec![Expr::var("q"), Expr::var("r")])]);
+        let expected = vec![("__chi".to_owned(), Expr::var("r")), ("__phi".to_owned(), Expr::var("p")), ("__psi".to_owned(), Expr::var("q"))];
+        for _ in 0..20 {
+            let subs = unify(&pattern, &subject).unwrap();
+            assert_eq!(sorted_bindings(&subs), expected);
+        }
+    }
+
+    #[test]
+    fn test_compose_substitutions_applies_second_to_the_range_of_first() {
+        let first: Substitution = vec![("__a".to_owned(), Expr::var("__b"))].into_iter().collect();
+        let second: Substitution = vec![("__b".to_owned(), Expr::Tautology)].into_iter().collect();
+        let composed = compose_substitutions(first, second);
+        assert_eq!(composed.get("__a"), Some(&Expr::Tautology));
+    }
+
+    #[test]
+    fn test_compose_substitutions_keeps_bindings_not_shadowed_by_first() {
+        let first: Substitution = vec![("__a".to_owned(), Expr::var("p"))].into_iter().collect();
+        let second: Substitution = vec![("__a".to_owned(), Expr::Contradiction), ("__b".to_owned(), Expr::Tautology)].into_iter().collect();
+        let composed = compose_substitutions(first, second);
+        // `first` already rebinds `__a`, so `second`'s binding for `__a` is
+        // shadowed (applying `first` then `second` never revisits a name
+        // `first` already substituted away).
+        assert_eq!(composed.get("__a"), Some(&Expr::var("p")));
+        assert_eq!(composed.get("__b"), Some(&Expr::Tautology));
+    }
+
+    #[test]
+    fn test_more_general_holds_for_the_empty_substitution_against_anything() {
+        let vars: std::collections::HashSet<String> = ["__x", "__y"].iter().map(|s| s.to_string()).collect();
+        let empty = Substitution::new();
+        let bound: Substitution = vec![("__x".to_owned(), Expr::var("p")), ("__y".to_owned(), Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("p")] })]
+            .into_iter()
+            .collect();
+        assert!(more_general(&empty, &bound, &vars));
+        assert!(!more_general(&bound, &empty, &vars));
+    }
+
+    #[test]
+    fn test_more_general_is_false_in_both_directions_for_incomparable_substitutions() {
+        let vars: std::collections::HashSet<String> = ["__x"].iter().map(|s| s.to_string()).collect();
+        let a: Substitution = vec![("__x".to_owned(), Expr::var("p"))].into_iter().collect();
+        let b: Substitution = vec![("__x".to_owned(), Expr::var("q"))].into_iter().collect();
+        assert!(!more_general(&a, &b, &vars));
+        assert!(!more_general(&b, &a, &vars));
+    }
+
+    #[test]
+    fn test_more_general_holds_when_a_nontrivial_sigma_is_required() {
+        let vars: std::collections::HashSet<String> = ["__x"].iter().map(|s| s.to_string()).collect();
+        // `a` binds `__x` to `f(__y)`, a term with a further metavariable;
+        // `b` binds `__x` to the concrete `f(p)`. `sigma = {__y -> p}`
+        // specializes `a` into `b`, so `a` is more general than `b`, but not
+        // the reverse since `b`'s range has nothing left for `sigma` to bind.
+        let a: Substitution = vec![("__x".to_owned(), Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("__y")] })].into_iter().collect();
+        let b: Substitution = vec![("__x".to_owned(), Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("p")] })].into_iter().collect();
+        assert!(more_general(&a, &b, &vars));
+        assert!(!more_general(&b, &a, &vars));
+    }
+
+    #[test]
+    fn test_compose_substitutions_satisfies_the_composition_law_on_random_expressions() {
+        let vars = ["__a", "__b", "__c", "x", "y"];
+        for seed in 0..30 {
+            let mut rng = testutil::Rng::new(0xC0FFEE ^ seed);
+            let e = testutil::arbitrary_expr(&mut rng, 3, &vars, true, false);
+            let first: Substitution = vec![("__a".to_owned(), Expr::var("x")), ("__b".to_owned(), Expr::var("__c"))].into_iter().collect();
+            let second: Substitution = vec![("__c".to_owned(), Expr::Tautology), ("__a".to_owned(), Expr::Contradiction)].into_iter().collect();
+            let composed = compose_substitutions(first.clone(), second.clone());
+            let via_compose = subst_map(&e, &composed);
+            let via_two_passes = subst_map(&subst_map(&e, &first), &second);
+            // Capture-avoidance may pick different fresh binder names on
+            // the two paths, so compare up to alpha-equivalence rather than
+            // requiring bit-for-bit equality.
+            assert!(
+                alpha_equal(&via_compose, &via_two_passes),
+                "compose(a,b).apply(e) should be alpha-equal to b.apply(&a.apply(e)) for {}: {} vs {}",
+                e,
+                via_compose,
+                via_two_passes
+            );
+        }
+    }
+
+    fn plus(l: Expr, r: Expr) -> Expr {
+        Expr::Binop { symbol: BSymbol::Plus, l: Box::new(l), r: Box::new(r) }
+    }
+
+    #[test]
+    fn test_unify_commutative_binop_matches_the_swapped_orientation() {
+        // `x + T` vs `y + F`: positionally `T` would have to unify with `F`,
+        // which fails; the swapped pairing (`x` with `F`, `T` with `y`) succeeds.
+        let a = plus(Expr::var("__x"), Expr::Tautology);
+        let b = plus(Expr::var("__y"), Expr::Contradiction);
+        let subs = unify(&a, &b).expect("should unify by trying the swapped orientation");
+        assert_eq!(subs.get("__x"), Some(&Expr::Contradiction));
+        assert_eq!(subs.get("__y"), Some(&Expr::Tautology));
+    }
+
+    #[test]
+    fn test_unify_commutative_binop_backtracks_when_only_the_swapped_orientation_works_downstream() {
+        // `__x + p` unify `p + q`: the same-order pairing binds `__x` to
+        // `p` but then needs `p` to unify with `q`, which fails since
+        // neither is a metavariable. Only the swapped pairing (`__x =?= q`,
+        // `p =?= p`) actually succeeds.
+        let a = plus(Expr::var("__x"), Expr::var("p"));
+        let b = plus(Expr::var("p"), Expr::var("q"));
+        let subs = unify(&a, &b).expect("should find the swapped pairing via backtracking");
+        assert_eq!(subs.get("__x"), Some(&Expr::var("q")));
+        assert_eq!(subs.len(), 1);
+    }
+
+    #[test]
+    fn test_unify_with_grouping_binds_a_trailing_pattern_variable_to_the_remainder() {
+        // `phi & psi` against `A & B & C` -- `phi` takes the first operand
+        // positionally, and `psi`, being last, absorbs everything left over.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("A"), Expr::var("B"), Expr::var("C")]);
+        let metavars: std::collections::HashSet<String> = vec!["__phi".to_owned(), "__psi".to_owned()].into_iter().collect();
+        let subs = unify_with_grouping(&pattern, &subject, &metavars).expect("should unify by grouping the remainder");
+        assert_eq!(subs.get("__phi"), Some(&Expr::var("A")));
+        assert_eq!(subs.get("__psi"), Some(&Expr::assoc(ASymbol::And, vec![Expr::var("B"), Expr::var("C")])));
+    }
+
+    #[test]
+    fn test_unify_with_grouping_still_requires_ordinary_unification_of_the_equal_length_case() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("A"), Expr::var("B")]);
+        let metavars: std::collections::HashSet<String> = vec!["__phi".to_owned(), "__psi".to_owned()].into_iter().collect();
+        let subs = unify_with_grouping(&pattern, &subject, &metavars).unwrap();
+        assert_eq!(subs.get("__phi"), Some(&Expr::var("A")));
+        assert_eq!(subs.get("__psi"), Some(&Expr::var("B")));
+    }
+
+    #[test]
+    fn test_unify_with_grouping_does_not_fire_without_a_bindable_operand_to_absorb_the_remainder() {
+        // Neither side's last operand is bindable, so a length mismatch is
+        // still just a failure, the same as plain `unify`.
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]);
+        let metavars = std::collections::HashSet::new();
+        assert_eq!(unify_with_grouping(&a, &b, &metavars), None);
+    }
+
+    #[test]
+    fn test_unify_with_grouping_still_fails_on_a_mismatched_non_variable_operand() {
+        // `B & phi` against `A & C & D`: the first (non-variable) operand
+        // `B` has to line up with `A` positionally and doesn't, so grouping
+        // `phi` can't rescue this.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("B"), Expr::var("__phi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("A"), Expr::var("C"), Expr::var("D")]);
+        let metavars: std::collections::HashSet<String> = vec!["__phi".to_owned()].into_iter().collect();
+        assert_eq!(unify_with_grouping(&pattern, &subject, &metavars), None);
+    }
+
+    #[test]
+    fn test_unify_without_grouping_rejects_the_same_length_mismatch_unify_with_grouping_accepts() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("A"), Expr::var("B"), Expr::var("C")]);
+        let metavars: std::collections::HashSet<String> = vec!["__phi".to_owned(), "__psi".to_owned()].into_iter().collect();
+        assert_eq!(unify_with_metavars(&pattern, &subject, &metavars), None);
+    }
+
+    #[test]
+    fn test_unify_non_commutative_binop_is_unaffected() {
+        let a = Expr::Binop { symbol: BSymbol::Implies, l: Box::new(Expr::var("__x")), r: Box::new(Expr::Tautology) };
+        let b = Expr::Binop { symbol: BSymbol::Implies, l: Box::new(Expr::Tautology), r: Box::new(Expr::var("__y")) };
+        // Positionally, __x would bind to Tautology (fine) but Tautology
+        // would have to unify with __y (fine too) -- so this one actually
+        // does unify positionally. Swap the operands so only a commutative
+        // algorithm bug would make it spuriously succeed on a mismatch.
+        assert!(unify(&a, &b).is_some());
+        let c = Expr::Binop { symbol: BSymbol::Implies, l: Box::new(Expr::var("p")), r: Box::new(Expr::Tautology) };
+        let d = Expr::Binop { symbol: BSymbol::Implies, l: Box::new(Expr::Tautology), r: Box::new(Expr::var("p")) };
+        // `p` is not a metavariable, so positionally `Var{p}` must equal
+        // `Tautology` on the left, which fails; `Implies` must not try the
+        // swapped orientation to rescue it.
+        assert!(unify(&c, &d).is_none());
+    }
+
+    #[test]
+    fn test_unify_with_metavars_only_binds_the_named_variables() {
+        let metavars: std::collections::HashSet<String> = vec!["phi".to_owned(), "psi".to_owned()].into_iter().collect();
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("phi"), Expr::var("psi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("q")]);
+        let subs = unify_with_metavars(&pattern, &subject, &metavars).expect("phi and psi should bind freely");
+        assert_eq!(subs.get("phi"), Some(&Expr::var("p")));
+        assert_eq!(subs.get("psi"), Some(&!Expr::var("q")));
+    }
+
+    #[test]
+    fn test_unify_with_metavars_treats_a_subject_variable_literally_named_phi_as_a_constant() {
+        // The subject's own `phi` is an ordinary object-level variable, not
+        // the rule schema's placeholder; since `phi` isn't in `metavars`
+        // here, unification must require it to match another `phi`
+        // literally rather than binding it away.
+        let metavars: std::collections::HashSet<String> = vec!["psi".to_owned()].into_iter().collect();
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("phi"), Expr::var("psi")]);
+        let matching_subject = Expr::assoc(ASymbol::And, vec![Expr::var("phi"), Expr::var("q")]);
+        let subs = unify_with_metavars(&pattern, &matching_subject, &metavars).expect("literal phi should unify with literal phi");
+        assert!(!subs.contains_key("phi"), "phi is not a metavariable, so it must not appear in the substitution");
+        assert_eq!(subs.get("psi"), Some(&Expr::var("q")));
+
+        let mismatching_subject = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("q")]);
+        assert!(
+            unify_with_metavars(&pattern, &mismatching_subject, &metavars).is_none(),
+            "phi is rigid, so it cannot be unified with an unrelated variable x"
+        );
+    }
+
+    #[test]
+    fn test_unify_with_metavars_agrees_with_unify_when_metavars_is_exactly_the_dunder_prefixed_names() {
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("p")]);
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("q"), Expr::var("p")]);
+        let metavars: std::collections::HashSet<String> = vec!["__a".to_owned()].into_iter().collect();
+        assert_eq!(unify(&a, &b), unify_with_metavars(&a, &b, &metavars));
+    }
+
+    #[test]
+    fn test_anti_unify_reuses_the_same_fresh_variable_for_the_same_mismatching_pair() {
+        // `p -> p` and `q -> q`: the generalization should be `__g -> __g`
+        // with a *single* fresh variable, not two unrelated ones, since both
+        // mismatching positions are the same pair (p, q).
+        let a = Expr::implies(Expr::var("p"), Expr::var("p"));
+        let b = Expr::implies(Expr::var("q"), Expr::var("q"));
+        let (generalization, subst_a, subst_b) = anti_unify(&a, &b);
+        match &generalization {
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => assert_eq!(l, r, "both operands should be the same fresh variable"),
+            other => panic!("expected an Implies, got {:?}", other),
+        }
+        assert_eq!(subst_map(&generalization, &subst_a), a);
+        assert_eq!(subst_map(&generalization, &subst_b), b);
+    }
+
+    #[test]
+    fn test_anti_unify_keeps_shared_structure_and_generalizes_each_mismatch_independently() {
+        let a = Expr::implies(Expr::var("p"), Expr::var("q"));
+        let b = Expr::implies(Expr::var("r"), Expr::var("s"));
+        let (generalization, subst_a, subst_b) = anti_unify(&a, &b);
+        match &generalization {
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => assert_ne!(l, r, "the two mismatches are unrelated and should get distinct variables"),
+            other => panic!("expected an Implies, got {:?}", other),
+        }
+        assert_eq!(subst_map(&generalization, &subst_a), a);
+        assert_eq!(subst_map(&generalization, &subst_b), b);
+    }
+
+    #[test]
+    fn test_anti_unify_of_completely_different_shapes_generalizes_to_a_single_variable() {
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        let b = Expr::var("r");
+        let (generalization, subst_a, subst_b) = anti_unify(&a, &b);
+        assert!(matches!(generalization, Expr::Var { .. }));
+        assert_eq!(subst_map(&generalization, &subst_a), a);
+        assert_eq!(subst_map(&generalization, &subst_b), b);
+    }
+
+    #[test]
+    fn test_anti_unify_of_identical_expressions_introduces_no_fresh_variables() {
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        let (generalization, subst_a, subst_b) = anti_unify(&a, &a);
+        assert_eq!(generalization, a);
+        assert!(subst_a.is_empty());
+        assert!(subst_b.is_empty());
+    }
+
+    #[test]
+    fn test_unify_explained_reports_symbol_clash() {
+        let a = Expr::var("p");
+        let b = Expr::Tautology;
+        assert_eq!(unify_explained(&a, &b), Err(UnificationError::SymbolClash { path: ExprPath(vec![]), a, b }));
+    }
+
+    #[test]
+    fn test_unify_explained_reports_arity_mismatch() {
+        let a = Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("x")] };
+        let b = Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("x"), Expr::var("y")] };
+        assert_eq!(
+            unify_explained(&a, &b),
+            Err(UnificationError::ArityMismatch { path: ExprPath(vec![]), left_arity: 1, right_arity: 2 })
+        );
+    }
+
+    #[test]
+    fn test_unify_explained_reports_occurs_check_failure() {
+        // __x =?= f(__x): binding __x would create a cyclic substitution.
+        let term = apply1("f", "__x");
+        assert_eq!(
+            unify_explained(&Expr::var("__x"), &term),
+            Err(UnificationError::OccursCheck { path: ExprPath(vec![]), var: "__x".to_owned(), term })
+        );
+    }
+
+    #[test]
+    fn test_unify_explained_reports_occurs_check_failure_for_a_head_position_occurrence() {
+        // __f =?= __f(__f): __f occurs as the head of the term, not just an
+        // argument, so `term_contains_var`'s `Apply` arm has to look at
+        // `func` too, not just `args`.
+        let term = Expr::Apply { func: Box::new(Expr::var("__f")), args: vec![Expr::var("__f")] };
+        assert_eq!(
+            unify_explained(&Expr::var("__f"), &term),
+            Err(UnificationError::OccursCheck { path: ExprPath(vec![]), var: "__f".to_owned(), term: term.clone() })
+        );
+        assert_eq!(unify(&Expr::var("__f"), &term), None);
+    }
+
+    #[test]
+    fn test_unify_explained_reports_occurs_check_failure_for_an_occurrence_under_a_quantifier() {
+        // __x =?= (forall y, f(__x, y)): __x occurs free inside the
+        // quantifier's body, which is still a cycle even though it's nested.
+        let term = Expr::quantifier(
+            QSymbol::Forall,
+            "y",
+            Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("__x"), Expr::var("y")] },
+        );
+        assert_eq!(
+            unify_explained(&Expr::var("__x"), &term),
+            Err(UnificationError::OccursCheck { path: ExprPath(vec![]), var: "__x".to_owned(), term: term.clone() })
+        );
+        assert_eq!(unify(&Expr::var("__x"), &term), None);
+    }
+
+    #[test]
+    fn test_unify_explained_reports_an_occurs_check_failure_that_only_appears_after_an_earlier_binding() {
+        // __x =?= __y, then __y =?= f(__x): neither step alone mentions a
+        // variable in its own binding, but composing them makes __x occur in
+        // its own (transitive) value via __y.
+        let mut subs = Substitution::new();
+        unify_explained_at(&Expr::var("__x"), &Expr::var("__y"), &mut subs, &mut Vec::new()).expect("first binding is not itself cyclic");
+        let term = apply1("f", "__x");
+        assert_eq!(
+            unify_explained_at(&Expr::var("__y"), &term, &mut subs, &mut Vec::new()),
+            Err(UnificationError::OccursCheck { path: ExprPath(vec![]), var: "__y".to_owned(), term: term.clone() })
+        );
+
+        let mut subs = Substitution::new();
+        assert!(unify_var_ref("__x", &Expr::var("__y"), &mut subs, &mut Vec::new()));
+        assert!(!unify_var_ref("__y", &term, &mut subs, &mut Vec::new()));
+    }
+
+    #[test]
+    fn test_unify_explained_reports_quantifier_escape() {
+        // (forall x, __a) =?= (forall y, Q(y)): __a would have to bind to the
+        // quantifier's own bound variable, which is meaningless outside it.
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::var("__a"));
+        let b = Expr::quantifier(QSymbol::Forall, "y", apply1("q", "y"));
+        match unify_explained(&a, &b) {
+            Err(UnificationError::QuantifierEscape { var, .. }) => assert_eq!(var, "__a"),
+            other => panic!("expected a QuantifierEscape error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unify_explained_agrees_with_unify_on_success() {
+        let a = apply1("f", "__x");
+        let b = apply1("f", "y");
+        assert_eq!(unify_explained(&a, &b), Ok(unify(&a, &b).unwrap()));
+    }
+
+    #[test]
+    fn test_unify_modulo_binder_permutation_succeeds_on_a_two_variable_swap() {
+        // forall x forall y, p(x, y, x) =?= forall y forall x, p(x, y, x):
+        // same body, binders declared in the opposite order. Plain `unify`
+        // can't reconcile this (the repeated `x` makes the two per-level
+        // alpha-renaming variables it tries land in a cycle, caught by the
+        // occurs check), but trying the other binder correspondence works.
+        fn apply3(name: &str, args: [&str; 3]) -> Expr {
+            Expr::Apply { func: Box::new(Expr::var(name)), args: args.iter().map(|a| Expr::var(a)).collect() }
+        }
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Forall, "y", apply3("p", ["x", "y", "x"])));
+        let b = Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Forall, "x", apply3("p", ["x", "y", "x"])));
+        assert_eq!(unify(&a, &b), None, "plain unify should not reconcile the swapped binder order here");
+        assert!(unify_modulo_binder_permutation(&a, &b).is_some());
+    }
+
+    #[test]
+    fn test_unify_modulo_binder_permutation_succeeds_when_only_one_of_six_correspondences_works() {
+        // forall x forall y forall z, p(x) & q(y) & r(z), unified against the
+        // same body under the binder declaration order z, x, y: of the six
+        // ways to pair up three binders, only mapping a's (x, y, z) to b's
+        // (x, y, z) — i.e. accounting for the cyclic shift in how they were
+        // declared — makes the distinct predicates line up.
+        fn matrix(x: &str, y: &str, z: &str) -> Expr {
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var(x)] },
+                    Expr::Apply { func: Box::new(Expr::var("q")), args: vec![Expr::var(y)] },
+                    Expr::Apply { func: Box::new(Expr::var("r")), args: vec![Expr::var(z)] },
+                ],
+            )
+        }
+        let a = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Forall, "z", matrix("x", "y", "z"))),
+        );
+        let b = Expr::quantifier(
+            QSymbol::Forall,
+            "z",
+            Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Forall, "y", matrix("x", "y", "z"))),
+        );
+        assert!(unify_modulo_binder_permutation(&a, &b).is_some());
+    }
+
+    #[test]
+    fn test_unify_modulo_binder_permutation_never_crosses_the_forall_exists_boundary() {
+        // A leading forall-forall run may still be permuted even with a
+        // trailing exists behind it...
+        fn matrix(x: &str, y: &str, z: &str) -> Expr {
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var(x)] },
+                    Expr::Apply { func: Box::new(Expr::var("q")), args: vec![Expr::var(y)] },
+                    Expr::Apply { func: Box::new(Expr::var("r")), args: vec![Expr::var(z)] },
+                ],
+            )
+        }
+        let a = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Exists, "z", matrix("x", "y", "z"))),
+        );
+        let b = Expr::quantifier(
+            QSymbol::Forall,
+            "y",
+            Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Exists, "z", matrix("x", "y", "z"))),
+        );
+        assert!(unify_modulo_binder_permutation(&a, &b).is_some());
+
+        // ...but an outer forall is never permuted against an outer exists,
+        // no matter how the variables underneath are renamed.
+        let c = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "y", Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] }),
+        );
+        let d = Expr::quantifier(
+            QSymbol::Exists,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "y", Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] }),
+        );
+        assert_eq!(unify(&c, &d), None);
+        assert_eq!(unify_modulo_binder_permutation(&c, &d), None);
+    }
+
+    #[test]
+    fn test_unify_does_not_overflow_the_stack_on_deeply_nested_expressions() {
+        // (p -> (p -> (p -> ... -> p))), nested ~50,000 deep, unified with itself.
+        let depth = 50_000;
+        let mut e = Expr::var("p");
+        for _ in 0..depth {
+            e = Expr::implies(Expr::var("p"), e);
+        }
+        let result = unify(&e, &e);
+        // `Expr`'s derived `Drop` recurses through the tree just like its derived
+        // `Clone` does, so dropping `e` normally here would overflow the stack for
+        // an unrelated reason; leak it instead now that unify has been exercised.
+        std::mem::forget(e);
+        assert_eq!(result, Some(Substitution::new()));
+    }
+
+    #[test]
+    fn test_unify_bounded_agrees_with_unify_on_ordinary_input_within_default_limits() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(unify_bounded(&pattern, &subject, UnifyLimits::default()), Ok(unify(&pattern, &subject)));
+        assert_eq!(unify_bounded(&pattern, &pattern, UnifyLimits::default()), Ok(unify(&pattern, &pattern)));
+    }
+
+    #[test]
+    fn test_unify_bounded_reports_too_many_constraints_on_an_adversarial_input() {
+        // (p -> (p -> ... -> p)), nested deep enough to blow well past a
+        // small constraint budget long before `unify` would ever finish —
+        // unlike the unbounded `unify`, `unify_bounded` gives up instead of
+        // grinding through the whole thing.
+        let depth = 10_000;
+        let mut e = Expr::var("p");
+        for _ in 0..depth {
+            e = Expr::implies(Expr::var("p"), e);
+        }
+        let limits = UnifyLimits { max_constraints: 100, max_term_size: 1_000_000 };
+        let result = unify_bounded(&e, &e, limits);
+        std::mem::forget(e);
+        assert_eq!(result, Err(ResourceLimit::TooManyConstraints));
+    }
+
+    #[test]
+    fn test_unify_bounded_reports_term_too_large_when_a_binding_would_exceed_the_size_limit() {
+        let mut big = Expr::var("p");
+        for _ in 0..1000 {
+            big = Expr::assoc(ASymbol::And, vec![big, Expr::var("p")]);
+        }
+        let pattern = Expr::var("__x");
+        let limits = UnifyLimits { max_constraints: 1_000_000, max_term_size: 10 };
+        assert_eq!(unify_bounded(&pattern, &big, limits), Err(ResourceLimit::TermTooLarge));
+        // The same pair unifies fine under generous limits.
+        assert!(unify_bounded(&pattern, &big, UnifyLimits::default()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_alpha_equal_shadowing() {
+        // forall x, forall x, x
+        let shadowed = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "x", Expr::var("x")),
+        );
+        let renamed = Expr::quantifier(
+            QSymbol::Forall,
+            "a",
+            Expr::quantifier(QSymbol::Forall, "b", Expr::var("b")),
+        );
+        assert!(alpha_equal(&shadowed, &renamed));
+
+        // but binding the outer x instead of the inner one is a different expression
+        let binds_outer = Expr::quantifier(
+            QSymbol::Forall,
+            "a",
+            Expr::quantifier(QSymbol::Forall, "b", Expr::var("a")),
+        );
+        assert!(!alpha_equal(&shadowed, &binds_outer));
+    }
+
+    #[test]
+    fn test_alpha_equal_free_vars_must_match() {
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::var("y"));
+        let b = Expr::quantifier(QSymbol::Forall, "x", Expr::var("z"));
+        assert!(!alpha_equal(&a, &b));
+    }
+
+    #[test]
+    fn test_alpha_equal_apply_and_assoc_binop_are_positional() {
+        let a = Expr::quantifier(
+            QSymbol::Exists,
+            "x",
+            Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]),
+        );
+        let b = Expr::quantifier(
+            QSymbol::Exists,
+            "w",
+            Expr::assoc(ASymbol::And, vec![Expr::var("w"), Expr::var("y")]),
+        );
+        assert!(alpha_equal(&a, &b));
+    }
+
+    /// Renames every bound variable in `e` to a name derived from a simple
+    /// counter-based PRNG, leaving free variables untouched.
+    fn rename_bound(e: &Expr, seed: &mut u64) -> Expr {
+        fn next(seed: &mut u64) -> u64 {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            *seed >> 32
+        }
+        match e.clone() {
+            Expr::Quantifier { symbol, name, body } => {
+                let fresh = format!("{}_{}", name, next(seed) % 1000);
+                let body = subst(&name, &Expr::var(&fresh), *body);
+                Expr::Quantifier { symbol, name: fresh, body: Box::new(rename_bound(&body, seed)) }
+            }
+            Expr::Apply { func, args } => Expr::Apply {
+                func: Box::new(rename_bound(&func, seed)),
+                args: args.iter().map(|a| rename_bound(a, seed)).collect(),
+            },
+            Expr::Unop { symbol, operand } => Expr::Unop { symbol, operand: Box::new(rename_bound(&operand, seed)) },
+            Expr::Binop { symbol, l, r } => {
+                Expr::Binop { symbol, l: Box::new(rename_bound(&l, seed)), r: Box::new(rename_bound(&r, seed)) }
+            }
+            Expr::AssocBinop { symbol, exprs } => {
+                Expr::AssocBinop { symbol, exprs: exprs.iter().map(|e| rename_bound(e, seed)).collect() }
+            }
+            other => other,
+        }
+    }
+
+    #[test]
+    fn test_alpha_equal_holds_under_random_renaming() {
+        let exprs = vec![
+            Expr::quantifier(QSymbol::Forall, "x", Expr::var("x")),
+            Expr::quantifier(
+                QSymbol::Exists,
+                "x",
+                Expr::assoc(
+                    ASymbol::And,
+                    vec![Expr::var("x"), Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"))],
+                ),
+            ),
+            Expr::assoc(
+                ASymbol::Or,
+                vec![Expr::quantifier(QSymbol::Forall, "y", Expr::var("y")), Expr::var("z")],
+            ),
+        ];
+        for (i, e) in exprs.into_iter().enumerate() {
+            let mut seed = 0xC0FFEE ^ (i as u64);
+            for _ in 0..5 {
+                let renamed = rename_bound(&e, &mut seed);
+                assert!(alpha_equal(&e, &renamed), "{} should be alpha-equal to {}", e, renamed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_freshen_binders_preserves_alpha_equivalence() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(
+                ASymbol::And,
+                vec![Expr::var("x"), Expr::quantifier(QSymbol::Exists, "x", Expr::var("x"))],
+            ),
+        );
+        let freshened = freshen_binders(&e, &std::collections::HashSet::new());
+        assert!(alpha_equal(&e, &freshened));
+    }
+
+    #[test]
+    fn test_freshen_binders_produces_unique_non_clashing_names() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(
+                ASymbol::And,
+                vec![Expr::var("x"), Expr::quantifier(QSymbol::Exists, "x", Expr::var("x"))],
+            ),
+        );
+        let avoid: std::collections::HashSet<String> = vec!["y".to_owned()].into_iter().collect();
+        let freshened = freshen_binders(&e, &avoid);
+        let binders = boundvars(&freshened);
+        assert_eq!(binders.len(), 2, "the two binders should get distinct names");
+        assert!(!binders.contains("y"), "binders must not clash with `avoid`");
+        assert!(freevars(&freshened).is_disjoint(&binders), "binders must not clash with free variables");
+    }
+
+    #[test]
+    fn test_reduce_pattern() {
+        let patterns = vec![(!!Expr::var("__a"), Expr::var("__a"))];
+        let e = !!Expr::var("p");
+        assert_eq!(reduce_pattern(e, &patterns).unwrap(), Expr::var("p"));
+    }
+
+    #[test]
+    fn test_reduce_pattern_rejects_a_replacement_that_introduces_an_unbound_metavariable() {
+        // `__a -> __b`: the replacement mentions `__b`, which the pattern
+        // never binds. This used to panic via a `debug_assert!`; now it
+        // should degrade gracefully to an `Err` naming the offending
+        // pattern and variable.
+        let patterns = vec![(Expr::var("__a"), Expr::var("__b"))];
+        let e = Expr::var("p");
+        let err = reduce_pattern(e, &patterns).unwrap_err();
+        assert_eq!(err.pattern_index, 0);
+        assert_eq!(err.kind, PatternErrorKind::UnboundReplacementVariable { name: "__b".to_owned() });
+    }
+
+    #[test]
+    fn test_reduce_pattern_reports_the_index_of_the_malformed_pattern_among_several() {
+        let patterns = vec![(Expr::var("__a"), Expr::var("__a")), (Expr::var("__c"), Expr::var("__d"))];
+        let e = Expr::var("p");
+        let err = reduce_pattern(e, &patterns).unwrap_err();
+        assert_eq!(err.pattern_index, 1);
+    }
+
+    /// The idempotence-shaped pattern `phi & phi -> phi`, applicable at two
+    /// nested sites in [`nested_idempotence_subject`]: once at the root
+    /// (whose two operands are the identical `a & a` subtree) and once at
+    /// each of those two identical children independently.
+    fn idempotence_and_pattern() -> (Expr, Expr) {
+        (Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]), Expr::var("__phi"))
+    }
+
+    /// `(a & a) & (a & a)` — the root and both children match
+    /// [`idempotence_and_pattern`].
+    fn nested_idempotence_subject() -> Expr {
+        let inner = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a")]);
+        Expr::assoc(ASymbol::And, vec![inner.clone(), inner])
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_strategy_fixpoint_collapses_every_nested_site() {
+        let patterns = vec![idempotence_and_pattern()];
+        let result = reduce_pattern_with_strategy(nested_idempotence_subject(), &patterns, Strategy::Fixpoint).unwrap();
+        assert_eq!(result, Expr::var("a"));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_strategy_innermost_once_cascades_within_a_single_pass() {
+        // Both children collapse to `a` during the same innermost-first
+        // pass, which then exposes a fresh match at the root (now `a & a`)
+        // before the pass ends — so one `InnermostOnce` call already
+        // reaches the same fixpoint here.
+        let patterns = vec![idempotence_and_pattern()];
+        let result = reduce_pattern_with_strategy(nested_idempotence_subject(), &patterns, Strategy::InnermostOnce).unwrap();
+        assert_eq!(result, Expr::var("a"));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_strategy_outermost_once_rewrites_only_the_root() {
+        // The root matches first (its two operands are the identical `a & a`
+        // subtree), so outermost-first stops there without ever looking at
+        // the children it just replaced.
+        let patterns = vec![idempotence_and_pattern()];
+        let result = reduce_pattern_with_strategy(nested_idempotence_subject(), &patterns, Strategy::OutermostOnce).unwrap();
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a")]));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_strategy_first_match_only_rewrites_one_site_and_leaves_the_other() {
+        let patterns = vec![idempotence_and_pattern()];
+        let result = reduce_pattern_with_strategy(nested_idempotence_subject(), &patterns, Strategy::FirstMatchOnly).unwrap();
+        // The leftmost innermost site (`a & a`) collapses to `a`; the other
+        // nested occurrence, and the root, are left exactly as they were.
+        assert_eq!(
+            result,
+            Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a")])])
+        );
+    }
+
+    #[test]
+    fn test_transform_expr_with_strategy_agrees_with_plain_transform_expr_on_fixpoint() {
+        let e = nested_idempotence_subject();
+        let f = |e: Expr| apply_patterns_once(e, &[idempotence_and_pattern()], &[pattern_vars_of(&idempotence_and_pattern().0)], false);
+        assert_eq!(transform_expr_with_strategy(e.clone(), &f, Strategy::Fixpoint), transform_expr(e, &f));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_strategy_is_a_no_op_when_nothing_matches() {
+        let patterns = vec![idempotence_and_pattern()];
+        let e = Expr::var("b");
+        for strategy in [Strategy::Fixpoint, Strategy::InnermostOnce, Strategy::OutermostOnce, Strategy::FirstMatchOnly] {
+            assert_eq!(reduce_pattern_with_strategy(e.clone(), &patterns, strategy).unwrap(), e);
+        }
+    }
+
+    #[test]
+    fn test_reduce_pattern_treats_a_subject_side_repeated_binding_conflict_as_no_match_not_an_error() {
+        // `__a & __a -> __a`: a well-formed pattern whose repeated
+        // metavariable simply fails to match a subject where the two
+        // operands differ. That is an ordinary non-match, not a
+        // `PatternError` — the pattern itself isn't malformed.
+        let patterns = vec![(Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]), Expr::var("__a"))];
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(reduce_pattern(e.clone(), &patterns).unwrap(), e);
+    }
+
+    fn idempotence_hole_pattern() -> (Pattern, Pattern) {
+        // `__phi <op> __phi -> __phi` for any `ASymbol`, not just `And`.
+        let pattern = Pattern::AnyAssocBinop {
+            hole: "__op".to_owned(),
+            exprs: vec![Pattern::Literal(Expr::var("__phi")), Pattern::Literal(Expr::var("__phi"))],
+        };
+        let replacement = Pattern::Literal(Expr::var("__phi"));
+        (pattern, replacement)
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_holes_expresses_idempotence_for_every_assoc_symbol_with_one_pattern() {
+        let patterns = vec![idempotence_hole_pattern()];
+        let and_input = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("p")]);
+        assert_eq!(reduce_pattern_with_holes(and_input, &patterns).unwrap(), Expr::var("p"));
+
+        let or_input = Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("p")]);
+        // Crucially, an `Or` input must reduce to plain `p`, not to an `And`
+        // or any other symbol — the hole reuses whichever symbol it
+        // actually matched, it doesn't default to the first one tried.
+        assert_eq!(reduce_pattern_with_holes(or_input, &patterns).unwrap(), Expr::var("p"));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_holes_leaves_non_idempotent_input_unchanged() {
+        let patterns = vec![idempotence_hole_pattern()];
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(reduce_pattern_with_holes(e.clone(), &patterns).unwrap(), e);
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_holes_rewrites_a_nested_any_binop_hole_reusing_the_matched_symbol() {
+        // `__phi <op> __phi -> __phi` for any `BSymbol`: `p + p` should
+        // reduce to `p`, keeping `Plus`, not silently becoming `Mult`.
+        let pattern =
+            Pattern::AnyBinop { hole: "__op".to_owned(), l: Box::new(Pattern::Literal(Expr::var("__phi"))), r: Box::new(Pattern::Literal(Expr::var("__phi"))) };
+        let replacement = Pattern::Literal(Expr::var("__phi"));
+        let patterns = vec![(pattern, replacement)];
+        let e = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("p")), r: Box::new(Expr::var("p")) };
+        assert_eq!(reduce_pattern_with_holes(e, &patterns).unwrap(), Expr::var("p"));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_holes_rejects_a_replacement_that_reuses_an_unbound_hole() {
+        let pattern = Pattern::Literal(Expr::var("__phi"));
+        let replacement = Pattern::AnyAssocBinop { hole: "__op".to_owned(), exprs: vec![Pattern::Literal(Expr::var("__phi"))] };
+        let patterns = vec![(pattern, replacement)];
+        let err = reduce_pattern_with_holes(Expr::var("p"), &patterns).unwrap_err();
+        assert_eq!(err.pattern_index, 0);
+        assert_eq!(err.kind, PatternErrorKind::UnboundReplacementHole { name: "__op".to_owned() });
+    }
+
+    #[test]
+    fn test_rewrite_system_rejects_the_same_malformed_pattern_reduce_pattern_would() {
+        let patterns = vec![(Expr::var("__a"), Expr::var("__b"))];
+        let err = RewriteSystem::new(patterns).unwrap_err();
+        assert_eq!(err.pattern_index, 0);
+        assert_eq!(err.kind, PatternErrorKind::UnboundReplacementVariable { name: "__b".to_owned() });
+    }
+
+    #[test]
+    fn test_rewrite_system_reduce_agrees_with_reduce_pattern_across_many_calls() {
+        // Preparing the system once and calling `reduce` many times over
+        // small expressions should agree with calling `reduce_pattern` fresh
+        // every time — precomputing the pattern variable sets up front must
+        // not change behavior, only when the per-pattern setup happens.
+        let patterns = vec![(!!Expr::var("__a"), Expr::var("__a"))];
+        let system = RewriteSystem::new(patterns.clone()).unwrap();
+        for name in ["p", "q", "r", "s", "t"] {
+            let e = !!Expr::var(name);
+            assert_eq!(system.reduce(e.clone()), reduce_pattern(e, &patterns).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_kbo_compare_prefers_the_heavier_side_when_the_variable_condition_holds() {
+        let small = Expr::var("__a");
+        let big = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]);
+        assert_eq!(kbo_compare(&big, &small), Some(Ordering::Greater));
+        assert_eq!(kbo_compare(&small, &big), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn test_kbo_compare_is_reflexive() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("q")]);
+        assert_eq!(kbo_compare(&e, &e), Some(Ordering::Equal));
+    }
+
+    #[test]
+    fn test_kbo_compare_returns_none_for_two_distinct_variables() {
+        // Equal weight, and neither side's variable condition holds, since
+        // each contains a variable the other doesn't: incomparable.
+        assert_eq!(kbo_compare(&Expr::var("__a"), &Expr::var("__b")), None);
+    }
+
+    #[test]
+    fn test_kbo_compare_returns_none_when_the_heavier_side_drops_a_variable() {
+        // `__a & __a` is heavier than `__b`, but doesn't contain `__b`, and
+        // `__b` doesn't contain `__a` either: the variable condition fails
+        // both ways, so the two remain incomparable despite differing weight.
+        let lighter = Expr::var("__b");
+        let heavier = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]);
+        assert_eq!(kbo_compare(&heavier, &lighter), None);
+    }
+
+    #[test]
+    fn test_rewrite_system_orient_accepts_a_set_of_genuinely_shrinking_rules() {
+        let patterns = vec![
+            (Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]), Expr::var("__phi")),
+            (!!Expr::var("__phi"), Expr::var("__phi")),
+        ];
+        let system = RewriteSystem::new(patterns).unwrap();
+        assert!(system.orient().is_ok());
+    }
+
+    #[test]
+    fn test_rewrite_system_orient_rejects_and_names_a_deliberately_increasing_rule() {
+        // `A ==> A & A`: growing, not shrinking, so no amount of applying it
+        // could ever be guaranteed to terminate.
+        let increasing = (Expr::var("__a"), Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]));
+        let shrinking = (!!Expr::var("__phi"), Expr::var("__phi"));
+        let system = RewriteSystem::new(vec![shrinking, increasing]).unwrap();
+        let err = system.orient().unwrap_err();
+        assert_eq!(err.pattern_index, 1);
+    }
+
+    #[test]
+    fn test_oriented_system_reduce_agrees_with_rewrite_system_reduce() {
+        let patterns = vec![(Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]), Expr::var("__phi"))];
+        let system = RewriteSystem::new(patterns).unwrap();
+        let oriented = system.orient().unwrap();
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("p")]);
+        assert_eq!(oriented.reduce(e.clone()), system.reduce(e));
+    }
+
+    #[test]
+    fn test_critical_pairs_is_empty_for_rules_with_unrelated_head_symbols() {
+        let and_idempotence = (Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]), Expr::var("__phi"));
+        let or_idempotence = (Expr::assoc(ASymbol::Or, vec![Expr::var("__psi"), Expr::var("__psi")]), Expr::var("__psi"));
+        let rules = vec![and_idempotence, or_idempotence];
+        assert_eq!(critical_pairs(&rules), vec![]);
+        assert_eq!(is_locally_confluent(&rules, 10), Ok(()));
+    }
+
+    #[test]
+    fn test_critical_pairs_finds_the_known_overlap_between_two_negation_rules_and_shows_it_joins() {
+        // `!!__phi -> __phi` and `!__psi -> __psi` overlap where the second
+        // rule's left side unifies with the inner `!__phi` of the first's:
+        // one rule collapses the whole double negation in one step, the
+        // other peels off only the outer negation first. The two results
+        // disagree immediately (a real critical pair), but a further
+        // rewrite with the same rules joins them back up.
+        let double_negation = (!!Expr::var("__phi"), Expr::var("__phi"));
+        let single_negation = (!Expr::var("__psi"), Expr::var("__psi"));
+        let rules = vec![double_negation, single_negation];
+
+        let pairs = critical_pairs(&rules);
+        assert!(!pairs.is_empty(), "expected at least one overlap between the two negation rules");
+        assert!(pairs.iter().all(|pair| pair.left != pair.right));
+
+        assert_eq!(is_locally_confluent(&rules, 10), Ok(()));
+    }
+
+    #[test]
+    fn test_is_locally_confluent_reports_an_overlap_that_cannot_be_joined() {
+        // Both rules match any `!X`, but send it to unrelated constants —
+        // there is no further rewrite available for `Tautology` or
+        // `Contradiction`, so the overlap can never be joined.
+        let to_tautology = (!Expr::var("__phi"), Expr::Tautology);
+        let to_contradiction = (!Expr::var("__psi"), Expr::Contradiction);
+        let rules = vec![to_tautology, to_contradiction];
+
+        let unjoined = is_locally_confluent(&rules, 10).unwrap_err();
+        assert!(!unjoined.is_empty());
+        assert!(unjoined.iter().any(|pair| pair.left == Expr::Tautology && pair.right == Expr::Contradiction));
+    }
+
+    #[test]
+    fn test_match_expr_binds_pattern_vars_to_matching_subtrees() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__b")]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__a".to_owned(), "__b".to_owned()].into_iter().collect();
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("q")]);
+        let bindings = match_expr(&pattern, &subject, &pattern_vars, false).expect("pattern should match");
+        assert_eq!(bindings.get("__a"), Some(&Expr::var("p")));
+        assert_eq!(bindings.get("__b"), Some(&!Expr::var("q")));
+    }
+
+    #[test]
+    fn test_match_expr_requires_non_pattern_vars_to_match_exactly() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("__a")]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__a".to_owned()].into_iter().collect();
+        let matching = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        assert!(match_expr(&pattern, &matching, &pattern_vars, false).is_some());
+        let mismatching = Expr::assoc(ASymbol::And, vec![Expr::var("r"), Expr::var("q")]);
+        assert!(match_expr(&pattern, &mismatching, &pattern_vars, false).is_none());
+    }
+
+    #[test]
+    fn test_match_expr_requires_repeated_pattern_vars_to_bind_structurally_equal_subtrees() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__a".to_owned()].into_iter().collect();
+        let matching = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("p")]);
+        assert!(match_expr(&pattern, &matching, &pattern_vars, false).is_some());
+        let mismatching = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        assert!(match_expr(&pattern, &mismatching, &pattern_vars, false).is_none());
+    }
+
+    #[test]
+    fn test_match_expr_never_binds_a_subject_var_that_merely_shares_a_pattern_vars_name() {
+        // The subject uses `__a` as an ordinary object-level variable name,
+        // not as a metavariable. `__a` isn't one of `pattern_vars` here, so
+        // it must be required to match literally rather than being bound.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("__a")]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__b".to_owned()].into_iter().collect();
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("__a")]);
+        let bindings = match_expr(&pattern, &subject, &pattern_vars, false).expect("literal `__a` should match literal `__a`");
+        assert!(bindings.is_empty());
+
+        let different = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("__c")]);
+        assert!(match_expr(&pattern, &different, &pattern_vars, false).is_none());
+    }
+
+    #[test]
+    fn test_reduce_pattern_matches_even_when_the_subject_reuses_a_pattern_variable_name() {
+        // The pattern binds `__a`, but the subject happens to contain an
+        // object-level variable that is also spelled `__a`. `match_expr` is
+        // one-sided, so this must still match `__a` against the whole `!p`
+        // subexpression rather than getting confused about which side owns
+        // the name.
+        let patterns = vec![(!!Expr::var("__a"), Expr::var("__a"))];
+        let e = !!Expr::var("__a");
+        assert_eq!(reduce_pattern(e, &patterns).unwrap(), Expr::var("__a"));
+    }
+
+    #[test]
+    fn test_match_expr_commutative_matches_assoc_binop_up_to_permutation() {
+        // `__a & (__a | __b)` against `(x | y) & x`: without AC matching the
+        // operand order wouldn't line up at all.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::assoc(ASymbol::Or, vec![Expr::var("__a"), Expr::var("__b")])]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__a".to_owned(), "__b".to_owned()].into_iter().collect();
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::assoc(ASymbol::Or, vec![Expr::var("x"), Expr::var("y")]), Expr::var("x")]);
+        assert!(match_expr(&pattern, &subject, &pattern_vars, false).is_none(), "should not match without the commutative flag");
+        let bindings = match_expr(&pattern, &subject, &pattern_vars, true).expect("should match with the commutative flag");
+        assert_eq!(bindings.get("__a"), Some(&Expr::var("x")));
+        assert_eq!(bindings.get("__b"), Some(&Expr::var("y")));
+    }
+
+    #[test]
+    fn test_match_expr_commutative_finds_the_matching_pair_in_the_middle_of_many_operands() {
+        // `__a & ~__a` against a 5-operand conjunction where the
+        // complementary pair sits in the middle of the list.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), !Expr::var("__a")]);
+        let pattern_vars: std::collections::HashSet<String> = vec!["__a".to_owned()].into_iter().collect();
+        let subject = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("p"), Expr::var("q"), Expr::var("r"), !Expr::var("r"), Expr::var("s")],
+        );
+        assert!(match_expr(&pattern, &subject, &pattern_vars, true).is_none(), "operand counts differ, so this can't match");
+
+        let pair_pattern = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("__w"), Expr::var("__x"), Expr::var("__a"), !Expr::var("__a"), Expr::var("__y")],
+        );
+        let pair_vars: std::collections::HashSet<String> =
+            vec!["__w".to_owned(), "__x".to_owned(), "__a".to_owned(), "__y".to_owned()].into_iter().collect();
+        let bindings = match_expr(&pair_pattern, &subject, &pair_vars, true).expect("the complementary pair should be found by permutation");
+        assert_eq!(bindings.get("__a"), Some(&Expr::var("r")));
+    }
+
+    #[test]
+    fn test_reduce_pattern_ac_rewrites_regardless_of_operand_order() {
+        // `phi & (phi | psi) -> phi` (absorption), applied to a subject
+        // where the disjunction's operands are swapped relative to the
+        // pattern.
+        let patterns = vec![(
+            Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::assoc(ASymbol::Or, vec![Expr::var("__phi"), Expr::var("__psi")])]),
+            Expr::var("__phi"),
+        )];
+        let e = Expr::assoc(ASymbol::And, vec![Expr::assoc(ASymbol::Or, vec![Expr::var("q"), Expr::var("p")]), Expr::var("p")]);
+        assert_eq!(reduce_pattern_ac(e, &patterns), Expr::var("p"));
+    }
+
+    #[test]
+    fn test_match_expr_rest_variable_binds_the_leftover_operands_of_an_assoc_binop() {
+        // `phi & ~phi & rest...` should find the complementary pair
+        // anywhere among the subject's operands and bind `rest...` to
+        // everything else, in their original relative order.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), !Expr::var("__phi"), Expr::var("__rest...")]);
+        let pattern_vars = pattern_vars_of(&pattern);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("r"), !Expr::var("r"), Expr::var("s")]);
+        let bindings = match_expr(&pattern, &subject, &pattern_vars, true).expect("should match the complementary pair in the middle");
+        assert_eq!(bindings.get("__phi"), Some(&Expr::var("r")));
+        assert_eq!(
+            bindings.get("__rest..."),
+            Some(&Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("s")]))
+        );
+    }
+
+    #[test]
+    fn test_match_expr_rest_variable_collapses_to_the_identity_when_nothing_is_left_over() {
+        // With exactly the complementary pair and nothing else, `rest...`
+        // has no leftover operands to bind, so it collapses to `And`'s
+        // identity element rather than an empty `AssocBinop`.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), !Expr::var("__phi"), Expr::var("__rest...")]);
+        let pattern_vars = pattern_vars_of(&pattern);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("r"), !Expr::var("r")]);
+        let bindings = match_expr(&pattern, &subject, &pattern_vars, true).expect("should match with nothing left over");
+        assert_eq!(bindings.get("__rest..."), Some(&Expr::Tautology));
+    }
+
+    #[test]
+    fn test_reduce_pattern_complement_with_rest_variable_reduces_nary_conjunction_in_one_step() {
+        // `phi & ~phi & rest... -> Contradiction`, applied to `p & q & ~p & r`.
+        let patterns = vec![(
+            Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), !Expr::var("__phi"), Expr::var("__rest...")]),
+            Expr::Contradiction,
+        )];
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), !Expr::var("p"), Expr::var("r")]);
+        assert_eq!(reduce_pattern_ac(e, &patterns), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_with_commutative_variants_expands_a_two_operand_assoc_binop_pattern() {
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__b")]);
+        let replacement = Expr::var("__a");
+        let expanded = with_commutative_variants(vec![(pattern, replacement.clone())]);
+        assert_eq!(
+            expanded,
+            vec![
+                (Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__b")]), replacement.clone()),
+                (Expr::assoc(ASymbol::And, vec![Expr::var("__b"), Expr::var("__a")]), replacement),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_commutative_variants_expands_a_three_operand_pattern_into_six_distinct_orderings() {
+        let pattern = Expr::assoc(ASymbol::Or, vec![Expr::var("__a"), Expr::var("__b"), Expr::var("__c")]);
+        let expanded = with_commutative_variants(vec![(pattern, Expr::var("__a"))]);
+        assert_eq!(expanded.len(), 6);
+        let distinct: std::collections::HashSet<_> = expanded.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(distinct.len(), 6);
+    }
+
+    #[test]
+    fn test_with_commutative_variants_deduplicates_orderings_that_coincide() {
+        // Both operands are the same subterm, so the swap produces the same pattern twice.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__a"), Expr::var("__a")]);
+        let expanded = with_commutative_variants(vec![(pattern.clone(), Expr::var("__a"))]);
+        assert_eq!(expanded, vec![(pattern, Expr::var("__a"))]);
+    }
+
+    #[test]
+    fn test_with_commutative_variants_expands_a_commutative_binop_pattern() {
+        let pattern = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("__a")), r: Box::new(Expr::var("__b")) };
+        let expanded = with_commutative_variants(vec![(pattern, Expr::var("__a"))]);
+        assert_eq!(expanded.len(), 2);
+        assert!(expanded.iter().any(|(p, _)| *p == Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("__b")), r: Box::new(Expr::var("__a")) }));
+    }
+
+    #[test]
+    fn test_with_commutative_variants_passes_non_commutative_implies_pattern_through_unchanged() {
+        let pattern = Expr::implies(Expr::var("__a"), Expr::var("__b"));
+        let expanded = with_commutative_variants(vec![(pattern.clone(), Expr::var("__a"))]);
+        assert_eq!(expanded, vec![(pattern, Expr::var("__a"))]);
+    }
+
+    #[test]
+    fn test_find_matches_locates_every_demorgan_site_and_apply_match_rewrites_only_the_chosen_one() {
+        // `!(phi & psi) -> !phi | !psi`, tried against three independent
+        // `!(_ & _)` sites so `find_matches` has to report all three without
+        // rewriting any of them, and `apply_match` can then be pointed at
+        // just the middle one.
+        let pattern = !Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let replacement = Expr::assoc(ASymbol::Or, vec![!Expr::var("__phi"), !Expr::var("__psi")]);
+        let pattern_vars = pattern_vars_of(&pattern);
+
+        let site = |a: &str, b: &str| !Expr::assoc(ASymbol::And, vec![Expr::var(a), Expr::var(b)]);
+        let subject = Expr::assoc(ASymbol::And, vec![site("p", "q"), site("r", "s"), site("t", "u")]);
+
+        let matches = find_matches(&pattern, &pattern_vars, &subject);
+        assert_eq!(matches.len(), 3, "expected exactly the three DeMorgan-reducible sites, got {:?}", matches);
+        let paths: std::collections::HashSet<_> = matches.iter().map(|(path, _)| path.clone()).collect();
+        let expected_paths: std::collections::HashSet<_> = vec![ExprPath(vec![0]), ExprPath(vec![1]), ExprPath(vec![2])].into_iter().collect();
+        assert_eq!(paths, expected_paths);
+
+        let (middle_path, middle_bindings) = matches.iter().find(|(path, _)| path == &ExprPath(vec![1])).expect("middle site should be found");
+        let rewritten = apply_match(&subject, middle_path, &replacement, middle_bindings).unwrap();
+        assert_eq!(
+            rewritten,
+            Expr::assoc(ASymbol::And, vec![site("p", "q"), Expr::assoc(ASymbol::Or, vec![!Expr::var("r"), !Expr::var("s")]), site("t", "u")])
+        );
+    }
+
+    #[test]
+    fn test_find_matches_returns_every_distinct_pairing_for_a_commutative_pattern() {
+        // `phi & phi` against `a & b & a`: the operand counts differ (two
+        // vs. three), so there is no match at all; the distinct pairings
+        // are checked below on a two-operand subject.
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]);
+        let pattern_vars = pattern_vars_of(&pattern);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("a")]);
+        let matches = find_matches(&pattern, &pattern_vars, &subject);
+        assert!(matches.is_empty(), "a two-operand pattern can't match a three-operand AssocBinop: {:?}", matches);
+
+        let pattern = Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]);
+        let pattern_vars = pattern_vars_of(&pattern);
+        let subject = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        let matches = find_matches(&pattern, &pattern_vars, &subject);
+        // Pairing (phi=a, psi=b) and (phi=b, psi=a) are both valid orderings
+        // of a commutative `And`, and distinct as bindings.
+        assert_eq!(matches.len(), 2);
+        let bindings: std::collections::HashSet<_> = matches.iter().map(|(_, b)| sorted_bindings(b)).collect();
+        assert!(bindings.contains(&vec![("__phi".to_owned(), Expr::var("a")), ("__psi".to_owned(), Expr::var("b"))]));
+        assert!(bindings.contains(&vec![("__phi".to_owned(), Expr::var("b")), ("__psi".to_owned(), Expr::var("a"))]));
+    }
+
+    #[test]
+    fn test_reduce_pattern_with_report_records_both_demorgan_sites_with_distinct_paths() {
+        // `!(phi & psi) -> !phi | !psi` and `!(phi | psi) -> !phi & !psi`.
+        let demorgan_and = (
+            !Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__psi")]),
+            Expr::assoc(ASymbol::Or, vec![!Expr::var("__phi"), !Expr::var("__psi")]),
+        );
+        let demorgan_or = (
+            !Expr::assoc(ASymbol::Or, vec![Expr::var("__phi"), Expr::var("__psi")]),
+            Expr::assoc(ASymbol::And, vec![!Expr::var("__phi"), !Expr::var("__psi")]),
+        );
+        let patterns = vec![demorgan_and, demorgan_or];
+        // Two independent reducible sites, one per pattern.
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![!Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]), !Expr::assoc(ASymbol::Or, vec![Expr::var("r"), Expr::var("s")])],
+        );
+        let (result, applications) = reduce_pattern_with_report(e, patterns);
+        assert_eq!(
+            result,
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(ASymbol::Or, vec![!Expr::var("p"), !Expr::var("q")]),
+                    Expr::assoc(ASymbol::And, vec![!Expr::var("r"), !Expr::var("s")]),
+                ]
+            )
+        );
+        assert_eq!(applications.len(), 2);
+        let paths: std::collections::HashSet<_> = applications.iter().map(|a| a.path.clone()).collect();
+        assert_eq!(paths.len(), 2, "the two applications should be recorded at distinct paths");
+        assert_eq!(applications[0].pattern_index, 0);
+        assert_eq!(applications[0].path, ExprPath(vec![0]));
+        assert_eq!(applications[1].pattern_index, 1);
+        assert_eq!(applications[1].path, ExprPath(vec![1]));
+    }
+
+    #[test]
+    fn test_normalize_vacuous_quantifiers_drops_nested_vacuous_binders() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "y", Expr::var("z")),
+        );
+        assert_eq!(normalize_vacuous_quantifiers(e), Expr::var("z"));
+    }
+
+    #[test]
+    fn test_normalize_vacuous_quantifiers_respects_shadowing() {
+        // forall x, exists x, x: the outer x is vacuous (shadowed by the
+        // inner binder), but the inner x is not.
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "x", Expr::var("x")),
+        );
+        assert_eq!(normalize_vacuous_quantifiers(e), Expr::quantifier(QSymbol::Exists, "x", Expr::var("x")));
+    }
+
+    #[test]
+    fn test_normalize_vacuous_quantifiers_keeps_binders_used_in_apply_arguments() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x")] },
+        );
+        assert_eq!(normalize_vacuous_quantifiers(e.clone()), e);
+    }
+
+    fn apply1(name: &str, arg: &str) -> Expr {
+        Expr::Apply { func: Box::new(Expr::var(name)), args: vec![Expr::var(arg)] }
+    }
+
+    #[test]
+    fn test_miniscope_distributes_forall_over_and() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), Expr::var("q")]));
+        let expected = Expr::assoc(ASymbol::And, vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::var("q")]);
+        assert_eq!(miniscope(e), expected);
+    }
+
+    #[test]
+    fn test_miniscope_distributes_exists_over_or() {
+        let e = Expr::quantifier(QSymbol::Exists, "x", Expr::assoc(ASymbol::Or, vec![apply1("p", "x"), apply1("q", "x")]));
+        let expected = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::quantifier(QSymbol::Exists, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Exists, "x", apply1("q", "x"))],
+        );
+        assert_eq!(miniscope(e), expected);
+    }
+
+    #[test]
+    fn test_miniscope_narrows_forall_over_or_to_the_free_disjunct() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::Or, vec![apply1("p", "x"), Expr::var("q")]));
+        let expected = Expr::assoc(ASymbol::Or, vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::var("q")]);
+        assert_eq!(miniscope(e), expected);
+    }
+
+    #[test]
+    fn test_miniscope_does_not_perform_unsound_distribution() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::Or, vec![apply1("p", "x"), apply1("q", "x")]));
+        assert_eq!(miniscope(e.clone()), e);
+    }
+
+    #[test]
+    fn test_miniscope_handles_nested_quantifiers_of_both_kinds() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "y", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "y")])),
+        );
+        let expected = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Exists, "y", apply1("q", "y"))],
+        );
+        assert_eq!(miniscope(e), expected);
+    }
+
+    #[test]
+    fn test_to_prenex_avoids_the_classic_capture_trap() {
+        // (forall x, p(x)) -> q(x): the free `x` in q(x) must not be captured
+        // by the hoisted (and flipped-to-exists) binder from the antecedent.
+        let e = Expr::implies(Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), apply1("q", "x"));
+        let prenexed = to_prenex(e).unwrap();
+        match prenexed {
+            Expr::Quantifier { symbol, name, body } => {
+                assert_eq!(symbol, QSymbol::Exists);
+                assert_ne!(name, "x", "the hoisted binder must be renamed to avoid capturing the free `x`");
+                assert!(freevars(&body).contains("x"), "the original free `x` must remain free");
+                assert!(!matches!(*body, Expr::Quantifier { .. }), "the matrix must be quantifier-free");
+            }
+            other => panic!("expected a hoisted quantifier, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_to_prenex_flattens_nested_and_assoc_binops() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Exists, "y", apply1("q", "y"))],
+        );
+        let prenexed = to_prenex(e).unwrap();
+        assert_eq!(
+            prenexed,
+            Expr::quantifier(
+                QSymbol::Forall,
+                "x",
+                Expr::quantifier(QSymbol::Exists, "y", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "y")]))
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_prenex_rejects_bicon_and_equiv() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(to_prenex(e), Err(PrenexError::UnsupportedConnective(ASymbol::Bicon)));
+        let e = Expr::assoc(ASymbol::Equiv, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(to_prenex(e), Err(PrenexError::UnsupportedConnective(ASymbol::Equiv)));
+    }
+
+    #[test]
+    fn test_skolemize_replaces_existential_with_function_of_enclosing_universals() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Exists,
+                "y",
+                Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] },
+            ),
+        );
+        let result = skolemize(e);
+        let (name, body) = match result {
+            Expr::Quantifier { symbol: QSymbol::Forall, name, body } => (name, *body),
+            other => panic!("expected the universal to be retained, got {}", other),
+        };
+        assert_eq!(name, "x");
+        let args = match body {
+            Expr::Apply { func, args } => {
+                assert_eq!(*func, Expr::var("p"));
+                args
+            }
+            other => panic!("expected p(...), got {}", other),
+        };
+        assert_eq!(args[0], Expr::var("x"));
+        match &args[1] {
+            Expr::Apply { func, args: sk_args } => {
+                match func.as_ref() {
+                    Expr::Var { name } => {
+                        assert_ne!(name, "x");
+                        assert_ne!(name, "p");
+                    }
+                    other => panic!("expected a skolem function symbol, got {}", other),
+                }
+                assert_eq!(sk_args, &vec![Expr::var("x")]);
+            }
+            other => panic!("expected y to be replaced by a skolem function of x, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_skolemize_gives_distinct_symbols_and_arities_at_different_depths() {
+        // forall x, exists y, forall z, exists w, p(x, y, z, w)
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Exists,
+                "y",
+                Expr::quantifier(
+                    QSymbol::Forall,
+                    "z",
+                    Expr::quantifier(
+                        QSymbol::Exists,
+                        "w",
+                        Expr::Apply {
+                            func: Box::new(Expr::var("p")),
+                            args: vec![Expr::var("x"), Expr::var("y"), Expr::var("z"), Expr::var("w")],
+                        },
+                    ),
+                ),
+            ),
+        );
+        let result = skolemize(e);
+        // Both universals should still be present, wrapping an application of `p`
+        // whose second and fourth arguments were replaced by skolem terms.
+        let inner = match &result {
+            Expr::Quantifier { symbol: QSymbol::Forall, name: x, body } if x == "x" => match body.as_ref() {
+                Expr::Quantifier { symbol: QSymbol::Forall, name: z, body } if z == "z" => body.as_ref(),
+                other => panic!("expected the inner forall z, got {}", other),
+            },
+            other => panic!("expected forall x, got {}", other),
+        };
+        let args = match inner {
+            Expr::Apply { args, .. } => args,
+            other => panic!("expected p(...), got {}", other),
+        };
+        let y_term = &args[1];
+        let w_term = &args[3];
+        let (y_symbol, y_arity) = match y_term {
+            Expr::Apply { func, args } => match func.as_ref() {
+                Expr::Var { name } => (name.clone(), args.len()),
+                _ => panic!("expected a skolem symbol"),
+            },
+            _ => panic!("expected y to be skolemized"),
+        };
+        let (w_symbol, w_arity) = match w_term {
+            Expr::Apply { func, args } => match func.as_ref() {
+                Expr::Var { name } => (name.clone(), args.len()),
+                _ => panic!("expected a skolem symbol"),
+            },
+            _ => panic!("expected w to be skolemized"),
+        };
+        assert_ne!(y_symbol, w_symbol, "the two existentials must get distinct skolem symbols");
+        assert_eq!(y_arity, 1, "y is only under one enclosing universal (x)");
+        assert_eq!(w_arity, 2, "w is under two enclosing universals (x, z)");
+    }
+
+    #[test]
+    fn test_skolemize_stripping_universals_drops_the_prefix() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Exists,
+                "y",
+                Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] },
+            ),
+        );
+        let result = skolemize_stripping_universals(e);
+        assert!(matches!(result, Expr::Apply { .. }));
+    }
+
+    fn nnf_fixtures() -> Vec<Expr> {
+        vec![
+            Expr::implies(Expr::var("a"), Expr::var("b")),
+            !Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]),
+            !Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+            !!Expr::var("a"),
+            Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]),
+            !Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")),
+            !Expr::implies(Expr::var("a"), Expr::var("b")),
+            Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), !Expr::var("c")),
+        ]
+    }
+
+    #[test]
+    fn test_to_nnf_output_always_satisfies_is_nnf() {
+        for e in nnf_fixtures() {
+            let nnf = to_nnf(e.clone());
+            assert!(is_nnf(&nnf), "{} normalized to {}, which is not in NNF", e, nnf);
+        }
+    }
+
+    #[test]
+    fn test_to_nnf_pushes_negation_through_and_and_flips_quantifier() {
+        let e = !Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(to_nnf(e), Expr::assoc(ASymbol::Or, vec![!Expr::var("a"), !Expr::var("b")]));
+
+        let q = !Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x"));
+        assert_eq!(to_nnf(q), Expr::quantifier(QSymbol::Exists, "x", !apply1("p", "x")));
+    }
+
+    #[test]
+    fn test_is_nnf_rejects_implies_and_bicon() {
+        assert!(!is_nnf(&Expr::implies(Expr::var("a"), Expr::var("b"))));
+        assert!(!is_nnf(&Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b")])));
+        assert!(!is_nnf(&!!Expr::var("a")));
+    }
+
+    #[test]
+    fn test_normalize_implication_eliminates_chained_implications() {
+        // A -> (B -> C)
+        let e = Expr::implies(Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("c")));
+        assert_eq!(
+            normalize_implication(e),
+            Expr::assoc(
+                ASymbol::Or,
+                vec![!Expr::var("a"), Expr::assoc(ASymbol::Or, vec![!Expr::var("b"), Expr::var("c")])]
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_implication_eliminates_implications_under_quantifiers() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::implies(apply1("p", "x"), apply1("q", "x")));
+        assert_eq!(
+            normalize_implication(e),
+            Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::Or, vec![!apply1("p", "x"), apply1("q", "x")]))
+        );
+    }
+
+    #[test]
+    fn test_introduce_implication_reverses_normalize_implication() {
+        let e = Expr::assoc(ASymbol::Or, vec![!Expr::var("a"), Expr::var("b")]);
+        assert_eq!(introduce_implication(e), Expr::implies(Expr::var("a"), Expr::var("b")));
+    }
+
+    #[test]
+    fn test_normalize_and_introduce_implication_round_trip_to_an_equivalent_formula() {
+        let e = Expr::implies(Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("c")));
+        let round_tripped = introduce_implication(normalize_implication(e.clone()));
+        assert_eq!(equivalent(&e, &round_tripped), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_combine_associative_ops_flattens_nested_same_symbol() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), Expr::var("c")],
+        );
+        assert_eq!(combine_associative_ops(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]));
+    }
+
+    #[test]
+    fn test_combine_associative_ops_leaves_different_symbols_nested() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]), Expr::var("c")],
+        );
+        assert_eq!(combine_associative_ops(e.clone()), e);
+    }
+
+    #[test]
+    fn test_to_cnf_distributes_or_over_and_and_flattens() {
+        // (A \/ (B /\ C)) /\ ~(D -> E)
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("c")])]),
+                !Expr::implies(Expr::var("d"), Expr::var("e")),
+            ],
+        );
+        let cnf = to_cnf(e);
+        assert_eq!(
+            cnf,
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                    Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")]),
+                    Expr::var("d"),
+                    !Expr::var("e"),
+                ]
+            )
+        );
+        assert!(is_cnf(&cnf));
+    }
+
+    #[test]
+    fn test_to_cnf_drops_tautological_clause_and_dedupes() {
+        // (P \/ ~P) /\ Q /\ Q
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::Or, vec![Expr::var("p"), !Expr::var("p")]), Expr::var("q"), Expr::var("q")],
+        );
+        assert_eq!(to_cnf(e), Expr::assoc(ASymbol::And, vec![Expr::var("q")]));
+    }
+
+    #[test]
+    fn test_to_cnf_output_always_satisfies_is_cnf() {
+        for e in nnf_fixtures().into_iter().filter(|e| count_quantifiers(e) == 0) {
+            let cnf = to_cnf(e.clone());
+            assert!(is_cnf(&cnf), "{} converted to {}, which is not in CNF", e, cnf);
+        }
+    }
+
+    #[test]
+    fn test_is_cnf_rejects_non_clausal_structure() {
+        assert!(!is_cnf(&Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("c")])]
+        )));
+        assert!(!is_cnf(&Expr::implies(Expr::var("a"), Expr::var("b"))));
+    }
+
+    #[test]
+    fn test_to_dnf_distributes_and_over_or_and_flattens() {
+        // (A /\ (B \/ C)) \/ ~(D /\ E)
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("c")])]),
+                !Expr::assoc(ASymbol::And, vec![Expr::var("d"), Expr::var("e")]),
+            ],
+        );
+        let dnf = to_dnf(e);
+        assert_eq!(
+            dnf,
+            Expr::assoc(
+                ASymbol::Or,
+                vec![
+                    Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]),
+                    Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("c")]),
+                    !Expr::var("d"),
+                    !Expr::var("e"),
+                ]
+            )
+        );
+        assert!(is_dnf(&dnf));
+    }
+
+    #[test]
+    fn test_to_dnf_drops_contradictory_conjuncts_and_dedupes() {
+        // (P /\ ~P) \/ (Q /\ ~P /\ P) collapses entirely, since both conjuncts contain P and ~P
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("p")]),
+                Expr::assoc(ASymbol::And, vec![Expr::var("q"), !Expr::var("p"), Expr::var("p")]),
+            ],
+        );
+        assert_eq!(to_dnf(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_to_dnf_output_always_satisfies_is_dnf() {
+        for e in nnf_fixtures().into_iter().filter(|e| count_quantifiers(e) == 0) {
+            let dnf = to_dnf(e.clone());
+            assert!(is_dnf(&dnf), "{} converted to {}, which is not in DNF", e, dnf);
+        }
+    }
+
+    #[test]
+    fn test_is_dnf_rejects_non_conjunctive_structure() {
+        assert!(!is_dnf(&Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("c")])]
+        )));
+        assert!(!is_dnf(&Expr::implies(Expr::var("a"), Expr::var("b"))));
+    }
+
+    #[test]
+    fn test_normalize_distribution_toward_dnf_distributes_and_over_or() {
+        // (A \/ B) /\ C
+        let e = Expr::assoc(ASymbol::And, vec![Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]), Expr::var("c")]);
+        let normalized = normalize_distribution(e, DistributionDirection::TowardDnf);
+        assert_eq!(
+            normalized,
+            Expr::assoc(
+                ASymbol::Or,
+                vec![
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("a")]),
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("b")]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_distribution_toward_dnf_handles_nary_assoc_binops_and_flattens() {
+        // (A \/ B) /\ C /\ (D \/ E)
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                Expr::var("c"),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("d"), Expr::var("e")]),
+            ],
+        );
+        let normalized = normalize_distribution(e, DistributionDirection::TowardDnf);
+        // Distribution alone nests `Or`s inside `Or`s; `normalize_distribution`
+        // must flatten that via `combine_associative_ops` into one 4-way `Or`
+        // rather than leaving it as nested binary `Or`s.
+        assert_eq!(
+            normalized,
+            Expr::assoc(
+                ASymbol::Or,
+                vec![
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("a"), Expr::var("d")]),
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("a"), Expr::var("e")]),
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("b"), Expr::var("d")]),
+                    Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("b"), Expr::var("e")]),
+                ]
+            )
+        );
+        if let Expr::AssocBinop { symbol: ASymbol::Or, exprs } = &normalized {
+            assert_eq!(exprs.len(), 4, "nested Ors from repeated distribution should flatten into one");
+        } else {
+            panic!("expected a top-level Or, got {}", normalized);
+        }
+    }
+
+    #[test]
+    fn test_normalize_distribution_toward_cnf_matches_to_cnf_distribution_direction() {
+        // A \/ (B /\ C), the mirror image of the toward-DNF case above
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("c")])]);
+        let normalized = normalize_distribution(e, DistributionDirection::TowardCnf);
+        assert_eq!(
+            normalized,
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                    Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")]),
+                ]
+            )
+        );
+    }
+
+    fn eval_prop(e: &Expr, assignment: &HashMap<String, bool>) -> bool {
+        match e {
+            Expr::Contradiction => false,
+            Expr::Tautology => true,
+            Expr::Var { name } => assignment[name],
+            Expr::Unop { symbol: USymbol::Not, operand } => !eval_prop(operand, assignment),
+            Expr::Binop { symbol: BSymbol::Implies, l, r } => !eval_prop(l, assignment) || eval_prop(r, assignment),
+            Expr::AssocBinop { symbol: ASymbol::And, exprs } => exprs.iter().all(|x| eval_prop(x, assignment)),
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs.iter().any(|x| eval_prop(x, assignment)),
+            Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, exprs } => {
+                let vals: Vec<bool> = exprs.iter().map(|x| eval_prop(x, assignment)).collect();
+                vals.windows(2).all(|w| w[0] == w[1])
+            }
+            other => panic!("{} is not a propositional expression", other),
+        }
+    }
+
+    fn eval_literal(l: &Expr, assignment: &HashMap<String, bool>) -> bool {
+        match l {
+            Expr::Var { name } => assignment[name],
+            Expr::Unop { symbol: USymbol::Not, operand } => !eval_literal(operand, assignment),
+            Expr::Tautology => true,
+            Expr::Contradiction => false,
+            other => panic!("{} is not a literal", other),
+        }
+    }
+
+    fn eval_clause(c: &Expr, assignment: &HashMap<String, bool>) -> bool {
+        match c {
+            Expr::AssocBinop { symbol: ASymbol::Or, exprs } => exprs.iter().any(|l| eval_literal(l, assignment)),
+            other => eval_literal(other, assignment),
+        }
+    }
+
+    fn implies_chain(vars: &[&str]) -> Expr {
+        let mut rev = vars.iter().rev();
+        let mut acc = Expr::var(rev.next().unwrap());
+        for v in rev {
+            acc = Expr::implies(Expr::var(v), acc);
+        }
+        acc
+    }
+
+    #[test]
+    fn test_tseitin_clause_count_grows_linearly_with_expression_size() {
+        let (clauses4, _) = tseitin(&implies_chain(&["a", "b", "c", "d"]));
+        let (clauses8, _) = tseitin(&implies_chain(&["a", "b", "c", "d", "e", "f", "g", "h"]));
+        // each Implies node contributes exactly 3 clauses, and leaves contribute none
+        assert_eq!(clauses4.len(), 3 * 3);
+        assert_eq!(clauses8.len(), 3 * 7);
+    }
+
+    #[test]
+    fn test_tseitin_trivial_atom_round_trips_without_extra_clauses() {
+        let (clauses, top) = tseitin(&Expr::var("p"));
+        assert!(clauses.is_empty());
+        assert_eq!(top, "p");
+    }
+
+    #[test]
+    fn test_tseitin_satisfiability_agrees_with_brute_force_on_small_formula() {
+        // (p /\ q) -> r
+        let e = Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]), Expr::var("r"));
+        let (clauses, top) = tseitin(&e);
+
+        let mut all_vars: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+        for c in &clauses {
+            all_vars.extend(freevars(c));
+        }
+        all_vars.insert(top.clone());
+        let all_vars: Vec<String> = all_vars.into_iter().collect();
+
+        let mut free: Vec<String> = freevars(&e).into_iter().collect();
+        free.sort();
+
+        let mut projected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << all_vars.len()) {
+            let assignment: HashMap<String, bool> =
+                all_vars.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if clauses.iter().all(|c| eval_clause(c, &assignment)) && assignment[&top] {
+                projected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        let mut expected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << free.len()) {
+            let assignment: HashMap<String, bool> = free.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if eval_prop(&e, &assignment) {
+                expected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        assert_eq!(projected_sat, expected_sat);
+    }
+
+    #[test]
+    fn test_truth_table_on_tautology_is_all_true() {
+        // p \/ ~p
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("p"), !Expr::var("p")]);
+        let table = truth_table(&e).unwrap();
+        assert_eq!(table.variables, vec!["p".to_owned()]);
+        assert_eq!(table.rows.len(), 2);
+        assert!(table.rows.iter().all(|row| row.result));
+    }
+
+    #[test]
+    fn test_truth_table_on_contingency_lists_every_row() {
+        // p -> q
+        let e = Expr::implies(Expr::var("p"), Expr::var("q"));
+        let table = truth_table(&e).unwrap();
+        assert_eq!(table.variables, vec!["p".to_owned(), "q".to_owned()]);
+        assert_eq!(
+            table.rows,
+            vec![
+                TruthTableRow { assignment: vec![true, true], result: true },
+                TruthTableRow { assignment: vec![true, false], result: false },
+                TruthTableRow { assignment: vec![false, true], result: true },
+                TruthTableRow { assignment: vec![false, false], result: true },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truth_table_n_ary_bicon_true_iff_all_operands_agree() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]);
+        let table = truth_table(&e).unwrap();
+        for row in &table.rows {
+            let all_true = row.assignment.iter().all(|b| *b);
+            let all_false = row.assignment.iter().all(|b| !*b);
+            assert_eq!(row.result, all_true || all_false, "{:?}", row.assignment);
+        }
+    }
+
+    #[test]
+    fn test_truth_table_n_ary_equiv_agrees_with_bicon() {
+        // `Equiv` gets the same "all operands agree" truth-table semantics as `Bicon`.
+        let bicon = Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]);
+        let equiv = Expr::assoc(ASymbol::Equiv, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]);
+        assert_eq!(truth_table(&bicon).unwrap().rows, truth_table(&equiv).unwrap().rows);
+    }
+
+    #[test]
+    fn test_truth_table_rejects_apply_quantifier_and_arithmetic() {
+        let apply = apply1("p", "x");
+        assert_eq!(truth_table(&apply), Err(NonPropositional::UnsupportedSubterm(apply)));
+
+        let quantified = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        assert_eq!(truth_table(&quantified), Err(NonPropositional::UnsupportedSubterm(quantified)));
+
+        let plus = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("y")) };
+        assert_eq!(truth_table(&plus), Err(NonPropositional::UnsupportedSubterm(plus)));
+    }
+
+    #[test]
+    fn test_truth_table_rejects_too_many_variables() {
+        let vars: Vec<Expr> = (0..(MAX_TRUTH_TABLE_VARIABLES + 1)).map(|i| Expr::var(&format!("v{}", i))).collect();
+        let e = Expr::assoc(ASymbol::And, vars);
+        assert_eq!(
+            truth_table(&e),
+            Err(NonPropositional::TooManyVariables { count: MAX_TRUTH_TABLE_VARIABLES + 1, limit: MAX_TRUTH_TABLE_VARIABLES })
+        );
+    }
+
+    #[test]
+    fn test_eval_basic_connectives() {
+        let mut assignment = HashMap::new();
+        assignment.insert("p".to_owned(), true);
+        assignment.insert("q".to_owned(), false);
+
+        assert_eq!(eval(&Expr::var("p"), &assignment), Ok(true));
+        assert_eq!(eval(&!Expr::var("p"), &assignment), Ok(false));
+        assert_eq!(eval(&Expr::implies(Expr::var("p"), Expr::var("q")), &assignment), Ok(false));
+        assert_eq!(eval(&Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]), &assignment), Ok(false));
+        assert_eq!(eval(&Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")]), &assignment), Ok(true));
+    }
+
+    #[test]
+    fn test_eval_reports_missing_variable() {
+        let assignment = HashMap::new();
+        assert_eq!(eval(&Expr::var("p"), &assignment), Err(EvalError::MissingVariable("p".to_owned())));
+    }
+
+    #[test]
+    fn test_eval_reports_unsupported_subterm() {
+        let assignment = HashMap::new();
+        let apply = apply1("p", "x");
+        assert_eq!(eval(&apply, &assignment), Err(EvalError::UnsupportedSubterm(apply)));
+    }
+
+    #[test]
+    fn test_eval_n_ary_bicon_uses_all_equal_not_parity_reading() {
+        // A <-> B <-> C under A=T, B=F, C=F: all-equal says false (not all the
+        // same); the repeated-binary-application parity reading would say true
+        // (an even number, two, of the operands are false).
+        let mut assignment = HashMap::new();
+        assignment.insert("a".to_owned(), true);
+        assignment.insert("b".to_owned(), false);
+        assignment.insert("c".to_owned(), false);
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        assert_eq!(eval(&e, &assignment), Ok(false));
+
+        let all_true = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("a"), Expr::var("a")]);
+        assert_eq!(eval(&all_true, &assignment), Ok(true));
+    }
+
+    #[test]
+    fn test_eval_equiv_agrees_with_bicon() {
+        let mut assignment = HashMap::new();
+        assignment.insert("a".to_owned(), true);
+        assignment.insert("b".to_owned(), false);
+        let bicon = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b")]);
+        let equiv = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(eval(&bicon, &assignment), eval(&equiv, &assignment));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_applies_the_identity_law() {
+        // A === T ==> A
+        let e = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::Tautology]);
+        assert_eq!(normalize_biconditional_constants(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_applies_the_complement_law() {
+        // A === F ==> ~A
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::Contradiction]);
+        assert_eq!(normalize_biconditional_constants(e), !Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_nary_tautology_forces_every_other_operand_true() {
+        // A === T === B ==> A /\ B, not A === B
+        let e = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::Tautology, Expr::var("b")]);
+        assert_eq!(normalize_biconditional_constants(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_nary_contradiction_forces_every_other_operand_false() {
+        // A === F === B ==> ~A /\ ~B
+        let e = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::Contradiction, Expr::var("b")]);
+        assert_eq!(normalize_biconditional_constants(e), Expr::assoc(ASymbol::And, vec![!Expr::var("a"), !Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_tautology_and_contradiction_together_is_unsatisfiable() {
+        let e = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::Tautology, Expr::Contradiction]);
+        assert_eq!(normalize_biconditional_constants(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_leaves_a_chain_with_no_constants_alone() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_biconditional_constants(e.clone()), e);
+    }
+
+    #[test]
+    fn test_normalize_biconditional_constants_is_sound() {
+        for symbol in [ASymbol::Bicon, ASymbol::Equiv] {
+            let e = Expr::assoc(symbol, vec![Expr::var("a"), Expr::Tautology, Expr::var("b")]);
+            let normalized = normalize_biconditional_constants(e.clone());
+            assert_eq!(equivalent(&e, &normalized), Ok(Equivalence::Equivalent));
+        }
+    }
+
+    #[test]
+    fn test_normalize_biconditional_implication_style_pins_the_three_operand_expansion() {
+        // A <-> B <-> C ==> (A -> B) /\ (B -> A) /\ (B -> C) /\ (C -> B)
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        assert_eq!(
+            normalize_biconditional(e, BiconStyle::Implication),
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(ASymbol::And, vec![Expr::implies(Expr::var("a"), Expr::var("b")), Expr::implies(Expr::var("b"), Expr::var("a"))]),
+                    Expr::assoc(ASymbol::And, vec![Expr::implies(Expr::var("b"), Expr::var("c")), Expr::implies(Expr::var("c"), Expr::var("b"))]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_biconditional_disjunction_style_pins_the_three_operand_expansion() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        assert_eq!(
+            normalize_biconditional(e, BiconStyle::Disjunction),
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(
+                        ASymbol::Or,
+                        vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), Expr::assoc(ASymbol::And, vec![!Expr::var("a"), !Expr::var("b")])]
+                    ),
+                    Expr::assoc(
+                        ASymbol::Or,
+                        vec![Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("c")]), Expr::assoc(ASymbol::And, vec![!Expr::var("b"), !Expr::var("c")])]
+                    ),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_biconditional_treats_equiv_the_same_as_bicon() {
+        let bicon = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b")]);
+        let equiv = Expr::assoc(ASymbol::Equiv, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_biconditional(bicon, BiconStyle::Implication), normalize_biconditional(equiv, BiconStyle::Implication));
+    }
+
+    #[test]
+    fn test_normalize_biconditional_is_equivalent_to_the_original_for_both_styles() {
+        for style in [BiconStyle::Implication, BiconStyle::Disjunction] {
+            let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+            let normalized = normalize_biconditional(e.clone(), style);
+            assert_eq!(equivalent(&e, &normalized), Ok(Equivalence::Equivalent));
+        }
+    }
+
+    #[test]
+    fn test_normalize_contraposition_cancels_double_negation_on_both_sides() {
+        // ~A -> ~B  ==>  B -> A
+        let e = Expr::implies(!Expr::var("a"), !Expr::var("b"));
+        assert_eq!(normalize_contraposition(e), Expr::implies(Expr::var("b"), Expr::var("a")));
+    }
+
+    #[test]
+    fn test_normalize_contraposition_fires_on_nested_implications() {
+        // A -> (~B -> ~C)  ==>  A -> (C -> B)
+        let e = Expr::implies(Expr::var("a"), Expr::implies(!Expr::var("b"), !Expr::var("c")));
+        assert_eq!(normalize_contraposition(e), Expr::implies(Expr::var("a"), Expr::implies(Expr::var("c"), Expr::var("b"))));
+    }
+
+    #[test]
+    fn test_normalize_contraposition_fires_under_a_quantifier() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::implies(!apply1("p", "x"), !apply1("q", "x")));
+        assert_eq!(
+            normalize_contraposition(e),
+            Expr::quantifier(QSymbol::Forall, "x", Expr::implies(apply1("q", "x"), apply1("p", "x")))
+        );
+    }
+
+    #[test]
+    fn test_normalize_contraposition_leaves_a_one_sided_negation_alone() {
+        let e = Expr::implies(!Expr::var("a"), Expr::var("b"));
+        assert_eq!(normalize_contraposition(e.clone()), e);
+    }
+
+    #[test]
+    fn test_normalize_contraposition_canonical_direction_is_idempotent() {
+        let e = Expr::implies(!Expr::var("a"), !Expr::var("b"));
+        let once = normalize_contraposition(e);
+        let twice = normalize_contraposition(once.clone());
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_normalize_exportation_curries_a_three_conjunct_antecedent() {
+        // (A /\ B /\ C) -> D  ==>  A -> (B -> (C -> D))
+        let e = Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]), Expr::var("d"));
+        let curried = normalize_exportation(e.clone(), ExportationDirection::Curry);
+        assert_eq!(
+            curried,
+            Expr::implies(Expr::var("a"), Expr::implies(Expr::var("b"), Expr::implies(Expr::var("c"), Expr::var("d"))))
+        );
+        assert_eq!(equivalent(&e, &curried), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_normalize_exportation_uncurries_and_flattens_into_one_nary_conjunction() {
+        // A -> (B -> (C -> D))  ==>  (A /\ B /\ C) -> D
+        let e = Expr::implies(Expr::var("a"), Expr::implies(Expr::var("b"), Expr::implies(Expr::var("c"), Expr::var("d"))));
+        let uncurried = normalize_exportation(e.clone(), ExportationDirection::Uncurry);
+        assert_eq!(
+            uncurried,
+            Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]), Expr::var("d"))
+        );
+        assert_eq!(equivalent(&e, &uncurried), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_normalize_exportation_round_trips_through_both_directions() {
+        let e = Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]), Expr::var("d"));
+        let curried = normalize_exportation(e.clone(), ExportationDirection::Curry);
+        let round_tripped = normalize_exportation(curried, ExportationDirection::Uncurry);
+        assert_eq!(round_tripped, e);
+    }
+
+    #[test]
+    fn test_normalize_idempotence_collapses_adjacent_duplicates() {
+        // A /\ A /\ B
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_idempotence(e, false), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_idempotence_collapses_non_adjacent_duplicates_preserving_order() {
+        // A /\ B /\ A
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("a")]);
+        assert_eq!(normalize_idempotence(e, false), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_idempotence_dedupes_compound_duplicate_operands() {
+        // (A \/ B) /\ (A \/ B) /\ C
+        let or_ab = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]);
+        let e = Expr::assoc(ASymbol::And, vec![or_ab.clone(), or_ab.clone(), Expr::var("c")]);
+        assert_eq!(normalize_idempotence(e, false), Expr::assoc(ASymbol::And, vec![or_ab, Expr::var("c")]));
+    }
+
+    #[test]
+    fn test_normalize_idempotence_collapses_to_a_single_operand() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a")]);
+        assert_eq!(normalize_idempotence(e, false), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_idempotence_leaves_non_commutative_duplicates_alone() {
+        let e = Expr::implies(Expr::var("a"), Expr::var("a"));
+        assert_eq!(normalize_idempotence(e.clone(), false), e);
+    }
+
+    #[test]
+    fn test_normalize_idempotence_does_not_merge_structurally_distinct_but_logically_equivalent_operands() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), !!Expr::var("a")]);
+        assert_eq!(normalize_idempotence(e.clone(), false), e);
+    }
+
+    #[test]
+    fn test_normalize_idempotence_collapses_alpha_equivalent_quantified_duplicates_only_under_the_flag() {
+        // (forall x. P(x)) /\ (forall y. P(y))
+        let forall_x = Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x"));
+        let forall_y = Expr::quantifier(QSymbol::Forall, "y", apply1("p", "y"));
+        let e = Expr::assoc(ASymbol::And, vec![forall_x.clone(), forall_y.clone()]);
+        assert_eq!(normalize_idempotence(e.clone(), false), e);
+        assert_eq!(normalize_idempotence(e, true), forall_x);
+    }
+
+    #[test]
+    fn test_normalize_assoc_arity_unwraps_a_singleton() {
+        let e = Expr::AssocBinop { symbol: ASymbol::And, exprs: vec![Expr::var("a")] };
+        assert_eq!(normalize_assoc_arity(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_assoc_arity_collapses_empty_and_to_tautology_and_empty_or_to_contradiction() {
+        let empty_and = Expr::AssocBinop { symbol: ASymbol::And, exprs: vec![] };
+        let empty_or = Expr::AssocBinop { symbol: ASymbol::Or, exprs: vec![] };
+        assert_eq!(normalize_assoc_arity(empty_and), Expr::Tautology);
+        assert_eq!(normalize_assoc_arity(empty_or), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_assoc_arity_collapses_empty_bicon_and_equiv_to_tautology_and_empty_xor_to_contradiction() {
+        // Vacuously "everyone agrees" (Bicon/Equiv) is true; the parity of zero operands (Xor) is false.
+        assert_eq!(normalize_assoc_arity(Expr::AssocBinop { symbol: ASymbol::Bicon, exprs: vec![] }), Expr::Tautology);
+        assert_eq!(normalize_assoc_arity(Expr::AssocBinop { symbol: ASymbol::Equiv, exprs: vec![] }), Expr::Tautology);
+        assert_eq!(normalize_assoc_arity(Expr::AssocBinop { symbol: ASymbol::Xor, exprs: vec![] }), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_assoc_arity_unwraps_a_singleton_nested_inside_a_well_formed_tree() {
+        // b & (singleton And[a]) -- the nested violation is repaired without disturbing the rest.
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::AssocBinop { symbol: ASymbol::And, exprs: vec![Expr::var("a")] }]);
+        assert_eq!(normalize_assoc_arity(e), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("a")]));
+    }
+
+    #[test]
+    fn test_normalize_assoc_arity_leaves_a_well_formed_assoc_binop_alone() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_assoc_arity(e.clone()), e);
+    }
+
+    #[test]
+    fn test_a_constructed_singleton_displays_as_its_bare_operand_once_normalized() {
+        let singleton = Expr::AssocBinop { symbol: ASymbol::Or, exprs: vec![Expr::var("a")] };
+        assert_eq!(normalize_assoc_arity(singleton).to_string(), "a");
+    }
+
+    #[test]
+    fn test_a_constructed_singleton_unifies_with_its_bare_operand_once_normalized() {
+        let singleton = Expr::AssocBinop { symbol: ASymbol::Or, exprs: vec![Expr::var("a")] };
+        let bare = Expr::var("a");
+        assert!(unify(&singleton, &bare).is_none(), "an un-normalized singleton does not unify against its bare operand");
+        assert_eq!(unify(&normalize_assoc_arity(singleton), &bare), Some(Substitution::new()));
+    }
+
+    #[test]
+    fn test_canonicalize_unwraps_a_constructed_singleton() {
+        let singleton = Expr::AssocBinop { symbol: ASymbol::Equiv, exprs: vec![Expr::var("a")] };
+        assert_eq!(canonicalize(singleton), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_canonicalize_collapses_a_constructed_empty_assoc_binop_to_its_identity() {
+        let empty_or = Expr::AssocBinop { symbol: ASymbol::Or, exprs: vec![] };
+        assert_eq!(canonicalize(empty_or), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_idempotence_collapses_a_constructed_empty_assoc_binop_to_its_identity() {
+        // An empty operand list can't arise from deduping a real input, but nothing
+        // stops a caller from handing one to normalize_idempotence directly.
+        let empty_and = Expr::AssocBinop { symbol: ASymbol::And, exprs: vec![] };
+        assert_eq!(normalize_idempotence(empty_and, false), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_validate_accepts_a_well_formed_tree() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        validate(&e);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn test_validate_flags_a_constructed_singleton_in_debug_builds() {
+        let e = Expr::AssocBinop { symbol: ASymbol::And, exprs: vec![Expr::var("a")] };
+        validate(&e);
+    }
+
+    #[test]
+    fn test_normalize_complement_collapses_a_non_adjacent_pair_in_a_conjunction() {
+        // A /\ B /\ ~A
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), !Expr::var("a")]);
+        assert_eq!(normalize_complement(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_complement_collapses_a_disjunction_dually_to_tautology() {
+        // A \/ B \/ ~A
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b"), !Expr::var("a")]);
+        assert_eq!(normalize_complement(e), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_normalize_complement_collapses_with_multiple_complementary_pairs() {
+        // A /\ ~A /\ B /\ ~B
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), !Expr::var("a"), Expr::var("b"), !Expr::var("b")]);
+        assert_eq!(normalize_complement(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_complement_treats_a_negation_and_its_double_negation_as_complementary() {
+        // ~B /\ ~~B /\ A
+        let e = Expr::assoc(ASymbol::And, vec![!Expr::var("b"), !!Expr::var("b"), Expr::var("a")]);
+        assert_eq!(normalize_complement(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_complement_leaves_a_conjunction_with_no_pair_alone() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        assert_eq!(normalize_complement(e.clone()), e);
+    }
+
+    #[test]
+    fn test_normalize_complement_treats_a_double_negation_and_a_single_negation_as_complementary() {
+        // ~~A /\ ~A -- even (2) vs odd (1) negations around the same core A
+        let e = Expr::assoc(ASymbol::And, vec![!!Expr::var("a"), !Expr::var("a")]);
+        assert_eq!(normalize_complement(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_complement_collapses_with_several_stacked_negations_on_both_sides() {
+        // ~A \/ ~~~~~A -- 1 (odd) vs 5 (odd) negations both reduce to "~A" in effect, so
+        // this pair is NOT complementary (they're the same literal); exercise it alongside
+        // a genuinely complementary high-count pair in the same operand list.
+        let e = Expr::assoc(ASymbol::Or, vec![!Expr::var("a"), !!!!!Expr::var("a"), !!!!Expr::var("b"), !Expr::var("b")]);
+        assert_eq!(normalize_complement(e), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_normalize_complement_does_not_confuse_a_pair_that_only_differs_in_an_inner_subterm() {
+        // ~~A /\ ~B -- B's core differs from A's core, so no amount of stripped negation
+        // makes this pair complementary.
+        let e = Expr::assoc(ASymbol::And, vec![!!Expr::var("a"), !Expr::var("b")]);
+        assert_eq!(normalize_complement(e.clone()), e);
+    }
+
+    #[test]
+    fn test_complements_agrees_on_parity_regardless_of_how_many_not_pairs_wrap_either_side() {
+        let a = Expr::var("a");
+        assert!(complements(&a, &!a.clone()), "A and ~A: odd number of negations differs from even (0)");
+        assert!(complements(&!!a.clone(), &!a.clone()), "~~A and ~A: even (2) vs odd (1)");
+        assert!(complements(&!a.clone(), &!!!!a.clone()), "~A and ~~~~A: odd (1) vs even (4)");
+        assert!(!complements(&a, &!!a.clone()), "A and ~~A are the same literal (both even), not complementary");
+        assert!(!complements(&!a.clone(), &!!!a.clone()), "~A and ~~~A are the same literal (both odd), not complementary");
+        assert!(!complements(&a, &Expr::var("b")), "different cores can never be complementary");
+    }
+
+    #[test]
+    fn test_canonicalize_collapses_an_and_with_a_complementary_pair_hidden_behind_double_negation() {
+        // A /\ ~~~A -- canonicalize's own complement fold should see past the ~~ pair too
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), !!!Expr::var("a")]);
+        assert_eq!(canonicalize(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_canonical_cmp_orders_atoms_before_negated_atoms_before_compounds() {
+        let atom = Expr::var("z");
+        let negated_atom = !Expr::var("a");
+        let compound = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(canonical_cmp(&atom, &negated_atom), Ordering::Less);
+        assert_eq!(canonical_cmp(&negated_atom, &compound), Ordering::Less);
+        assert_eq!(canonical_cmp(&atom, &compound), Ordering::Less);
+    }
+
+    #[test]
+    fn test_canonical_cmp_orders_atoms_by_name() {
+        assert_eq!(canonical_cmp(&Expr::var("a"), &Expr::var("b")), Ordering::Less);
+        assert_eq!(canonical_cmp(&Expr::var("b"), &Expr::var("a")), Ordering::Greater);
+        assert_eq!(canonical_cmp(&Expr::var("a"), &Expr::var("a")), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_canonical_cmp_orders_negated_atoms_by_the_name_of_what_they_negate() {
+        assert_eq!(canonical_cmp(&!Expr::var("a"), &!Expr::var("b")), Ordering::Less);
+        assert_eq!(canonical_cmp(&!Expr::var("z"), &!Expr::var("a")), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_canonical_cmp_orders_compounds_by_size_before_shape() {
+        let smaller = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]);
+        let larger = Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y"), Expr::var("z")]);
+        assert_eq!(canonical_cmp(&smaller, &larger), Ordering::Less);
+    }
+
+    #[test]
+    fn test_canonical_cmp_is_a_total_order_that_sorts_a_mixed_operand_list_as_documented() {
+        // A mix of atoms, negated atoms, and compounds of varying size: the documented
+        // order is atoms (by name), then negated atoms (by name), then compounds (by size).
+        let mut operands = vec![
+            Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]),
+            !Expr::var("y"),
+            Expr::var("b"),
+            Expr::assoc(ASymbol::Or, vec![Expr::var("m"), Expr::var("n")]),
+            Expr::var("a"),
+            !Expr::var("x"),
+        ];
+        operands.sort_by(canonical_cmp);
+        assert_eq!(
+            operands,
+            vec![
+                Expr::var("a"),
+                Expr::var("b"),
+                !Expr::var("x"),
+                !Expr::var("y"),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("m"), Expr::var("n")]),
+                Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_canonical_cmp_is_consistent_with_canonicalize_resorting_is_a_no_op() {
+        // c & a & b -- canonicalize sorts this once; sorting the already-sorted
+        // operand list again must not move anything.
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("a"), Expr::var("b")]);
+        let sorted_once = canonicalize(e);
+        let sorted_twice = canonicalize(sorted_once.clone());
+        assert_eq!(sorted_once, sorted_twice);
+    }
+
+    #[test]
+    fn test_normalize_identity_removes_tautologies_from_any_position_in_a_conjunction() {
+        // A /\ T /\ B /\ T
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::Tautology, Expr::var("b"), Expr::Tautology]);
+        assert_eq!(normalize_identity(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_identity_removes_contradictions_from_a_disjunction_and_unwraps_a_single_survivor() {
+        // F \/ A \/ F
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::Contradiction, Expr::var("a"), Expr::Contradiction]);
+        assert_eq!(normalize_identity(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_identity_an_all_tautology_conjunction_collapses_to_tautology() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::Tautology, Expr::Tautology]);
+        assert_eq!(normalize_identity(e), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_normalize_annihilation_collapses_a_conjunction_with_a_contradiction_in_any_position() {
+        // A /\ B /\ F
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::Contradiction]);
+        assert_eq!(normalize_annihilation(e), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_normalize_annihilation_collapses_a_disjunction_with_a_tautology_in_any_position() {
+        // T \/ A \/ B
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::Tautology, Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_annihilation(e), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_normalize_absorption_removes_an_or_operand_absorbed_by_a_sibling_conjunct() {
+        // A /\ B /\ (A \/ C)
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), Expr::var("b"), Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")])],
+        );
+        assert_eq!(normalize_absorption(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_absorption_removes_an_and_operand_absorbed_by_a_sibling_disjunct_dually() {
+        // A \/ (C /\ D /\ A)
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("d"), Expr::var("a")])],
+        );
+        assert_eq!(normalize_absorption(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_absorption_checks_every_sibling_not_just_the_first() {
+        // B /\ A /\ (A \/ C) -- the absorbing operand A is not the first sibling
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("b"), Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")])],
+        );
+        assert_eq!(normalize_absorption(e), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("a")]));
+    }
+
+    #[test]
+    fn test_normalize_absorption_combines_with_idempotence_to_fully_simplify() {
+        // A /\ A /\ (A \/ C) -- absorption drops the Or, then idempotence drops the duplicate A
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")])],
+        );
+        let absorbed = normalize_absorption(e);
+        assert_eq!(normalize_idempotence(absorbed, false), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_remove_subsumed_drops_a_clause_subsumed_by_a_shorter_sibling() {
+        // (A \/ B) /\ (A \/ B \/ C) -- the second conjunct is subsumed by the first
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]),
+            ],
+        );
+        assert_eq!(remove_subsumed(e), Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_remove_subsumed_keeps_two_clauses_that_dont_subsume_each_other() {
+        // (A \/ B) /\ (A \/ C) -- neither disjunct set contains the other
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c")]),
+            ],
+        );
+        assert_eq!(remove_subsumed(e.clone()), e);
+    }
+
+    #[test]
+    fn test_remove_subsumed_lets_a_bare_literal_subsume_a_clause_containing_it() {
+        // A /\ (A \/ B \/ C) -- this is generalized absorption: a one-literal clause
+        // subsumes any clause whose disjunct set contains that literal.
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")])],
+        );
+        assert_eq!(remove_subsumed(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_remove_subsumed_is_order_independent_after_canonicalize() {
+        // (B \/ A) /\ (A \/ C \/ B) -- same pair as the first test, reordered and
+        // with the subsuming clause's disjuncts in a different order too.
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("a")]),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("c"), Expr::var("b")]),
+            ],
+        );
+        assert_eq!(remove_subsumed(e), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("a")]));
+    }
+
+    #[test]
+    fn test_remove_subsumed_dually_drops_an_implicant_in_a_dnf_example() {
+        // (A /\ B) \/ (A /\ B /\ C) -- dual case: an Or of Ands, second implicant subsumed
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]),
+                Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]),
+            ],
+        );
+        assert_eq!(remove_subsumed(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_double_negation_collapses_a_single_pair() {
+        let e = !!Expr::var("a");
+        assert_eq!(normalize_double_negation(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_double_negation_collapses_a_chain_to_a_fixpoint() {
+        let e = !!(!!Expr::var("a"));
+        assert_eq!(normalize_double_negation(e), Expr::var("a"));
+    }
+
+    #[test]
+    fn test_normalize_double_negation_leaves_a_single_negation_alone() {
+        let e = !Expr::var("a");
+        assert_eq!(normalize_double_negation(e.clone()), e);
+    }
+
+    #[test]
+    fn test_simplify_reduces_the_textbook_example_to_a_single_variable() {
+        // (A /\ T /\ (B \/ ~B)) \/ _|_  ->  A
+        let a = Expr::var("a");
+        let b = Expr::var("b");
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::assoc(ASymbol::And, vec![a.clone(), Expr::Tautology, Expr::assoc(ASymbol::Or, vec![b.clone(), !b])]),
+                Expr::Contradiction,
+            ],
+        );
+        let result = simplify(e);
+        assert_eq!(result.expr, a);
+        assert!(!result.hit_iteration_limit);
+    }
+
+    #[test]
+    fn test_simplify_interleaves_absorption_double_negation_and_idempotence() {
+        // A /\ (A \/ B) /\ ~~A  -- absorption drops the Or, double negation
+        // collapses ~~A, and idempotence then dedupes the two remaining A's.
+        let a = Expr::var("a");
+        let b = Expr::var("b");
+        let e = Expr::assoc(ASymbol::And, vec![a.clone(), Expr::assoc(ASymbol::Or, vec![a.clone(), b]), !!a.clone()]);
+        let result = simplify(e);
+        assert_eq!(result.expr, a);
+        assert!(!result.hit_iteration_limit);
+    }
+
+    #[test]
+    fn test_simplify_is_idempotent() {
+        let a = Expr::var("a");
+        let b = Expr::var("b");
+        let e = Expr::assoc(ASymbol::And, vec![a.clone(), Expr::assoc(ASymbol::Or, vec![a.clone(), b]), !!a]);
+        let once = simplify(e);
+        let twice = simplify(once.expr.clone());
+        assert_eq!(twice.expr, once.expr);
+    }
+
+    #[test]
+    fn test_simplify_leaves_an_already_simplest_expression_unchanged() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        let result = simplify(e.clone());
+        assert_eq!(result.expr, e);
+        assert!(!result.hit_iteration_limit);
+    }
+
+    #[test]
+    fn test_normalize_quantifier_demorgans_resolves_an_alternating_depth_three_prefix() {
+        // ~(forall x, exists y, forall z, P(x,y,z))
+        // ==> exists x, forall y, exists z, ~P(x,y,z)
+        let p = |x: &str, y: &str, z: &str| Expr::Apply {
+            func: Box::new(Expr::var("p")),
+            args: vec![Expr::var(x), Expr::var(y), Expr::var(z)],
+        };
+        let e = !Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Exists, "y", Expr::quantifier(QSymbol::Forall, "z", p("x", "y", "z"))),
+        );
+        let expected = Expr::quantifier(
+            QSymbol::Exists,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Exists, "z", !p("x", "y", "z"))),
+        );
+        assert_eq!(normalize_quantifier_demorgans(e), expected);
+    }
+
+    #[test]
+    fn test_normalize_quantifier_demorgans_resolves_a_negation_buried_in_a_conjunction() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), !Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::var("b")],
+        );
+        let expected = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("a"), Expr::quantifier(QSymbol::Exists, "x", !apply1("p", "x")), Expr::var("b")],
+        );
+        assert_eq!(normalize_quantifier_demorgans(e), expected);
+    }
+
+    #[test]
+    fn test_normalize_quantifier_demorgans_cancels_a_double_negation_created_along_the_way() {
+        // ~(forall x, ~P(x)) ==> exists x, P(x), not exists x, ~~P(x)
+        let e = !Expr::quantifier(QSymbol::Forall, "x", !apply1("p", "x"));
+        assert_eq!(normalize_quantifier_demorgans(e), Expr::quantifier(QSymbol::Exists, "x", apply1("p", "x")));
+    }
+
+    #[test]
+    fn test_normalize_quantifier_demorgans_leaves_negated_and_or_alone() {
+        let e = !Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_quantifier_demorgans(e.clone()), e);
+    }
+
+    #[test]
+    fn test_sort_quantifier_blocks_canonicalizes_a_two_variable_swap_identically() {
+        let p = |x: &str, y: &str| Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var(x), Expr::var(y)] };
+        let written_x_first = Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Forall, "y", p("x", "y")));
+        let written_y_first = Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Forall, "x", p("x", "y")));
+        assert_eq!(sort_quantifier_blocks(written_x_first.clone()), written_x_first);
+        assert_eq!(sort_quantifier_blocks(written_y_first), written_x_first);
+    }
+
+    #[test]
+    fn test_sort_quantifier_blocks_sorts_each_block_of_a_forall_exists_alternation_independently() {
+        let q = |names: &[&str]| Expr::Apply { func: Box::new(Expr::var("q")), args: names.iter().map(|n| Expr::var(n)).collect() };
+        // forall y forall x exists w exists z, Q(x,y,z,w)
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "y",
+            Expr::quantifier(
+                QSymbol::Forall,
+                "x",
+                Expr::quantifier(QSymbol::Exists, "w", Expr::quantifier(QSymbol::Exists, "z", q(&["x", "y", "z", "w"]))),
+            ),
+        );
+        // each block reordered to match the matrix's left-to-right use, but
+        // the forall/exists boundary between them is never crossed
+        let expected = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Forall,
+                "y",
+                Expr::quantifier(QSymbol::Exists, "z", Expr::quantifier(QSymbol::Exists, "w", q(&["x", "y", "z", "w"]))),
+            ),
+        );
+        assert_eq!(sort_quantifier_blocks(e), expected);
+    }
+
+    #[test]
+    fn test_sort_quantifier_blocks_leaves_a_mixed_forall_exists_prefix_untouched() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Exists, "y", apply1("p", "x")));
+        assert_eq!(sort_quantifier_blocks(e.clone()), e);
+    }
+
+    #[test]
+    fn test_sort_quantifier_blocks_is_alpha_equal_to_its_input() {
+        let p = |x: &str, y: &str| Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var(x), Expr::var(y)] };
+        let inputs = vec![
+            // already in canonical order, so sorting is a no-op
+            Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Forall, "y", p("x", "y"))),
+            // a shadowed `forall x forall x, Q(x)`: the outer binder is
+            // entirely vacuous, so renaming it apart must not also displace
+            // it, or the result would stop being alpha-equal to the input
+            Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Forall, "x", apply1("q", "x"))),
+        ];
+        for e in inputs {
+            let sorted = sort_quantifier_blocks(e.clone());
+            assert!(alpha_equal(&e, &sorted), "{} should be alpha-equal to {}", e, sorted);
+        }
+    }
+
+    #[test]
+    fn test_normalize_demorgans_pushes_a_negation_through_and() {
+        let e = !Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_demorgans(e), Expr::assoc(ASymbol::Or, vec![!Expr::var("a"), !Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_demorgans_pushes_a_negation_through_or_dually() {
+        let e = !Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(normalize_demorgans(e), Expr::assoc(ASymbol::And, vec![!Expr::var("a"), !Expr::var("b")]));
+    }
+
+    #[test]
+    fn test_normalize_demorgans_cancels_double_negation_and_negated_constants_on_the_fly() {
+        assert_eq!(normalize_demorgans(!!Expr::var("p")), Expr::var("p"));
+        assert_eq!(normalize_demorgans(!Expr::Tautology), Expr::Contradiction);
+        assert_eq!(normalize_demorgans(!Expr::Contradiction), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_normalize_demorgans_leaves_negated_quantifiers_alone() {
+        let e = !Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x"));
+        assert_eq!(normalize_demorgans(e.clone()), e);
+    }
+
+    #[test]
+    fn test_normalize_demorgans_resolves_a_six_deep_alternation_in_one_call() {
+        // A chain of six alternating `~(... & x_i)` / `~(... | x_i)` layers
+        // around a single innermost `And`, all pushed down to NNF (every
+        // `Not` sitting directly on an atom) in one `normalize_demorgans`
+        // call, with no `~~` litter left over for a second pass to clean up.
+        let mut e = Expr::var("a");
+        for i in 0..6 {
+            let sibling = Expr::var(&format!("x{}", i));
+            e = if i % 2 == 0 { !Expr::assoc(ASymbol::And, vec![e, sibling]) } else { !Expr::assoc(ASymbol::Or, vec![e, sibling]) };
+        }
+        let original = e.clone();
+        let result = normalize_demorgans(e);
+        assert!(is_nnf(&result), "{} should be in NNF", result);
+        assert_eq!(equivalent(&original, &result), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_split_forall_over_and() {
+        // forall x, (P(x) /\ Q(x)) ==> (forall x, P(x)) /\ (forall x, Q(x))
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "x")]));
+        let expected = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Forall, "x", apply1("q", "x"))],
+        );
+        assert_eq!(distribute_quantifiers(e, QuantifierDistributionDirection::Split), expected);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_split_exists_over_or_is_nary() {
+        // exists x, (P(x) \/ Q(x) \/ R(x)) ==> (exists x,P(x)) \/ (exists x,Q(x)) \/ (exists x,R(x))
+        let e = Expr::quantifier(
+            QSymbol::Exists,
+            "x",
+            Expr::assoc(ASymbol::Or, vec![apply1("p", "x"), apply1("q", "x"), apply1("r", "x")]),
+        );
+        let expected = Expr::assoc(
+            ASymbol::Or,
+            vec![
+                Expr::quantifier(QSymbol::Exists, "x", apply1("p", "x")),
+                Expr::quantifier(QSymbol::Exists, "x", apply1("q", "x")),
+                Expr::quantifier(QSymbol::Exists, "x", apply1("r", "x")),
+            ],
+        );
+        assert_eq!(distribute_quantifiers(e, QuantifierDistributionDirection::Split), expected);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_split_drops_the_quantifier_from_an_operand_that_does_not_mention_the_bound_variable() {
+        // forall x, (P(x) /\ Q(y)) ==> (forall x, P(x)) /\ Q(y), not (forall x, P(x)) /\ (forall x, Q(y))
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "y")]));
+        let expected = Expr::assoc(ASymbol::And, vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), apply1("q", "y")]);
+        assert_eq!(distribute_quantifiers(e, QuantifierDistributionDirection::Split), expected);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_split_leaves_the_unsound_shapes_untouched() {
+        let forall_or = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::Or, vec![apply1("p", "x"), apply1("q", "x")]));
+        let exists_and = Expr::quantifier(QSymbol::Exists, "x", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "x")]));
+        assert_eq!(distribute_quantifiers(forall_or.clone(), QuantifierDistributionDirection::Split), forall_or);
+        assert_eq!(distribute_quantifiers(exists_and.clone(), QuantifierDistributionDirection::Split), exists_and);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_merge_is_the_reverse_of_split() {
+        let merged = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![apply1("p", "x"), apply1("q", "x")]));
+        let split = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Forall, "x", apply1("q", "x"))],
+        );
+        assert_eq!(distribute_quantifiers(split, QuantifierDistributionDirection::Merge), merged);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_merge_leaves_the_unsound_shapes_untouched() {
+        // (forall x,P(x)) \/ (forall x,Q(x)) and (exists x,P(x)) /\ (exists x,Q(x))
+        // must not be merged under their binder: Forall only distributes over
+        // And, Exists only over Or.
+        let forall_or = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Forall, "x", apply1("q", "x"))],
+        );
+        let exists_and = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Exists, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Exists, "x", apply1("q", "x"))],
+        );
+        assert_eq!(distribute_quantifiers(forall_or.clone(), QuantifierDistributionDirection::Merge), forall_or);
+        assert_eq!(distribute_quantifiers(exists_and.clone(), QuantifierDistributionDirection::Merge), exists_and);
+    }
+
+    #[test]
+    fn test_distribute_quantifiers_merge_requires_every_operand_to_share_the_same_bound_name() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x")), Expr::quantifier(QSymbol::Forall, "y", apply1("q", "y"))],
+        );
+        assert_eq!(distribute_quantifiers(e.clone(), QuantifierDistributionDirection::Merge), e);
+    }
+
+    #[test]
+    fn test_flatten_arithmetic_associativity_only_difference() {
+        // (a + b) + c vs a + (b + c)
+        let left_nested = Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) }),
+            r: Box::new(Expr::var("c")),
+        };
+        let right_nested = Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(Expr::var("a")),
+            r: Box::new(Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("b")), r: Box::new(Expr::var("c")) }),
+        };
+        assert_eq!(flatten_arithmetic(left_nested), flatten_arithmetic(right_nested));
+    }
+
+    #[test]
+    fn test_flatten_arithmetic_commutativity_only_difference() {
+        // a + (c + b) vs a + (b + c)
+        let ab = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        let first = Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(Expr::var("a")),
+            r: Box::new(Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("c")), r: Box::new(Expr::var("b")) }),
+        };
+        let second = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(ab), r: Box::new(Expr::var("c")) };
+        assert_eq!(flatten_arithmetic(first), flatten_arithmetic(second));
+    }
+
+    #[test]
+    fn test_flatten_arithmetic_only_merges_like_symbols() {
+        // (a * b) + c should flatten to a canonical form but must not merge the `*` into the `+` chain.
+        let e = Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(Expr::Binop { symbol: BSymbol::Mult, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) }),
+            r: Box::new(Expr::var("c")),
+        };
+        let flattened = flatten_arithmetic(e);
+        match flattened {
+            Expr::Binop { symbol: BSymbol::Plus, l, r } => {
+                // `c` is an atom and `a * b` is compound, so canonical_cmp orders `c` first.
+                assert_eq!(*l, Expr::var("c"));
+                assert!(matches!(*r, Expr::Binop { symbol: BSymbol::Mult, .. }), "the `a * b` subterm must stay a Mult, not get absorbed into the Plus chain");
+            }
+            other => panic!("expected a top-level Plus, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_unflatten_arithmetic_is_the_inverse_of_flattening() {
+        let exprs = vec![Expr::var("a"), Expr::var("b"), Expr::var("c")];
+        let rebuilt = unflatten_arithmetic(BSymbol::Plus, exprs.clone());
+        let mut leaves = Vec::new();
+        flatten_binop_chain(rebuilt, BSymbol::Plus, &mut leaves);
+        assert_eq!(leaves, exprs);
+    }
+
+    #[test]
+    fn test_fold_arithmetic_evaluates_a_numeral_plus() {
+        let e = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("2")), r: Box::new(Expr::var("3")) };
+        assert_eq!(fold_arithmetic(e), Expr::var("5"));
+    }
+
+    #[test]
+    fn test_fold_arithmetic_evaluates_a_numeral_mult() {
+        let e = Expr::Binop { symbol: BSymbol::Mult, l: Box::new(Expr::var("2")), r: Box::new(Expr::var("3")) };
+        assert_eq!(fold_arithmetic(e), Expr::var("6"));
+    }
+
+    #[test]
+    fn test_fold_arithmetic_applies_the_additive_unit_law() {
+        let e = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("0")) };
+        assert_eq!(fold_arithmetic(e), Expr::var("x"));
+    }
+
+    #[test]
+    fn test_fold_arithmetic_applies_the_multiplicative_unit_law() {
+        let e = Expr::Binop { symbol: BSymbol::Mult, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("1")) };
+        assert_eq!(fold_arithmetic(e), Expr::var("x"));
+    }
+
+    #[test]
+    fn test_fold_arithmetic_applies_the_multiplicative_annihilator() {
+        let e = Expr::Binop { symbol: BSymbol::Mult, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("0")) };
+        assert_eq!(fold_arithmetic(e), Expr::var("0"));
+    }
+
+    #[test]
+    fn test_fold_arithmetic_leaves_symbolic_operands_alone() {
+        let e = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("y")) };
+        assert_eq!(fold_arithmetic(e.clone()), e);
+    }
+
+    #[test]
+    fn test_fold_arithmetic_folds_the_numerals_of_a_mixed_symbolic_chain() {
+        // 1 + x + 2 ==> x + 3
+        let e = Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("1")), r: Box::new(Expr::var("x")) }),
+            r: Box::new(Expr::var("2")),
+        };
+        let expected = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("3")) };
+        assert_eq!(fold_arithmetic(e), expected);
+    }
+
+    #[test]
+    fn test_fold_arithmetic_leaves_an_overflowing_sum_unfolded() {
+        let e = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var(&i64::MAX.to_string())), r: Box::new(Expr::var("1")) };
+        assert_eq!(fold_arithmetic(e.clone()), e);
+    }
+
+    #[test]
+    fn test_expand_bicon_chain_two_operand_case_is_the_ordinary_biconditional_definition() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(
+            expand_bicon_chain(e),
+            Expr::assoc(
+                ASymbol::And,
+                vec![Expr::assoc(ASymbol::And, vec![Expr::implies(Expr::var("a"), Expr::var("b")), Expr::implies(Expr::var("b"), Expr::var("a"))])]
+            )
+        );
+    }
+
+    #[test]
+    fn test_expand_bicon_chain_pinned_three_operand_expansion() {
+        // A <-> B <-> C ==> (A <-> B) /\ (B <-> C), each pair expanded
+        assert_eq!(
+            expand_bicon_chain(Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")])),
+            normalize_biconditional(Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]), BiconStyle::Implication)
+        );
+    }
+
+    #[test]
+    fn test_expand_bicon_chain_agrees_with_the_all_operands_equal_reading_on_the_disagreement_case_from_eval() {
+        // The same A=T, B=F, C=F assignment that
+        // `test_eval_n_ary_bicon_uses_all_equal_not_parity_reading` uses to
+        // show the all-equal reading is false (unlike the rejected parity
+        // reading, which would say true): the pairwise-chain reading here
+        // must agree with `eval`'s all-equal reading, not the parity one.
+        let mut assignment = HashMap::new();
+        assignment.insert("a".to_owned(), true);
+        assignment.insert("b".to_owned(), false);
+        assignment.insert("c".to_owned(), false);
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let expanded = expand_bicon_chain(e.clone());
+        assert_eq!(eval(&expanded, &assignment), eval(&e, &assignment));
+        assert_eq!(eval(&expanded, &assignment), Ok(false));
+    }
+
+    #[test]
+    fn test_expand_bicon_chain_is_semantically_equivalent_to_the_original_for_bicon_and_equiv() {
+        for symbol in [ASymbol::Bicon, ASymbol::Equiv] {
+            let e = Expr::assoc(symbol, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+            let expanded = expand_bicon_chain(e.clone());
+            assert_eq!(equivalent(&e, &expanded), Ok(Equivalence::Equivalent));
+        }
+    }
+
+    #[test]
+    fn test_to_nnf_routes_bicon_through_expand_bicon_chain() {
+        let e = Expr::assoc(ASymbol::Bicon, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let nnf = to_nnf(e.clone());
+        assert!(is_nnf(&nnf));
+        assert_eq!(equivalent(&e, &nnf), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_display_xor_uses_ascii_caret() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(e.to_string(), "a ^ b");
+        assert_eq!(format!("{:#}", e), "(a ^ b)");
+    }
+
+    #[test]
+    fn test_eval_xor_two_operands_is_exclusive_or() {
+        let a = Expr::var("a");
+        let b = Expr::var("b");
+        let e = Expr::assoc(ASymbol::Xor, vec![a, b]);
+        for (av, bv, expected) in [(false, false, false), (false, true, true), (true, false, true), (true, true, false)] {
+            let mut assignment = HashMap::new();
+            assignment.insert("a".to_owned(), av);
+            assignment.insert("b".to_owned(), bv);
+            assert_eq!(eval(&e, &assignment), Ok(expected), "a={} b={}", av, bv);
+        }
+    }
+
+    #[test]
+    fn test_eval_xor_three_operands_is_parity() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        for (av, bv, cv, expected) in [
+            (false, false, false, false),
+            (true, false, false, true),
+            (true, true, false, false),
+            (true, true, true, true),
+        ] {
+            let mut assignment = HashMap::new();
+            assignment.insert("a".to_owned(), av);
+            assignment.insert("b".to_owned(), bv);
+            assignment.insert("c".to_owned(), cv);
+            assert_eq!(eval(&e, &assignment), Ok(expected), "a={} b={} c={}", av, bv, cv);
+        }
+    }
+
+    #[test]
+    fn test_eval_xor_agrees_with_truth_table() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let table = truth_table(&e).unwrap();
+        for row in &table.rows {
+            let assignment: HashMap<String, bool> = table.variables.iter().cloned().zip(row.assignment.iter().copied()).collect();
+            assert_eq!(eval(&e, &assignment), Ok(row.result));
+        }
+    }
+
+    #[test]
+    fn test_normalize_xor_two_operand_case_is_the_textbook_definition() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(
+            normalize_xor(e),
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]),
+                    negate(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")])),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_xor_is_semantically_equivalent_to_the_original() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let expanded = normalize_xor(e.clone());
+        assert_eq!(equivalent(&e, &expanded), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_to_nnf_routes_xor_through_normalize_xor() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let nnf = to_nnf(e.clone());
+        assert!(is_nnf(&nnf));
+        assert_eq!(equivalent(&e, &nnf), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_tseitin_xor_satisfiability_agrees_with_brute_force() {
+        let e = Expr::assoc(ASymbol::Xor, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let (clauses, top) = tseitin(&e);
+
+        let mut all_vars: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+        for c in &clauses {
+            all_vars.extend(freevars(c));
+        }
+        all_vars.insert(top.clone());
+        let all_vars: Vec<String> = all_vars.into_iter().collect();
+
+        let mut free: Vec<String> = freevars(&e).into_iter().collect();
+        free.sort();
+
+        let mut projected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << all_vars.len()) {
+            let assignment: HashMap<String, bool> =
+                all_vars.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if clauses.iter().all(|c| eval_clause(c, &assignment)) && assignment[&top] {
+                projected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        let mut expected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << free.len()) {
+            let assignment: HashMap<String, bool> = free.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if eval(&e, &assignment).unwrap() {
+                expected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        assert_eq!(projected_sat, expected_sat);
+    }
+
+    #[test]
+    fn test_display_nand_and_nor_use_ascii_tokens() {
+        let nand = Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        let nor = Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        assert_eq!(nand.to_string(), "(a !& b)");
+        assert_eq!(nor.to_string(), "(a !| b)");
+    }
+
+    #[test]
+    fn test_eval_nand_and_nor_truth_tables() {
+        let a = Expr::var("a");
+        let b = Expr::var("b");
+        let nand = Expr::Binop { symbol: BSymbol::Nand, l: Box::new(a.clone()), r: Box::new(b.clone()) };
+        let nor = Expr::Binop { symbol: BSymbol::Nor, l: Box::new(a), r: Box::new(b) };
+        for (av, bv, nand_expected, nor_expected) in
+            [(false, false, true, true), (false, true, true, false), (true, false, true, false), (true, true, false, false)]
+        {
+            let mut assignment = HashMap::new();
+            assignment.insert("a".to_owned(), av);
+            assignment.insert("b".to_owned(), bv);
+            assert_eq!(eval(&nand, &assignment), Ok(nand_expected), "nand a={} b={}", av, bv);
+            assert_eq!(eval(&nor, &assignment), Ok(nor_expected), "nor a={} b={}", av, bv);
+        }
+    }
+
+    #[test]
+    fn test_eval_nand_nor_agree_with_truth_table() {
+        let e = Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        let table = truth_table(&e).unwrap();
+        for row in &table.rows {
+            let assignment: HashMap<String, bool> = table.variables.iter().cloned().zip(row.assignment.iter().copied()).collect();
+            assert_eq!(eval(&e, &assignment), Ok(row.result));
+        }
+    }
+
+    #[test]
+    fn test_is_commutative_bsymbol_includes_nand_and_nor() {
+        assert!(is_commutative_bsymbol(BSymbol::Nand));
+        assert!(is_commutative_bsymbol(BSymbol::Nor));
+    }
+
+    #[test]
+    fn test_normalize_sheffer_nand_matches_de_morgan_expansion() {
+        let e = Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        assert_eq!(
+            normalize_sheffer(e.clone()),
+            negate(Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]))
+        );
+    }
+
+    #[test]
+    fn test_normalize_sheffer_nor_matches_de_morgan_expansion() {
+        let e = Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+        assert_eq!(
+            normalize_sheffer(e.clone()),
+            negate(Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]))
+        );
+    }
+
+    #[test]
+    fn test_normalize_sheffer_is_semantically_equivalent_to_the_original() {
+        for symbol in [BSymbol::Nand, BSymbol::Nor] {
+            let e = Expr::Binop { symbol, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+            let expanded = normalize_sheffer(e.clone());
+            assert_eq!(equivalent(&e, &expanded), Ok(Equivalence::Equivalent));
+        }
+    }
+
+    #[test]
+    fn test_to_nnf_routes_nand_and_nor_through_normalize_sheffer() {
+        for symbol in [BSymbol::Nand, BSymbol::Nor] {
+            let e = Expr::Binop { symbol, l: Box::new(Expr::var("a")), r: Box::new(Expr::var("b")) };
+            let nnf = to_nnf(e.clone());
+            assert!(is_nnf(&nnf));
+            assert_eq!(equivalent(&e, &nnf), Ok(Equivalence::Equivalent));
+        }
+    }
+
+    #[test]
+    fn test_to_nand_only_contains_no_other_connectives() {
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), !Expr::var("b")]), Expr::var("c")],
+        );
+        let nand_only = to_nand_only(e.clone());
+        let counts = count_connectives(&nand_only);
+        assert_eq!(counts.implies, 0);
+        assert_eq!(counts.plus, 0);
+        assert_eq!(counts.mult, 0);
+        assert_eq!(counts.and, 0);
+        assert_eq!(counts.or, 0);
+        assert_eq!(counts.bicon, 0);
+        assert_eq!(counts.equiv, 0);
+        assert_eq!(counts.xor, 0);
+        assert_eq!(counts.nor, 0);
+        assert!(counts.nand > 0);
+    }
+
+    #[test]
+    fn test_to_nand_only_is_semantically_equivalent_to_the_original() {
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), !Expr::var("b")]), Expr::var("c")],
+        );
+        let nand_only = to_nand_only(e.clone());
+        assert_eq!(equivalent(&e, &nand_only), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_tseitin_nand_satisfiability_agrees_with_brute_force() {
+        let e = Expr::Binop {
+            symbol: BSymbol::Nand,
+            l: Box::new(Expr::var("a")),
+            r: Box::new(Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("b")), r: Box::new(Expr::var("c")) }),
+        };
+        let (clauses, top) = tseitin(&e);
+
+        let mut all_vars: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+        for c in &clauses {
+            all_vars.extend(freevars(c));
+        }
+        all_vars.insert(top.clone());
+        let all_vars: Vec<String> = all_vars.into_iter().collect();
+
+        let mut free: Vec<String> = freevars(&e).into_iter().collect();
+        free.sort();
+
+        let mut projected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << all_vars.len()) {
+            let assignment: HashMap<String, bool> =
+                all_vars.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if clauses.iter().all(|c| eval_clause(c, &assignment)) && assignment[&top] {
+                projected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        let mut expected_sat: std::collections::BTreeSet<Vec<bool>> = std::collections::BTreeSet::new();
+        for mask in 0..(1u32 << free.len()) {
+            let assignment: HashMap<String, bool> = free.iter().enumerate().map(|(i, v)| (v.clone(), (mask >> i) & 1 == 1)).collect();
+            if eval(&e, &assignment).unwrap() {
+                expected_sat.insert(free.iter().map(|v| assignment[v]).collect());
+            }
+        }
+
+        assert_eq!(projected_sat, expected_sat);
+    }
+
+    #[test]
+    fn test_eval_agrees_with_truth_table_on_every_row() {
+        let e = Expr::implies(Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]), Expr::var("r"));
+        let table = truth_table(&e).unwrap();
+        for row in &table.rows {
+            let assignment: HashMap<String, bool> =
+                table.variables.iter().cloned().zip(row.assignment.iter().copied()).collect();
+            assert_eq!(eval(&e, &assignment), Ok(row.result));
+        }
+    }
+
+    #[test]
+    fn test_is_tautology_peirces_law() {
+        let p = Expr::var("p");
+        let q = Expr::var("q");
+        // ((p -> q) -> p) -> p
+        let peirce = Expr::implies(Expr::implies(Expr::implies(p.clone(), q), p.clone()), p);
+        assert_eq!(is_tautology(&peirce), Ok(true));
+    }
+
+    #[test]
+    fn test_is_tautology_distribution_law() {
+        let p = Expr::var("p");
+        let q = Expr::var("q");
+        let r = Expr::var("r");
+        // (p /\ (q \/ r)) <-> ((p /\ q) \/ (p /\ r))
+        let lhs = Expr::assoc(ASymbol::And, vec![p.clone(), Expr::assoc(ASymbol::Or, vec![q.clone(), r.clone()])]);
+        let rhs = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::assoc(ASymbol::And, vec![p.clone(), q]), Expr::assoc(ASymbol::And, vec![p, r])],
+        );
+        assert_eq!(is_tautology(&Expr::assoc(ASymbol::Bicon, vec![lhs, rhs])), Ok(true));
+    }
+
+    #[test]
+    fn test_is_tautology_false_for_contingent_formula_with_verified_countermodel() {
+        let e = Expr::implies(Expr::var("p"), Expr::var("q"));
+        assert_eq!(is_tautology(&e), Ok(false));
+
+        let counterexample = negate(e.clone());
+        let model = satisfying_assignment(&counterexample).unwrap().unwrap();
+        assert_eq!(eval(&counterexample, &model), Ok(true));
+    }
+
+    #[test]
+    fn test_satisfying_assignment_returns_model_verified_by_eval() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("q")]);
+        let model = satisfying_assignment(&e).unwrap().unwrap();
+        assert_eq!(eval(&e, &model), Ok(true));
+    }
+
+    #[test]
+    fn test_satisfying_assignment_none_for_unsatisfiable_formula() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("p"), !Expr::var("p")]);
+        assert_eq!(satisfying_assignment(&e), Ok(None));
+    }
+
+    #[test]
+    fn test_satisfying_assignment_scales_past_brute_force_variable_counts() {
+        // 40 variables: 2^40 assignments would make brute-force enumeration
+        // hang, but unit propagation alone solves a conjunction instantly.
+        let vars: Vec<Expr> = (0..40).map(|i| Expr::var(&format!("v{}", i))).collect();
+        let e = Expr::assoc(ASymbol::And, vars);
+        let model = satisfying_assignment(&e).unwrap().unwrap();
+        assert_eq!(eval(&e, &model), Ok(true));
+    }
+
+    #[test]
+    fn test_satisfying_assignment_rejects_non_propositional() {
+        let apply = apply1("p", "x");
+        assert_eq!(satisfying_assignment(&apply), Err(NonPropositional::UnsupportedSubterm(apply)));
+    }
+
+    #[test]
+    fn test_equivalent_implies_and_its_material_form() {
+        // p -> q  ==  ~p \/ q
+        let a = Expr::implies(Expr::var("p"), Expr::var("q"));
+        let b = Expr::assoc(ASymbol::Or, vec![!Expr::var("p"), Expr::var("q")]);
+        assert_eq!(equivalent(&a, &b), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_equivalent_ignores_associativity_and_commutativity() {
+        let a = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::var("p"), Expr::assoc(ASymbol::And, vec![Expr::var("q"), Expr::var("r")])],
+        );
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("r"), Expr::var("q"), Expr::var("p")]);
+        assert_eq!(equivalent(&a, &b), Ok(Equivalence::Equivalent));
+    }
+
+    #[test]
+    fn test_equivalent_returns_countermodel_on_which_eval_disagrees() {
+        // p -> q is not equivalent to q -> p
+        let a = Expr::implies(Expr::var("p"), Expr::var("q"));
+        let b = Expr::implies(Expr::var("q"), Expr::var("p"));
+        match equivalent(&a, &b).unwrap() {
+            Equivalence::Equivalent => panic!("p -> q and q -> p are not equivalent"),
+            Equivalence::NotEquivalent { countermodel } => {
+                assert_ne!(eval(&a, &countermodel), eval(&b, &countermodel));
+            }
+        }
+    }
+
+    #[test]
+    fn test_equivalent_rejects_non_propositional() {
+        let apply = apply1("p", "x");
+        assert_eq!(equivalent(&apply, &Expr::var("q")), Err(NonPropositional::UnsupportedSubterm(apply)));
+    }
+
+    #[test]
+    fn test_find_countermodel_valid_modus_ponens_has_none() {
+        // p, p -> q |- q
+        let premises = vec![Expr::var("p"), Expr::implies(Expr::var("p"), Expr::var("q"))];
+        let conclusion = Expr::var("q");
+        assert_eq!(find_countermodel(&premises, &conclusion), Ok(None));
+    }
+
+    #[test]
+    fn test_find_countermodel_affirming_the_consequent_has_a_model() {
+        // p -> q, q |/- p
+        let premises = vec![Expr::implies(Expr::var("p"), Expr::var("q")), Expr::var("q")];
+        let conclusion = Expr::var("p");
+        let model = find_countermodel(&premises, &conclusion).unwrap().expect("affirming the consequent is invalid");
+        assert!(premises.iter().all(|p| eval(p, &model) == Ok(true)));
+        assert_eq!(eval(&conclusion, &model), Ok(false));
+    }
+
+    #[test]
+    fn test_find_countermodel_rejects_non_propositional() {
+        let apply = apply1("p", "x");
+        assert_eq!(
+            find_countermodel(std::slice::from_ref(&apply), &Expr::var("q")),
+            Err(NonPropositional::UnsupportedSubterm(apply))
+        );
+    }
+
+    #[test]
+    fn test_check_nnf_reports_path_to_implies() {
+        // a /\ (b -> c)
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("c"))]);
+        assert_eq!(check_nnf(&e), Err(WhyNot { path: ExprPath(vec![1]), reason: "`->` must be eliminated in negation normal form".to_owned() }));
+    }
+
+    #[test]
+    fn test_check_nnf_reports_path_to_double_negation() {
+        // a \/ ~~b
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), !!Expr::var("b")]);
+        assert_eq!(
+            check_nnf(&e),
+            Err(WhyNot { path: ExprPath(vec![1]), reason: "negation must sit directly above an atom".to_owned() })
+        );
+    }
+
+    #[test]
+    fn test_check_nnf_accepts_to_nnf_output() {
+        for e in nnf_fixtures() {
+            assert_eq!(check_nnf(&to_nnf(e)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_check_cnf_reports_path_to_non_literal_clause_operand() {
+        // (a \/ (b /\ c)) /\ d
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("c")])]), Expr::var("d")],
+        );
+        assert_eq!(
+            check_cnf(&e),
+            Err(WhyNot { path: ExprPath(vec![0, 1]), reason: "clause operand is not a literal".to_owned() })
+        );
+    }
+
+    #[test]
+    fn test_check_cnf_accepts_to_cnf_output() {
+        for e in nnf_fixtures().into_iter().filter(|e| count_quantifiers(e) == 0) {
+            assert_eq!(check_cnf(&to_cnf(e)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_check_dnf_reports_path_to_non_literal_conjunct_operand() {
+        // (a /\ (b \/ c)) \/ d
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("c")])]), Expr::var("d")],
+        );
+        assert_eq!(
+            check_dnf(&e),
+            Err(WhyNot { path: ExprPath(vec![0, 1]), reason: "conjunct operand is not a literal".to_owned() })
+        );
+    }
+
+    #[test]
+    fn test_check_dnf_accepts_to_dnf_output() {
+        for e in nnf_fixtures().into_iter().filter(|e| count_quantifiers(e) == 0) {
+            assert_eq!(check_dnf(&to_dnf(e)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_is_prenex_accepts_leading_quantifiers_and_rejects_nested_ones() {
+        // forall x, exists y, p(x, y) is prenex
+        let prenex = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Exists,
+                "y",
+                Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] },
+            ),
+        );
+        assert!(is_prenex(&prenex));
+
+        // forall x, (p(x) /\ exists y, q(y)) is not prenex: the exists is buried in the matrix
+        let not_prenex = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(ASymbol::And, vec![apply1("p", "x"), Expr::quantifier(QSymbol::Exists, "y", apply1("q", "y"))]),
+        );
+        assert_eq!(
+            check_prenex(&not_prenex),
+            Err(WhyNot {
+                path: ExprPath(vec![0, 1]),
+                reason: "quantifier nested inside the matrix; prenex form requires all quantifiers at the front".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_prenex_accepts_quantifier_free_expressions() {
+        assert!(is_prenex(&Expr::assoc(ASymbol::And, vec![Expr::var("a"), !Expr::var("b")])));
+    }
+
+    #[test]
+    fn test_canonicalize_ignores_associativity_and_argument_order() {
+        // a & (b & a)
+        let lhs = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::assoc(ASymbol::And, vec![Expr::var("b"), Expr::var("a")])]);
+        // (a & b) & a
+        let rhs = Expr::assoc(ASymbol::And, vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), Expr::var("a")]);
+        assert_eq!(canonicalize(lhs), canonicalize(rhs));
+    }
+
+    #[test]
+    fn test_canonicalize_collapses_double_negation_and_complement() {
+        assert_eq!(canonicalize(!!Expr::var("a")), Expr::var("a"));
+        // a | ~a | b
+        let e = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), !Expr::var("a"), Expr::var("b")]);
+        assert_eq!(canonicalize(e), Expr::Tautology);
+    }
+
+    #[test]
+    fn test_canonicalize_folds_identity_and_annihilator() {
+        // (a & T) & (b | F)
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![
+                Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::Tautology]),
+                Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::Contradiction]),
+            ],
+        );
+        assert_eq!(canonicalize(e), Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]));
+
+        // a & F collapses to F: the annihilator absorbs the whole conjunction
+        let with_annihilator = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::Contradiction]);
+        assert_eq!(canonicalize(with_annihilator), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_canonicalize_does_not_conflate_implies_with_its_converse() {
+        let forward = Expr::implies(Expr::var("a"), Expr::var("b"));
+        let converse = Expr::implies(Expr::var("b"), Expr::var("a"));
+        assert_ne!(canonicalize(forward), canonicalize(converse));
+    }
+
+    #[test]
+    fn test_canonicalize_is_idempotent() {
+        for e in nnf_fixtures() {
+            let once = canonicalize(e);
+            let twice = canonicalize(once.clone());
+            assert_eq!(once, twice);
+        }
+    }
+
+    #[test]
+    fn test_conjuncts_flattens_nested_and() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), Expr::var("c")],
+        );
+        assert_eq!(conjuncts(&e), vec![&Expr::var("a"), &Expr::var("b"), &Expr::var("c")]);
+    }
+
+    #[test]
+    fn test_conjuncts_of_non_conjunction_is_a_singleton() {
+        let e = Expr::var("a");
+        assert_eq!(conjuncts(&e), vec![&Expr::var("a")]);
+        let or_e = Expr::assoc(ASymbol::Or, vec![Expr::var("a"), Expr::var("b")]);
+        assert_eq!(conjuncts(&or_e), vec![&or_e]);
+    }
+
+    #[test]
+    fn test_disjuncts_flattens_nested_or() {
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![Expr::var("a"), Expr::assoc(ASymbol::Or, vec![Expr::var("b"), Expr::var("c")])],
+        );
+        assert_eq!(disjuncts(&e), vec![&Expr::var("a"), &Expr::var("b"), &Expr::var("c")]);
+    }
+
+    #[test]
+    fn test_from_conjuncts_and_from_disjuncts_handle_singleton_and_empty() {
+        assert_eq!(from_conjuncts(vec![Expr::var("a")]), Expr::var("a"));
+        assert_eq!(from_conjuncts(vec![]), Expr::Tautology);
+        assert_eq!(from_disjuncts(vec![Expr::var("a")]), Expr::var("a"));
+        assert_eq!(from_disjuncts(vec![]), Expr::Contradiction);
+    }
+
+    #[test]
+    fn test_conjuncts_and_from_conjuncts_round_trip() {
+        let e = Expr::assoc(
+            ASymbol::And,
+            vec![Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]), Expr::var("c")],
+        );
+        let flat = from_conjuncts(conjuncts_owned(e.clone()));
+        assert_eq!(flat, Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]));
+        assert_eq!(conjuncts_owned(flat).len(), 3);
+    }
+
+    #[test]
+    fn test_alpha_hash_is_invariant_under_binder_renaming() {
+        let renamings = ["x", "y", "long_name", "q"];
+        let hashes: Vec<u64> = renamings
+            .iter()
+            .map(|name| {
+                alpha_hash(&Expr::quantifier(
+                    QSymbol::Forall,
+                    name,
+                    Expr::assoc(ASymbol::And, vec![Expr::var(name), Expr::quantifier(QSymbol::Exists, "inner", Expr::var("inner"))]),
+                ))
+            })
+            .collect();
+        assert!(hashes.windows(2).all(|w| w[0] == w[1]), "{:?}", hashes);
+    }
+
+    #[test]
+    fn test_alpha_hash_still_distinguishes_free_variable_names() {
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("z")]));
+        let b = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("w")]));
+        assert_ne!(alpha_hash(&a), alpha_hash(&b));
+    }
+
+    #[test]
+    fn test_alpha_hashed_works_as_a_hashmap_key() {
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::var("x"));
+        let b = Expr::quantifier(QSymbol::Forall, "y", Expr::var("y"));
+        let mut map = HashMap::new();
+        map.insert(AlphaHashed(a), "renamed binder");
+        assert_eq!(map.get(&AlphaHashed(b)), Some(&"renamed binder"));
+    }
+
+    #[test]
+    fn test_expr_diff_identical_formulas_is_empty() {
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("c"))]);
+        assert_eq!(expr_diff(&e, &e), vec![]);
+    }
+
+    #[test]
+    fn test_expr_diff_finds_single_deeply_nested_literal() {
+        // a & (b -> c)  vs  a & (b -> d)
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("c"))]);
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::implies(Expr::var("b"), Expr::var("d"))]);
+        assert_eq!(
+            expr_diff(&a, &b),
+            vec![DiffSite { path: ExprPath(vec![1, 1]), a: Expr::var("c"), b: Expr::var("d") }]
+        );
+    }
+
+    #[test]
+    fn test_expr_diff_reports_differing_connective_without_descending() {
+        let a = Expr::implies(Expr::var("p"), Expr::var("q"));
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]);
+        assert_eq!(expr_diff(&a, &b), vec![DiffSite { path: ExprPath(vec![]), a: a.clone(), b: b.clone() }]);
+    }
+
+    #[test]
+    fn test_expr_diff_on_mismatched_length_assoc_binop_trims_common_prefix_and_suffix() {
+        // (a & b & c) vs (a & x & y & c): common prefix [a], common suffix [c]
+        let lhs = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b"), Expr::var("c")]);
+        let rhs = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("x"), Expr::var("y"), Expr::var("c")]);
+        assert_eq!(
+            expr_diff(&lhs, &rhs),
+            vec![DiffSite {
+                path: ExprPath(vec![]),
+                a: Expr::assoc(ASymbol::And, vec![Expr::var("b")]),
+                b: Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_replace_subexpr_counts_overlapping_occurrences() {
+        // (a & b) & (a & b), replace (a & b) with c
+        let ab = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        let e = Expr::assoc(ASymbol::And, vec![ab.clone(), ab.clone()]);
+        let (result, count) = replace_subexpr(e, &ab, &Expr::var("c"), false);
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![Expr::var("c"), Expr::var("c")]));
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_replace_subexpr_does_not_descend_into_inserted_replacement() {
+        // a & b, replace `a` with (a & d): the `a` inside the replacement must survive untouched.
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        let replacement = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("d")]);
+        let (result, count) = replace_subexpr(e, &Expr::var("a"), &replacement, false);
+        assert_eq!(result, Expr::assoc(ASymbol::And, vec![replacement, Expr::var("b")]));
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_replace_subexpr_alpha_mode_changes_the_count_under_quantifiers() {
+        // (forall x, P(x)) | (forall y, P(y)), target = forall x, P(x)
+        let target = Expr::quantifier(QSymbol::Forall, "x", apply1("p", "x"));
+        let e = Expr::assoc(
+            ASymbol::Or,
+            vec![target.clone(), Expr::quantifier(QSymbol::Forall, "y", apply1("p", "y"))],
+        );
+        let (_, structural_count) = replace_subexpr(e.clone(), &target, &Expr::Tautology, false);
+        assert_eq!(structural_count, 1);
+        let (_, alpha_count) = replace_subexpr(e, &target, &Expr::Tautology, true);
+        assert_eq!(alpha_count, 2);
+    }
+
+    #[test]
+    fn test_to_dot_emits_one_node_per_subterm_with_unique_ids() {
+        // a & a: two occurrences of the same variable still get distinct node ids.
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("a")]);
+        let dot = to_dot(&e);
+        assert_eq!(dot.matches("label=").count(), expr_size(&e));
+        assert!(dot.contains("n0 ["));
+        assert!(dot.contains("n1 ["));
+        assert!(dot.contains("n2 ["));
+        assert_eq!(dot.matches(" -> ").count(), 2);
+    }
+
+    #[test]
+    fn test_to_dot_labels_connectives_and_leaves() {
+        let e = Expr::implies(Expr::var("p"), !Expr::var("q"));
+        let dot = to_dot(&e);
+        assert!(dot.contains("label=\"->\""));
+        assert!(dot.contains("label=\"~\""));
+        assert!(dot.contains("label=\"p\""));
+        assert!(dot.contains("label=\"q\""));
+    }
+
+    #[test]
+    fn test_to_dot_with_highlights_only_the_requested_path() {
+        // a & b, highlight just `b` (path [1])
+        let e = Expr::assoc(ASymbol::And, vec![Expr::var("a"), Expr::var("b")]);
+        let mut options = DotOptions::default();
+        options.highlighted.insert(ExprPath(vec![1]));
+        let dot = to_dot_with(&e, &options);
+        assert_eq!(dot.matches(", color=\"red\"").count(), 1);
+        assert!(dot.contains("label=\"b\", color=\"red\""));
+    }
+
+    #[test]
+    fn test_arbitrary_expr_is_always_well_formed() {
+        use super::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(12345);
+        for _ in 0..200 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, true);
+            assert_eq!(check_well_formed(&e), Ok(()), "{} is not well-formed", e);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_is_idempotent_on_random_expressions() {
+        use super::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(54321);
+        for _ in 0..200 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, true);
+            let once = canonicalize(e);
+            let twice = canonicalize(once.clone());
+            assert_eq!(once, twice);
+        }
+    }
+
+    #[test]
+    fn test_to_nnf_output_satisfies_is_nnf_on_random_expressions() {
+        use super::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(99999);
+        for _ in 0..200 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, false);
+            let nnf = to_nnf(e.clone());
+            assert!(is_nnf(&nnf), "{} normalized to {}, which is not in NNF", e, nnf);
+        }
+    }
+
+    #[test]
+    fn test_unify_with_itself_is_always_the_empty_substitution_on_random_expressions() {
+        use super::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(24680);
+        for _ in 0..200 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, true);
+            assert_eq!(unify(&e, &e), Some(Substitution::new()));
+        }
+    }
+
+    #[test]
+    fn test_to_ascii_string_agrees_with_display_and_round_trips_every_connective_and_quantifier() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Exists,
+                "y",
+                Expr::assoc(
+                    ASymbol::Bicon,
+                    vec![
+                        Expr::assoc(
+                            ASymbol::Equiv,
+                            vec![
+                                Expr::assoc(
+                                    ASymbol::Xor,
+                                    vec![
+                                        Expr::implies(
+                                            Expr::assoc(ASymbol::Or, vec![Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]), !Expr::var("x")]),
+                                            Expr::Tautology,
+                                        ),
+                                        Expr::Contradiction,
+                                    ],
+                                ),
+                                Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("y")) },
+                            ],
+                        ),
+                        Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("y")) },
+                    ],
+                ),
+            ),
+        );
+        let ascii = e.to_ascii_string();
+        assert!(ascii.is_ascii(), "{:?} is not ASCII-only", ascii);
+        assert_eq!(ascii, e.to_string());
+        assert_eq!(crate::parser::parse(&ascii).unwrap(), e);
+    }
+
+    #[test]
+    fn test_display_symbol_table_agrees_with_what_the_parser_lexes_back() {
+        for symbol in [BSymbol::Implies, BSymbol::Plus, BSymbol::Mult, BSymbol::Nand, BSymbol::Nor] {
+            let l = Expr::var("x");
+            let r = Expr::var("y");
+            let e = Expr::Binop { symbol, l: Box::new(l), r: Box::new(r) };
+            assert_eq!(crate::parser::parse(&format!("({})", e)).unwrap(), e, "{} did not round-trip", bsymbol_str(symbol));
+        }
+        for symbol in [ASymbol::And, ASymbol::Or, ASymbol::Bicon, ASymbol::Equiv, ASymbol::Xor] {
+            let e = Expr::assoc(symbol, vec![Expr::var("x"), Expr::var("y")]);
+            assert_eq!(crate::parser::parse(&e.to_string()).unwrap(), e, "{} did not round-trip", asymbol_str(symbol));
+        }
+        for symbol in [QSymbol::Forall, QSymbol::Exists] {
+            let e = Expr::quantifier(symbol, "x", Expr::var("x"));
+            assert_eq!(crate::parser::parse(&e.to_string()).unwrap(), e, "{} did not round-trip", qsymbol_str(symbol));
+        }
+    }
+
+    #[test]
+    fn test_to_prenex_output_always_satisfies_is_prenex() {
+        for e in nnf_fixtures().into_iter().filter(|e| !matches!(e, Expr::AssocBinop { symbol: ASymbol::Bicon | ASymbol::Equiv, .. })) {
+            if let Ok(prenex) = to_prenex(e.clone()) {
+                assert!(is_prenex(&prenex), "{} converted to {}, which is not in prenex form", e, prenex);
+            }
+        }
+    }
+}
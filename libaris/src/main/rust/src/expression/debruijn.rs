@@ -0,0 +1,130 @@
+//! A locally-nameless/de Bruijn representation of [`Expr`](super::Expr),
+//! used for canonical hashing and for interfacing with external provers
+//! that expect bound variables to be indices rather than names.
+//!
+//! Two alpha-equivalent `Expr`s always convert to structurally equal
+//! `DeBruijnExpr`s, since binder names are erased entirely and occurrences
+//! are replaced by the number of quantifiers between the occurrence and its
+//! binder.
+
+use super::{gensym, ASymbol, BSymbol, Expr, QSymbol, USymbol};
+
+/// The de Bruijn counterpart of [`Expr`](super::Expr). Quantifiers carry no
+/// name, bound variable occurrences are counted by the number of enclosing
+/// quantifiers between the occurrence and its binder, and free variables
+/// stay named (since there is nothing to index them against).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DeBruijnExpr {
+    Contradiction,
+    Tautology,
+    FreeVar { name: String },
+    BoundVar { index: usize },
+    Apply { func: Box<DeBruijnExpr>, args: Vec<DeBruijnExpr> },
+    Unop { symbol: USymbol, operand: Box<DeBruijnExpr> },
+    Binop { symbol: BSymbol, l: Box<DeBruijnExpr>, r: Box<DeBruijnExpr> },
+    AssocBinop { symbol: ASymbol, exprs: Vec<DeBruijnExpr> },
+    Quantifier { symbol: QSymbol, body: Box<DeBruijnExpr> },
+}
+
+/// Converts `e` to its de Bruijn form. Total: every `Expr` has a de Bruijn
+/// representation, since free variables simply stay named.
+pub fn to_debruijn(e: &Expr) -> DeBruijnExpr {
+    fn go(e: &Expr, env: &mut Vec<String>) -> DeBruijnExpr {
+        match e {
+            Expr::Contradiction => DeBruijnExpr::Contradiction,
+            Expr::Tautology => DeBruijnExpr::Tautology,
+            Expr::Var { name } => match env.iter().rev().position(|bound| bound == name) {
+                Some(index) => DeBruijnExpr::BoundVar { index },
+                None => DeBruijnExpr::FreeVar { name: name.clone() },
+            },
+            Expr::Apply { func, args } => {
+                DeBruijnExpr::Apply { func: Box::new(go(func, env)), args: args.iter().map(|a| go(a, env)).collect() }
+            }
+            Expr::Unop { symbol, operand } => {
+                DeBruijnExpr::Unop { symbol: *symbol, operand: Box::new(go(operand, env)) }
+            }
+            Expr::Binop { symbol, l, r } => {
+                DeBruijnExpr::Binop { symbol: *symbol, l: Box::new(go(l, env)), r: Box::new(go(r, env)) }
+            }
+            Expr::AssocBinop { symbol, exprs } => {
+                DeBruijnExpr::AssocBinop { symbol: *symbol, exprs: exprs.iter().map(|x| go(x, env)).collect() }
+            }
+            Expr::Quantifier { symbol, name, body } => {
+                env.push(name.clone());
+                let body = go(body, env);
+                env.pop();
+                DeBruijnExpr::Quantifier { symbol: *symbol, body: Box::new(body) }
+            }
+        }
+    }
+    go(e, &mut Vec::new())
+}
+
+/// Converts `e` back to an [`Expr`](super::Expr), inventing readable binder
+/// names with [`gensym`] as quantifiers are uncovered. Total: every
+/// `DeBruijnExpr` produced by a well-scoped `to_debruijn` call has a binder
+/// for each `BoundVar`, so no index ever falls off the end of `env`.
+pub fn from_debruijn(e: &DeBruijnExpr) -> Expr {
+    fn go(e: &DeBruijnExpr, env: &mut Vec<String>) -> Expr {
+        match e {
+            DeBruijnExpr::Contradiction => Expr::Contradiction,
+            DeBruijnExpr::Tautology => Expr::Tautology,
+            DeBruijnExpr::FreeVar { name } => Expr::var(name),
+            DeBruijnExpr::BoundVar { index } => Expr::var(&env[env.len() - 1 - index]),
+            DeBruijnExpr::Apply { func, args } => {
+                Expr::Apply { func: Box::new(go(func, env)), args: args.iter().map(|a| go(a, env)).collect() }
+            }
+            DeBruijnExpr::Unop { symbol, operand } => Expr::Unop { symbol: *symbol, operand: Box::new(go(operand, env)) },
+            DeBruijnExpr::Binop { symbol, l, r } => {
+                Expr::Binop { symbol: *symbol, l: Box::new(go(l, env)), r: Box::new(go(r, env)) }
+            }
+            DeBruijnExpr::AssocBinop { symbol, exprs } => {
+                Expr::AssocBinop { symbol: *symbol, exprs: exprs.iter().map(|x| go(x, env)).collect() }
+            }
+            DeBruijnExpr::Quantifier { symbol, body } => {
+                let name = gensym("db");
+                env.push(name.clone());
+                let body = go(body, env);
+                env.pop();
+                Expr::Quantifier { symbol: *symbol, name, body: Box::new(body) }
+            }
+        }
+    }
+    go(e, &mut Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::alpha_equal;
+
+    #[test]
+    fn test_round_trip_is_alpha_equivalent() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::quantifier(QSymbol::Exists, "y", Expr::var("y"))]),
+        );
+        let round_tripped = from_debruijn(&to_debruijn(&e));
+        assert!(alpha_equal(&e, &round_tripped), "{} should be alpha-equal to {}", e, round_tripped);
+    }
+
+    #[test]
+    fn test_alpha_equivalent_inputs_produce_equal_debruijn() {
+        let a = Expr::quantifier(QSymbol::Forall, "x", Expr::assoc(ASymbol::And, vec![Expr::var("x"), Expr::var("y")]));
+        let b = Expr::quantifier(QSymbol::Forall, "z", Expr::assoc(ASymbol::And, vec![Expr::var("z"), Expr::var("y")]));
+        assert_eq!(to_debruijn(&a), to_debruijn(&b));
+    }
+
+    #[test]
+    fn test_free_variables_stay_named_and_distinct_binders_differ() {
+        let e = Expr::quantifier(QSymbol::Forall, "x", Expr::var("z"));
+        assert_eq!(
+            to_debruijn(&e),
+            DeBruijnExpr::Quantifier {
+                symbol: QSymbol::Forall,
+                body: Box::new(DeBruijnExpr::FreeVar { name: "z".to_owned() }),
+            }
+        );
+    }
+}
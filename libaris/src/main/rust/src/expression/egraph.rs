@@ -0,0 +1,289 @@
+//! A lightweight equality-saturation backend for checking whether two
+//! expressions are interconvertible under a set of *bidirectional* rewrite
+//! rules, even when neither ordinary direction of [`super::reduce_pattern`]
+//! alone can close the gap (e.g. a proof step that applies a law
+//! "backwards").
+//!
+//! The representation here is deliberately simple: rather than a full
+//! e-node/e-class graph with e-matching, equivalence classes are tracked by
+//! a union-find keyed directly on concrete [`Expr`](super::Expr) values, and
+//! saturation grows a frontier of known-equivalent terms one rewrite step at
+//! a time. This is enough to answer [`equiv_under`] within a bounded
+//! [`Budget`], though it means a rule whose "backward" direction introduces
+//! more than one fresh pattern variable (not matched anywhere in the
+//! original side) can't be applied, since there would be no way to choose
+//! what to bind those extra variables to; a rule introducing exactly one
+//! extra variable is handled by trying every term [`equiv_under`] has seen
+//! so far as a candidate binding.
+
+use super::{match_expr, pattern_vars_of, subst_map, Expr};
+use std::collections::{HashMap, HashSet};
+
+/// A bidirectional rewrite rule: `lhs` and `rhs` may each serve as pattern
+/// or template, in either direction. `lhs_vars`/`rhs_vars` are each side's
+/// pattern variables (per the `__`-prefixed [`super::Expr`] convention),
+/// computed once so [`equiv_under`] doesn't recompute them every step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub lhs: Expr,
+    pub rhs: Expr,
+    lhs_vars: HashSet<String>,
+    rhs_vars: HashSet<String>,
+}
+
+impl Rule {
+    pub fn new(lhs: Expr, rhs: Expr) -> Rule {
+        let lhs_vars = pattern_vars_of(&lhs);
+        let rhs_vars = pattern_vars_of(&rhs);
+        Rule { lhs, rhs, lhs_vars, rhs_vars }
+    }
+}
+
+/// Limits how far [`equiv_under`] is willing to saturate before giving up:
+/// at most `max_iterations` rounds of growing the frontier, and no more than
+/// `max_nodes` distinct expressions discovered in total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Budget {
+    pub max_iterations: usize,
+    pub max_nodes: usize,
+}
+
+/// Union-find over concrete expressions, used to track which terms
+/// [`equiv_under`] has proven equivalent so far.
+struct EGraph {
+    parent: HashMap<Expr, Expr>,
+}
+
+impl EGraph {
+    fn new() -> EGraph {
+        EGraph { parent: HashMap::new() }
+    }
+
+    fn find(&mut self, e: &Expr) -> Expr {
+        match self.parent.get(e).cloned() {
+            None => {
+                self.parent.insert(e.clone(), e.clone());
+                e.clone()
+            }
+            Some(p) if &p == e => p,
+            Some(p) => {
+                let root = self.find(&p);
+                self.parent.insert(e.clone(), root.clone());
+                root
+            }
+        }
+    }
+
+    fn union(&mut self, a: &Expr, b: &Expr) {
+        let ra = self.find(a);
+        let rb = self.find(b);
+        if ra != rb {
+            self.parent.insert(ra, rb);
+        }
+    }
+}
+
+/// All expressions one rewrite step away from `e` under `rules`, trying both
+/// directions of every rule at every subterm of `e`. `known` supplies
+/// candidate bindings for the single case this simplified backend can
+/// handle of a rewrite direction introducing a pattern variable that the
+/// matched side didn't bind (see the module docs).
+fn one_step_rewrites(e: &Expr, rules: &[Rule], known: &HashSet<Expr>) -> Vec<Expr> {
+    let mut out = Vec::new();
+    for rule in rules {
+        try_direction(&rule.lhs, &rule.rhs, &rule.lhs_vars, &rule.rhs_vars, e, known, &mut out);
+        try_direction(&rule.rhs, &rule.lhs, &rule.rhs_vars, &rule.lhs_vars, e, known, &mut out);
+    }
+    match e {
+        Expr::Contradiction | Expr::Tautology | Expr::Var { .. } => {}
+        Expr::Apply { func, args } => {
+            for rewritten in one_step_rewrites(func, rules, known) {
+                out.push(Expr::Apply { func: Box::new(rewritten), args: args.clone() });
+            }
+            for (i, a) in args.iter().enumerate() {
+                for rewritten in one_step_rewrites(a, rules, known) {
+                    let mut new_args = args.clone();
+                    new_args[i] = rewritten;
+                    out.push(Expr::Apply { func: func.clone(), args: new_args });
+                }
+            }
+        }
+        Expr::Unop { symbol, operand } => {
+            for rewritten in one_step_rewrites(operand, rules, known) {
+                out.push(Expr::Unop { symbol: *symbol, operand: Box::new(rewritten) });
+            }
+        }
+        Expr::Binop { symbol, l, r } => {
+            for rewritten in one_step_rewrites(l, rules, known) {
+                out.push(Expr::Binop { symbol: *symbol, l: Box::new(rewritten), r: r.clone() });
+            }
+            for rewritten in one_step_rewrites(r, rules, known) {
+                out.push(Expr::Binop { symbol: *symbol, l: l.clone(), r: Box::new(rewritten) });
+            }
+        }
+        Expr::AssocBinop { symbol, exprs } => {
+            for (i, x) in exprs.iter().enumerate() {
+                for rewritten in one_step_rewrites(x, rules, known) {
+                    let mut new_exprs = exprs.clone();
+                    new_exprs[i] = rewritten;
+                    out.push(Expr::AssocBinop { symbol: *symbol, exprs: new_exprs });
+                }
+            }
+        }
+        Expr::Quantifier { symbol, name, body } => {
+            for rewritten in one_step_rewrites(body, rules, known) {
+                out.push(Expr::Quantifier { symbol: *symbol, name: name.clone(), body: Box::new(rewritten) });
+            }
+        }
+    }
+    out
+}
+
+fn try_direction(
+    pattern: &Expr,
+    template: &Expr,
+    pattern_vars: &HashSet<String>,
+    template_vars: &HashSet<String>,
+    e: &Expr,
+    known: &HashSet<Expr>,
+    out: &mut Vec<Expr>,
+) {
+    let subs = match match_expr(pattern, e, pattern_vars, false) {
+        Some(subs) => subs,
+        None => return,
+    };
+    let extra: Vec<&String> = template_vars.difference(pattern_vars).collect();
+    match extra.as_slice() {
+        [] => out.push(subst_map(template, &subs)),
+        [var] => {
+            for candidate in known {
+                let mut subs = subs.clone();
+                subs.insert((*var).clone(), candidate.clone());
+                out.push(subst_map(template, &subs));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `a` and `b` are interconvertible under `rules`, each rule usable
+/// in either direction, within `budget`. Saturates a frontier of
+/// known-equivalent terms starting from `{a, b}`, unioning every rewrite
+/// with the term it came from, until `a` and `b` land in the same class or
+/// the budget runs out.
+pub fn equiv_under(rules: &[(Expr, Expr)], a: &Expr, b: &Expr, budget: Budget) -> bool {
+    let rules: Vec<Rule> = rules.iter().map(|(l, r)| Rule::new(l.clone(), r.clone())).collect();
+    let mut egraph = EGraph::new();
+    egraph.find(a);
+    egraph.find(b);
+
+    let mut known: HashSet<Expr> = HashSet::new();
+    known.extend(a.subexprs().cloned());
+    known.extend(b.subexprs().cloned());
+
+    let mut frontier: Vec<Expr> = vec![a.clone(), b.clone()];
+    let mut seen: HashSet<Expr> = frontier.iter().cloned().collect();
+
+    for _ in 0..budget.max_iterations {
+        if egraph.find(a) == egraph.find(b) {
+            return true;
+        }
+        if seen.len() >= budget.max_nodes {
+            break;
+        }
+        let mut next_frontier = Vec::new();
+        'frontier: for e in &frontier {
+            for rewritten in one_step_rewrites(e, &rules, &known) {
+                egraph.union(e, &rewritten);
+                if seen.insert(rewritten.clone()) {
+                    known.extend(rewritten.subexprs().cloned());
+                    next_frontier.push(rewritten);
+                    if seen.len() >= budget.max_nodes {
+                        break 'frontier;
+                    }
+                }
+            }
+        }
+        if next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+    egraph.find(a) == egraph.find(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::{ASymbol, BSymbol};
+
+    fn absorption_rule() -> (Expr, Expr) {
+        // `phi & (phi | psi) <-> phi`
+        (Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::assoc(ASymbol::Or, vec![Expr::var("__phi"), Expr::var("__psi")])]), Expr::var("__phi"))
+    }
+
+    fn idempotence_rule() -> (Expr, Expr) {
+        // `phi & phi <-> phi`
+        (Expr::assoc(ASymbol::And, vec![Expr::var("__phi"), Expr::var("__phi")]), Expr::var("__phi"))
+    }
+
+    #[test]
+    fn test_equiv_under_applies_absorption_left_to_right() {
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])]);
+        let b = Expr::var("p");
+        let budget = Budget { max_iterations: 4, max_nodes: 64 };
+        assert!(equiv_under(&[absorption_rule()], &a, &b, budget));
+    }
+
+    #[test]
+    fn test_equiv_under_applies_absorption_right_to_left() {
+        // Going from `p` to `p & (p | q)` requires inventing `psi = q`,
+        // which this backend can only do by trying an existing term as the
+        // candidate — so `q` has to already be reachable from `b`.
+        let a = Expr::var("p");
+        let b = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])]);
+        let budget = Budget { max_iterations: 4, max_nodes: 256 };
+        assert!(equiv_under(&[absorption_rule()], &a, &b, budget));
+    }
+
+    #[test]
+    fn test_equiv_under_finds_a_case_ordered_reduce_pattern_cannot_close() {
+        // `p & (p | q)` and `(p & (p | q)) & (p & (p | q))` are related only
+        // by *growing* one side via idempotence before absorption can apply
+        // to both — ordered, shrink-only `reduce_pattern` over these same
+        // two patterns can reduce each side down to `p`, but can't grow the
+        // smaller expression to match the larger one the way a bidirectional
+        // rule set can show they're already interconvertible without ever
+        // touching a third, totally different normal form.
+        let inner = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])]);
+        let a = inner.clone();
+        let b = Expr::assoc(ASymbol::And, vec![inner.clone(), inner]);
+        let rules = vec![absorption_rule(), idempotence_rule()];
+        let budget = Budget { max_iterations: 4, max_nodes: 256 };
+        assert!(equiv_under(&rules, &a, &b, budget));
+    }
+
+    #[test]
+    fn test_equiv_under_returns_false_when_the_budget_is_exhausted_before_closing() {
+        let a = Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])]);
+        let b = Expr::var("p");
+        let budget = Budget { max_iterations: 0, max_nodes: 64 };
+        assert!(!equiv_under(&[absorption_rule()], &a, &b, budget));
+    }
+
+    #[test]
+    fn test_equiv_under_rejects_genuinely_inequivalent_expressions() {
+        let a = Expr::var("p");
+        let b = Expr::var("q");
+        let budget = Budget { max_iterations: 4, max_nodes: 64 };
+        assert!(!equiv_under(&[absorption_rule()], &a, &b, budget));
+    }
+
+    #[test]
+    fn test_equiv_under_is_unaffected_by_an_unrelated_commutative_binop() {
+        let a = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("x")), r: Box::new(Expr::var("y")) };
+        let b = Expr::Binop { symbol: BSymbol::Plus, l: Box::new(Expr::var("y")), r: Box::new(Expr::var("x")) };
+        let budget = Budget { max_iterations: 4, max_nodes: 64 };
+        assert!(!equiv_under(&[absorption_rule()], &a, &b, budget));
+    }
+}
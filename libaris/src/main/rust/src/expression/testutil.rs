@@ -0,0 +1,144 @@
+//! A small hand-rolled random [`Expr`](super::Expr) generator for
+//! property-style tests elsewhere in this module. This crate otherwise has
+//! no dependencies, so rather than pull in `proptest` for one generator,
+//! `arbitrary_expr` draws from a tiny dependency-free PRNG instead.
+
+use super::{ASymbol, BSymbol, Expr, QSymbol};
+
+/// A minimal xorshift64 PRNG. Not suitable for anything beyond varying test
+/// input across calls: deterministic given a seed, dependency-free, and
+/// nothing more.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(if seed == 0 { 0xdead_beef_cafe_f00d } else { seed })
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A random index in `0..bound`. Panics if `bound == 0`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FormulaKind {
+    Not,
+    Implies,
+    And,
+    Or,
+    Apply,
+    Bicon,
+    Equiv,
+    Forall,
+    Exists,
+}
+
+#[derive(Clone, Copy)]
+enum TermKind {
+    Var,
+    Apply,
+    Plus,
+    Mult,
+}
+
+fn formula_operands(rng: &mut Rng, depth: usize, vars: &[&str], allow_quantifiers: bool, allow_arith: bool) -> Vec<Expr> {
+    // Never fewer than two: an `AssocBinop` with 0 or 1 operands isn't well-formed.
+    let n = 2 + rng.below(2);
+    (0..n).map(|_| arbitrary_formula(rng, depth, vars, allow_quantifiers, allow_arith)).collect()
+}
+
+/// Generates a random term: a `Var`, an `Apply` of terms, or (if
+/// `allow_arith`) a `Plus`/`Mult` of terms. Terms never contain a logical
+/// connective or quantifier, matching what
+/// [`check_well_formed`](super::check_well_formed) requires of `Apply`
+/// arguments and `Plus`/`Mult` operands.
+fn arbitrary_term(rng: &mut Rng, depth: usize, vars: &[&str], allow_arith: bool) -> Expr {
+    if depth == 0 {
+        return Expr::var(vars[rng.below(vars.len())]);
+    }
+    let mut kinds = vec![TermKind::Var, TermKind::Apply];
+    if allow_arith {
+        kinds.extend([TermKind::Plus, TermKind::Mult]);
+    }
+    let next_depth = depth - 1;
+    match kinds[rng.below(kinds.len())] {
+        TermKind::Var => Expr::var(vars[rng.below(vars.len())]),
+        TermKind::Apply => {
+            let func = Expr::var(vars[rng.below(vars.len())]);
+            let args = (0..1 + rng.below(2)).map(|_| arbitrary_term(rng, next_depth, vars, allow_arith)).collect();
+            Expr::Apply { func: Box::new(func), args }
+        }
+        TermKind::Plus => Expr::Binop {
+            symbol: BSymbol::Plus,
+            l: Box::new(arbitrary_term(rng, next_depth, vars, allow_arith)),
+            r: Box::new(arbitrary_term(rng, next_depth, vars, allow_arith)),
+        },
+        TermKind::Mult => Expr::Binop {
+            symbol: BSymbol::Mult,
+            l: Box::new(arbitrary_term(rng, next_depth, vars, allow_arith)),
+            r: Box::new(arbitrary_term(rng, next_depth, vars, allow_arith)),
+        },
+    }
+}
+
+/// Generates a random well-formed [`Expr`] with at most `depth` levels of
+/// connectives, drawing variable and binder names from `vars` (must be
+/// non-empty). `Bicon`/`Equiv`/`Forall`/`Exists` only appear if
+/// `allow_quantifiers`; `Plus`/`Mult` only appear (nested inside `Apply`
+/// arguments, since they are terms, not formulas) if `allow_arith`. Every
+/// generated `AssocBinop` has at least two operands, and every `Apply` has a
+/// `Var` head, matching what [`check_well_formed`](super::check_well_formed)
+/// requires.
+pub fn arbitrary_expr(rng: &mut Rng, depth: usize, vars: &[&str], allow_quantifiers: bool, allow_arith: bool) -> Expr {
+    arbitrary_formula(rng, depth, vars, allow_quantifiers, allow_arith)
+}
+
+fn arbitrary_formula(rng: &mut Rng, depth: usize, vars: &[&str], allow_quantifiers: bool, allow_arith: bool) -> Expr {
+    assert!(!vars.is_empty(), "arbitrary_expr needs at least one variable name to draw from");
+    if depth == 0 {
+        return match rng.below(3) {
+            0 => Expr::var(vars[rng.below(vars.len())]),
+            1 => Expr::Tautology,
+            _ => Expr::Contradiction,
+        };
+    }
+
+    let mut kinds = vec![FormulaKind::Not, FormulaKind::Implies, FormulaKind::And, FormulaKind::Or, FormulaKind::Apply];
+    if allow_quantifiers {
+        kinds.extend([FormulaKind::Bicon, FormulaKind::Equiv, FormulaKind::Forall, FormulaKind::Exists]);
+    }
+
+    let next_depth = depth - 1;
+    match kinds[rng.below(kinds.len())] {
+        FormulaKind::Not => !arbitrary_formula(rng, next_depth, vars, allow_quantifiers, allow_arith),
+        FormulaKind::Implies => Expr::implies(
+            arbitrary_formula(rng, next_depth, vars, allow_quantifiers, allow_arith),
+            arbitrary_formula(rng, next_depth, vars, allow_quantifiers, allow_arith),
+        ),
+        FormulaKind::And => Expr::assoc(ASymbol::And, formula_operands(rng, next_depth, vars, allow_quantifiers, allow_arith)),
+        FormulaKind::Or => Expr::assoc(ASymbol::Or, formula_operands(rng, next_depth, vars, allow_quantifiers, allow_arith)),
+        FormulaKind::Bicon => Expr::assoc(ASymbol::Bicon, formula_operands(rng, next_depth, vars, allow_quantifiers, allow_arith)),
+        FormulaKind::Equiv => Expr::assoc(ASymbol::Equiv, formula_operands(rng, next_depth, vars, allow_quantifiers, allow_arith)),
+        FormulaKind::Forall => {
+            Expr::quantifier(QSymbol::Forall, vars[rng.below(vars.len())], arbitrary_formula(rng, next_depth, vars, allow_quantifiers, allow_arith))
+        }
+        FormulaKind::Exists => {
+            Expr::quantifier(QSymbol::Exists, vars[rng.below(vars.len())], arbitrary_formula(rng, next_depth, vars, allow_quantifiers, allow_arith))
+        }
+        FormulaKind::Apply => {
+            let func = Expr::var(vars[rng.below(vars.len())]);
+            let args = (0..1 + rng.below(2)).map(|_| arbitrary_term(rng, next_depth, vars, allow_arith)).collect();
+            Expr::Apply { func: Box::new(func), args }
+        }
+    }
+}
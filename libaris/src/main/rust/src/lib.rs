@@ -0,0 +1,4 @@
+pub mod expression;
+pub mod parser;
+
+pub use expression::Expr;
@@ -0,0 +1,1221 @@
+//! A hand-rolled recursive-descent parser for [`Expr`]'s own concrete
+//! syntax — the textual form produced by [`Expr`]'s `Display` impl (`(A & B
+//! & C)`, `~A`, `forall x, P(x)`, `_|_`, and so on). This crate has no
+//! dependencies, so there is no parser-combinator library backing this; the
+//! lexer and parser below are both plain functions over a `Vec<Token>`.
+//!
+//! Every failure is reported as a [`ParseError`] carrying the byte offset
+//! (plus the derived line/column) of the failure point and either a short
+//! list of expected tokens or a human-readable expectation, rather than
+//! exposing any lexer/parser-internal state.
+//!
+//! Every connective also accepts a plain-ASCII synonym, so a formula can be
+//! typed on a keyboard with no `∧`/`∨`/`¬` key: `/\` or `and` for `&`, `\/`
+//! or `or` for `|`, `!` or `not` for `~`, `=>` for `->`, `<->` or `iff` for
+//! `<=>`, `A.` for `forall`, `E.` for `exists`, `xor` for `^`, `nand` for
+//! `!&`, `nor` for `!|`, and `^|^` for `T`. The synonyms tokenize to the
+//! same [`TokenKind`] as the forms above, so the rest of the grammar can't
+//! tell which spelling was used. There's no Unicode `⊕`/`↑`/`↓` spelling
+//! for xor/nand/nor (or for any other connective) — [`lex`] reads bytes
+//! one at a time and casts each straight to a `char` (see [`line_col`]'s
+//! doc comment), so it can only ever see one grammar's worth of ASCII, not
+//! a multi-byte UTF-8 sequence.
+//!
+//! Five connectives — `&`, `|`, `^`, `->`, and `<=>`/`===` — form a
+//! precedence ladder and may be chained bare, with no parens at all:
+//! tightest to loosest, `&` (5) binds tighter than `|` (4), which binds
+//! tighter than `^` (3), which binds tighter than `->` (2), which binds
+//! tighter than `<=>`/`===` (1). So `a & b | c -> d` parses as `((a & b) |
+//! c) -> d`. `->` is right-associative (`a -> b -> c` is `a -> (b -> c)`);
+//! the other four are parsed as a single flat [`Expr::AssocBinop`] the way
+//! they always have been, so a bare run of the same connective (`a & b &
+//! c`) collects into one node rather than nesting. `<=>` and `===` don't
+//! mix in one bare run any more than they did inside one pair of parens —
+//! see below. [`Parser::parse_ladder_from`] is this whole ladder, and
+//! everywhere this grammar parses "an expression" ([`Parser::parse_expr`])
+//! climbs it from the bottom.
+//!
+//! `!&`, `!|`, `+`, and `*` aren't part of that ladder — there's no
+//! established convention for how tightly nand/nor/plus/mult bind relative
+//! to it, so (as before the ladder existed) they only ever appear as an
+//! explicit `(A op B)` pair, and mixing one with a bare ladder chain
+//! without an extra nested group, e.g. `(a xor b nand c)`, is a plain
+//! parse error (`expected ')'`) rather than an ambiguity this grammar has
+//! to resolve — see [`Parser::parse_parenthesized`].
+//!
+//! A binder may also carry a `: SortName` annotation for many-sorted logic,
+//! e.g. `forall n: Nat, P(n)`. There's no `Expr` node for "sort" — a sorted
+//! binder desugars at parse time into an ordinary guard: the sort name is
+//! applied to the bound variable like any other predicate, so `forall n:
+//! Nat, P(n)` parses to exactly what `forall n, Nat(n) -> P(n)` would, and
+//! `exists n: Nat, P(n)` to what `exists n, Nat(n) & P(n)` would. `Expr`'s
+//! `Display` impl recognizes that exact shape and re-sugars it back into
+//! the `: SortName` form.
+//!
+//! Infix `a = b` (and its negation `a != b`, ASCII-spelled the same way
+//! every other connective in this grammar is — see above) is likewise not
+//! its own `Expr` node: it desugars to the two-argument predicate
+//! application `=(a, b)` (or `~=(a, b)`), so `unify`, `subst`, and every
+//! other `Expr`-generic function already handle it correctly through their
+//! existing `Apply` case. `=`/`!=` bind tighter than every ladder
+//! connective — they're parsed by [`Parser::parse_equality`], which sits
+//! below the whole ladder and one level above [`Parser::parse_primary`],
+//! so `(a = b & c = d)` parses as a conjunction of two equality atoms
+//! without needing extra parens around either side.
+//!
+//! A run of ASCII digits is a numeral literal, e.g. `12`. There's no
+//! dedicated `Expr` variant for these either — see
+//! [`crate::expression::as_numeral`] for the `Var`-with-digit-name
+//! representation they parse into, which is also what [`Display`] prints
+//! and [`crate::expression::fold_arithmetic`] evaluates. A `-` directly
+//! before a digit is a parse error rather than negation applied to a
+//! numeral: this grammar has no numeric negation yet.
+//!
+//! [`Display`]: std::fmt::Display
+
+use crate::expression::{numeral, ASymbol, BSymbol, Expr, QSymbol};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Comma,
+    Colon,
+    Equals,
+    NotEquals,
+    Tilde,
+    Arrow,
+    Plus,
+    Star,
+    Nand,
+    Nor,
+    And,
+    Or,
+    Bicon,
+    Equiv,
+    Xor,
+    Forall,
+    Exists,
+    Tautology,
+    Contradiction,
+    Ident(String),
+    /// A nonnegative integer literal, already parsed to an `i64` — see
+    /// [`crate::expression::as_numeral`] for the `Expr` representation it
+    /// becomes.
+    Number(i64),
+    Eof,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Equals => write!(f, "'='"),
+            TokenKind::NotEquals => write!(f, "'!='"),
+            TokenKind::Tilde => write!(f, "'~'"),
+            TokenKind::Arrow => write!(f, "'->'"),
+            TokenKind::Plus => write!(f, "'+'"),
+            TokenKind::Star => write!(f, "'*'"),
+            TokenKind::Nand => write!(f, "'!&'"),
+            TokenKind::Nor => write!(f, "'!|'"),
+            TokenKind::And => write!(f, "'&'"),
+            TokenKind::Or => write!(f, "'|'"),
+            TokenKind::Bicon => write!(f, "'<=>'"),
+            TokenKind::Equiv => write!(f, "'==='"),
+            TokenKind::Xor => write!(f, "'^'"),
+            TokenKind::Forall => write!(f, "'forall'"),
+            TokenKind::Exists => write!(f, "'exists'"),
+            TokenKind::Tautology => write!(f, "'T'"),
+            TokenKind::Contradiction => write!(f, "'_|_'"),
+            TokenKind::Ident(name) => write!(f, "identifier \"{}\"", name),
+            TokenKind::Number(n) => write!(f, "numeral \"{}\"", n),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+/// What went wrong while parsing, independent of where. See [`ParseError`]
+/// for the byte offset/line/column that goes with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character (or character sequence) that isn't part of any token in
+    /// this grammar, e.g. `@` or a lone `<`.
+    UnknownSymbol { symbol: String },
+    /// A `(` was never matched by a `)` before the input ran out.
+    UnclosedParen { open_offset: usize },
+    /// The input ended where a specific construct (an operand, a binder
+    /// name, a closing token) was still expected.
+    UnexpectedEnd { expected: String },
+    /// A token was found where a specific, different construct was
+    /// expected.
+    UnexpectedToken { found: String, expected: String },
+    /// The same name was bound twice in one quantifier prefix, e.g.
+    /// `forall x x, P(x)`.
+    DuplicateBinderName { name: String },
+    /// A `-` immediately before a digit, e.g. `-3`. Negative numeral
+    /// literals aren't supported yet — see [`numeral`](crate::expression::numeral).
+    NegativeNumeral,
+    /// A digit run too long to fit in an `i64`.
+    NumeralOverflow { digits: String },
+}
+
+/// A parse failure, carrying both *what* went wrong ([`ParseErrorKind`]) and
+/// *where*: the byte offset into the original input, plus the 1-indexed
+/// line and column derived from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::UnknownSymbol { symbol } => {
+                write!(f, "line {}, column {}: unknown symbol \"{}\"", self.line, self.column, symbol)
+            }
+            ParseErrorKind::UnclosedParen { .. } => {
+                write!(f, "line {}, column {}: unclosed '('", self.line, self.column)
+            }
+            ParseErrorKind::UnexpectedEnd { expected } => {
+                write!(f, "line {}, column {}: unexpected end of input, expected {}", self.line, self.column, expected)
+            }
+            ParseErrorKind::UnexpectedToken { found, expected } => {
+                write!(f, "line {}, column {}: expected {}, found {}", self.line, self.column, expected, found)
+            }
+            ParseErrorKind::DuplicateBinderName { name } => {
+                write!(f, "line {}, column {}: \"{}\" is already bound in this quantifier prefix", self.line, self.column, name)
+            }
+            ParseErrorKind::NegativeNumeral => {
+                write!(
+                    f,
+                    "line {}, column {}: negative numeral literals aren't supported; write the numeral without the leading '-'",
+                    self.line, self.column
+                )
+            }
+            ParseErrorKind::NumeralOverflow { digits } => {
+                write!(f, "line {}, column {}: numeral \"{}\" is too large to fit in an i64", self.line, self.column, digits)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The 1-indexed (line, column) that byte offset `offset` falls on within
+/// `input`. Both line and column count in bytes, not Unicode scalar
+/// values or graphemes, matching the ASCII-only token set this grammar
+/// recognizes.
+fn line_col(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input.as_bytes()[..offset];
+    let line = 1 + before.iter().filter(|&&b| b == b'\n').count();
+    let column = match before.iter().rposition(|&b| b == b'\n') {
+        Some(last_newline) => offset - last_newline,
+        None => offset + 1,
+    };
+    (line, column)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Splits `input` into [`Token`]s, each tagged with the byte offset it
+/// started at. Fails eagerly on the first character sequence that doesn't
+/// begin any token in the grammar, e.g. a bare `@`.
+fn lex(input: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let err = |offset: usize, kind: ParseErrorKind| {
+        let (line, column) = line_col(input, offset);
+        Err(ParseError { offset, line, column, kind })
+    };
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let offset = i;
+        let (kind, len) = match c {
+            '(' => (TokenKind::LParen, 1),
+            ')' => (TokenKind::RParen, 1),
+            ',' => (TokenKind::Comma, 1),
+            ':' => (TokenKind::Colon, 1),
+            '~' => (TokenKind::Tilde, 1),
+            '+' => (TokenKind::Plus, 1),
+            '*' => (TokenKind::Star, 1),
+            '&' => (TokenKind::And, 1),
+            '/' if input[i..].starts_with("/\\") => (TokenKind::And, 2),
+            '\\' if input[i..].starts_with("\\/") => (TokenKind::Or, 2),
+            '^' if input[i..].starts_with("^|^") => (TokenKind::Tautology, 3),
+            '^' => (TokenKind::Xor, 1),
+            '-' if input[i..].starts_with("->") => (TokenKind::Arrow, 2),
+            '-' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                return err(offset, ParseErrorKind::NegativeNumeral);
+            }
+            '=' if input[i..].starts_with("===") => (TokenKind::Equiv, 3),
+            '=' if input[i..].starts_with("=>") => (TokenKind::Arrow, 2),
+            '=' => (TokenKind::Equals, 1),
+            '!' if input[i..].starts_with("!&") => (TokenKind::Nand, 2),
+            '!' if input[i..].starts_with("!|") => (TokenKind::Nor, 2),
+            '!' if input[i..].starts_with("!=") => (TokenKind::NotEquals, 2),
+            '!' => (TokenKind::Tilde, 1),
+            '<' if input[i..].starts_with("<=>") => (TokenKind::Bicon, 3),
+            '<' if input[i..].starts_with("<->") => (TokenKind::Bicon, 3),
+            '_' if input[i..].starts_with("_|_") => (TokenKind::Contradiction, 3),
+            '|' => (TokenKind::Or, 1),
+            'A' if input[i..].starts_with("A.") => (TokenKind::Forall, 2),
+            'E' if input[i..].starts_with("E.") => (TokenKind::Exists, 2),
+            _ if c.is_ascii_digit() => {
+                let rest = &input[i..];
+                let digit_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+                let digits = &rest[..digit_len];
+                match digits.parse() {
+                    Ok(n) => (TokenKind::Number(n), digit_len),
+                    Err(_) => return err(offset, ParseErrorKind::NumeralOverflow { digits: digits.to_owned() }),
+                }
+            }
+            _ if is_ident_start(c) => {
+                let rest = &input[i..];
+                let ident_len = rest.char_indices().take_while(|&(_, c)| is_ident_continue(c)).count();
+                let text = &rest[..ident_len];
+                let kind = match text {
+                    "forall" => TokenKind::Forall,
+                    "exists" => TokenKind::Exists,
+                    "T" => TokenKind::Tautology,
+                    "and" => TokenKind::And,
+                    "or" => TokenKind::Or,
+                    "not" => TokenKind::Tilde,
+                    "iff" => TokenKind::Bicon,
+                    "xor" => TokenKind::Xor,
+                    "nand" => TokenKind::Nand,
+                    "nor" => TokenKind::Nor,
+                    _ => TokenKind::Ident(text.to_owned()),
+                };
+                (kind, ident_len)
+            }
+            _ => {
+                return err(offset, ParseErrorKind::UnknownSymbol { symbol: c.to_string() });
+            }
+        };
+        tokens.push(Token { kind, offset });
+        i += len;
+    }
+    tokens.push(Token { kind: TokenKind::Eof, offset: bytes.len() });
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error_here(&self, kind: ParseErrorKind) -> ParseError {
+        let offset = self.peek().offset;
+        let (line, column) = line_col(self.input, offset);
+        ParseError { offset, line, column, kind }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match &self.peek().kind {
+            TokenKind::Eof => self.error_here(ParseErrorKind::UnexpectedEnd { expected: expected.to_owned() }),
+            found => {
+                self.error_here(ParseErrorKind::UnexpectedToken { found: found.to_string(), expected: expected.to_owned() })
+            }
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token, ParseError> {
+        if self.peek().kind == kind {
+            Ok(self.advance())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn expect_ident(&mut self, expected: &str) -> Result<String, ParseError> {
+        match &self.peek().kind {
+            TokenKind::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// A run of one or more binder names after a quantifier symbol, e.g.
+    /// the `x y z` in `forall x y z, P(x,y,z)`, each optionally carrying a
+    /// `: SortName` annotation (`forall n: Nat m: Nat, P(n,m)`). Names may
+    /// be separated by whitespace, by commas, or a mix of both. A comma is
+    /// only consumed as a name separator when it's unambiguously followed
+    /// by another name in the list (a name itself followed by a further
+    /// comma or name); otherwise it's left for the caller to consume as the
+    /// comma that introduces the quantifier body, so `forall x, P(x)` and
+    /// `forall x, y` (a quantifier over `x` whose body is the bare variable
+    /// `y`) still parse the same as before this method existed. Rejects a
+    /// name that repeats one already bound earlier in the same prefix.
+    fn parse_binder_names(&mut self) -> Result<Vec<(String, Option<String>)>, ParseError> {
+        let mut names = vec![self.parse_one_binder_name()?];
+        loop {
+            match self.peek().kind {
+                TokenKind::Ident(_) => names.push(self.parse_one_binder_name()?),
+                TokenKind::Comma if self.comma_starts_another_binder_name() => {
+                    self.advance();
+                    names.push(self.parse_one_binder_name()?);
+                }
+                _ => break,
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (offset, name, _) in &names {
+            if !seen.insert(name.clone()) {
+                let (line, column) = line_col(self.input, *offset);
+                return Err(ParseError { offset: *offset, line, column, kind: ParseErrorKind::DuplicateBinderName { name: name.clone() } });
+            }
+        }
+        Ok(names.into_iter().map(|(_, name, sort)| (name, sort)).collect())
+    }
+
+    /// A single binder name and its optional `: SortName` annotation, along
+    /// with the byte offset the name started at (used to point a duplicate
+    /// name error at the right place).
+    fn parse_one_binder_name(&mut self) -> Result<(usize, String, Option<String>), ParseError> {
+        let offset = self.peek().offset;
+        let name = self.expect_ident("a binder name")?;
+        let sort = if self.peek().kind == TokenKind::Colon {
+            self.advance();
+            Some(self.expect_ident("a sort name after ':'")?)
+        } else {
+            None
+        };
+        Ok((offset, name, sort))
+    }
+
+    /// Whether the `,` at the current position separates two binder names
+    /// rather than introducing the quantifier body: true only when it's
+    /// followed by a (possibly sorted) binder name that is itself followed
+    /// by another comma or name, i.e. the list keeps going. A lone `,
+    /// name` (sorted or not) is left alone so it parses as the
+    /// body-introducing comma instead.
+    fn comma_starts_another_binder_name(&self) -> bool {
+        let mut probe = self.pos + 1;
+        if !matches!(self.tokens.get(probe).map(|t| &t.kind), Some(TokenKind::Ident(_))) {
+            return false;
+        }
+        probe += 1;
+        if matches!(self.tokens.get(probe).map(|t| &t.kind), Some(TokenKind::Colon)) {
+            probe += 1;
+            if !matches!(self.tokens.get(probe).map(|t| &t.kind), Some(TokenKind::Ident(_))) {
+                return false;
+            }
+            probe += 1;
+        }
+        matches!(self.tokens.get(probe).map(|t| &t.kind), Some(TokenKind::Ident(_)) | Some(TokenKind::Comma))
+    }
+
+    /// `expr := equality ladder_tail`, i.e. an equality atom climbed all
+    /// the way up the precedence ladder described in the module docs — see
+    /// [`Self::parse_ladder_from`].
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let first = self.parse_equality()?;
+        self.parse_ladder_from(first)
+    }
+
+    /// Like [`Self::parse_primary_after`], but for [`Self::parse_expr`].
+    fn parse_expr_after(&mut self, expected: &str) -> Result<Expr, ParseError> {
+        if self.peek().kind == TokenKind::Eof {
+            return Err(self.error_here(ParseErrorKind::UnexpectedEnd { expected: expected.to_owned() }));
+        }
+        self.parse_expr()
+    }
+
+    /// `equality := primary [('=' | '!=') primary]`
+    ///
+    /// A bare `primary` covers everything that isn't an equality atom;
+    /// wrapping it here — rather than inside [`Self::parse_primary`] —
+    /// keeps `=`/`!=` from chaining (`a = b = c` isn't equality's to
+    /// parse, same as any other non-associative connective) while still
+    /// letting it sit tighter than the whole precedence ladder above it.
+    fn parse_equality(&mut self) -> Result<Expr, ParseError> {
+        let lhs = self.parse_primary()?;
+        match self.peek().kind {
+            TokenKind::Equals => {
+                self.advance();
+                let rhs = self.parse_primary_after("an expression after '='")?;
+                Ok(equals(lhs, rhs))
+            }
+            TokenKind::NotEquals => {
+                self.advance();
+                let rhs = self.parse_primary_after("an expression after '!='")?;
+                Ok(!equals(lhs, rhs))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    /// Like [`Self::parse_expr_after`], but for [`Self::parse_equality`] —
+    /// used where an operand must stay below the ladder, e.g. `~`'s
+    /// operand, which binds tighter than every ladder connective (`~a &
+    /// b` is `(~a) & b`, not `~(a & b)`).
+    fn parse_equality_after(&mut self, expected: &str) -> Result<Expr, ParseError> {
+        if self.peek().kind == TokenKind::Eof {
+            return Err(self.error_here(ParseErrorKind::UnexpectedEnd { expected: expected.to_owned() }));
+        }
+        self.parse_equality()
+    }
+
+    /// Like [`Self::parse_expr_after`], but for [`Self::parse_primary`].
+    fn parse_primary_after(&mut self, expected: &str) -> Result<Expr, ParseError> {
+        if self.peek().kind == TokenKind::Eof {
+            return Err(self.error_here(ParseErrorKind::UnexpectedEnd { expected: expected.to_owned() }));
+        }
+        self.parse_primary()
+    }
+
+    /// `primary := '_|_' | 'T' | ('forall'|'exists') binder_names ',' expr
+    ///           | '~' equality | ident ['(' expr (',' expr)* ')'] | number
+    ///           | '(' compound ')'`
+    /// where `binder_names` is a space- and/or comma-separated run of at
+    /// least one identifier — see [`Self::parse_binder_names`].
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        match self.peek().kind.clone() {
+            TokenKind::Contradiction => {
+                self.advance();
+                Ok(Expr::Contradiction)
+            }
+            TokenKind::Tautology => {
+                self.advance();
+                Ok(Expr::Tautology)
+            }
+            TokenKind::Forall | TokenKind::Exists => {
+                let symbol = if matches!(self.peek().kind, TokenKind::Forall) { QSymbol::Forall } else { QSymbol::Exists };
+                self.advance();
+                let names = self.parse_binder_names()?;
+                self.expect(TokenKind::Comma, "',' after the binder name")?;
+                let body = self.parse_expr_after("the quantifier body")?;
+                Ok(names.into_iter().rev().fold(body, |body, (name, sort)| sorted_quantifier(symbol, &name, sort.as_deref(), body)))
+            }
+            TokenKind::Tilde => {
+                self.advance();
+                let operand = self.parse_equality_after("an expression after '~'")?;
+                Ok(!operand)
+            }
+            TokenKind::Ident(name) => {
+                self.advance();
+                if self.peek().kind == TokenKind::LParen {
+                    self.advance();
+                    let args = self.parse_operand_list(TokenKind::RParen, "an argument")?;
+                    Ok(Expr::Apply { func: Box::new(Expr::var(&name)), args })
+                } else {
+                    Ok(Expr::var(&name))
+                }
+            }
+            TokenKind::Number(n) => {
+                self.advance();
+                Ok(numeral(n))
+            }
+            TokenKind::LParen => {
+                let open = self.advance();
+                self.parse_parenthesized(open.offset)
+            }
+            _ => Err(self.unexpected("an expression")),
+        }
+    }
+
+    /// Reports running out of input as "an expression after the operator"
+    /// rather than continuing on to `next` and letting it report the
+    /// generic "an expression" — used by every ladder level below to parse
+    /// the operand right after a connective it just consumed.
+    fn expr_after_operator(&mut self, next: fn(&mut Self) -> Result<Expr, ParseError>) -> Result<Expr, ParseError> {
+        if self.peek().kind == TokenKind::Eof {
+            return Err(self.error_here(ParseErrorKind::UnexpectedEnd { expected: "an expression after the operator".to_owned() }));
+        }
+        next(self)
+    }
+
+    /// Climbs the whole precedence ladder from the module docs, starting
+    /// from `first` (already parsed at [`Self::parse_equality`]'s
+    /// tightness — the bottom of the ladder). Every level is skipped when
+    /// its connective isn't next, so a bare atom just passes straight
+    /// through unchanged.
+    fn parse_ladder_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        let first = self.and_level_from(first)?;
+        let first = self.or_level_from(first)?;
+        let first = self.xor_level_from(first)?;
+        let first = self.implies_level_from(first)?;
+        self.iff_level_from(first)
+    }
+
+    /// `and_level := equality ('&' equality)*`, continued from an already-
+    /// parsed `first`. A run collects into one flat [`Expr::AssocBinop`],
+    /// same as the old `(a & b & c)` syntax always did.
+    fn and_level_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        if self.peek().kind != TokenKind::And {
+            return Ok(first);
+        }
+        let mut exprs = vec![first];
+        while self.peek().kind == TokenKind::And {
+            self.advance();
+            exprs.push(self.expr_after_operator(Self::parse_equality)?);
+        }
+        Ok(Expr::assoc(ASymbol::And, exprs))
+    }
+
+    /// `or_level := and_level ('|' and_level)*`, continued from an
+    /// already-parsed `first` (itself already climbed through
+    /// [`Self::and_level_from`], so `|` binds looser than `&`).
+    fn or_level_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        if self.peek().kind != TokenKind::Or {
+            return Ok(first);
+        }
+        let mut exprs = vec![first];
+        while self.peek().kind == TokenKind::Or {
+            self.advance();
+            let mut operand = self.expr_after_operator(Self::parse_equality)?;
+            operand = self.and_level_from(operand)?;
+            exprs.push(operand);
+        }
+        Ok(Expr::assoc(ASymbol::Or, exprs))
+    }
+
+    /// `xor_level := or_level ('^' or_level)*`, continued from an
+    /// already-parsed `first`.
+    fn xor_level_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        if self.peek().kind != TokenKind::Xor {
+            return Ok(first);
+        }
+        let mut exprs = vec![first];
+        while self.peek().kind == TokenKind::Xor {
+            self.advance();
+            let mut operand = self.expr_after_operator(Self::parse_equality)?;
+            operand = self.and_level_from(operand)?;
+            operand = self.or_level_from(operand)?;
+            exprs.push(operand);
+        }
+        Ok(Expr::assoc(ASymbol::Xor, exprs))
+    }
+
+    /// `implies_level := xor_level ['->' implies_level]`, continued from an
+    /// already-parsed `first`. Right-recursive rather than a loop, so `a ->
+    /// b -> c` associates as `a -> (b -> c)`.
+    fn implies_level_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        if self.peek().kind != TokenKind::Arrow {
+            return Ok(first);
+        }
+        self.advance();
+        let mut operand = self.expr_after_operator(Self::parse_equality)?;
+        operand = self.and_level_from(operand)?;
+        operand = self.or_level_from(operand)?;
+        operand = self.xor_level_from(operand)?;
+        operand = self.implies_level_from(operand)?;
+        Ok(Expr::implies(first, operand))
+    }
+
+    /// `iff_level := implies_level (('<=>' implies_level)* | ('===' implies_level)*)`,
+    /// continued from an already-parsed `first` — the loosest level. Same
+    /// as before the ladder existed, a chain must stick to one of `<=>` or
+    /// `===`; switching partway through still needs an extra nested group.
+    fn iff_level_from(&mut self, first: Expr) -> Result<Expr, ParseError> {
+        let token_kind = match self.peek().kind {
+            TokenKind::Bicon => TokenKind::Bicon,
+            TokenKind::Equiv => TokenKind::Equiv,
+            _ => return Ok(first),
+        };
+        let symbol = if token_kind == TokenKind::Bicon { ASymbol::Bicon } else { ASymbol::Equiv };
+        let mut exprs = vec![first];
+        while self.peek().kind == token_kind {
+            self.advance();
+            let mut operand = self.expr_after_operator(Self::parse_equality)?;
+            operand = self.and_level_from(operand)?;
+            operand = self.or_level_from(operand)?;
+            operand = self.xor_level_from(operand)?;
+            operand = self.implies_level_from(operand)?;
+            exprs.push(operand);
+        }
+        Ok(Expr::assoc(symbol, exprs))
+    }
+
+    /// A comma-separated, possibly-empty list of operands up to (and
+    /// including) `closing`, used for `Apply` argument lists.
+    fn parse_operand_list(&mut self, closing: TokenKind, operand_desc: &str) -> Result<Vec<Expr>, ParseError> {
+        let mut operands = Vec::new();
+        if self.peek().kind == closing {
+            self.advance();
+            return Ok(operands);
+        }
+        loop {
+            operands.push(self.parse_expr_after(operand_desc)?);
+            if self.peek().kind == closing {
+                self.advance();
+                return Ok(operands);
+            }
+            self.expect(TokenKind::Comma, "',' or ')'")?;
+        }
+    }
+
+    /// The inside of a `(...)` group, after the opening paren (at byte
+    /// offset `open_offset`, kept so an unterminated group names the paren
+    /// it failed to close) has already been consumed: either a single
+    /// redundantly-parenthesized expression, a run of the precedence
+    /// ladder from the module docs (`(a & b -> c)`), or one of the
+    /// off-ladder binary operators `!&`/`!|`/`+`/`*` applied to a single
+    /// equality atom on each side (`(A op B)`).
+    ///
+    /// The off-ladder check happens before climbing the ladder, and only
+    /// against a bare equality atom, not whatever the ladder might have
+    /// already built out of it — that's what makes `(a xor b nand c)` a
+    /// parse error rather than `(a xor b) !& c`: nand isn't on the ladder,
+    /// so it only ever combines with operands that are themselves either
+    /// atoms or their own separately-parenthesized group.
+    fn parse_parenthesized(&mut self, open_offset: usize) -> Result<Expr, ParseError> {
+        let first = self.parse_equality_after("an expression after '('")?;
+        if let Some(symbol) = self.binop_symbol() {
+            self.advance();
+            let right = self.parse_equality_after("an expression after the operator")?;
+            self.close_paren(open_offset)?;
+            return Ok(Expr::Binop { symbol, l: Box::new(first), r: Box::new(right) });
+        }
+        let expr = self.parse_ladder_from(first)?;
+        self.close_paren(open_offset)?;
+        Ok(expr)
+    }
+
+    fn binop_symbol(&self) -> Option<BSymbol> {
+        match self.peek().kind {
+            TokenKind::Plus => Some(BSymbol::Plus),
+            TokenKind::Star => Some(BSymbol::Mult),
+            TokenKind::Nand => Some(BSymbol::Nand),
+            TokenKind::Nor => Some(BSymbol::Nor),
+            _ => None,
+        }
+    }
+
+    fn close_paren(&mut self, open_offset: usize) -> Result<(), ParseError> {
+        if self.peek().kind == TokenKind::RParen {
+            self.advance();
+            Ok(())
+        } else if self.peek().kind == TokenKind::Eof {
+            Err(self.error_here(ParseErrorKind::UnclosedParen { open_offset }))
+        } else {
+            Err(self.unexpected("')'"))
+        }
+    }
+}
+
+/// Builds a single quantifier over `name`, guarding its body with `sort`
+/// applied to `name` like an ordinary predicate when a sort annotation was
+/// given: `forall n: Nat, P` becomes `forall n, Nat(n) -> P`, and `exists
+/// n: Nat, P` becomes `exists n, Nat(n) & P`. With no sort, this is just
+/// [`Expr::quantifier`].
+fn sorted_quantifier(symbol: QSymbol, name: &str, sort: Option<&str>, body: Expr) -> Expr {
+    let guarded_body = match (symbol, sort) {
+        (_, None) => body,
+        (QSymbol::Forall, Some(sort)) => {
+            Expr::implies(Expr::Apply { func: Box::new(Expr::var(sort)), args: vec![Expr::var(name)] }, body)
+        }
+        (QSymbol::Exists, Some(sort)) => {
+            Expr::assoc(ASymbol::And, vec![Expr::Apply { func: Box::new(Expr::var(sort)), args: vec![Expr::var(name)] }, body])
+        }
+    };
+    Expr::quantifier(symbol, name, guarded_body)
+}
+
+/// Builds the equality atom `a = b` desugars to: `=` applied to `l` and `r`
+/// like an ordinary two-argument predicate, so every `Expr`-generic
+/// function (`unify`, `subst`, `freevars`, ...) already treats it correctly
+/// via their existing `Apply` case.
+fn equals(l: Expr, r: Expr) -> Expr {
+    Expr::Apply { func: Box::new(Expr::var("=")), args: vec![l, r] }
+}
+
+/// Parses `input` as an [`Expr`] in the concrete syntax `Expr`'s own
+/// `Display` impl produces, failing with a [`ParseError`] that pinpoints the
+/// byte offset (and derived line/column) of the first problem, rather than
+/// exposing any lexer/parser-internal state. Trailing input after a
+/// complete expression is itself reported as an error — `parse` expects the
+/// whole of `input` to be a single expression.
+pub fn parse(input: &str) -> Result<Expr, ParseError> {
+    let tokens = lex(input)?;
+    let mut parser = Parser { input, tokens, pos: 0 };
+    let expr = parser.parse_expr()?;
+    if parser.peek().kind != TokenKind::Eof {
+        return Err(parser.unexpected("end of input"));
+    }
+    Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::USymbol;
+
+    #[test]
+    fn test_parse_round_trips_a_variety_of_display_output() {
+        let exprs = vec![
+            Expr::Contradiction,
+            Expr::Tautology,
+            Expr::var("p"),
+            Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] },
+            Expr::Unop { symbol: USymbol::Not, operand: Box::new(Expr::var("p")) },
+            Expr::implies(Expr::var("p"), Expr::var("q")),
+            Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q"), Expr::var("r")]),
+            Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")]),
+            Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q")]),
+            Expr::assoc(ASymbol::Equiv, vec![Expr::var("p"), Expr::var("q")]),
+            Expr::assoc(ASymbol::Xor, vec![Expr::var("p"), Expr::var("q")]),
+            Expr::quantifier(QSymbol::Forall, "x", Expr::quantifier(QSymbol::Exists, "y", Expr::var("x"))),
+        ];
+        for e in exprs {
+            let printed = e.to_string();
+            assert_eq!(parse(&printed).unwrap_or_else(|err| panic!("failed to parse {:?}: {}", printed, err)), e);
+        }
+    }
+
+    #[test]
+    fn test_parse_reports_the_offset_of_an_unknown_symbol() {
+        let err = parse("p & @ & q").unwrap_err();
+        assert_eq!(err.offset, 4);
+        assert_eq!((err.line, err.column), (1, 5));
+        assert_eq!(err.kind, ParseErrorKind::UnknownSymbol { symbol: "@".to_owned() });
+    }
+
+    #[test]
+    fn test_parse_reports_an_unclosed_paren_distinctly() {
+        let err = parse("(p & q").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnclosedParen { open_offset: 0 });
+    }
+
+    #[test]
+    fn test_parse_reports_the_innermost_unclosed_paren_when_several_are_open() {
+        // both parens are left open, but the inner one is where parsing
+        // actually gives up, so its offset — not the outer paren's — is
+        // the one reported
+        let err = parse("(p & (q | r").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnclosedParen { open_offset: 5 });
+    }
+
+    #[test]
+    fn test_parse_reports_a_dangling_connective_at_end_of_input() {
+        let err = parse("(p &").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd { expected: "an expression after the operator".to_owned() });
+        assert_eq!(err.offset, 4);
+    }
+
+    #[test]
+    fn test_parse_reports_a_dangling_tilde_at_end_of_input() {
+        let err = parse("~").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEnd { expected: "an expression after '~'".to_owned() });
+    }
+
+    #[test]
+    fn test_parse_distinguishes_a_mismatched_closing_token_from_an_unclosed_paren() {
+        let err = parse("(p & q, r)").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken { found: "','".to_owned(), expected: "')'".to_owned() });
+    }
+
+    #[test]
+    fn test_parse_reports_trailing_input_after_a_complete_expression() {
+        let err = parse("p q").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::UnexpectedToken { found: "identifier \"q\"".to_owned(), expected: "end of input".to_owned() }
+        );
+    }
+
+    #[test]
+    fn test_parse_computes_line_and_column_across_multiple_lines() {
+        let err = parse("p &\n@").unwrap_err();
+        assert_eq!(err.offset, 4);
+        assert_eq!((err.line, err.column), (2, 1));
+    }
+
+    #[test]
+    fn test_parse_accepts_a_quantifier_with_a_nested_apply() {
+        let e = parse("forall x, p(x, y)").unwrap();
+        assert_eq!(
+            e,
+            Expr::quantifier(
+                QSymbol::Forall,
+                "x",
+                Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y")] }
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_an_incomplete_quantifier_missing_its_comma() {
+        let err = parse("forall x ~p").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::UnexpectedToken { found: "'~'".to_owned(), expected: "',' after the binder name".to_owned() }
+        );
+    }
+
+    #[test]
+    fn test_parse_accepts_each_ascii_operator_synonym() {
+        let cases = vec![
+            ("(p /\\ q)", Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p and q)", Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p \\/ q)", Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p or q)", Expr::assoc(ASymbol::Or, vec![Expr::var("p"), Expr::var("q")])),
+            ("!p", !Expr::var("p")),
+            ("not p", !Expr::var("p")),
+            ("(p => q)", Expr::implies(Expr::var("p"), Expr::var("q"))),
+            ("(p <-> q)", Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p iff q)", Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q")])),
+            ("A. x, p(x)", Expr::quantifier(QSymbol::Forall, "x", Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x")] })),
+            ("E. x, p(x)", Expr::quantifier(QSymbol::Exists, "x", Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x")] })),
+            ("^|^", Expr::Tautology),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse(input).unwrap_or_else(|err| panic!("failed to parse {:?}: {}", input, err)), expected, "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_accepts_the_ascii_word_and_symbolic_spellings_of_xor_nand_nor() {
+        let cases = vec![
+            ("(p ^ q)", Expr::assoc(ASymbol::Xor, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p xor q)", Expr::assoc(ASymbol::Xor, vec![Expr::var("p"), Expr::var("q")])),
+            ("(p !& q)", Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("p")), r: Box::new(Expr::var("q")) }),
+            ("(p nand q)", Expr::Binop { symbol: BSymbol::Nand, l: Box::new(Expr::var("p")), r: Box::new(Expr::var("q")) }),
+            ("(p !| q)", Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("p")), r: Box::new(Expr::var("q")) }),
+            ("(p nor q)", Expr::Binop { symbol: BSymbol::Nor, l: Box::new(Expr::var("p")), r: Box::new(Expr::var("q")) }),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse(input).unwrap_or_else(|err| panic!("failed to parse {:?}: {}", input, err)), expected, "parsing {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_requires_explicit_nesting_between_xor_and_and_or_or() {
+        let e = parse("((p xor q) & (r or s))").unwrap();
+        assert_eq!(
+            e,
+            Expr::assoc(
+                ASymbol::And,
+                vec![Expr::assoc(ASymbol::Xor, vec![Expr::var("p"), Expr::var("q")]), Expr::assoc(ASymbol::Or, vec![Expr::var("r"), Expr::var("s")])]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_requires_explicit_nesting_between_xor_and_implies() {
+        let e = parse("((p xor q) => r)").unwrap();
+        assert_eq!(e, Expr::implies(Expr::assoc(ASymbol::Xor, vec![Expr::var("p"), Expr::var("q")]), Expr::var("r")));
+    }
+
+    #[test]
+    fn test_parse_rejects_mixing_xor_and_nand_without_an_explicit_nested_group() {
+        let err = parse("(a xor b nand c)").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken { found: "'!&'".to_owned(), expected: "')'".to_owned() });
+    }
+
+    #[test]
+    fn test_parse_does_not_misread_an_identifier_starting_with_a_keyword() {
+        let e = parse("(android and roid)").unwrap();
+        assert_eq!(e, Expr::assoc(ASymbol::And, vec![Expr::var("android"), Expr::var("roid")]));
+    }
+
+    #[test]
+    fn test_parse_accepts_a_mixed_ascii_and_symbolic_spelling_formula() {
+        let e = parse("((p /\\ q) => (not r or (p iff q)))").unwrap();
+        assert_eq!(
+            e,
+            Expr::implies(
+                Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]),
+                Expr::assoc(
+                    ASymbol::Or,
+                    vec![!Expr::var("r"), Expr::assoc(ASymbol::Bicon, vec![Expr::var("p"), Expr::var("q")])]
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_desugars_a_space_separated_binder_prefix_into_nested_quantifiers() {
+        let e = parse("forall x y z, p(x, y, z)").unwrap();
+        let nested = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(
+                QSymbol::Forall,
+                "y",
+                Expr::quantifier(
+                    QSymbol::Forall,
+                    "z",
+                    Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("x"), Expr::var("y"), Expr::var("z")] },
+                ),
+            ),
+        );
+        assert_eq!(e, nested);
+    }
+
+    #[test]
+    fn test_parse_desugars_a_comma_separated_binder_prefix_the_same_as_space_separated() {
+        assert_eq!(parse("forall x, y, z, p(x, y, z)").unwrap(), parse("forall x y z, p(x, y, z)").unwrap());
+    }
+
+    #[test]
+    fn test_parse_still_reads_a_single_binder_followed_by_a_bare_variable_body() {
+        // the comma after `x` must stay the body-introducing comma, not get
+        // swallowed as a second binder name, since there's nothing after
+        // `y` to prove the list keeps going
+        let e = parse("forall x, y").unwrap();
+        assert_eq!(e, Expr::quantifier(QSymbol::Forall, "x", Expr::var("y")));
+    }
+
+    #[test]
+    fn test_parse_rejects_a_repeated_name_in_one_quantifier_prefix() {
+        let err = parse("forall x y x, p(x, y)").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::DuplicateBinderName { name: "x".to_owned() });
+    }
+
+    #[test]
+    fn test_display_resugars_a_maximal_run_of_the_same_quantifier_kind() {
+        let e = Expr::quantifier(
+            QSymbol::Forall,
+            "x",
+            Expr::quantifier(QSymbol::Forall, "y", Expr::quantifier(QSymbol::Exists, "z", Expr::var("x"))),
+        );
+        assert_eq!(e.to_string(), "forall x y, exists z, x");
+    }
+
+    #[test]
+    fn test_display_and_parse_round_trip_a_multi_binder_quantifier() {
+        let e = parse("forall x y z, p(x, y, z)").unwrap();
+        assert_eq!(e.to_string(), "forall x y z, p(x, y, z)");
+        assert_eq!(parse(&e.to_string()).unwrap(), e);
+    }
+
+    #[test]
+    fn test_parse_desugars_a_forall_sort_annotation_into_an_implication_guard() {
+        let e = parse("forall n: Nat, p(n)").unwrap();
+        assert_eq!(
+            e,
+            Expr::quantifier(
+                QSymbol::Forall,
+                "n",
+                Expr::implies(
+                    Expr::Apply { func: Box::new(Expr::var("Nat")), args: vec![Expr::var("n")] },
+                    Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("n")] },
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_desugars_an_exists_sort_annotation_into_a_conjunction_guard() {
+        let e = parse("exists n: Nat, p(n)").unwrap();
+        assert_eq!(
+            e,
+            Expr::quantifier(
+                QSymbol::Exists,
+                "n",
+                Expr::assoc(
+                    ASymbol::And,
+                    vec![
+                        Expr::Apply { func: Box::new(Expr::var("Nat")), args: vec![Expr::var("n")] },
+                        Expr::Apply { func: Box::new(Expr::var("p")), args: vec![Expr::var("n")] },
+                    ],
+                ),
+            )
+        );
+    }
+
+    #[test]
+    fn test_display_resugars_a_sorted_binder_back_into_colon_syntax() {
+        let e = parse("forall n: Nat, p(n)").unwrap();
+        assert_eq!(e.to_string(), "forall n: Nat, p(n)");
+    }
+
+    #[test]
+    fn test_parse_accepts_a_mix_of_sorted_and_unsorted_binders_in_one_prefix() {
+        let e = parse("forall n: Nat, x, p(n, x)").unwrap();
+        assert_eq!(e.to_string(), "forall n: Nat x, p(n, x)");
+    }
+
+    #[test]
+    fn test_parse_reads_infix_equality_as_a_two_argument_apply() {
+        let e = parse("a = b").unwrap();
+        assert_eq!(e, Expr::Apply { func: Box::new(Expr::var("=")), args: vec![Expr::var("a"), Expr::var("b")] });
+    }
+
+    #[test]
+    fn test_display_prints_equality_infix_rather_than_as_an_apply() {
+        let e = Expr::Apply { func: Box::new(Expr::var("=")), args: vec![Expr::var("a"), Expr::var("b")] };
+        assert_eq!(e.to_string(), "(a = b)");
+        assert_eq!(parse(&e.to_string()).unwrap(), e);
+    }
+
+    #[test]
+    fn test_parse_reads_the_negated_equality_synonym() {
+        let e = parse("a != b").unwrap();
+        assert_eq!(e, !Expr::Apply { func: Box::new(Expr::var("=")), args: vec![Expr::var("a"), Expr::var("b")] });
+        assert_eq!(e.to_string(), "~(a = b)");
+    }
+
+    #[test]
+    fn test_parse_equality_of_compound_terms() {
+        let e = parse("f(x) = g(y, z)").unwrap();
+        assert_eq!(
+            e,
+            Expr::Apply {
+                func: Box::new(Expr::var("=")),
+                args: vec![
+                    Expr::Apply { func: Box::new(Expr::var("f")), args: vec![Expr::var("x")] },
+                    Expr::Apply { func: Box::new(Expr::var("g")), args: vec![Expr::var("y"), Expr::var("z")] },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_equality_binds_tighter_than_a_conjunction() {
+        let e = parse("(a = b & c = d)").unwrap();
+        assert_eq!(
+            e,
+            Expr::assoc(
+                ASymbol::And,
+                vec![
+                    Expr::Apply { func: Box::new(Expr::var("=")), args: vec![Expr::var("a"), Expr::var("b")] },
+                    Expr::Apply { func: Box::new(Expr::var("=")), args: vec![Expr::var("c"), Expr::var("d")] },
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_unify_two_equality_atoms() {
+        use crate::expression::unify;
+        let a = parse("f(__x) = g(y)").unwrap();
+        let b = parse("f(z) = g(y)").unwrap();
+        let subs = unify(&a, &b).unwrap();
+        assert_eq!(subs.get("__x"), Some(&Expr::var("z")));
+    }
+
+
+    #[test]
+    fn test_parse_reads_a_numeral_literal_as_a_var_with_a_digit_name() {
+        assert_eq!(parse("12").unwrap(), Expr::var("12"));
+    }
+
+    #[test]
+    fn test_parse_reads_an_arithmetic_expression_mixing_numerals_and_variables() {
+        let e = parse("(x + (12 * 3))").unwrap();
+        assert_eq!(
+            e,
+            Expr::Binop {
+                symbol: BSymbol::Plus,
+                l: Box::new(Expr::var("x")),
+                r: Box::new(Expr::Binop { symbol: BSymbol::Mult, l: Box::new(Expr::var("12")), r: Box::new(Expr::var("3")) }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_prints_a_numeral_bare_and_round_trips() {
+        let e = parse("(12 + 3)").unwrap();
+        assert_eq!(e.to_string(), "(12 + 3)");
+        assert_eq!(parse(&e.to_string()).unwrap(), e);
+    }
+
+    #[test]
+    fn test_subst_never_replaces_into_a_numeral_even_when_the_name_matches() {
+        use crate::expression::subst;
+        let twelve = Expr::var("12");
+        assert_eq!(subst("12", &Expr::var("x"), twelve.clone()), twelve);
+    }
+
+    #[test]
+    fn test_parse_reports_a_negative_numeral_literal_clearly() {
+        let err = parse("-3").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::NegativeNumeral);
+    }
+
+    #[test]
+    fn test_expr_from_str_accepts_input_without_a_trailing_newline() {
+        let e: Expr = "(p & q)".parse().unwrap();
+        assert_eq!(e, Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]));
+    }
+
+    #[test]
+    fn test_expr_from_str_accepts_input_with_a_trailing_newline() {
+        let e: Expr = "(p & q)\n".parse().unwrap();
+        assert_eq!(e, Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]));
+    }
+
+    #[test]
+    fn test_expr_from_str_trims_surrounding_whitespace() {
+        let e: Expr = "  (p & q)  \n".parse().unwrap();
+        assert_eq!(e, Expr::assoc(ASymbol::And, vec![Expr::var("p"), Expr::var("q")]));
+    }
+
+    #[test]
+    fn test_expr_from_str_rejects_trailing_garbage_after_a_complete_expression() {
+        let err: ParseError = "(p & q) r".parse::<Expr>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken { found: "identifier \"r\"".to_owned(), expected: "end of input".to_owned() });
+    }
+
+    #[test]
+    fn test_expr_parse_is_equivalent_to_from_str() {
+        assert_eq!(Expr::parse("(p & q)"), "(p & q)".parse());
+    }
+
+    #[test]
+    fn test_parse_of_minimal_display_is_the_identity_on_random_expressions() {
+        use crate::expression::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(0x5eed_1e55);
+        for _ in 0..500 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, true);
+            let printed = e.to_string();
+            let reparsed = parse(&printed).unwrap_or_else(|err| panic!("failed to reparse {:?} (from {:?}): {}", printed, e, err));
+            assert_eq!(reparsed, e, "printed as {:?}", printed);
+        }
+    }
+
+    #[test]
+    fn test_parse_of_fully_parenthesized_display_is_also_the_identity_on_random_expressions() {
+        use crate::expression::testutil::{arbitrary_expr, Rng};
+        let mut rng = Rng::new(0x0ff_beef);
+        for _ in 0..500 {
+            let e = arbitrary_expr(&mut rng, 4, &["a", "b", "c"], true, true);
+            let printed = format!("{:#}", e);
+            let reparsed = parse(&printed).unwrap_or_else(|err| panic!("failed to reparse {:?} (from {:?}): {}", printed, e, err));
+            assert_eq!(reparsed, e, "printed as {:?}", printed);
+        }
+    }
+}